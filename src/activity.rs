@@ -0,0 +1,588 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The activity stream of the server.
+//!
+//! The tool calls, the failed connections to pgmoneta, the schedule runs and
+//! the alert changes are published as an [`Activity`] with an increasing
+//! identifier. The latest activities are kept in a bounded ring so that a
+//! client of the `/events` route can resume from the `Last-Event-ID` it saw
+//! last, as Server-Sent Events clients do when they reconnect.
+
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
+
+use crate::configuration::CONFIG;
+use crate::constant::ManagementError;
+use crate::handler::catalog::BackupClock;
+use crate::security::SecurityUtil;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use once_cell::sync::Lazy;
+use rmcp::ErrorData as McpError;
+use rmcp::model::{CallToolRequestParams, CallToolResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+/// The number of activities kept for clients resuming the stream.
+const RING_CAPACITY: usize = 1000;
+
+/// The number of activities a client may lag behind before it is refilled
+/// from the ring.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// The realm of the Basic authentication of the stream.
+const REALM: &str = "Basic realm=\"pgmoneta-mcp\"";
+
+/// The header a reconnecting client sends with the last identifier it saw.
+const LAST_EVENT_ID: &str = "last-event-id";
+
+static FEED: Lazy<Mutex<Feed>> = Lazy::new(|| Mutex::new(Feed::new(RING_CAPACITY)));
+
+/// The type of an activity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    ToolCallStarted,
+    ToolCallFinished,
+    ConnectionError,
+    ScheduleRunStarted,
+    ScheduleRunFinished,
+    AlertChanged,
+}
+
+impl ActivityKind {
+    /// Every type of activity.
+    pub const ALL: [ActivityKind; 6] = [
+        ActivityKind::ToolCallStarted,
+        ActivityKind::ToolCallFinished,
+        ActivityKind::ConnectionError,
+        ActivityKind::ScheduleRunStarted,
+        ActivityKind::ScheduleRunFinished,
+        ActivityKind::AlertChanged,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityKind::ToolCallStarted => "tool_call_started",
+            ActivityKind::ToolCallFinished => "tool_call_finished",
+            ActivityKind::ConnectionError => "connection_error",
+            ActivityKind::ScheduleRunStarted => "schedule_run_started",
+            ActivityKind::ScheduleRunFinished => "schedule_run_finished",
+            ActivityKind::AlertChanged => "alert_changed",
+        }
+    }
+
+    /// Parses a type of activity, e.g. `tool_call_finished`.
+    pub fn parse(kind: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == kind)
+    }
+}
+
+/// Something the server did.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Activity {
+    /// The identifier in the stream, assigned when published.
+    pub id: u64,
+    #[serde(rename = "Type")]
+    pub kind: ActivityKind,
+    pub time: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub details: Map<String, Value>,
+}
+
+impl Activity {
+    pub fn new(kind: ActivityKind) -> Self {
+        Activity {
+            id: 0,
+            kind,
+            time: String::new(),
+            server: None,
+            username: None,
+            details: Map::new(),
+        }
+    }
+
+    pub fn server(mut self, server: &str) -> Self {
+        self.server = Some(server.to_string());
+        self
+    }
+
+    pub fn username(mut self, username: &str) -> Self {
+        self.username = Some(username.to_string());
+        self
+    }
+
+    pub fn detail(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.details.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+/// The published activities: the ring of the latest ones and the channel of
+/// the live ones.
+struct Feed {
+    next_id: u64,
+    capacity: usize,
+    recent: VecDeque<Activity>,
+    sender: broadcast::Sender<Activity>,
+}
+
+impl Feed {
+    fn new(capacity: usize) -> Self {
+        Feed {
+            next_id: 1,
+            capacity,
+            recent: VecDeque::with_capacity(capacity),
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+        }
+    }
+
+    fn publish(&mut self, mut activity: Activity) -> u64 {
+        let clock = BackupClock::configured();
+        activity.id = self.next_id;
+        activity.time = clock.format(clock.now());
+        self.next_id += 1;
+        if self.recent.len() == self.capacity {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(activity.clone());
+        // Without clients the activity is only kept in the ring
+        let _ = self.sender.send(activity);
+        self.next_id - 1
+    }
+
+    /// The activities of the ring after `after`; all of them if `after` was
+    /// never assigned, as after a restart of the server.
+    fn after(&self, after: u64) -> VecDeque<Activity> {
+        let after = if after >= self.next_id { 0 } else { after };
+        self.recent
+            .iter()
+            .filter(|activity| activity.id > after)
+            .cloned()
+            .collect()
+    }
+
+    /// Subscribes to the live activities, with the ones to replay first if
+    /// the client resumes after `last_event_id`.
+    fn subscribe(&self, last_event_id: Option<u64>) -> Tail {
+        let (replay, last) = match last_event_id {
+            Some(last_event_id) => (self.after(last_event_id), 0),
+            None => (VecDeque::new(), self.next_id - 1),
+        };
+        Tail {
+            replay,
+            receiver: self.sender.subscribe(),
+            last,
+        }
+    }
+}
+
+/// Publishes an activity, returning its identifier.
+pub fn publish(activity: Activity) -> u64 {
+    FEED.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .publish(activity)
+}
+
+/// A tool call in progress, published when it starts and when it finishes.
+pub struct ToolCall {
+    id: u64,
+    tool: String,
+    server: Option<String>,
+    username: Option<String>,
+    start: Instant,
+}
+
+impl ToolCall {
+    pub fn start(request: &CallToolRequestParams) -> Self {
+        let argument = |key: &str| {
+            request
+                .arguments
+                .as_ref()
+                .and_then(|arguments| arguments.get(key))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        let mut call = ToolCall {
+            id: 0,
+            tool: request.name.to_string(),
+            server: argument("server"),
+            username: argument("username"),
+            start: Instant::now(),
+        };
+        call.id = publish(call.activity(ActivityKind::ToolCallStarted));
+        call
+    }
+
+    /// Publishes the finish of the call, returning its outcome: `error` if the
+    /// tool failed or pgmoneta answered with `error_code`, `success` otherwise.
+    pub fn finish(
+        self,
+        result: &Result<CallToolResult, McpError>,
+        error_code: Option<u32>,
+    ) -> &'static str {
+        let error = match result {
+            Ok(result) if result.is_error == Some(true) => Some(
+                result
+                    .content
+                    .iter()
+                    .find_map(|content| content.as_text().map(|text| text.text.clone()))
+                    .unwrap_or_default(),
+            ),
+            Ok(_) => error_code.map(|code| ManagementError::translate_error_enum(code).to_string()),
+            Err(e) => Some(e.message.to_string()),
+        };
+        let outcome = if error.is_some() { "error" } else { "success" };
+        let mut activity = self
+            .activity(ActivityKind::ToolCallFinished)
+            .detail("Call", self.id)
+            .detail("Outcome", outcome)
+            .detail("Seconds", self.start.elapsed().as_secs_f64());
+        if let Some(error) = error {
+            activity = activity.detail("Error", error);
+        }
+        if let Some(code) = error_code {
+            activity = activity.detail("ErrorCode", code);
+        }
+        publish(activity);
+        outcome
+    }
+
+    fn activity(&self, kind: ActivityKind) -> Activity {
+        let mut activity = Activity::new(kind).detail("Tool", self.tool.as_str());
+        activity.server = self.server.clone();
+        activity.username = self.username.clone();
+        activity
+    }
+}
+
+/// The filter of a client, from the `type` and `server` query parameters.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Filter {
+    kinds: Vec<ActivityKind>,
+    servers: Vec<String>,
+}
+
+impl Filter {
+    fn parse(query: &EventsQuery) -> Result<Self, String> {
+        let kinds = list(query.kind.as_deref())
+            .map(|kind| {
+                ActivityKind::parse(kind).ok_or_else(|| {
+                    format!(
+                        "Unknown type '{kind}': expected one of {}",
+                        ActivityKind::ALL.map(|kind| kind.as_str()).join(", ")
+                    )
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let servers = list(query.server.as_deref()).map(str::to_string).collect();
+        Ok(Filter { kinds, servers })
+    }
+
+    /// Whether the client wants `activity`; activities of no server are
+    /// excluded by a server filter.
+    fn accepts(&self, activity: &Activity) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&activity.kind))
+            && (self.servers.is_empty()
+                || activity
+                    .server
+                    .as_ref()
+                    .is_some_and(|server| self.servers.contains(server)))
+    }
+}
+
+fn list(value: Option<&str>) -> impl Iterator<Item = &str> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+/// The activities of a client: the ones to replay, then the live ones.
+struct Tail {
+    replay: VecDeque<Activity>,
+    receiver: broadcast::Receiver<Activity>,
+    /// The identifier of the last activity sent, or skipped by the filter.
+    last: u64,
+}
+
+impl Tail {
+    /// The next activity, `None` once the feed is closed.
+    async fn next(&mut self) -> Option<Activity> {
+        loop {
+            if let Some(activity) = self.replay.pop_front() {
+                if activity.id > self.last {
+                    self.last = activity.id;
+                    return Some(activity);
+                }
+                continue;
+            }
+            match self.receiver.recv().await {
+                Ok(activity) if activity.id <= self.last => continue,
+                Ok(activity) => {
+                    self.last = activity.id;
+                    return Some(activity);
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    // The ring has the missed activities unless the client
+                    // lagged behind the whole ring
+                    self.replay = FEED
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .after(self.last);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// The query parameters of the `/events` route.
+#[derive(Debug, Default, Deserialize)]
+pub struct EventsQuery {
+    /// A comma separated list of types of activity.
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    /// A comma separated list of servers.
+    pub server: Option<String>,
+}
+
+/// Streams the activities as Server-Sent Events, until the server shuts down.
+///
+/// The client authenticates as an admin with HTTP Basic authentication.
+pub async fn events_handler(
+    State(shutdown): State<CancellationToken>,
+    Query(query): Query<EventsQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(username) = authenticate(&headers).await else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, HeaderValue::from_static(REALM))],
+            "Authentication required\n",
+        )
+            .into_response();
+    };
+    let filter = match Filter::parse(&query) {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("{e}\n")).into_response(),
+    };
+    let last_event_id = headers
+        .get(LAST_EVENT_ID)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+    tracing::info!(
+        "Streaming the activity to {} (resuming after {:?})",
+        username,
+        last_event_id
+    );
+
+    let tail = FEED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .subscribe(last_event_id);
+    let stream = futures_util::stream::unfold(
+        (tail, filter, shutdown),
+        |(mut tail, filter, shutdown)| async move {
+            loop {
+                let activity = tokio::select! {
+                    _ = shutdown.cancelled() => return None,
+                    activity = tail.next() => activity?,
+                };
+                if filter.accepts(&activity) {
+                    return Some((
+                        Ok::<_, Infallible>(sse_event(&activity)),
+                        (tail, filter, shutdown),
+                    ));
+                }
+            }
+        },
+    );
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+fn sse_event(activity: &Activity) -> SseEvent {
+    SseEvent::default()
+        .id(activity.id.to_string())
+        .event(activity.kind.as_str())
+        .data(serde_json::to_string(activity).unwrap_or_default())
+}
+
+/// The admin of the Basic `Authorization` header, if its password is the one
+/// of the users configuration.
+pub(crate) async fn authenticate(headers: &HeaderMap) -> Option<String> {
+    let (username, password) = basic_credentials(headers)?;
+    let password_encrypted = CONFIG.get()?.admins.get(&username)?.clone();
+    let verified = {
+        let username = username.clone();
+        // The first use of the master key derives it, which takes a while
+        tokio::task::spawn_blocking(move || {
+            let security_util = SecurityUtil::new();
+            let (master_password, master_salt) = security_util.load_master_key()?;
+            security_util.verify_password(
+                &password_encrypted,
+                password.as_bytes(),
+                &master_password,
+                &master_salt,
+            )
+        })
+        .await
+        .ok()?
+        .inspect_err(|e| tracing::warn!("Failed to verify the password of {}: {:#}", username, e))
+        .ok()?
+    };
+    if !verified {
+        tracing::warn!("Rejected the password of {}", username);
+    }
+    verified.then_some(username)
+}
+
+/// The username and password of a Basic `Authorization` header.
+fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, encoded) = value.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = SecurityUtil::new().base64_decode(encoded.trim()).ok()?;
+    let (username, password) = String::from_utf8(decoded)
+        .ok()?
+        .split_once(':')
+        .map(|(username, password)| (username.to_string(), password.to_string()))?;
+    Some((username, password))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn published(feed: &mut Feed, count: usize) {
+        for _ in 0..count {
+            feed.publish(Activity::new(ActivityKind::AlertChanged).server("primary"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tail_resumes_from_the_ring() {
+        let mut feed = Feed::new(3);
+        published(&mut feed, 5);
+        let ids = |tail: &Tail| tail.replay.iter().map(|a| a.id).collect::<Vec<_>>();
+
+        assert_eq!(ids(&feed.subscribe(Some(3))), vec![4, 5]);
+        // The ring only has the last three
+        assert_eq!(ids(&feed.subscribe(Some(0))), vec![3, 4, 5]);
+        // An identifier of a previous run of the server replays the ring
+        assert_eq!(ids(&feed.subscribe(Some(42))), vec![3, 4, 5]);
+        assert!(ids(&feed.subscribe(None)).is_empty());
+
+        let mut tail = feed.subscribe(Some(4));
+        published(&mut feed, 1);
+        assert_eq!(tail.next().await.map(|a| a.id), Some(5));
+        assert_eq!(tail.next().await.map(|a| a.id), Some(6));
+
+        let mut tail = feed.subscribe(None);
+        published(&mut feed, 1);
+        assert_eq!(tail.next().await.map(|a| a.id), Some(7));
+    }
+
+    #[test]
+    fn test_filter() {
+        let query = EventsQuery {
+            kind: Some("tool_call_started, alert_changed".to_string()),
+            server: Some("primary".to_string()),
+        };
+        let filter = Filter::parse(&query).expect("The filter should parse");
+        assert_eq!(
+            filter.kinds,
+            vec![ActivityKind::ToolCallStarted, ActivityKind::AlertChanged]
+        );
+
+        assert!(filter.accepts(&Activity::new(ActivityKind::AlertChanged).server("primary")));
+        assert!(!filter.accepts(&Activity::new(ActivityKind::AlertChanged).server("replica")));
+        assert!(!filter.accepts(&Activity::new(ActivityKind::AlertChanged)));
+        assert!(!filter.accepts(&Activity::new(ActivityKind::ConnectionError).server("primary")));
+        assert!(Filter::default().accepts(&Activity::new(ActivityKind::ConnectionError)));
+
+        let query = EventsQuery {
+            kind: Some("backup_started".to_string()),
+            server: None,
+        };
+        let error = Filter::parse(&query).expect_err("The type should be unknown");
+        assert!(error.starts_with("Unknown type 'backup_started'"));
+    }
+
+    #[test]
+    fn test_basic_credentials() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(basic_credentials(&headers), None);
+
+        // admin:se:cret
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Basic YWRtaW46c2U6Y3JldA=="),
+        );
+        assert_eq!(
+            basic_credentials(&headers),
+            Some(("admin".to_string(), "se:cret".to_string()))
+        );
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer YWRtaW46c2U6Y3JldA=="),
+        );
+        assert_eq!(basic_credentials(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_events_require_authentication() {
+        let app = axum::Router::new()
+            .route("/events", axum::routing::get(events_handler))
+            .with_state(CancellationToken::new());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("The listener should bind");
+        let address = listener.local_addr().expect("The listener has an address");
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let response = reqwest::Client::new()
+            .get(format!("http://{address}/events"))
+            .basic_auth("admin", Some("wrong"))
+            .send()
+            .await
+            .expect("The request should succeed");
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.headers()[reqwest::header::WWW_AUTHENTICATE],
+            "Basic realm=\"pgmoneta-mcp\""
+        );
+    }
+}
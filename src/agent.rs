@@ -14,10 +14,13 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::llm::{ChatMessage, LlmClient, LlmResponse, ToolDefinition};
+use crate::otel;
 use anyhow::anyhow;
+use opentelemetry::trace::TraceId;
 use rmcp::RoleClient;
-use rmcp::model::CallToolRequestParams;
+use rmcp::model::{CallToolRequestParams, Meta};
 use rmcp::service::Peer;
+use tracing::Instrument;
 
 /// Default system prompt for the pgmoneta backup management assistant.
 pub const SYSTEM_PROMPT: &str = "\
@@ -85,9 +88,23 @@ impl<'a, L: LlmClient> Agent<'a, L> {
     /// # Arguments
     /// * `user_input` - The user's question or command.
     ///
+    /// The tool calls of a prompt carry its trace context, so the server
+    /// traces them as one trace.
+    ///
     /// # Returns
     /// The LLM's final text response, or an error.
     pub async fn prompt(&mut self, user_input: &str) -> anyhow::Result<String> {
+        let span = tracing::info_span!(target: otel::SPAN_TARGET, "agent.prompt");
+        self.prompt_rounds(user_input, otel::new_trace_id())
+            .instrument(span)
+            .await
+    }
+
+    async fn prompt_rounds(
+        &mut self,
+        user_input: &str,
+        trace_id: TraceId,
+    ) -> anyhow::Result<String> {
         self.history.push(ChatMessage::user(user_input));
 
         for round in 0..self.max_tool_rounds {
@@ -120,7 +137,16 @@ impl<'a, L: LlmClient> Agent<'a, L> {
                             "Executing tool call"
                         );
 
-                        let result = self.execute_tool_call(tool_name, tool_args).await;
+                        let span = tracing::info_span!(
+                            target: otel::SPAN_TARGET,
+                            "agent.tool_call",
+                            otel.kind = "client",
+                            mcp.tool.name = tool_name.as_str(),
+                        );
+                        let result = self
+                            .execute_tool_call(tool_name, tool_args, trace_id)
+                            .instrument(span)
+                            .await;
 
                         match result {
                             Ok(content) => {
@@ -165,6 +191,7 @@ impl<'a, L: LlmClient> Agent<'a, L> {
     /// # Arguments
     /// * `tool_name` - The name of the tool to invoke.
     /// * `arguments` - The arguments to pass to the tool.
+    /// * `trace_id` - The trace of the calls when the current span is not exported.
     ///
     /// # Returns
     /// The text content of the tool's response.
@@ -172,11 +199,14 @@ impl<'a, L: LlmClient> Agent<'a, L> {
         &self,
         tool_name: &str,
         arguments: &std::collections::HashMap<String, serde_json::Value>,
+        trace_id: TraceId,
     ) -> anyhow::Result<String> {
         let arguments: serde_json::Map<String, serde_json::Value> =
             arguments.clone().into_iter().collect();
 
-        let request = CallToolRequestParams::new(tool_name.to_string()).with_arguments(arguments);
+        let mut request =
+            CallToolRequestParams::new(tool_name.to_string()).with_arguments(arguments);
+        otel::inject(request.meta.get_or_insert_with(Meta::new), trace_id);
 
         let result = self
             .mcp_peer
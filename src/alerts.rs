@@ -0,0 +1,728 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Alert rules.
+//!
+//! Every `[alert:<name>]` section of the configuration holds a `query_metrics`
+//! expression, evaluated every `interval` seconds of the `[alerting]` section
+//! against a scrape of the pgmoneta metrics and the backup catalog, or against
+//! the metrics history when it has range selectors. Every series the
+//! expression returns is an alert: pending at first, firing once the series
+//! has been returned for the `for` of the rule, and resolved once it is no
+//! longer returned. Alerts that fire or resolve are sent to the sinks.
+//!
+//! The active alerts are kept in `alerts.json` of the `state_directory`, when
+//! one is configured, so that a restart does not notify them again.
+
+pub mod sink;
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+use crate::activity::{self, Activity, ActivityKind};
+use crate::client::PgmonetaClient;
+use crate::configuration::{AlertRule, AlertingConfiguration, CONFIG};
+use crate::handler::catalog::{self, BackupClock, BackupEntry};
+use crate::handler::metrics::exposition::{
+    Exposition, MetricFamily, MetricType, Sample, serialize_value,
+};
+use crate::handler::metrics::query::{Query, QueryValue, Series};
+use crate::handler::verify::{self, VerifyResult};
+use crate::history;
+use crate::utils::SafeFileWriter;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Deserializer, Serialize};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// The age of the newest valid backup of each server, `+Inf` without one.
+pub const BACKUP_AGE_METRIC: &str = "pgmoneta_mcp_backup_age_seconds";
+
+/// Whether the last verification of each verified backup failed.
+pub const VERIFY_FAILED_METRIC: &str = "pgmoneta_mcp_verify_failed";
+
+/// The file in `state_directory` holding the active alerts.
+const STATE_FILE: &str = "alerts.json";
+
+/// The number of resolved alerts kept.
+const RESOLVED_LIMIT: usize = 50;
+
+static STATE: Mutex<Option<AlertsState>> = Mutex::new(None);
+
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{\{\s*\$(?:value|labels\.([A-Za-z_][A-Za-z0-9_]*))\s*\}\}").unwrap()
+});
+
+/// The state of an alert.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertState {
+    Pending,
+    Firing,
+    Resolved,
+}
+
+/// A series returned by the expression of a rule.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Alert {
+    pub rule: String,
+    pub state: AlertState,
+    pub severity: String,
+    pub labels: BTreeMap<String, String>,
+    /// The value at the last evaluation.
+    #[serde(
+        serialize_with = "serialize_value",
+        deserialize_with = "deserialize_value"
+    )]
+    pub value: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub active_since: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fired_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<String>,
+}
+
+/// The alerts at the last evaluation.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AlertsSnapshot {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evaluated_at: Option<String>,
+    pub firing: usize,
+    pub pending: usize,
+    /// The firing alerts first, then the pending ones.
+    pub alerts: Vec<Alert>,
+    /// The recently resolved alerts, newest first, when requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<Vec<Alert>>,
+    /// The error of each rule that could not be evaluated.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<String, String>,
+}
+
+/// An active alert and when it became active, in Unix milliseconds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Tracked {
+    since: i64,
+    alert: Alert,
+}
+
+#[derive(Debug, Default)]
+struct AlertsState {
+    active: BTreeMap<(String, BTreeMap<String, String>), Tracked>,
+    resolved: VecDeque<Alert>,
+    errors: BTreeMap<String, String>,
+    evaluated_at: Option<String>,
+}
+
+impl AlertsState {
+    /// Applies the result of evaluating `rule` at `now`, returning the alerts
+    /// that fired or resolved.
+    fn apply(
+        &mut self,
+        rule: &AlertRule,
+        result: Result<Vec<Series>>,
+        now: i64,
+        clock: BackupClock,
+    ) -> Vec<Alert> {
+        let series = match result {
+            Ok(series) => {
+                self.errors.remove(&rule.name);
+                series
+            }
+            Err(e) => {
+                self.errors.insert(rule.name.clone(), e.to_string());
+                return Vec::new();
+            }
+        };
+        let format = |time: i64| format_time(time, clock);
+
+        let mut notifications = Vec::new();
+        let mut returned = BTreeSet::new();
+        for series in series {
+            let key = (rule.name.clone(), series.labels.clone());
+            let tracked = self.active.entry(key.clone()).or_insert_with(|| Tracked {
+                since: now,
+                alert: Alert {
+                    rule: rule.name.clone(),
+                    state: AlertState::Pending,
+                    severity: rule.severity.clone(),
+                    labels: series.labels.clone(),
+                    value: series.value,
+                    summary: None,
+                    active_since: format(now),
+                    fired_at: None,
+                    resolved_at: None,
+                },
+            });
+            tracked.alert.value = series.value;
+            tracked.alert.severity = rule.severity.clone();
+            tracked.alert.summary = rule
+                .summary
+                .as_deref()
+                .map(|summary| render_summary(summary, &series.labels, series.value));
+            if tracked.alert.state == AlertState::Pending
+                && now - tracked.since >= (rule.for_seconds as i64).saturating_mul(1000)
+            {
+                tracked.alert.state = AlertState::Firing;
+                tracked.alert.fired_at = Some(format(now));
+                notifications.push(tracked.alert.clone());
+            }
+            returned.insert(key);
+        }
+
+        let gone = self
+            .active
+            .keys()
+            .filter(|key| key.0 == rule.name && !returned.contains(*key))
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in gone {
+            let Some(mut tracked) = self.active.remove(&key) else {
+                continue;
+            };
+            if tracked.alert.state == AlertState::Firing {
+                tracked.alert.state = AlertState::Resolved;
+                tracked.alert.resolved_at = Some(format(now));
+                notifications.push(tracked.alert.clone());
+                self.resolved.push_front(tracked.alert);
+                self.resolved.truncate(RESOLVED_LIMIT);
+            }
+        }
+        notifications
+    }
+
+    fn snapshot(&self, include_resolved: bool) -> AlertsSnapshot {
+        let mut alerts = self
+            .active
+            .values()
+            .map(|tracked| tracked.alert.clone())
+            .collect::<Vec<_>>();
+        alerts.sort_by_key(|alert| alert.state != AlertState::Firing);
+        AlertsSnapshot {
+            evaluated_at: self.evaluated_at.clone(),
+            firing: alerts
+                .iter()
+                .filter(|alert| alert.state == AlertState::Firing)
+                .count(),
+            pending: alerts
+                .iter()
+                .filter(|alert| alert.state == AlertState::Pending)
+                .count(),
+            alerts,
+            resolved: include_resolved.then(|| self.resolved.iter().cloned().collect()),
+            errors: self.errors.clone(),
+        }
+    }
+}
+
+/// The active alerts, and the recently resolved ones when `include_resolved`.
+pub fn snapshot(include_resolved: bool) -> AlertsSnapshot {
+    let state = STATE.lock().unwrap_or_else(PoisonError::into_inner);
+    state
+        .as_ref()
+        .map(|state| state.snapshot(include_resolved))
+        .unwrap_or_else(|| AlertsSnapshot {
+            resolved: include_resolved.then(Vec::new),
+            ..Default::default()
+        })
+}
+
+/// Starts evaluating the alert rules, unless none are configured.
+///
+/// The loop ends when `shutdown` is cancelled.
+pub fn start(shutdown: CancellationToken) -> Option<JoinHandle<()>> {
+    let config = CONFIG.get()?;
+    let alerting = config.alerting.clone()?;
+    if alerting.rules.is_empty() {
+        return None;
+    }
+    let Some(username) = alerting
+        .username
+        .clone()
+        .or_else(|| config.admins.keys().min().cloned())
+    else {
+        tracing::error!("Not evaluating the alert rules: no admin is configured to evaluate as");
+        return None;
+    };
+
+    let mut state = AlertsState {
+        active: load_state(state_directory().as_deref()),
+        ..Default::default()
+    };
+    state
+        .active
+        .retain(|(rule, _), _| alerting.rules.iter().any(|r| &r.name == rule));
+    *STATE.lock().unwrap_or_else(PoisonError::into_inner) = Some(state);
+
+    tracing::info!(
+        "Evaluating {} alert rules every {} seconds",
+        alerting.rules.len(),
+        alerting.interval
+    );
+    Some(tokio::spawn(run_loop(alerting, username, shutdown)))
+}
+
+async fn run_loop(alerting: AlertingConfiguration, username: String, shutdown: CancellationToken) {
+    let mut interval =
+        tokio::time::interval(std::time::Duration::from_secs(alerting.interval.into()));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+        let notifications = evaluate(&alerting, &username).await;
+        for alert in &notifications {
+            publish(alert);
+        }
+        sink::deliver(&alerting, &notifications).await;
+    }
+    tracing::info!("Alert evaluation stopped");
+}
+
+/// Publishes an alert that fired or resolved to the activity stream.
+fn publish(alert: &Alert) {
+    if let Ok(serde_json::Value::Object(details)) = serde_json::to_value(alert) {
+        let mut changed = Activity::new(ActivityKind::AlertChanged);
+        changed.server = alert.labels.get("name").cloned();
+        changed.details = details;
+        activity::publish(changed);
+    }
+}
+
+/// Evaluates every rule once, returning the alerts that fired or resolved.
+async fn evaluate(alerting: &AlertingConfiguration, username: &str) -> Vec<Alert> {
+    let clock = BackupClock::configured();
+    let now = Utc::now().timestamp_millis();
+    let rules = alerting
+        .rules
+        .iter()
+        .map(|rule| (rule, Query::parse(&rule.expr)))
+        .collect::<Vec<_>>();
+
+    let scraped = rules
+        .iter()
+        .any(|(_, query)| query.as_ref().is_ok_and(|query| !query.needs_history()));
+    let exposition = if scraped {
+        let uses_catalog = rules.iter().any(|(_, query)| {
+            query.as_ref().is_ok_and(|query| {
+                query.metric_names().is_none_or(|names| {
+                    names.contains(BACKUP_AGE_METRIC) || names.contains(VERIFY_FAILED_METRIC)
+                })
+            })
+        });
+        Some(
+            scrape(username, uses_catalog)
+                .await
+                .map_err(|e| format!("{e:#}")),
+        )
+    } else {
+        None
+    };
+
+    let mut results = Vec::new();
+    for (rule, query) in &rules {
+        let result = match query {
+            Err(e) => Err(anyhow!("Invalid expression: {e}")),
+            Ok(query) if query.needs_history() => evaluate_history(query, now).await,
+            Ok(query) => match &exposition {
+                Some(Ok(exposition)) => query.evaluate(exposition),
+                Some(Err(e)) => Err(anyhow!("{e}")),
+                None => Err(anyhow!("The pgmoneta metrics were not scraped")),
+            },
+        };
+        results.push((*rule, result.and_then(into_series)));
+    }
+
+    let mut state = STATE.lock().unwrap_or_else(PoisonError::into_inner);
+    let state = state.get_or_insert_with(AlertsState::default);
+    let mut notifications = Vec::new();
+    for (rule, result) in results {
+        if let Err(e) = &result {
+            tracing::warn!("Failed to evaluate alert rule {}: {:#}", rule.name, e);
+        }
+        notifications.extend(state.apply(rule, result, now, clock));
+    }
+    state.evaluated_at = Some(format_time(now, clock));
+    if let Some(directory) = state_directory() {
+        save_state(&directory, state);
+    }
+    notifications
+}
+
+/// Scrapes the pgmoneta metrics, with the catalog series when `catalog`.
+async fn scrape(username: &str, catalog: bool) -> Result<Exposition> {
+    let metrics = PgmonetaClient::request_metrics(username).await?;
+    let mut exposition = Exposition::parse(&metrics)?;
+    if catalog {
+        exposition.families.extend(
+            catalog_families(username)
+                .await
+                .map_err(|e| anyhow!("Failed to read the backup catalog: {}", e.message))?,
+        );
+    }
+    Ok(exposition)
+}
+
+async fn evaluate_history(query: &Query, now: i64) -> Result<QueryValue> {
+    let store = history::store().ok_or_else(|| anyhow!("The metrics history is not enabled"))?;
+    let lookback = history::lookback();
+    let start = now - query.window().max(lookback);
+    let names = query.metric_names();
+    let series =
+        tokio::task::spawn_blocking(move || store.read(start, now, names.as_ref())).await??;
+    query.evaluate_at(&series, lookback, now)
+}
+
+fn into_series(value: QueryValue) -> Result<Vec<Series>> {
+    match value {
+        QueryValue::Vector { series } => Ok(series),
+        QueryValue::Scalar { .. } | QueryValue::Matrix { .. } => Err(anyhow!(
+            "The expression must return a vector, such as 'pgmoneta_wal_streaming == 0'"
+        )),
+    }
+}
+
+/// The series of the backup catalog: [`BACKUP_AGE_METRIC`] per server and
+/// [`VERIFY_FAILED_METRIC`] per verified backup.
+async fn catalog_families(username: &str) -> Result<Vec<MetricFamily>, rmcp::ErrorData> {
+    let now = BackupClock::configured().now();
+    let status = catalog::fetch_status(username).await?;
+    let mut ages = Vec::new();
+    let mut verifications = Vec::new();
+    for server in catalog::parse_server_names(&status) {
+        let backups = catalog::fetch_backups(username, &server).await?;
+        let age = catalog::newest_valid(&backups)
+            .and_then(BackupEntry::end_timestamp)
+            .map(|end| (now - end).num_seconds().max(0) as f64)
+            .unwrap_or(f64::INFINITY);
+        ages.push(catalog_sample(BACKUP_AGE_METRIC, &server, None, age));
+        for backup in &backups {
+            if let Some(last) = verify::last_verification(&server, &backup.backup) {
+                let failed = if last.result == VerifyResult::Passed {
+                    0.0
+                } else {
+                    1.0
+                };
+                verifications.push(catalog_sample(
+                    VERIFY_FAILED_METRIC,
+                    &server,
+                    Some(&backup.backup),
+                    failed,
+                ));
+            }
+        }
+    }
+    Ok(vec![
+        catalog_family(
+            BACKUP_AGE_METRIC,
+            "The age of the newest valid backup of a server",
+            ages,
+        ),
+        catalog_family(
+            VERIFY_FAILED_METRIC,
+            "Whether the last verification of a backup failed",
+            verifications,
+        ),
+    ])
+}
+
+fn catalog_sample(metric: &str, server: &str, backup: Option<&str>, value: f64) -> Sample {
+    let mut labels = BTreeMap::from([("name".to_string(), server.to_string())]);
+    if let Some(backup) = backup {
+        labels.insert("label".to_string(), backup.to_string());
+    }
+    Sample {
+        name: metric.to_string(),
+        labels,
+        value,
+        timestamp: None,
+        exemplar: None,
+    }
+}
+
+fn catalog_family(name: &str, help: &str, samples: Vec<Sample>) -> MetricFamily {
+    MetricFamily {
+        name: name.to_string(),
+        metric_type: MetricType::Gauge,
+        help: Some(help.to_string()),
+        unit: None,
+        samples,
+    }
+}
+
+/// Replaces `{{ $value }}` and `{{ $labels.<label> }}` in a summary.
+fn render_summary(summary: &str, labels: &BTreeMap<String, String>, value: f64) -> String {
+    PLACEHOLDER
+        .replace_all(summary, |captures: &Captures| match captures.get(1) {
+            Some(label) => labels.get(label.as_str()).cloned().unwrap_or_default(),
+            None => value.to_string(),
+        })
+        .into_owned()
+}
+
+fn format_time(time: i64, clock: BackupClock) -> String {
+    match DateTime::from_timestamp_millis(time) {
+        Some(time) => clock.format(clock.localize(time.fixed_offset())),
+        None => time.to_string(),
+    }
+}
+
+fn deserialize_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Number(f64),
+        Text(String),
+    }
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Number(number) => number,
+        Value::Text(text) => match text.as_str() {
+            "+Inf" => f64::INFINITY,
+            "-Inf" => f64::NEG_INFINITY,
+            _ => f64::NAN,
+        },
+    })
+}
+
+fn state_directory() -> Option<String> {
+    CONFIG
+        .get()
+        .and_then(|config| config.pgmoneta_mcp.state_directory.clone())
+}
+
+fn load_state(directory: Option<&str>) -> BTreeMap<(String, BTreeMap<String, String>), Tracked> {
+    let Some(directory) = directory else {
+        return BTreeMap::new();
+    };
+    let path = Path::new(directory).join(STATE_FILE);
+    let tracked: Vec<Tracked> = match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable alerts {}: {}", path.display(), e);
+            Vec::new()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => {
+            tracing::warn!("Failed to read alerts {}: {}", path.display(), e);
+            Vec::new()
+        }
+    };
+    tracked
+        .into_iter()
+        .map(|tracked| {
+            (
+                (tracked.alert.rule.clone(), tracked.alert.labels.clone()),
+                tracked,
+            )
+        })
+        .collect()
+}
+
+fn save_state(directory: &str, state: &AlertsState) {
+    let tracked = state.active.values().collect::<Vec<_>>();
+    let result = serde_json::to_string_pretty(&tracked)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| {
+            SafeFileWriter::new(directory)
+                .allowed_extensions(vec!["json"])
+                .write(STATE_FILE, &contents)
+        });
+    if let Err(e) = result {
+        tracing::warn!("Failed to save the alerts: {:?}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock() -> BackupClock {
+        BackupClock::new(Some(chrono::FixedOffset::east_opt(0).unwrap()))
+    }
+
+    fn rule(for_seconds: u64) -> AlertRule {
+        AlertRule {
+            name: "wal_streaming".to_string(),
+            expr: "pgmoneta_wal_streaming == 0".to_string(),
+            for_seconds,
+            severity: "critical".to_string(),
+            summary: Some("WAL streaming of {{ $labels.name }} is {{$value}}".to_string()),
+        }
+    }
+
+    fn series(name: &str, value: f64) -> Series {
+        Series {
+            name: Some("pgmoneta_wal_streaming".to_string()),
+            labels: BTreeMap::from([("name".to_string(), name.to_string())]),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_apply_pending_firing_resolved() {
+        let mut state = AlertsState::default();
+        let rule = rule(120);
+
+        let fired = state.apply(&rule, Ok(vec![series("replica", 0.0)]), 0, clock());
+        assert!(fired.is_empty());
+        let snapshot = state.snapshot(false);
+        assert_eq!((snapshot.firing, snapshot.pending), (0, 1));
+        assert_eq!(snapshot.alerts[0].state, AlertState::Pending);
+        assert_eq!(
+            snapshot.alerts[0].summary.as_deref(),
+            Some("WAL streaming of replica is 0")
+        );
+
+        assert!(
+            state
+                .apply(&rule, Ok(vec![series("replica", 0.0)]), 60_000, clock())
+                .is_empty()
+        );
+        let fired = state.apply(&rule, Ok(vec![series("replica", 0.0)]), 120_000, clock());
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].state, AlertState::Firing);
+        assert_eq!(fired[0].active_since, "1970-01-01 00:00:00+00:00");
+        assert_eq!(
+            fired[0].fired_at.as_deref(),
+            Some("1970-01-01 00:02:00+00:00")
+        );
+
+        // A failed evaluation keeps the alerts
+        let failed = state.apply(&rule, Err(anyhow!("connection refused")), 180_000, clock());
+        assert!(failed.is_empty());
+        let snapshot = state.snapshot(false);
+        assert_eq!(snapshot.firing, 1);
+        assert_eq!(snapshot.errors["wal_streaming"], "connection refused");
+
+        let resolved = state.apply(&rule, Ok(vec![]), 240_000, clock());
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].state, AlertState::Resolved);
+        assert_eq!(
+            resolved[0].resolved_at.as_deref(),
+            Some("1970-01-01 00:04:00+00:00")
+        );
+        let snapshot = state.snapshot(true);
+        assert!(snapshot.alerts.is_empty());
+        assert!(snapshot.errors.is_empty());
+        assert_eq!(snapshot.resolved.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_apply_drops_pending_alerts_silently() {
+        let mut state = AlertsState::default();
+        let rule = rule(300);
+        state.apply(
+            &rule,
+            Ok(vec![series("primary", 0.0), series("replica", 0.0)]),
+            0,
+            clock(),
+        );
+        let notifications = state.apply(&rule, Ok(vec![series("replica", 0.0)]), 60_000, clock());
+        assert!(notifications.is_empty());
+        let snapshot = state.snapshot(true);
+        assert_eq!(snapshot.pending, 1);
+        assert_eq!(snapshot.alerts[0].labels["name"], "replica");
+        assert_eq!(snapshot.resolved, Some(vec![]));
+
+        // Without a `for`, an alert fires at once
+        let fired = state.apply(&self::rule(0), Ok(vec![series("primary", 0.0)]), 0, clock());
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].state, AlertState::Firing);
+    }
+
+    #[test]
+    fn test_alerts_round_trip_through_the_state_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = directory.path().to_str().unwrap();
+        let mut state = AlertsState::default();
+        let mut age = series("primary", f64::INFINITY);
+        age.name = Some(BACKUP_AGE_METRIC.to_string());
+        state.apply(&rule(0), Ok(vec![age]), 0, clock());
+        save_state(directory, &state);
+
+        let active = load_state(Some(directory));
+        assert_eq!(active, state.active);
+        assert_eq!(active.values().next().unwrap().alert.value, f64::INFINITY);
+        assert!(load_state(None).is_empty());
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let labels = BTreeMap::from([("name".to_string(), "primary".to_string())]);
+        assert_eq!(
+            render_summary(
+                "{{ $labels.name }}: {{ $value }}s{{ $labels.missing }}",
+                &labels,
+                97200.0
+            ),
+            "primary: 97200s"
+        );
+        assert_eq!(
+            render_summary("No placeholders", &labels, 1.5),
+            "No placeholders"
+        );
+    }
+
+    #[test]
+    fn test_alert_rules_evaluate_catalog_series() {
+        let exposition = Exposition {
+            families: vec![
+                catalog_family(
+                    BACKUP_AGE_METRIC,
+                    "",
+                    vec![
+                        catalog_sample(BACKUP_AGE_METRIC, "primary", None, 3600.0),
+                        catalog_sample(BACKUP_AGE_METRIC, "replica", None, f64::INFINITY),
+                    ],
+                ),
+                catalog_family(
+                    VERIFY_FAILED_METRIC,
+                    "",
+                    vec![catalog_sample(
+                        VERIFY_FAILED_METRIC,
+                        "primary",
+                        Some("20260918080000"),
+                        1.0,
+                    )],
+                ),
+            ],
+            eof: false,
+        };
+        let evaluate = |expr: &str| {
+            into_series(Query::parse(expr).unwrap().evaluate(&exposition).unwrap()).unwrap()
+        };
+
+        let old = evaluate(&format!("{BACKUP_AGE_METRIC} > 26 * 3600"));
+        assert_eq!(old.len(), 1);
+        assert_eq!(old[0].labels["name"], "replica");
+        let failed = evaluate(&format!("{VERIFY_FAILED_METRIC} == 1"));
+        assert_eq!(failed[0].labels["label"], "20260918080000");
+        assert!(
+            into_series(Query::parse("1").unwrap().evaluate(&exposition).unwrap())
+                .unwrap_err()
+                .to_string()
+                .contains("must return a vector")
+        );
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The sinks of the alerts that fire or resolve: a webhook, a file of JSON
+//! lines and syslog. A sink that fails is logged and does not stop the others.
+
+use std::time::Duration;
+
+use super::{Alert, AlertState};
+use crate::configuration::AlertingConfiguration;
+use anyhow::{Result, bail};
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+/// The longest a webhook may take to answer.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The body posted to the webhook.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct WebhookPayload<'a> {
+    pub receiver: &'static str,
+    pub alerts: &'a [Alert],
+}
+
+/// Sends `alerts` to every sink of `alerting`.
+pub async fn deliver(alerting: &AlertingConfiguration, alerts: &[Alert]) {
+    if alerts.is_empty() {
+        return;
+    }
+    for alert in alerts {
+        tracing::info!("{}", describe(alert));
+    }
+    if let Some(url) = &alerting.webhook
+        && let Err(e) = post_webhook(url, alerts).await
+    {
+        tracing::warn!("Failed to post the alerts to {}: {:#}", url, e);
+    }
+    if let Some(path) = &alerting.file
+        && let Err(e) = append_file(path, alerts).await
+    {
+        tracing::warn!("Failed to append the alerts to {}: {:#}", path, e);
+    }
+    if alerting.syslog {
+        for alert in alerts {
+            write_syslog(alert);
+        }
+    }
+}
+
+/// Posts `alerts` as one [`WebhookPayload`].
+pub async fn post_webhook(url: &str, alerts: &[Alert]) -> Result<()> {
+    let response = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()?
+        .post(url)
+        .json(&WebhookPayload {
+            receiver: env!("CARGO_PKG_NAME"),
+            alerts,
+        })
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        bail!("the webhook answered {}", response.status());
+    }
+    Ok(())
+}
+
+/// Appends `alerts` to the file at `path`, one JSON object per line.
+pub async fn append_file(path: &str, alerts: &[Alert]) -> Result<()> {
+    let mut lines = String::new();
+    for alert in alerts {
+        lines.push_str(&serde_json::to_string(alert)?);
+        lines.push('\n');
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(lines.as_bytes()).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+/// A line describing an alert, e.g.
+/// `Alert backup_age{name="primary"} is firing (critical): ...`.
+pub fn describe(alert: &Alert) -> String {
+    let labels = alert
+        .labels
+        .iter()
+        .map(|(key, value)| format!("{key}=\"{value}\""))
+        .collect::<Vec<_>>()
+        .join(",");
+    let state = match alert.state {
+        AlertState::Pending => "pending",
+        AlertState::Firing => "firing",
+        AlertState::Resolved => "resolved",
+    };
+    let mut line = format!(
+        "Alert {}{{{}}} is {} ({})",
+        alert.rule, labels, state, alert.severity
+    );
+    if let Some(summary) = &alert.summary {
+        line.push_str(": ");
+        line.push_str(summary);
+    }
+    line
+}
+
+#[cfg(unix)]
+fn write_syslog(alert: &Alert) {
+    let priority = match (alert.state, alert.severity.as_str()) {
+        (AlertState::Resolved, _) | (_, "info") => libc::LOG_INFO,
+        (_, "critical") => libc::LOG_CRIT,
+        _ => libc::LOG_WARNING,
+    };
+    let Ok(message) = std::ffi::CString::new(describe(alert).replace('\0', "")) else {
+        return;
+    };
+    // The format is a literal, so the message cannot inject conversions
+    unsafe { libc::syslog(libc::LOG_USER | priority, c"%s".as_ptr(), message.as_ptr()) };
+}
+
+#[cfg(not(unix))]
+fn write_syslog(alert: &Alert) {
+    tracing::warn!("{}", describe(alert));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, extract::State, routing::post};
+    use std::collections::BTreeMap;
+    use tokio::sync::mpsc;
+
+    fn alert(state: AlertState) -> Alert {
+        Alert {
+            rule: "backup_age".to_string(),
+            state,
+            severity: "critical".to_string(),
+            labels: BTreeMap::from([("name".to_string(), "primary".to_string())]),
+            value: f64::INFINITY,
+            summary: Some("primary has no valid backup".to_string()),
+            active_since: "2026-03-01 00:00:00+00:00".to_string(),
+            fired_at: Some("2026-03-01 00:05:00+00:00".to_string()),
+            resolved_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_post_webhook() {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<serde_json::Value>();
+        let app = Router::new()
+            .route(
+                "/alerts",
+                post(
+                    |State(sender): State<mpsc::UnboundedSender<serde_json::Value>>,
+                     body: axum::Json<serde_json::Value>| async move {
+                        sender.send(body.0).unwrap();
+                    },
+                ),
+            )
+            .route(
+                "/broken",
+                post(|| async { axum::http::StatusCode::INTERNAL_SERVER_ERROR }),
+            )
+            .with_state(sender);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        post_webhook(
+            &format!("http://{address}/alerts"),
+            &[alert(AlertState::Firing)],
+        )
+        .await
+        .unwrap();
+        let body = receiver.recv().await.unwrap();
+        assert_eq!(body["Receiver"], "pgmoneta-mcp");
+        assert_eq!(body["Alerts"][0]["Rule"], "backup_age");
+        assert_eq!(body["Alerts"][0]["State"], "firing");
+        assert_eq!(body["Alerts"][0]["Value"], "+Inf");
+
+        let err = post_webhook(
+            &format!("http://{address}/broken"),
+            &[alert(AlertState::Firing)],
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("500"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_append_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("alerts.log");
+        let path = path.to_str().unwrap();
+        append_file(path, &[alert(AlertState::Firing)])
+            .await
+            .unwrap();
+        append_file(path, &[alert(AlertState::Resolved)])
+            .await
+            .unwrap();
+
+        let lines = std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Alert>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![alert(AlertState::Firing), alert(AlertState::Resolved)]
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            describe(&alert(AlertState::Firing)),
+            "Alert backup_age{name=\"primary\"} is firing (critical): primary has no valid backup"
+        );
+    }
+}
@@ -75,6 +75,7 @@ const SLASH_COMMANDS: &[&str] = &[
     "/list-models",
     "/model",
     "/reload",
+    "/report",
     "/quit",
     "/tools",
     "/user",
@@ -93,6 +94,8 @@ Basic usage:
   /connect [url]        Connect to [url] or the configured MCP server target
   /disconnect           Disconnect from the current MCP server target
   /reload               Reconnect with the original client URL and model configuration
+  /report [format] [file]
+                        Generate a backup health report (markdown, html or json)
   /user                 User mode (default). Accept natural-language requests
   /developer            Developer mode. Accept <tool-name> {JSON} input and print full JSON responses
   /list-models          List configured LLM profiles as name, model, and provider
@@ -127,6 +130,10 @@ uses the configured URL. If the client is already connected, it disconnects firs
 The prompt and status header show the current MCP target URL, including after a
 failed `/connect` or after `/disconnect`.
 
+`/report` calls the `backup_report` tool for all servers and prints the report.
+The optional format is `markdown` (default), `html` or `json`. With a file name
+the report is written below the `report_directory` of the MCP server instead.
+
 `/reload` disconnects the current session, restores the MCP target URL and
 active `/model` selection from the client configuration loaded at startup, and
 reconnects with that original state.
@@ -177,6 +184,10 @@ enum ClientCommand {
     DeveloperMode,
     Model(Option<String>),
     Tools,
+    Report {
+        format: Option<String>,
+        file: Option<String>,
+    },
     Exit,
     ToolCall {
         name: String,
@@ -674,6 +685,29 @@ fn run_repl(
                                 Err(error) => eprintln!("{}", format_runtime_error(&error)),
                             }
                         }
+                        Ok(ClientCommand::Report { format, file }) => {
+                            let Some(active_client) = client.as_ref() else {
+                                eprintln!("{}", disconnected_message());
+                                continue;
+                            };
+                            let tools = match runtime.block_on(active_client.list_tools()) {
+                                Ok(tools) => tools,
+                                Err(error) => {
+                                    eprintln!("{}", format_runtime_error(&error));
+                                    continue;
+                                }
+                            };
+                            execute_tool_command(
+                                runtime,
+                                active_client,
+                                &mut editor,
+                                &tools,
+                                defaults,
+                                mode,
+                                "backup_report".to_string(),
+                                report_arguments(format, file),
+                            )?;
+                        }
                         Ok(ClientCommand::Exit) => break,
                         Ok(_) => unreachable!("slash commands should not resolve to tool calls"),
                         Err(e) => eprintln!("Error: {e}"),
@@ -784,6 +818,16 @@ fn run_repl(
                         client.is_some(),
                     ),
                     Ok(ClientCommand::Tools) => println!("{}", format_tools(&tools)),
+                    Ok(ClientCommand::Report { format, file }) => execute_tool_command(
+                        runtime,
+                        active_client,
+                        &mut editor,
+                        &tools,
+                        defaults,
+                        mode,
+                        "backup_report".to_string(),
+                        report_arguments(format, file),
+                    )?,
                     Ok(ClientCommand::Exit) => break,
                     Ok(ClientCommand::ToolCall { name, args }) => execute_tool_command(
                         runtime,
@@ -988,6 +1032,17 @@ fn format_metric_tool_result(result: &CallToolResult) -> Result<String> {
         return format_tool_result(result);
     };
 
+    if let Some(samples) = metric_family_samples(&text) {
+        return Ok(match samples.as_slice() {
+            [(_, value)] => value.clone(),
+            _ => samples
+                .into_iter()
+                .map(|(series, value)| format!("{series} {value}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        });
+    }
+
     if text.lines().count() > 1 {
         return Ok(text);
     }
@@ -1034,6 +1089,36 @@ fn format_text_response(text: &str) -> Result<String> {
     }
 }
 
+/// The samples of a metric family response as `name{labels}` and value.
+fn metric_family_samples(text: &str) -> Option<Vec<(String, String)>> {
+    let family = serde_json::from_str::<Value>(text).ok()?;
+    let samples = family.get("Samples")?.as_array()?;
+
+    samples
+        .iter()
+        .map(|sample| {
+            let name = sample.get("Name")?.as_str()?;
+            let labels = sample
+                .get("Labels")?
+                .as_object()?
+                .iter()
+                .map(|(key, value)| Some(format!("{key}={:?}", value.as_str()?)))
+                .collect::<Option<Vec<_>>>()?;
+            let series = if labels.is_empty() {
+                name.to_string()
+            } else {
+                format!("{name}{{{}}}", labels.join(","))
+            };
+            let value = match sample.get("Value")? {
+                Value::Number(number) => format!("{}", number.as_f64()?),
+                Value::String(text) => text.clone(),
+                _ => return None,
+            };
+            Some((series, value))
+        })
+        .collect()
+}
+
 fn metric_sample_value(sample: &str) -> Option<&str> {
     let mut in_braces = false;
 
@@ -1303,6 +1388,7 @@ fn parse_slash_command(
             }
         }
         "/tools" => parse_no_argument_slash_command(input, command_name, ClientCommand::Tools),
+        "/report" => parse_report_command(input),
         "/exit" | "/quit" => {
             parse_no_argument_slash_command(input, command_name, ClientCommand::Exit)
         }
@@ -1352,6 +1438,48 @@ fn parse_connect_command(input: &str) -> Result<ClientCommand> {
     Ok(ClientCommand::Connect(Some(url.to_string())))
 }
 
+fn parse_report_command(input: &str) -> Result<ClientCommand> {
+    let Some(arguments) = input.strip_prefix("/report") else {
+        bail!("Missing report command");
+    };
+    let arguments: Vec<&str> = arguments.split_whitespace().collect();
+
+    match arguments.as_slice() {
+        [] => Ok(ClientCommand::Report {
+            format: None,
+            file: None,
+        }),
+        [format] | [format, _]
+            if !matches!(
+                format.to_ascii_lowercase().as_str(),
+                "markdown" | "html" | "json"
+            ) =>
+        {
+            bail!("Unsupported report format '{format}'. Supported values: markdown, html, json")
+        }
+        [format] => Ok(ClientCommand::Report {
+            format: Some(format.to_ascii_lowercase()),
+            file: None,
+        }),
+        [format, file] => Ok(ClientCommand::Report {
+            format: Some(format.to_ascii_lowercase()),
+            file: Some(file.to_string()),
+        }),
+        _ => bail!("Usage: /report [format] [file]"),
+    }
+}
+
+fn report_arguments(format: Option<String>, file: Option<String>) -> HashMap<String, Value> {
+    let mut args = HashMap::new();
+    if let Some(format) = format {
+        args.insert("format".to_string(), Value::String(format));
+    }
+    if let Some(file) = file {
+        args.insert("file".to_string(), Value::String(file));
+    }
+    args
+}
+
 fn parse_model_command(input: &str, available_models: &HashSet<String>) -> Result<ClientCommand> {
     let Some(name) = input.strip_prefix(MODEL_COMMAND_PREFIX) else {
         bail!("Missing model command");
@@ -2521,6 +2649,38 @@ mod tests {
         assert_eq!(err.to_string(), "Usage: /help");
     }
 
+    #[test]
+    fn test_parse_report_command() {
+        assert_eq!(
+            parse_report_command("/report").unwrap(),
+            ClientCommand::Report {
+                format: None,
+                file: None,
+            }
+        );
+        assert_eq!(
+            parse_report_command("/report HTML daily.html").unwrap(),
+            ClientCommand::Report {
+                format: Some("html".to_string()),
+                file: Some("daily.html".to_string()),
+            }
+        );
+        assert_eq!(
+            parse_report_command("/report pdf").unwrap_err().to_string(),
+            "Unsupported report format 'pdf'. Supported values: markdown, html, json"
+        );
+        assert_eq!(
+            parse_report_command("/report json a.json b.json")
+                .unwrap_err()
+                .to_string(),
+            "Usage: /report [format] [file]"
+        );
+        assert_eq!(
+            report_arguments(Some("json".to_string()), None),
+            HashMap::from([("format".to_string(), json!("json"))])
+        );
+    }
+
     #[test]
     fn test_parse_connect_command_rejects_extra_arguments() {
         let err = parse_connect_command("/connect http://localhost:9000/mcp now").unwrap_err();
@@ -2930,6 +3090,7 @@ mod tests {
                 "/model",
                 "/quit",
                 "/reload",
+                "/report",
                 "/tools",
                 "/user",
             ]
@@ -3214,6 +3375,28 @@ mod tests {
         assert_eq!(format_metric_tool_result(&result).unwrap(), "1");
     }
 
+    #[test]
+    fn test_format_metric_tool_result_formats_metric_family() {
+        let single = CallToolResult::success(vec![
+            RawContent::text(
+                r#"{"Name":"pgmoneta_version","Type":"gauge","Samples":[{"Name":"pgmoneta_version","Labels":{"version":"0.19.0"},"Value":1.0}]}"#,
+            )
+            .no_annotation(),
+        ]);
+        assert_eq!(format_metric_tool_result(&single).unwrap(), "1");
+
+        let multiple = CallToolResult::success(vec![
+            RawContent::text(
+                r#"{"Name":"latency","Type":"histogram","Samples":[{"Name":"latency_bucket","Labels":{"le":"+Inf"},"Value":4.0},{"Name":"latency_sum","Labels":{},"Value":1.25}]}"#,
+            )
+            .no_annotation(),
+        ]);
+        assert_eq!(
+            format_metric_tool_result(&multiple).unwrap(),
+            "latency_bucket{le=\"+Inf\"} 4\nlatency_sum 1.25"
+        );
+    }
+
     #[test]
     fn test_format_metric_tool_result_preserves_multiple_metric_lines() {
         let result = CallToolResult::success(vec![
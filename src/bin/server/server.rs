@@ -14,15 +14,27 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use clap::Parser;
+use pgmoneta_mcp::activity;
+use pgmoneta_mcp::alerts;
 use pgmoneta_mcp::configuration;
 use pgmoneta_mcp::handler::PgmonetaHandler;
+use pgmoneta_mcp::handler::prompts;
+use pgmoneta_mcp::handler::runbooks;
+use pgmoneta_mcp::handler::sla;
+use pgmoneta_mcp::handler::verify;
+use pgmoneta_mcp::health;
+use pgmoneta_mcp::history;
 use pgmoneta_mcp::logging::Logger;
+use pgmoneta_mcp::otel;
+use pgmoneta_mcp::scheduler;
 use pgmoneta_mcp::telemetry;
 use pgmoneta_mcp::utils::Utility;
+use pgmoneta_mcp::webhooks;
 use rmcp::transport::streamable_http_server::{
     StreamableHttpServerConfig, StreamableHttpService, session::local::LocalSessionManager,
 };
 use std::io::{self, IsTerminal};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio_util::sync::CancellationToken;
 use tower_http::cors::{Any, CorsLayer};
@@ -66,6 +78,7 @@ async fn main() -> anyhow::Result<()> {
     let config = configuration::load_configuration(&args.conf, &args.users)?;
     let address = format!("{BIND_ADDRESS}:{}", config.pgmoneta_mcp.port);
 
+    let tracer_provider = config.opentelemetry.as_ref().map(otel::init).transpose()?;
     let _guard = Logger::init(
         config.pgmoneta_mcp.log_level.as_str(),
         config.pgmoneta_mcp.log_type.as_str(),
@@ -73,11 +86,33 @@ async fn main() -> anyhow::Result<()> {
         config.pgmoneta_mcp.log_path.as_str(),
         config.pgmoneta_mcp.log_mode.as_str(),
         config.pgmoneta_mcp.log_rotation_age.as_str(),
+        tracer_provider.as_ref().map(otel::tracer),
     );
 
+    let prompt_count = prompts::init(
+        config
+            .pgmoneta_mcp
+            .prompts_directory
+            .as_deref()
+            .map(std::path::Path::new),
+    )?;
+    tracing::info!("Loaded {prompt_count} MCP prompts");
+    let runbook_count = runbooks::init(
+        config
+            .pgmoneta_mcp
+            .runbooks_directory
+            .as_deref()
+            .map(std::path::Path::new),
+    )?;
+    tracing::info!("Loaded {runbook_count} runbooks");
+    let sla_count = sla::init();
+    tracing::info!("Loaded the restore and verify durations of {sla_count} servers");
+    let verified_count = verify::init();
+    tracing::info!("Loaded the verification history of {verified_count} servers");
+
     let shutdown_token = CancellationToken::new();
     let handler = StreamableHttpService::new(
-        || Ok(PgmonetaHandler::new()),
+        || Ok(PgmonetaHandler::for_session()),
         LocalSessionManager::default().into(),
         StreamableHttpServerConfig::default().with_cancellation_token(shutdown_token.child_token()),
     );
@@ -92,7 +127,22 @@ async fn main() -> anyhow::Result<()> {
 
     let router = axum::Router::new()
         .route("/metrics", axum::routing::get(telemetry::metrics_handler))
-        .nest_service("/mcp", handler)
+        .route("/healthz", axum::routing::get(health::healthz_handler))
+        .route(
+            "/readyz",
+            axum::routing::get(health::readyz_handler)
+                .with_state(Arc::new(health::Readiness::new())),
+        )
+        .route("/version", axum::routing::get(health::version_handler))
+        .route(
+            "/events",
+            axum::routing::get(activity::events_handler).with_state(shutdown_token.child_token()),
+        )
+        .merge(
+            axum::Router::new()
+                .nest_service("/mcp", handler)
+                .layer(axum::middleware::from_fn(otel::mcp_middleware)),
+        )
         .layer(axum::middleware::from_fn_with_state(
             metrics.clone(),
             telemetry::metrics_middleware,
@@ -105,6 +155,11 @@ async fn main() -> anyhow::Result<()> {
         .set(config)
         .expect("CONFIG already initialized");
 
+    webhooks::start(shutdown_token.child_token());
+    scheduler::start(shutdown_token.child_token());
+    history::start(shutdown_token.child_token());
+    alerts::start(shutdown_token.child_token());
+
     tracing::info!("Starting MCP server at {address}");
 
     let shutdown_signal = {
@@ -121,5 +176,10 @@ async fn main() -> anyhow::Result<()> {
     let _ = axum::serve(tcp_listener, router)
         .with_graceful_shutdown(shutdown_signal)
         .await;
+
+    if let Some(provider) = tracer_provider {
+        // Flushing the pending spans blocks on the exporter
+        let _ = tokio::task::spawn_blocking(move || provider.shutdown()).await;
+    }
     Ok(())
 }
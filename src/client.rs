@@ -31,10 +31,13 @@ mod shutdown;
 mod status;
 mod verify;
 
+use super::activity::{self, Activity, ActivityKind};
 use super::compression::CompressionUtil;
 use super::configuration::{CONFIG, Configuration};
 use super::constant::*;
+use super::otel;
 use super::security::SecurityUtil;
+use super::telemetry::{self, MessageDirection};
 use anyhow::anyhow;
 use chrono::Local;
 use serde::Serialize;
@@ -44,6 +47,7 @@ use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio::time::timeout;
+use tracing::Instrument;
 
 #[cfg(test)]
 fn parse_encryption(encryption: &str) -> anyhow::Result<u8> {
@@ -184,14 +188,17 @@ impl PgmonetaClient {
     /// password, decrypts it using the master key, and initiates the connection.
     ///
     /// # Arguments
+    /// * `config` - The configuration holding the pgmoneta address and the admins.
+    /// * `security_util` - The master key the stored password is decrypted with.
     /// * `username` - The admin username requesting the connection.
     ///
     /// # Returns
     /// An authenticated `TcpStream` ready for read/write operations.
-    async fn connect_to_server(username: &str) -> anyhow::Result<TcpStream> {
-        let config = CONFIG.get().expect("Configuration should be enabled");
-        let security_util = SecurityUtil::new();
-
+    async fn connect_to_server(
+        config: &Configuration,
+        security_util: &SecurityUtil,
+        username: &str,
+    ) -> anyhow::Result<TcpStream> {
         Self::ensure_admin_user(config, username)?;
 
         let password_encrypted = config
@@ -211,14 +218,22 @@ impl PgmonetaClient {
                 )
             })?;
         let password = String::from_utf8(decrypted_password)?;
-        let stream = SecurityUtil::connect_to_server(
+        SecurityUtil::connect_to_server(
             &config.pgmoneta.host,
             config.pgmoneta.port,
             username,
             &password,
         )
-        .await?;
-        Ok(stream)
+        .await
+        .inspect_err(|e| {
+            activity::publish(
+                Activity::new(ActivityKind::ConnectionError)
+                    .username(username)
+                    .detail("Host", config.pgmoneta.host.as_str())
+                    .detail("Port", config.pgmoneta.port)
+                    .detail("Error", format!("{e:#}")),
+            );
+        })
     }
 
     /// Writes a management request to the provided stream.
@@ -231,27 +246,48 @@ impl PgmonetaClient {
         stream: &mut W,
         compression: u8,
         encryption: u8,
+        security_util: &SecurityUtil,
     ) -> anyhow::Result<()>
     where
         W: tokio::io::AsyncWrite + Unpin,
     {
-        let security_util = SecurityUtil::new();
+        let encode = tracing::info_span!(
+            target: otel::SPAN_TARGET,
+            "pgmoneta.encode",
+            pgmoneta.compression = compression,
+            pgmoneta.encryption = encryption,
+        );
+        let payload = encode.in_scope(|| -> anyhow::Result<String> {
+            if compression != Compression::NONE || encryption != Encryption::NONE {
+                let mut data = request_str.as_bytes().to_vec();
 
-        let payload = if compression != Compression::NONE || encryption != Encryption::NONE {
-            let mut data = request_str.as_bytes().to_vec();
+                if compression != Compression::NONE {
+                    data = tracing::info_span!(target: otel::SPAN_TARGET, "pgmoneta.compress")
+                        .in_scope(|| CompressionUtil::compress(&data, compression))?;
+                }
+                telemetry::metrics().record_pgmoneta_message(
+                    MessageDirection::Request,
+                    request_str.len(),
+                    data.len(),
+                );
 
-            if compression != Compression::NONE {
-                data = CompressionUtil::compress(&data, compression)?;
-            }
+                if encryption != Encryption::NONE {
+                    data = tracing::info_span!(target: otel::SPAN_TARGET, "pgmoneta.encrypt")
+                        .in_scope(|| {
+                            security_util.encrypt_text_aes_gcm_bundle(&data, encryption)
+                        })?;
+                }
 
-            if encryption != Encryption::NONE {
-                data = security_util.encrypt_text_aes_gcm_bundle(&data, encryption)?;
+                security_util.base64_encode(&data)
+            } else {
+                telemetry::metrics().record_pgmoneta_message(
+                    MessageDirection::Request,
+                    request_str.len(),
+                    request_str.len(),
+                );
+                Ok(request_str.to_string())
             }
-
-            security_util.base64_encode(&data)?
-        } else {
-            request_str.to_string()
-        };
+        })?;
 
         if payload.len() > u32::MAX as usize {
             return Err(anyhow!(
@@ -285,7 +321,10 @@ impl PgmonetaClient {
         Ok(())
     }
 
-    async fn read_response<R>(stream: &mut R) -> anyhow::Result<String>
+    async fn read_response<R>(
+        stream: &mut R,
+        security_util: &SecurityUtil,
+    ) -> anyhow::Result<String>
     where
         R: tokio::io::AsyncRead + Unpin,
     {
@@ -367,7 +406,13 @@ impl PgmonetaClient {
             buf.pop();
         }
 
-        let security_util = SecurityUtil::new();
+        let _decode = tracing::info_span!(
+            target: otel::SPAN_TARGET,
+            "pgmoneta.decode",
+            pgmoneta.compression = compression,
+            pgmoneta.encryption = encryption,
+        )
+        .entered();
 
         if compression != Compression::NONE || encryption != Encryption::NONE {
             let secure_parse = || -> anyhow::Result<String> {
@@ -375,13 +420,24 @@ impl PgmonetaClient {
                 let mut decrypted = data.clone();
 
                 if encryption != Encryption::NONE {
-                    decrypted = security_util.decrypt_text_aes_gcm_bundle(&data, encryption)?;
+                    decrypted = tracing::info_span!(target: otel::SPAN_TARGET, "pgmoneta.decrypt")
+                        .in_scope(|| {
+                            security_util.decrypt_text_aes_gcm_bundle(&data, encryption)
+                        })?;
                 }
 
+                let compressed = decrypted.len();
                 let mut decompressed = decrypted;
                 if compression != Compression::NONE {
-                    decompressed = CompressionUtil::decompress(&decompressed, compression)?;
+                    decompressed =
+                        tracing::info_span!(target: otel::SPAN_TARGET, "pgmoneta.decompress")
+                            .in_scope(|| CompressionUtil::decompress(&decompressed, compression))?;
                 }
+                telemetry::metrics().record_pgmoneta_message(
+                    MessageDirection::Response,
+                    decompressed.len(),
+                    compressed,
+                );
 
                 let response =
                     String::from_utf8(decompressed).map_err(|e| anyhow!("Invalid UTF-8: {}", e))?;
@@ -399,6 +455,11 @@ impl PgmonetaClient {
                 }
             }
         } else {
+            telemetry::metrics().record_pgmoneta_message(
+                MessageDirection::Response,
+                buf.len(),
+                buf.len(),
+            );
             let response_str =
                 String::from_utf8(buf).map_err(|e| anyhow!("Invalid UTF-8: {}", e))?;
             Ok(response_str)
@@ -415,6 +476,28 @@ impl PgmonetaClient {
     /// # Returns
     /// The raw string response from the pgmoneta server.
     async fn forward_request<R>(username: &str, command: u32, request: R) -> anyhow::Result<String>
+    where
+        R: Serialize + Clone + Debug,
+    {
+        let config = CONFIG.get().expect("Configuration should be enabled");
+        Self::exchange(config, &SecurityUtil::new(), username, command, request).await
+    }
+
+    /// Sends a request to the pgmoneta server of a configuration and awaits its response.
+    ///
+    /// # Arguments
+    /// * `config` - The configuration holding the pgmoneta address and the admins.
+    /// * `security_util` - The master key of the stored passwords and the payloads.
+    /// * `username` - The admin username making the request.
+    /// * `command` - The numeric command code (e.g., `Command::INFO`).
+    /// * `request` - The specific request payload object.
+    async fn exchange<R>(
+        config: &Configuration,
+        security_util: &SecurityUtil,
+        username: &str,
+        command: u32,
+        request: R,
+    ) -> anyhow::Result<String>
     where
         R: Serialize + Clone + Debug,
     {
@@ -422,14 +505,58 @@ impl PgmonetaClient {
         let compression = header.compression;
         let encryption = header.encryption;
         let request = PgmonetaRequest { request, header };
+        let span = tracing::info_span!(
+            target: otel::SPAN_TARGET,
+            "pgmoneta.request",
+            otel.kind = "client",
+            pgmoneta.command = command,
+            pgmoneta.error_code = tracing::field::Empty,
+            otel.status_code = tracing::field::Empty,
+        );
 
-        let mut stream = Self::connect_to_server(username).await?;
-        tracing::debug!(username = username, "Connected to server");
+        let response = async {
+            let mut stream = Self::connect_to_server(config, security_util, username).await?;
+            tracing::debug!(username = username, "Connected to server");
+
+            let request_str = serde_json::to_string(&request)?;
+            Self::write_request(
+                &request_str,
+                &mut stream,
+                compression,
+                encryption,
+                security_util,
+            )
+            .await?;
+            tracing::debug!(username = username, request = ?request, "Sent request to server");
+            Self::read_response(&mut stream, security_util).await
+        }
+        .instrument(span.clone())
+        .await;
 
-        let request_str = serde_json::to_string(&request)?;
-        Self::write_request(&request_str, &mut stream, compression, encryption).await?;
-        tracing::debug!(username = username, request = ?request, "Sent request to server");
-        Self::read_response(&mut stream).await
+        let error_code = response.as_deref().ok().and_then(Self::error_code);
+        if let Some(code) = error_code {
+            telemetry::record_pgmoneta_error(code);
+            span.record("pgmoneta.error_code", code);
+        }
+        span.record(
+            "otel.status_code",
+            if response.is_ok() && error_code.is_none() {
+                "ok"
+            } else {
+                "error"
+            },
+        );
+        response
+    }
+
+    /// The error code of the outcome of a pgmoneta response, if it failed.
+    fn error_code(response: &str) -> Option<u32> {
+        serde_json::from_str::<serde_json::Value>(response)
+            .ok()?
+            .get(MANAGEMENT_CATEGORY_OUTCOME)?
+            .get("Error")?
+            .as_u64()
+            .and_then(|code| u32::try_from(code).ok())
     }
 }
 
@@ -453,6 +580,11 @@ mod tests {
                     log_line_prefix: "%Y-%m-%d %H:%M:%S".to_string(),
                     log_mode: "append".to_string(),
                     log_rotation_age: "0".to_string(),
+                    timezone: "local".to_string(),
+                    prompts_directory: None,
+                    runbooks_directory: None,
+                    report_directory: None,
+                    state_directory: None,
                 },
                 pgmoneta: PgmonetaConfiguration {
                     host: "127.0.0.1".to_string(),
@@ -463,11 +595,33 @@ mod tests {
                 },
                 admins: HashMap::new(),
                 llm: None,
+                sla: Vec::new(),
+                schedules: Vec::new(),
+                history: None,
+                alerting: None,
+                webhooks: None,
+                opentelemetry: None,
+                metrics: None,
             };
             let _ = CONFIG.set(config);
         });
     }
 
+    #[test]
+    fn test_error_code() {
+        assert_eq!(
+            PgmonetaClient::error_code(
+                r#"{"Outcome": {"Status": false, "Command": 1, "Error": 111}}"#
+            ),
+            Some(111)
+        );
+        assert_eq!(
+            PgmonetaClient::error_code(r#"{"Outcome": {"Status": true, "Command": 1}}"#),
+            None
+        );
+        assert_eq!(PgmonetaClient::error_code("not json"), None);
+    }
+
     #[test]
     fn test_build_request_header() {
         init_test_config();
@@ -564,6 +718,7 @@ mod tests {
             &mut buffer,
             Compression::NONE,
             Encryption::NONE,
+            &SecurityUtil::new(),
         )
         .await
         .unwrap();
@@ -596,7 +751,7 @@ mod tests {
         buffer.extend_from_slice(response_str.as_bytes());
 
         let mut cursor = Cursor::new(buffer);
-        let result = PgmonetaClient::read_response(&mut cursor)
+        let result = PgmonetaClient::read_response(&mut cursor, &SecurityUtil::new())
             .await
             .expect("Read should succeed");
 
@@ -87,6 +87,11 @@ mod tests {
                 log_line_prefix: "%Y-%m-%d %H:%M:%S".to_string(),
                 log_mode: "append".to_string(),
                 log_rotation_age: "0".to_string(),
+                timezone: "local".to_string(),
+                prompts_directory: None,
+                runbooks_directory: None,
+                report_directory: None,
+                state_directory: None,
             },
             pgmoneta: PgmonetaConfiguration {
                 host: host.to_string(),
@@ -97,6 +102,13 @@ mod tests {
             },
             admins: HashMap::new(),
             llm: None,
+            sla: Vec::new(),
+            schedules: Vec::new(),
+            history: None,
+            alerting: None,
+            webhooks: None,
+            opentelemetry: None,
+            metrics: None,
         }
     }
 
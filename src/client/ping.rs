@@ -14,7 +14,10 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use super::PgmonetaClient;
-use crate::constant::Command;
+use crate::configuration::Configuration;
+use crate::constant::{Command, ManagementError};
+use crate::security::SecurityUtil;
+use anyhow::anyhow;
 use serde::Serialize;
 
 #[derive(Serialize, Clone, Debug)]
@@ -24,4 +27,29 @@ impl PgmonetaClient {
     pub async fn request_ping(username: &str) -> anyhow::Result<String> {
         Self::forward_request(username, Command::PING, PingRequest {}).await
     }
+
+    /// Pings the pgmoneta server of a configuration, for the readiness probe.
+    ///
+    /// Fails if pgmoneta cannot be reached or answers with an error.
+    pub async fn ping(
+        config: &Configuration,
+        security_util: &SecurityUtil,
+        username: &str,
+    ) -> anyhow::Result<()> {
+        let response = Self::exchange(
+            config,
+            security_util,
+            username,
+            Command::PING,
+            PingRequest {},
+        )
+        .await?;
+        match Self::error_code(&response) {
+            Some(code) => Err(anyhow!(
+                "pgmoneta answered with error {code}: {}",
+                ManagementError::translate_error_enum(code)
+            )),
+            None => Ok(()),
+        }
+    }
 }
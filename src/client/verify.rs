@@ -26,20 +26,27 @@ struct VerifyRequest {
     backup: String,
     #[serde(rename = "Directory")]
     directory: String,
+    #[serde(rename = "Files")]
+    files: String,
 }
 
 impl PgmonetaClient {
     /// Sends a verify command for a specific backup on a given server.
+    ///
+    /// `files` is `VerifyFiles::FAILED` to report the failed files only, or
+    /// `VerifyFiles::ALL` to report every verified file.
     pub async fn request_verify(
         username: &str,
         server: &str,
         backup: &str,
         directory: &str,
+        files: &str,
     ) -> anyhow::Result<String> {
         let verify_request = VerifyRequest {
             server: server.to_string(),
             backup: backup.to_string(),
             directory: directory.to_string(),
+            files: files.to_string(),
         };
         Self::forward_request(username, Command::VERIFY, verify_request).await
     }
@@ -14,7 +14,9 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use super::constant::{LogLevel, LogType};
+use crate::handler::events::EventKind;
 use anyhow::anyhow;
+use chrono::FixedOffset;
 use config::{Config, FileFormat};
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
@@ -26,6 +28,39 @@ use std::collections::HashMap;
 /// globally throughout the application lifecycle.
 pub static CONFIG: OnceCell<Configuration> = OnceCell::new();
 
+/// The name of the default SLA section, and prefix of the per server sections.
+pub const SLA_SECTION: &str = "sla";
+
+/// The prefix of the per server backup schedule sections.
+pub const SCHEDULE_SECTION: &str = "schedule";
+
+/// The name of the metrics history section.
+pub const HISTORY_SECTION: &str = "history";
+
+/// The prefix of the alert rule sections.
+pub const ALERT_SECTION: &str = "alert";
+
+/// The name of the alerting section.
+pub const ALERTING_SECTION: &str = "alerting";
+
+/// The severities of an alert rule.
+pub const ALERT_SEVERITIES: [&str; 3] = ["info", "warning", "critical"];
+
+/// The prefix of the webhook endpoint sections.
+pub const WEBHOOK_SECTION: &str = "webhook";
+
+/// The name of the webhooks section.
+pub const WEBHOOKS_SECTION: &str = "webhooks";
+
+/// The name of the OpenTelemetry section.
+pub const OPENTELEMETRY_SECTION: &str = "opentelemetry";
+
+/// The name of the metrics section.
+pub const METRICS_SECTION: &str = "metrics";
+
+/// The directory scheduled verifications restore into unless configured.
+pub const DEFAULT_VERIFY_DIRECTORY: &str = "/tmp";
+
 /// Type alias representing the parsed user configuration.
 ///
 /// Maps a section name (e.g., username) to a dictionary of properties (e.g., password).
@@ -46,6 +81,29 @@ pub struct Configuration {
     pub admins: HashMap<String, String>,
     /// Optional configuration for the local LLM integration.
     pub llm: Option<LlmConfiguration>,
+    /// Backup SLA policies from the `[sla]` and `[sla:<pattern>]` sections.
+    #[serde(skip)]
+    pub sla: Vec<SlaPolicy>,
+    /// Backup schedules from the `[schedule:<server>]` sections.
+    #[serde(skip)]
+    pub schedules: Vec<ScheduleConfiguration>,
+    /// The metrics history from the `[history]` section, if any.
+    #[serde(skip)]
+    pub history: Option<HistoryConfiguration>,
+    /// The alert rules and sinks from the `[alert:<name>]` and `[alerting]`
+    /// sections, if any.
+    #[serde(skip)]
+    pub alerting: Option<AlertingConfiguration>,
+    /// The webhook endpoints of the backup events from the `[webhook:<name>]`
+    /// and `[webhooks]` sections, if any.
+    #[serde(skip)]
+    pub webhooks: Option<WebhooksConfiguration>,
+    /// The export of the traces from the `[opentelemetry]` section, if any.
+    #[serde(skip)]
+    pub opentelemetry: Option<OpenTelemetryConfiguration>,
+    /// The protection of `/metrics` from the `[metrics]` section, if any.
+    #[serde(skip)]
+    pub metrics: Option<MetricsConfiguration>,
 }
 
 /// Configuration properties for connecting to the remote `pgmoneta` instance.
@@ -108,6 +166,352 @@ pub struct PgmonetaMcpConfiguration {
     /// Default: `0`.
     #[serde(default = "default_log_rotation_age")]
     pub log_rotation_age: String,
+    /// The timezone used to interpret human readable times and backup identifiers.
+    ///
+    /// Supported values are `local`, `UTC` and fixed offsets such as `+02:00`.
+    /// Default: `local`.
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// A directory of additional MCP prompt templates (`*.yaml`, `*.yml`).
+    ///
+    /// Templates with the name of a built-in prompt replace it. Default: none.
+    #[serde(default)]
+    pub prompts_directory: Option<String>,
+    /// A directory of additional runbooks (`*.yaml`, `*.yml`), each exposed as
+    /// an MCP tool.
+    ///
+    /// Runbooks with the name of a built-in runbook replace it. Default: none.
+    #[serde(default)]
+    pub runbooks_directory: Option<String>,
+    /// The directory `backup_report` is allowed to write report files to.
+    ///
+    /// Reports can only be returned inline when unset. Default: none.
+    #[serde(default)]
+    pub report_directory: Option<String>,
+    /// The directory for state kept across restarts, such as restore and
+    /// verify durations.
+    ///
+    /// The state is only kept in memory when unset. Default: none.
+    #[serde(default)]
+    pub state_directory: Option<String>,
+}
+
+/// A backup service level agreement.
+///
+/// This corresponds to the optional `[sla]` section, which applies to every
+/// server, and the `[sla:<pattern>]` sections, which apply to the servers
+/// matching the pattern. Settings missing from a `[sla:<pattern>]` section are
+/// taken from `[sla]`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct SlaPolicy {
+    /// The server name or glob pattern (`*`, `?`), or `None` for `[sla]`.
+    pub pattern: Option<String>,
+    /// The maximum age of the newest valid backup, in hours.
+    pub max_backup_age: Option<u32>,
+    /// The maximum duration of a restore, in minutes.
+    pub max_restore_time: Option<u32>,
+    /// The percentage of a limit from which a server is reported as `warn`.
+    pub warning_threshold: Option<u32>,
+}
+
+impl SlaPolicy {
+    /// The section name of the policy, e.g. `sla:prod-*`.
+    pub fn section(&self) -> String {
+        match &self.pattern {
+            Some(pattern) => format!("{SLA_SECTION}:{pattern}"),
+            None => SLA_SECTION.to_string(),
+        }
+    }
+}
+
+/// A scheduled backup of one server.
+///
+/// This corresponds to an optional `[schedule:<server>]` section. Each run
+/// takes a full backup, then optionally verifies and archives it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ScheduleConfiguration {
+    /// The pgmoneta server to back up.
+    pub server: String,
+    /// The cron expression (minute hour day month weekday) of the runs.
+    pub cron: String,
+    /// Whether to verify the backup once it is taken.
+    pub verify: bool,
+    /// The directory the verification restores into.
+    pub verify_directory: String,
+    /// The directory to archive the backup to, if any.
+    pub archive_directory: Option<String>,
+    /// The maximum random delay of a run, in seconds.
+    pub jitter: u32,
+    /// The admin the runs are made as; the first configured admin otherwise.
+    pub username: Option<String>,
+}
+
+impl ScheduleConfiguration {
+    /// The section name of the schedule, e.g. `schedule:primary`.
+    pub fn section(&self) -> String {
+        format!("{SCHEDULE_SECTION}:{}", self.server)
+    }
+}
+
+/// The local history of the pgmoneta metrics.
+///
+/// This corresponds to the optional `[history]` section. The metrics endpoint
+/// is scraped every `interval` seconds into an on-disk store, whose samples
+/// are downsampled after `raw_retention` and dropped after `retention`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HistoryConfiguration {
+    /// The directory of the store; `history` in `state_directory` by default.
+    pub directory: String,
+    /// The seconds between two scrapes. Default: 60.
+    pub interval: u32,
+    /// The hours samples are kept at full resolution. Default: 48.
+    pub raw_retention: u32,
+    /// The seconds of the downsampled resolution. Default: 300.
+    pub resolution: u32,
+    /// The days samples are kept. Default: 30.
+    pub retention: u32,
+    /// The admin the scrapes are made as; the first configured admin otherwise.
+    pub username: Option<String>,
+}
+
+impl Default for HistoryConfiguration {
+    fn default() -> Self {
+        Self {
+            directory: String::new(),
+            interval: 60,
+            raw_retention: 48,
+            resolution: 300,
+            retention: 30,
+            username: None,
+        }
+    }
+}
+
+/// An alert rule.
+///
+/// This corresponds to an optional `[alert:<name>]` section. The alert is
+/// pending for every series the expression returns, and fires once the series
+/// has been returned for `for` seconds.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AlertRule {
+    /// The name of the rule.
+    pub name: String,
+    /// The `query_metrics` expression.
+    pub expr: String,
+    /// The seconds a series must be returned before the alert fires. Default: 0.
+    pub for_seconds: u64,
+    /// One of [`ALERT_SEVERITIES`]. Default: `warning`.
+    pub severity: String,
+    /// The summary, with `{{ $labels.<label> }}` and `{{ $value }}` placeholders.
+    pub summary: Option<String>,
+}
+
+impl AlertRule {
+    /// The section name of the rule, e.g. `alert:backup_age`.
+    pub fn section(&self) -> String {
+        format!("{ALERT_SECTION}:{}", self.name)
+    }
+}
+
+/// The evaluation of the alert rules and the sinks they notify.
+///
+/// This corresponds to the optional `[alerting]` section, together with the
+/// `[alert:<name>]` sections.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AlertingConfiguration {
+    /// The rules, sorted by name.
+    pub rules: Vec<AlertRule>,
+    /// The seconds between two evaluations. Default: 60.
+    pub interval: u32,
+    /// The URL notifications are posted to, if any.
+    pub webhook: Option<String>,
+    /// The file notifications are appended to as JSON lines, if any.
+    pub file: Option<String>,
+    /// Whether notifications are sent to syslog. Default: off.
+    pub syslog: bool,
+    /// The admin the evaluations are made as; the first configured admin otherwise.
+    pub username: Option<String>,
+}
+
+impl Default for AlertingConfiguration {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            interval: 60,
+            webhook: None,
+            file: None,
+            syslog: false,
+            username: None,
+        }
+    }
+}
+
+/// A webhook endpoint of the backup events.
+///
+/// This corresponds to an optional `[webhook:<name>]` section. The events are
+/// posted as JSON, signed with HMAC-SHA256 using the secret.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct WebhookEndpoint {
+    /// The name of the endpoint.
+    pub name: String,
+    /// The http or https URL the events are posted to.
+    pub url: String,
+    /// The key of the signatures.
+    #[serde(skip)]
+    pub secret: String,
+    /// The types of events posted; every type when empty.
+    pub events: Vec<EventKind>,
+    /// The servers whose events are posted; every server when empty.
+    pub servers: Vec<String>,
+}
+
+impl WebhookEndpoint {
+    /// The section name of the endpoint, e.g. `webhook:chatops`.
+    pub fn section(&self) -> String {
+        format!("{WEBHOOK_SECTION}:{}", self.name)
+    }
+
+    /// Whether the endpoint takes events of type `kind` of `server`.
+    pub fn accepts(&self, kind: EventKind, server: &str) -> bool {
+        (self.events.is_empty() || self.events.contains(&kind))
+            && (self.servers.is_empty() || self.servers.iter().any(|s| s == server))
+    }
+}
+
+/// The delivery of the backup events to the webhook endpoints.
+///
+/// This corresponds to the optional `[webhooks]` section, together with the
+/// `[webhook:<name>]` sections.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct WebhooksConfiguration {
+    /// The endpoints, sorted by name.
+    pub endpoints: Vec<WebhookEndpoint>,
+    /// The seconds between two polls of the backup catalog. Default: 60.
+    pub catalog_interval: u32,
+    /// The retries of a failed delivery. Default: 5.
+    pub retries: u32,
+    /// The seconds before the first retry, doubled for every further retry. Default: 10.
+    pub retry_interval: u32,
+    /// The events queued for an endpoint at most; further events go to the
+    /// dead-letter file. Default: 1000.
+    pub queue_size: usize,
+    /// The file the undeliverable events are appended to as JSON lines;
+    /// `dead_letter.jsonl` in `state_directory` by default.
+    pub dead_letter: Option<String>,
+    /// The admin the catalog is polled as; the first configured admin otherwise.
+    pub username: Option<String>,
+}
+
+impl Default for WebhooksConfiguration {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            catalog_interval: 60,
+            retries: 5,
+            retry_interval: 10,
+            queue_size: 1000,
+            dead_letter: None,
+            username: None,
+        }
+    }
+}
+
+/// How the traces are exported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceExporter {
+    /// OTLP over HTTP with protobuf payloads.
+    OtlpHttp,
+    /// OTLP over gRPC.
+    OtlpGrpc,
+    /// OTLP JSON lines appended to a file.
+    File,
+}
+
+impl TraceExporter {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TraceExporter::OtlpHttp => "otlp_http",
+            TraceExporter::OtlpGrpc => "otlp_grpc",
+            TraceExporter::File => "file",
+        }
+    }
+}
+
+/// The export of the traces with OpenTelemetry.
+///
+/// This corresponds to the optional `[opentelemetry]` section.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OpenTelemetryConfiguration {
+    /// The exporter. Default: `otlp_http`.
+    pub exporter: TraceExporter,
+    /// The collector URL of the OTLP exporters; the `OTEL_EXPORTER_OTLP_*`
+    /// environment variables or the OTLP default otherwise.
+    pub endpoint: Option<String>,
+    /// The file of the file exporter.
+    pub file: Option<String>,
+    /// The `service.name` of the traces. Default: `pgmoneta-mcp`.
+    pub service_name: String,
+    /// The ratio of the traces sampled, unless the caller sampled the trace. Default: 1.0.
+    pub sample_ratio: f64,
+    /// The seconds an export may take. Default: 10.
+    pub timeout: u32,
+}
+
+impl Default for OpenTelemetryConfiguration {
+    fn default() -> Self {
+        Self {
+            exporter: TraceExporter::OtlpHttp,
+            endpoint: None,
+            file: None,
+            service_name: "pgmoneta-mcp".to_string(),
+            sample_ratio: 1.0,
+            timeout: 10,
+        }
+    }
+}
+
+/// How the scrapes of `/metrics` authenticate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricsAuth {
+    /// No authentication.
+    None,
+    /// HTTP Basic authentication as an admin.
+    Basic,
+    /// A bearer token.
+    Bearer,
+}
+
+impl MetricsAuth {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetricsAuth::None => "none",
+            MetricsAuth::Basic => "basic",
+            MetricsAuth::Bearer => "bearer",
+        }
+    }
+}
+
+/// The protection of `/metrics`.
+///
+/// This corresponds to the optional `[metrics]` section.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MetricsConfiguration {
+    /// The authentication of the scrapes. Default: `none`.
+    pub auth: MetricsAuth,
+    /// The token of the `bearer` authentication.
+    #[serde(skip_serializing)]
+    pub token: Option<String>,
+}
+
+impl Default for MetricsConfiguration {
+    fn default() -> Self {
+        Self {
+            auth: MetricsAuth::None,
+            token: None,
+        }
+    }
 }
 
 /// Configuration properties for the local LLM integration.
@@ -198,7 +602,17 @@ pub fn load_configuration(config_path: &str, user_path: &str) -> anyhow::Result<
         .add_source(config::File::with_name(config_path).format(FileFormat::Ini))
         .add_source(config::File::with_name(user_path).format(FileFormat::Ini))
         .build()?;
-    let conf = conf.try_deserialize::<Configuration>().map_err(|e| {
+    let sections = conf
+        .clone()
+        .try_deserialize::<HashMap<String, config::Value>>()
+        .map_err(|e| {
+            anyhow!(
+                "Error parsing configuration at path {}: {:?}",
+                config_path,
+                e
+            )
+        })?;
+    let mut conf = conf.try_deserialize::<Configuration>().map_err(|e| {
         anyhow!(
             "Error parsing configuration at path {}, user {}: {:?}",
             config_path,
@@ -206,7 +620,23 @@ pub fn load_configuration(config_path: &str, user_path: &str) -> anyhow::Result<
             e
         )
     })?;
-    normalize_configuration(conf)
+    conf.schedules = parse_schedules(&sections, &conf.admins)?;
+    let mut conf = normalize_configuration(conf)?;
+    conf.history = parse_history(
+        &sections,
+        &conf.admins,
+        conf.pgmoneta_mcp.state_directory.as_deref(),
+    )?;
+    conf.alerting = parse_alerting(&sections, &conf.admins, conf.history.is_some())?;
+    conf.webhooks = parse_webhooks(
+        &sections,
+        &conf.admins,
+        conf.pgmoneta_mcp.state_directory.as_deref(),
+    )?;
+    conf.opentelemetry = parse_opentelemetry(&sections)?;
+    conf.metrics = parse_metrics(&sections)?;
+    conf.sla = parse_sla_policies(sections)?;
+    Ok(conf)
 }
 
 /// Loads only the user configuration from the specified file path.
@@ -310,11 +740,42 @@ fn default_log_rotation_age() -> String {
     "0".to_string()
 }
 
+fn default_timezone() -> String {
+    "local".to_string()
+}
+
 fn default_llm_max_tool_rounds() -> usize {
     10
 }
 
 fn normalize_configuration(mut conf: Configuration) -> anyhow::Result<Configuration> {
+    conf.pgmoneta_mcp.timezone = conf.pgmoneta_mcp.timezone.trim().to_string();
+    parse_timezone(&conf.pgmoneta_mcp.timezone)?;
+    conf.pgmoneta_mcp.prompts_directory = conf
+        .pgmoneta_mcp
+        .prompts_directory
+        .take()
+        .map(|directory| directory.trim().to_string())
+        .filter(|directory| !directory.is_empty());
+    conf.pgmoneta_mcp.runbooks_directory = conf
+        .pgmoneta_mcp
+        .runbooks_directory
+        .take()
+        .map(|directory| directory.trim().to_string())
+        .filter(|directory| !directory.is_empty());
+    conf.pgmoneta_mcp.report_directory = conf
+        .pgmoneta_mcp
+        .report_directory
+        .take()
+        .map(|directory| directory.trim().to_string())
+        .filter(|directory| !directory.is_empty());
+    conf.pgmoneta_mcp.state_directory = conf
+        .pgmoneta_mcp
+        .state_directory
+        .take()
+        .map(|directory| directory.trim().to_string())
+        .filter(|directory| !directory.is_empty());
+
     if let Some(llm) = conf.llm.as_mut() {
         normalize_llm_configuration(llm)?;
     }
@@ -322,6 +783,767 @@ fn normalize_configuration(mut conf: Configuration) -> anyhow::Result<Configurat
     Ok(conf)
 }
 
+/// Collects the `[sla]` and `[sla:<pattern>]` sections of the configuration.
+///
+/// Policies are returned sorted by section name.
+fn parse_sla_policies(sections: HashMap<String, config::Value>) -> anyhow::Result<Vec<SlaPolicy>> {
+    let mut policies = Vec::new();
+    for (section, value) in sections {
+        let pattern = if section == SLA_SECTION {
+            None
+        } else if let Some(pattern) = section
+            .strip_prefix(SLA_SECTION)
+            .and_then(|rest| rest.strip_prefix(':'))
+        {
+            let pattern = pattern.trim();
+            if pattern.is_empty() {
+                return Err(anyhow!("SLA section [{}] needs a server pattern", section));
+            }
+            Some(pattern.to_string())
+        } else {
+            continue;
+        };
+
+        let settings = section_settings(&section, value)?;
+        policies.push(parse_sla_policy(&section, pattern, &settings)?);
+    }
+    policies.sort_by_key(SlaPolicy::section);
+    Ok(policies)
+}
+
+/// Collects the `[schedule:<server>]` sections of the configuration.
+///
+/// Schedules are returned sorted by server name.
+fn parse_schedules(
+    sections: &HashMap<String, config::Value>,
+    admins: &HashMap<String, String>,
+) -> anyhow::Result<Vec<ScheduleConfiguration>> {
+    let mut schedules = Vec::new();
+    for (section, value) in sections {
+        let Some(server) = section
+            .strip_prefix(SCHEDULE_SECTION)
+            .and_then(|rest| rest.strip_prefix(':'))
+        else {
+            continue;
+        };
+        let server = server.trim();
+        if server.is_empty() {
+            return Err(anyhow!(
+                "Schedule section [{}] needs a server name",
+                section
+            ));
+        }
+
+        let settings = section_settings(section, value.clone())?;
+        let mut schedule = ScheduleConfiguration {
+            server: server.to_string(),
+            verify_directory: DEFAULT_VERIFY_DIRECTORY.to_string(),
+            ..Default::default()
+        };
+        for (key, value) in &settings {
+            let value = value.trim();
+            let invalid = |expected: &str| {
+                anyhow!(
+                    "Invalid {} '{}' in [{}]: expected {}",
+                    key,
+                    value,
+                    section,
+                    expected
+                )
+            };
+            match key.as_str() {
+                "cron" => {
+                    crate::scheduler::cron::CronSchedule::parse(value)?;
+                    schedule.cron = value.to_string();
+                }
+                "verify" => {
+                    schedule.verify = match value.to_ascii_lowercase().as_str() {
+                        "on" | "true" | "yes" | "1" => true,
+                        "off" | "false" | "no" | "0" => false,
+                        _ => return Err(invalid("on or off")),
+                    }
+                }
+                "verify_directory" | "archive_directory" => {
+                    if !std::path::Path::new(value).is_absolute() {
+                        return Err(invalid("an absolute directory"));
+                    }
+                    if key == "verify_directory" {
+                        schedule.verify_directory = value.to_string();
+                    } else {
+                        schedule.archive_directory = Some(value.to_string());
+                    }
+                }
+                "jitter" => {
+                    schedule.jitter = value.parse().map_err(|_| invalid("a number of seconds"))?
+                }
+                "username" => {
+                    if !admins.contains_key(value) {
+                        return Err(invalid("a configured admin"));
+                    }
+                    schedule.username = Some(value.to_string());
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "Unknown schedule setting '{}' in [{}]",
+                        key,
+                        section
+                    ));
+                }
+            }
+        }
+        if schedule.cron.is_empty() {
+            return Err(anyhow!(
+                "Schedule section [{}] needs a cron setting",
+                section
+            ));
+        }
+        schedules.push(schedule);
+    }
+    schedules.sort_by(|a, b| a.server.cmp(&b.server));
+    Ok(schedules)
+}
+
+/// Reads the `[history]` section of the configuration, if any.
+fn parse_history(
+    sections: &HashMap<String, config::Value>,
+    admins: &HashMap<String, String>,
+    state_directory: Option<&str>,
+) -> anyhow::Result<Option<HistoryConfiguration>> {
+    let Some(value) = sections.get(HISTORY_SECTION) else {
+        return Ok(None);
+    };
+
+    let settings = section_settings(HISTORY_SECTION, value.clone())?;
+    let mut history = HistoryConfiguration::default();
+    for (key, value) in &settings {
+        let value = value.trim();
+        let invalid = |expected: &str| {
+            anyhow!(
+                "Invalid {} '{}' in [{}]: expected {}",
+                key,
+                value,
+                HISTORY_SECTION,
+                expected
+            )
+        };
+        let number = || {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|number| *number > 0)
+                .ok_or_else(|| invalid("a positive number"))
+        };
+        match key.as_str() {
+            "directory" => {
+                if !std::path::Path::new(value).is_absolute() {
+                    return Err(invalid("an absolute directory"));
+                }
+                history.directory = value.to_string();
+            }
+            "interval" => history.interval = number()?,
+            "raw_retention" => history.raw_retention = number()?,
+            "resolution" => history.resolution = number()?,
+            "retention" => history.retention = number()?,
+            "username" => {
+                if !admins.contains_key(value) {
+                    return Err(invalid("a configured admin"));
+                }
+                history.username = Some(value.to_string());
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Unknown history setting '{}' in [{}]",
+                    key,
+                    HISTORY_SECTION
+                ));
+            }
+        }
+    }
+
+    if history.directory.is_empty() {
+        let Some(state_directory) = state_directory else {
+            return Err(anyhow!(
+                "[{}] needs a directory setting or a state_directory",
+                HISTORY_SECTION
+            ));
+        };
+        history.directory = std::path::Path::new(state_directory)
+            .join(HISTORY_SECTION)
+            .to_string_lossy()
+            .into_owned();
+    }
+    if history.resolution < history.interval {
+        return Err(anyhow!(
+            "The resolution of [{}] must not be shorter than its interval",
+            HISTORY_SECTION
+        ));
+    }
+    if u64::from(history.raw_retention) > u64::from(history.retention) * 24 {
+        return Err(anyhow!(
+            "The raw_retention of [{}] must not be longer than its retention",
+            HISTORY_SECTION
+        ));
+    }
+    Ok(Some(history))
+}
+
+/// Reads the `[alerting]` and `[alert:<name>]` sections of the configuration,
+/// if any.
+fn parse_alerting(
+    sections: &HashMap<String, config::Value>,
+    admins: &HashMap<String, String>,
+    history: bool,
+) -> anyhow::Result<Option<AlertingConfiguration>> {
+    let mut alerting = AlertingConfiguration::default();
+    let mut configured = false;
+
+    if let Some(value) = sections.get(ALERTING_SECTION) {
+        configured = true;
+        let settings = section_settings(ALERTING_SECTION, value.clone())?;
+        for (key, value) in &settings {
+            let value = value.trim();
+            let invalid = |expected: &str| {
+                anyhow!(
+                    "Invalid {} '{}' in [{}]: expected {}",
+                    key,
+                    value,
+                    ALERTING_SECTION,
+                    expected
+                )
+            };
+            match key.as_str() {
+                "interval" => {
+                    alerting.interval = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| invalid("a positive number"))?
+                }
+                "webhook" => {
+                    reqwest::Url::parse(value)
+                        .ok()
+                        .filter(|url| matches!(url.scheme(), "http" | "https"))
+                        .ok_or_else(|| invalid("an http or https URL"))?;
+                    alerting.webhook = Some(value.to_string());
+                }
+                "file" => {
+                    if !std::path::Path::new(value).is_absolute() {
+                        return Err(invalid("an absolute file path"));
+                    }
+                    alerting.file = Some(value.to_string());
+                }
+                "syslog" => {
+                    alerting.syslog = match value.to_ascii_lowercase().as_str() {
+                        "on" | "true" | "yes" | "1" => true,
+                        "off" | "false" | "no" | "0" => false,
+                        _ => return Err(invalid("on or off")),
+                    }
+                }
+                "username" => {
+                    if !admins.contains_key(value) {
+                        return Err(invalid("a configured admin"));
+                    }
+                    alerting.username = Some(value.to_string());
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "Unknown alerting setting '{}' in [{}]",
+                        key,
+                        ALERTING_SECTION
+                    ));
+                }
+            }
+        }
+    }
+
+    for (section, value) in sections {
+        let Some(name) = section
+            .strip_prefix(ALERT_SECTION)
+            .and_then(|rest| rest.strip_prefix(':'))
+        else {
+            continue;
+        };
+        configured = true;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Alert section [{}] needs a rule name", section));
+        }
+
+        let settings = section_settings(section, value.clone())?;
+        let mut rule = AlertRule {
+            name: name.to_string(),
+            expr: String::new(),
+            for_seconds: 0,
+            severity: "warning".to_string(),
+            summary: None,
+        };
+        for (key, value) in &settings {
+            let value = value.trim();
+            let invalid = |expected: &str| {
+                anyhow!(
+                    "Invalid {} '{}' in [{}]: expected {}",
+                    key,
+                    value,
+                    section,
+                    expected
+                )
+            };
+            match key.as_str() {
+                "expr" => {
+                    let query = crate::handler::metrics::query::Query::parse(value)
+                        .map_err(|e| invalid(&format!("a valid expression ({e})")))?;
+                    if query.needs_history() && !history {
+                        return Err(anyhow!(
+                            "The expression of [{}] uses range selectors, which need the [{}] section",
+                            section,
+                            HISTORY_SECTION
+                        ));
+                    }
+                    rule.expr = value.to_string();
+                }
+                "for" => {
+                    rule.for_seconds = value
+                        .parse::<u64>()
+                        .ok()
+                        .or_else(|| {
+                            crate::handler::metrics::query::parse_duration(value)
+                                .and_then(|milliseconds| u64::try_from(milliseconds / 1000).ok())
+                        })
+                        .ok_or_else(|| invalid("a duration such as 30m"))?
+                }
+                "severity" => {
+                    let severity = value.to_ascii_lowercase();
+                    if !ALERT_SEVERITIES.contains(&severity.as_str()) {
+                        return Err(invalid("info, warning or critical"));
+                    }
+                    rule.severity = severity;
+                }
+                "summary" => rule.summary = Some(value.to_string()).filter(|s| !s.is_empty()),
+                _ => {
+                    return Err(anyhow!("Unknown alert setting '{}' in [{}]", key, section));
+                }
+            }
+        }
+        if rule.expr.is_empty() {
+            return Err(anyhow!("Alert section [{}] needs an expr setting", section));
+        }
+        alerting.rules.push(rule);
+    }
+    alerting.rules.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(configured.then_some(alerting))
+}
+
+/// Reads the `[webhooks]` and `[webhook:<name>]` sections of the configuration,
+/// if any.
+fn parse_webhooks(
+    sections: &HashMap<String, config::Value>,
+    admins: &HashMap<String, String>,
+    state_directory: Option<&str>,
+) -> anyhow::Result<Option<WebhooksConfiguration>> {
+    let mut webhooks = WebhooksConfiguration::default();
+    let mut configured = false;
+
+    if let Some(value) = sections.get(WEBHOOKS_SECTION) {
+        configured = true;
+        let settings = section_settings(WEBHOOKS_SECTION, value.clone())?;
+        for (key, value) in &settings {
+            let value = value.trim();
+            let invalid = |expected: &str| {
+                anyhow!(
+                    "Invalid {} '{}' in [{}]: expected {}",
+                    key,
+                    value,
+                    WEBHOOKS_SECTION,
+                    expected
+                )
+            };
+            match key.as_str() {
+                "catalog_interval" => {
+                    webhooks.catalog_interval = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| invalid("a positive number"))?
+                }
+                "retries" => {
+                    webhooks.retries = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|retries| *retries <= 20)
+                        .ok_or_else(|| invalid("a number of at most 20"))?
+                }
+                "retry_interval" => {
+                    webhooks.retry_interval = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| invalid("a positive number"))?
+                }
+                "queue_size" => {
+                    webhooks.queue_size = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|size| (1..=100_000).contains(size))
+                        .ok_or_else(|| invalid("a number between 1 and 100000"))?
+                }
+                "dead_letter" => {
+                    if !std::path::Path::new(value).is_absolute() {
+                        return Err(invalid("an absolute file path"));
+                    }
+                    webhooks.dead_letter = Some(value.to_string());
+                }
+                "username" => {
+                    if !admins.contains_key(value) {
+                        return Err(invalid("a configured admin"));
+                    }
+                    webhooks.username = Some(value.to_string());
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "Unknown webhooks setting '{}' in [{}]",
+                        key,
+                        WEBHOOKS_SECTION
+                    ));
+                }
+            }
+        }
+    }
+
+    for (section, value) in sections {
+        let Some(name) = section
+            .strip_prefix(WEBHOOK_SECTION)
+            .and_then(|rest| rest.strip_prefix(':'))
+        else {
+            continue;
+        };
+        configured = true;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!(
+                "Webhook section [{}] needs an endpoint name",
+                section
+            ));
+        }
+
+        let settings = section_settings(section, value.clone())?;
+        let mut endpoint = WebhookEndpoint {
+            name: name.to_string(),
+            url: String::new(),
+            secret: String::new(),
+            events: Vec::new(),
+            servers: Vec::new(),
+        };
+        for (key, value) in &settings {
+            let value = value.trim();
+            let invalid = |expected: &str| {
+                anyhow!(
+                    "Invalid {} '{}' in [{}]: expected {}",
+                    key,
+                    value,
+                    section,
+                    expected
+                )
+            };
+            let list = || {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+            };
+            match key.as_str() {
+                "url" => {
+                    reqwest::Url::parse(value)
+                        .ok()
+                        .filter(|url| matches!(url.scheme(), "http" | "https"))
+                        .ok_or_else(|| invalid("an http or https URL"))?;
+                    endpoint.url = value.to_string();
+                }
+                "secret" => endpoint.secret = value.to_string(),
+                "events" => {
+                    endpoint.events = list()
+                        .map(|kind| {
+                            EventKind::parse(&kind.to_ascii_lowercase()).ok_or_else(|| {
+                                let kinds = EventKind::ALL.map(|kind| kind.as_str());
+                                invalid(&format!("event types among {}", kinds.join(", ")))
+                            })
+                        })
+                        .collect::<anyhow::Result<_>>()?;
+                    endpoint.events.sort();
+                    endpoint.events.dedup();
+                }
+                "servers" => endpoint.servers = list().map(str::to_string).collect(),
+                _ => {
+                    return Err(anyhow!(
+                        "Unknown webhook setting '{}' in [{}]",
+                        key,
+                        section
+                    ));
+                }
+            }
+        }
+        if endpoint.url.is_empty() {
+            return Err(anyhow!("Webhook section [{}] needs a url setting", section));
+        }
+        if endpoint.secret.is_empty() {
+            return Err(anyhow!(
+                "Webhook section [{}] needs a secret setting",
+                section
+            ));
+        }
+        webhooks.endpoints.push(endpoint);
+    }
+    webhooks.endpoints.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if webhooks.dead_letter.is_none() {
+        webhooks.dead_letter = state_directory.map(|directory| {
+            std::path::Path::new(directory)
+                .join("dead_letter.jsonl")
+                .to_string_lossy()
+                .into_owned()
+        });
+    }
+    Ok(configured.then_some(webhooks))
+}
+
+/// Reads the `[opentelemetry]` section of the configuration, if any.
+fn parse_opentelemetry(
+    sections: &HashMap<String, config::Value>,
+) -> anyhow::Result<Option<OpenTelemetryConfiguration>> {
+    let Some(value) = sections.get(OPENTELEMETRY_SECTION) else {
+        return Ok(None);
+    };
+
+    let settings = section_settings(OPENTELEMETRY_SECTION, value.clone())?;
+    let mut opentelemetry = OpenTelemetryConfiguration::default();
+    for (key, value) in &settings {
+        let value = value.trim();
+        let invalid = |expected: &str| {
+            anyhow!(
+                "Invalid {} '{}' in [{}]: expected {}",
+                key,
+                value,
+                OPENTELEMETRY_SECTION,
+                expected
+            )
+        };
+        match key.as_str() {
+            "exporter" => {
+                opentelemetry.exporter = [
+                    TraceExporter::OtlpHttp,
+                    TraceExporter::OtlpGrpc,
+                    TraceExporter::File,
+                ]
+                .into_iter()
+                .find(|exporter| exporter.as_str() == value.to_ascii_lowercase())
+                .ok_or_else(|| invalid("otlp_http, otlp_grpc or file"))?
+            }
+            "endpoint" => {
+                reqwest::Url::parse(value)
+                    .ok()
+                    .filter(|url| matches!(url.scheme(), "http" | "https"))
+                    .ok_or_else(|| invalid("an http or https URL"))?;
+                opentelemetry.endpoint = Some(value.to_string());
+            }
+            "file" => {
+                if !std::path::Path::new(value).is_absolute() {
+                    return Err(invalid("an absolute file path"));
+                }
+                opentelemetry.file = Some(value.to_string());
+            }
+            "service_name" => {
+                if value.is_empty() {
+                    return Err(invalid("a name"));
+                }
+                opentelemetry.service_name = value.to_string();
+            }
+            "sample_ratio" => {
+                opentelemetry.sample_ratio = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|ratio| (0.0..=1.0).contains(ratio))
+                    .ok_or_else(|| invalid("a ratio between 0 and 1"))?
+            }
+            "timeout" => {
+                opentelemetry.timeout = value
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|timeout| *timeout > 0)
+                    .ok_or_else(|| invalid("a positive number"))?
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Unknown opentelemetry setting '{}' in [{}]",
+                    key,
+                    OPENTELEMETRY_SECTION
+                ));
+            }
+        }
+    }
+
+    if opentelemetry.exporter == TraceExporter::File && opentelemetry.file.is_none() {
+        return Err(anyhow!(
+            "The file exporter of [{}] needs a file setting",
+            OPENTELEMETRY_SECTION
+        ));
+    }
+    Ok(Some(opentelemetry))
+}
+
+/// Reads the `[metrics]` section of the configuration, if any.
+fn parse_metrics(
+    sections: &HashMap<String, config::Value>,
+) -> anyhow::Result<Option<MetricsConfiguration>> {
+    let Some(value) = sections.get(METRICS_SECTION) else {
+        return Ok(None);
+    };
+
+    let settings = section_settings(METRICS_SECTION, value.clone())?;
+    let mut metrics = MetricsConfiguration::default();
+    for (key, value) in &settings {
+        let value = value.trim();
+        match key.as_str() {
+            "auth" => {
+                metrics.auth = [MetricsAuth::None, MetricsAuth::Basic, MetricsAuth::Bearer]
+                    .into_iter()
+                    .find(|auth| auth.as_str() == value.to_ascii_lowercase())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Invalid {} '{}' in [{}]: expected none, basic or bearer",
+                            key,
+                            value,
+                            METRICS_SECTION
+                        )
+                    })?
+            }
+            "token" => {
+                if value.is_empty() {
+                    return Err(anyhow!(
+                        "Invalid {} in [{}]: expected a token",
+                        key,
+                        METRICS_SECTION
+                    ));
+                }
+                metrics.token = Some(value.to_string());
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Unknown metrics setting '{}' in [{}]",
+                    key,
+                    METRICS_SECTION
+                ));
+            }
+        }
+    }
+
+    if metrics.auth == MetricsAuth::Bearer && metrics.token.is_none() {
+        return Err(anyhow!(
+            "The bearer authentication of [{}] needs a token setting",
+            METRICS_SECTION
+        ));
+    }
+    Ok(Some(metrics))
+}
+
+/// Reads the settings of a section as strings.
+fn section_settings(
+    section: &str,
+    value: config::Value,
+) -> anyhow::Result<HashMap<String, String>> {
+    value
+        .into_table()
+        .map_err(|e| anyhow!("Invalid section [{}]: {}", section, e))?
+        .into_iter()
+        .map(|(key, value)| {
+            value
+                .into_string()
+                .map(|value| (key, value))
+                .map_err(|e| anyhow!("Invalid section [{}]: {}", section, e))
+        })
+        .collect()
+}
+
+fn parse_sla_policy(
+    section: &str,
+    pattern: Option<String>,
+    settings: &HashMap<String, String>,
+) -> anyhow::Result<SlaPolicy> {
+    let mut policy = SlaPolicy {
+        pattern,
+        ..Default::default()
+    };
+    for (key, value) in settings {
+        let number = value
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|number| *number > 0);
+        let target = match key.as_str() {
+            "max_backup_age" => &mut policy.max_backup_age,
+            "max_restore_time" => &mut policy.max_restore_time,
+            "warning_threshold" => &mut policy.warning_threshold,
+            _ => return Err(anyhow!("Unknown SLA setting '{}' in [{}]", key, section)),
+        };
+        match number {
+            Some(number) if key != "warning_threshold" || number <= 100 => *target = Some(number),
+            _ => {
+                return Err(anyhow!(
+                    "Invalid {} '{}' in [{}]: expected a positive number{}",
+                    key,
+                    value,
+                    section,
+                    if key == "warning_threshold" {
+                        " up to 100"
+                    } else {
+                        ""
+                    }
+                ));
+            }
+        }
+    }
+    Ok(policy)
+}
+
+/// Parses the `timezone` setting of the `[pgmoneta_mcp]` section.
+///
+/// # Returns
+///
+/// Returns `None` for the local timezone of the host, or the fixed offset for
+/// `UTC` and `+HH:MM`/`-HH:MM` values.
+pub fn parse_timezone(timezone: &str) -> anyhow::Result<Option<FixedOffset>> {
+    let trimmed = timezone.trim();
+    if trimmed.is_empty() || trimmed.eq_ignore_ascii_case("local") {
+        return Ok(None);
+    }
+    if trimmed.eq_ignore_ascii_case("utc") || trimmed.eq_ignore_ascii_case("z") {
+        return Ok(FixedOffset::east_opt(0));
+    }
+
+    let offset = trimmed
+        .strip_prefix("UTC")
+        .or_else(|| trimmed.strip_prefix("utc"))
+        .unwrap_or(trimmed);
+    let (sign, rest) = match offset.chars().next() {
+        Some('+') => (1, &offset[1..]),
+        Some('-') => (-1, &offset[1..]),
+        _ => return Err(anyhow!("Unsupported timezone '{}'", timezone)),
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let hours: i32 = hours
+        .parse()
+        .map_err(|_| anyhow!("Unsupported timezone '{}'", timezone))?;
+    let minutes: i32 = minutes
+        .parse()
+        .map_err(|_| anyhow!("Unsupported timezone '{}'", timezone))?;
+    if hours > 14 || minutes > 59 {
+        return Err(anyhow!("Unsupported timezone '{}'", timezone));
+    }
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .map(Some)
+        .ok_or_else(|| anyhow!("Unsupported timezone '{}'", timezone))
+}
+
 fn normalize_client_configuration(
     mut conf: ClientAppConfiguration,
 ) -> anyhow::Result<ClientAppConfiguration> {
@@ -556,4 +1778,719 @@ mod tests {
 
         assert_eq!(conf.pgmoneta.metrics, 7001);
     }
+
+    #[test]
+    fn test_load_configuration_defaults_timezone_to_local() {
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+        writeln!(
+            config_file,
+            "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = localhost\nport = 5000\n"
+        )
+        .unwrap();
+        writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+        let conf = load_configuration(
+            config_file.path().to_str().unwrap(),
+            user_file.path().to_str().unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(conf.pgmoneta_mcp.timezone, "local");
+        assert_eq!(conf.pgmoneta_mcp.prompts_directory, None);
+        assert_eq!(conf.pgmoneta_mcp.runbooks_directory, None);
+        assert_eq!(conf.pgmoneta_mcp.report_directory, None);
+        assert_eq!(conf.pgmoneta_mcp.state_directory, None);
+        assert!(conf.sla.is_empty());
+        assert!(conf.schedules.is_empty());
+    }
+
+    #[test]
+    fn test_load_configuration_with_sla_sections() {
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+        writeln!(
+            config_file,
+            "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n[sla]\nmax_backup_age = 24\nmax_restore_time = 60\n\n[sla:prod-*]\nmax_backup_age = 6\nwarning_threshold = 75\n"
+        )
+        .unwrap();
+        writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+        let conf = load_configuration(
+            config_file.path().to_str().unwrap(),
+            user_file.path().to_str().unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            conf.sla,
+            vec![
+                SlaPolicy {
+                    pattern: None,
+                    max_backup_age: Some(24),
+                    max_restore_time: Some(60),
+                    warning_threshold: None,
+                },
+                SlaPolicy {
+                    pattern: Some("prod-*".to_string()),
+                    max_backup_age: Some(6),
+                    max_restore_time: None,
+                    warning_threshold: Some(75),
+                },
+            ]
+        );
+        assert_eq!(conf.sla[1].section(), "sla:prod-*");
+    }
+
+    #[test]
+    fn test_load_configuration_rejects_invalid_sla_settings() {
+        for (section, expected) in [
+            ("[sla]\nmax_backup_age = 0\n", "Invalid max_backup_age"),
+            ("[sla]\nwarning_threshold = 120\n", "up to 100"),
+            (
+                "[sla:primary]\nmax_backup_hours = 4\n",
+                "Unknown SLA setting",
+            ),
+            ("[sla: ]\nmax_backup_age = 4\n", "needs a server pattern"),
+        ] {
+            let mut config_file = tempfile::NamedTempFile::new().unwrap();
+            let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+            writeln!(
+                config_file,
+                "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n{section}"
+            )
+            .unwrap();
+            writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+            let err = load_configuration(
+                config_file.path().to_str().unwrap(),
+                user_file.path().to_str().unwrap(),
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains(expected), "{section}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_load_configuration_with_schedule_sections() {
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+        writeln!(
+            config_file,
+            "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n[schedule:replica]\ncron = @daily\n\n[schedule:primary]\ncron = 30 2 * * *\nverify = on\narchive_directory = /srv/archive\njitter = 300\nusername = admin\n"
+        )
+        .unwrap();
+        writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+        let conf = load_configuration(
+            config_file.path().to_str().unwrap(),
+            user_file.path().to_str().unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            conf.schedules,
+            vec![
+                ScheduleConfiguration {
+                    server: "primary".to_string(),
+                    cron: "30 2 * * *".to_string(),
+                    verify: true,
+                    verify_directory: DEFAULT_VERIFY_DIRECTORY.to_string(),
+                    archive_directory: Some("/srv/archive".to_string()),
+                    jitter: 300,
+                    username: Some("admin".to_string()),
+                },
+                ScheduleConfiguration {
+                    server: "replica".to_string(),
+                    cron: "@daily".to_string(),
+                    verify: false,
+                    verify_directory: DEFAULT_VERIFY_DIRECTORY.to_string(),
+                    archive_directory: None,
+                    jitter: 0,
+                    username: None,
+                },
+            ]
+        );
+        assert_eq!(conf.schedules[0].section(), "schedule:primary");
+    }
+
+    #[test]
+    fn test_load_configuration_rejects_invalid_schedule_settings() {
+        for (section, expected) in [
+            ("[schedule:primary]\nverify = on\n", "needs a cron setting"),
+            (
+                "[schedule:primary]\ncron = 0 25 * * *\n",
+                "hour 25 is out of range",
+            ),
+            (
+                "[schedule:primary]\ncron = @daily\nverify = maybe\n",
+                "Invalid verify",
+            ),
+            (
+                "[schedule:primary]\ncron = @daily\njitter = -5\n",
+                "Invalid jitter",
+            ),
+            (
+                "[schedule:primary]\ncron = @daily\nusername = bob\n",
+                "a configured admin",
+            ),
+            (
+                "[schedule:primary]\ncron = @daily\narchive_directory = archive\n",
+                "an absolute directory",
+            ),
+            (
+                "[schedule:primary]\ncron = @daily\nretention = 7\n",
+                "Unknown schedule setting",
+            ),
+            ("[schedule: ]\ncron = @daily\n", "needs a server name"),
+        ] {
+            let mut config_file = tempfile::NamedTempFile::new().unwrap();
+            let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+            writeln!(
+                config_file,
+                "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n{section}"
+            )
+            .unwrap();
+            writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+            let err = load_configuration(
+                config_file.path().to_str().unwrap(),
+                user_file.path().to_str().unwrap(),
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains(expected), "{section}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_load_configuration_with_history_section() {
+        for (section, expected) in [
+            ("", None),
+            (
+                "[history]\n",
+                Some(HistoryConfiguration {
+                    directory: "/var/lib/pgmoneta-mcp/history".to_string(),
+                    ..Default::default()
+                }),
+            ),
+            (
+                "[history]\ndirectory = /srv/history\ninterval = 30\nraw_retention = 12\nresolution = 600\nretention = 90\nusername = admin\n",
+                Some(HistoryConfiguration {
+                    directory: "/srv/history".to_string(),
+                    interval: 30,
+                    raw_retention: 12,
+                    resolution: 600,
+                    retention: 90,
+                    username: Some("admin".to_string()),
+                }),
+            ),
+        ] {
+            let mut config_file = tempfile::NamedTempFile::new().unwrap();
+            let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+            writeln!(
+                config_file,
+                "[pgmoneta_mcp]\nport = 8000\nstate_directory = /var/lib/pgmoneta-mcp\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n{section}"
+            )
+            .unwrap();
+            writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+            let conf = load_configuration(
+                config_file.path().to_str().unwrap(),
+                user_file.path().to_str().unwrap(),
+            )
+            .unwrap();
+            assert_eq!(conf.history, expected, "{section}");
+        }
+    }
+
+    #[test]
+    fn test_load_configuration_rejects_invalid_history_settings() {
+        for (pgmoneta_mcp, section, expected) in [
+            (
+                "",
+                "[history]\n",
+                "needs a directory setting or a state_directory",
+            ),
+            (
+                "",
+                "[history]\ndirectory = history\n",
+                "an absolute directory",
+            ),
+            (
+                "",
+                "[history]\ndirectory = /srv/history\ninterval = 0\n",
+                "Invalid interval",
+            ),
+            (
+                "state_directory = /var/lib/pgmoneta-mcp\n",
+                "[history]\ninterval = 600\n",
+                "must not be shorter than its interval",
+            ),
+            (
+                "state_directory = /var/lib/pgmoneta-mcp\n",
+                "[history]\nretention = 1\nraw_retention = 48\n",
+                "must not be longer than its retention",
+            ),
+            (
+                "state_directory = /var/lib/pgmoneta-mcp\n",
+                "[history]\nusername = bob\n",
+                "a configured admin",
+            ),
+            (
+                "state_directory = /var/lib/pgmoneta-mcp\n",
+                "[history]\nsize = 5\n",
+                "Unknown history setting",
+            ),
+        ] {
+            let mut config_file = tempfile::NamedTempFile::new().unwrap();
+            let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+            writeln!(
+                config_file,
+                "[pgmoneta_mcp]\nport = 8000\n{pgmoneta_mcp}\n[pgmoneta]\nhost = localhost\nport = 5000\n\n{section}"
+            )
+            .unwrap();
+            writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+            let err = load_configuration(
+                config_file.path().to_str().unwrap(),
+                user_file.path().to_str().unwrap(),
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains(expected), "{section}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_load_configuration_with_opentelemetry_section() {
+        for (section, expected) in [
+            ("", None),
+            (
+                "[opentelemetry]\n",
+                Some(OpenTelemetryConfiguration::default()),
+            ),
+            (
+                "[opentelemetry]\nexporter = otlp_grpc\nendpoint = https://collector:4317\nservice_name = backup-mcp\nsample_ratio = 0.25\ntimeout = 5\n",
+                Some(OpenTelemetryConfiguration {
+                    exporter: TraceExporter::OtlpGrpc,
+                    endpoint: Some("https://collector:4317".to_string()),
+                    service_name: "backup-mcp".to_string(),
+                    sample_ratio: 0.25,
+                    timeout: 5,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "[opentelemetry]\nexporter = file\nfile = /var/log/pgmoneta-mcp/traces.jsonl\n",
+                Some(OpenTelemetryConfiguration {
+                    exporter: TraceExporter::File,
+                    file: Some("/var/log/pgmoneta-mcp/traces.jsonl".to_string()),
+                    ..Default::default()
+                }),
+            ),
+        ] {
+            let mut config_file = tempfile::NamedTempFile::new().unwrap();
+            let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+            writeln!(
+                config_file,
+                "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n{section}"
+            )
+            .unwrap();
+            writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+            let conf = load_configuration(
+                config_file.path().to_str().unwrap(),
+                user_file.path().to_str().unwrap(),
+            )
+            .unwrap();
+            assert_eq!(conf.opentelemetry, expected, "{section}");
+        }
+    }
+
+    #[test]
+    fn test_load_configuration_rejects_invalid_opentelemetry_settings() {
+        for (section, expected) in [
+            (
+                "[opentelemetry]\nexporter = zipkin\n",
+                "otlp_http, otlp_grpc or file",
+            ),
+            (
+                "[opentelemetry]\nendpoint = collector:4318\n",
+                "an http or https URL",
+            ),
+            ("[opentelemetry]\nexporter = file\n", "needs a file setting"),
+            (
+                "[opentelemetry]\nfile = traces.jsonl\n",
+                "an absolute file path",
+            ),
+            (
+                "[opentelemetry]\nsample_ratio = 2\n",
+                "a ratio between 0 and 1",
+            ),
+            ("[opentelemetry]\ntimeout = 0\n", "Invalid timeout"),
+            (
+                "[opentelemetry]\nheaders = x\n",
+                "Unknown opentelemetry setting",
+            ),
+        ] {
+            let mut config_file = tempfile::NamedTempFile::new().unwrap();
+            let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+            writeln!(
+                config_file,
+                "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n{section}"
+            )
+            .unwrap();
+            writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+            let err = load_configuration(
+                config_file.path().to_str().unwrap(),
+                user_file.path().to_str().unwrap(),
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains(expected), "{section}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_load_configuration_with_metrics_section() {
+        for (section, expected) in [
+            ("", None),
+            ("[metrics]\n", Some(MetricsConfiguration::default())),
+            (
+                "[metrics]\nauth = basic\n",
+                Some(MetricsConfiguration {
+                    auth: MetricsAuth::Basic,
+                    token: None,
+                }),
+            ),
+            (
+                "[metrics]\nauth = Bearer\ntoken = s3cr3t\n",
+                Some(MetricsConfiguration {
+                    auth: MetricsAuth::Bearer,
+                    token: Some("s3cr3t".to_string()),
+                }),
+            ),
+        ] {
+            let mut config_file = tempfile::NamedTempFile::new().unwrap();
+            let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+            writeln!(
+                config_file,
+                "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n{section}"
+            )
+            .unwrap();
+            writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+            let conf = load_configuration(
+                config_file.path().to_str().unwrap(),
+                user_file.path().to_str().unwrap(),
+            )
+            .unwrap();
+            assert_eq!(conf.metrics, expected, "{section}");
+        }
+
+        let metrics = MetricsConfiguration {
+            auth: MetricsAuth::Bearer,
+            token: Some("s3cr3t".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_string(&metrics).unwrap(),
+            r#"{"auth":"bearer"}"#
+        );
+    }
+
+    #[test]
+    fn test_load_configuration_rejects_invalid_metrics_settings() {
+        for (section, expected) in [
+            ("[metrics]\nauth = digest\n", "none, basic or bearer"),
+            ("[metrics]\nauth = bearer\n", "needs a token setting"),
+            ("[metrics]\nusername = admin\n", "Unknown metrics setting"),
+        ] {
+            let mut config_file = tempfile::NamedTempFile::new().unwrap();
+            let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+            writeln!(
+                config_file,
+                "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n{section}"
+            )
+            .unwrap();
+            writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+            let err = load_configuration(
+                config_file.path().to_str().unwrap(),
+                user_file.path().to_str().unwrap(),
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains(expected), "{section}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_load_configuration_with_alert_sections() {
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+        writeln!(
+            config_file,
+            "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n[alerting]\ninterval = 30\nwebhook = http://localhost:9093/alerts\nfile = /var/log/pgmoneta-mcp/alerts.log\nsyslog = on\nusername = admin\n\n[alert:wal_streaming]\nexpr = pgmoneta_wal_streaming{{name=\"replica\"}} == 0\nfor = 5m\nseverity = Critical\nsummary = WAL streaming of {{{{ $labels.name }}}} stopped\n\n[alert:backup_age]\nexpr = pgmoneta_mcp_backup_age_seconds > 26 * 3600\n"
+        )
+        .unwrap();
+        writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+        let conf = load_configuration(
+            config_file.path().to_str().unwrap(),
+            user_file.path().to_str().unwrap(),
+        )
+        .unwrap();
+
+        let alerting = conf.alerting.unwrap();
+        assert_eq!(
+            alerting.rules,
+            vec![
+                AlertRule {
+                    name: "backup_age".to_string(),
+                    expr: "pgmoneta_mcp_backup_age_seconds > 26 * 3600".to_string(),
+                    for_seconds: 0,
+                    severity: "warning".to_string(),
+                    summary: None,
+                },
+                AlertRule {
+                    name: "wal_streaming".to_string(),
+                    expr: "pgmoneta_wal_streaming{name=\"replica\"} == 0".to_string(),
+                    for_seconds: 300,
+                    severity: "critical".to_string(),
+                    summary: Some("WAL streaming of {{ $labels.name }} stopped".to_string()),
+                },
+            ]
+        );
+        assert_eq!(alerting.interval, 30);
+        assert_eq!(
+            alerting.webhook.as_deref(),
+            Some("http://localhost:9093/alerts")
+        );
+        assert_eq!(
+            alerting.file.as_deref(),
+            Some("/var/log/pgmoneta-mcp/alerts.log")
+        );
+        assert!(alerting.syslog);
+        assert_eq!(alerting.username.as_deref(), Some("admin"));
+        assert_eq!(alerting.rules[0].section(), "alert:backup_age");
+    }
+
+    #[test]
+    fn test_load_configuration_rejects_invalid_alert_settings() {
+        for (section, expected) in [
+            ("[alert:backup_age]\nfor = 5m\n", "needs an expr setting"),
+            ("[alert:backup_age]\nexpr = sum(\n", "a valid expression"),
+            (
+                "[alert:errors]\nexpr = increase(pgmoneta_logging_error[1h]) > 0\n",
+                "need the [history] section",
+            ),
+            (
+                "[alert:backup_age]\nexpr = pgmoneta_state == 0\nfor = soon\n",
+                "Invalid for",
+            ),
+            (
+                "[alert:backup_age]\nexpr = pgmoneta_state == 0\nseverity = page\n",
+                "info, warning or critical",
+            ),
+            (
+                "[alert:backup_age]\nexpr = pgmoneta_state == 0\nlabels = team\n",
+                "Unknown alert setting",
+            ),
+            (
+                "[alert: ]\nexpr = pgmoneta_state == 0\n",
+                "needs a rule name",
+            ),
+            (
+                "[alerting]\nwebhook = ftp://alerts\n",
+                "an http or https URL",
+            ),
+            ("[alerting]\nfile = alerts.log\n", "an absolute file path"),
+            ("[alerting]\ninterval = 0\n", "a positive number"),
+            ("[alerting]\nsyslog = maybe\n", "on or off"),
+            ("[alerting]\nusername = bob\n", "a configured admin"),
+            ("[alerting]\nsinks = all\n", "Unknown alerting setting"),
+        ] {
+            let mut config_file = tempfile::NamedTempFile::new().unwrap();
+            let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+            writeln!(
+                config_file,
+                "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n{section}"
+            )
+            .unwrap();
+            writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+            let err = load_configuration(
+                config_file.path().to_str().unwrap(),
+                user_file.path().to_str().unwrap(),
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains(expected), "{section}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_load_configuration_with_webhook_sections() {
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+        writeln!(
+            config_file,
+            "[pgmoneta_mcp]\nport = 8000\nstate_directory = /var/lib/pgmoneta-mcp\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n[webhooks]\ncatalog_interval = 120\nretries = 3\nretry_interval = 5\nqueue_size = 50\nusername = admin\n\n[webhook:chatops]\nurl = https://chatops.example.com/pgmoneta\nsecret = s3cr3t\nevents = backup_failed, Backup_Finished, backup_failed\nservers = primary\n\n[webhook:audit]\nurl = http://localhost:9000/events\nsecret = other\n"
+        )
+        .unwrap();
+        writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+        let conf = load_configuration(
+            config_file.path().to_str().unwrap(),
+            user_file.path().to_str().unwrap(),
+        )
+        .unwrap();
+
+        let webhooks = conf.webhooks.unwrap();
+        assert_eq!(
+            webhooks.endpoints,
+            vec![
+                WebhookEndpoint {
+                    name: "audit".to_string(),
+                    url: "http://localhost:9000/events".to_string(),
+                    secret: "other".to_string(),
+                    events: Vec::new(),
+                    servers: Vec::new(),
+                },
+                WebhookEndpoint {
+                    name: "chatops".to_string(),
+                    url: "https://chatops.example.com/pgmoneta".to_string(),
+                    secret: "s3cr3t".to_string(),
+                    events: vec![EventKind::BackupFinished, EventKind::BackupFailed],
+                    servers: vec!["primary".to_string()],
+                },
+            ]
+        );
+        assert_eq!(webhooks.catalog_interval, 120);
+        assert_eq!(webhooks.retries, 3);
+        assert_eq!(webhooks.retry_interval, 5);
+        assert_eq!(webhooks.queue_size, 50);
+        assert_eq!(webhooks.username.as_deref(), Some("admin"));
+        assert_eq!(
+            webhooks.dead_letter.as_deref(),
+            Some("/var/lib/pgmoneta-mcp/dead_letter.jsonl")
+        );
+
+        let chatops = &webhooks.endpoints[1];
+        assert_eq!(chatops.section(), "webhook:chatops");
+        assert!(chatops.accepts(EventKind::BackupFailed, "primary"));
+        assert!(!chatops.accepts(EventKind::BackupFailed, "replica"));
+        assert!(!chatops.accepts(EventKind::BackupDeleted, "primary"));
+        assert!(webhooks.endpoints[0].accepts(EventKind::BackupDeleted, "replica"));
+    }
+
+    #[test]
+    fn test_load_configuration_rejects_invalid_webhook_settings() {
+        for (section, expected) in [
+            (
+                "[webhook:chatops]\nsecret = s3cr3t\n",
+                "needs a url setting",
+            ),
+            (
+                "[webhook:chatops]\nurl = http://localhost/events\n",
+                "needs a secret setting",
+            ),
+            (
+                "[webhook:chatops]\nurl = ftp://localhost/events\nsecret = s3cr3t\n",
+                "an http or https URL",
+            ),
+            (
+                "[webhook:chatops]\nurl = http://localhost/events\nsecret = s3cr3t\nevents = backup_done\n",
+                "event types among backup_started",
+            ),
+            (
+                "[webhook:chatops]\nurl = http://localhost/events\nsecret = s3cr3t\nformat = slack\n",
+                "Unknown webhook setting",
+            ),
+            (
+                "[webhook: ]\nurl = http://localhost/events\nsecret = s3cr3t\n",
+                "needs an endpoint name",
+            ),
+            ("[webhooks]\nretries = 50\n", "a number of at most 20"),
+            ("[webhooks]\nretry_interval = 0\n", "a positive number"),
+            (
+                "[webhooks]\nqueue_size = 0\n",
+                "a number between 1 and 100000",
+            ),
+            (
+                "[webhooks]\ncatalog_interval = often\n",
+                "a positive number",
+            ),
+            (
+                "[webhooks]\ndead_letter = dead.jsonl\n",
+                "an absolute file path",
+            ),
+            ("[webhooks]\nusername = bob\n", "a configured admin"),
+            ("[webhooks]\ntimeout = 5\n", "Unknown webhooks setting"),
+        ] {
+            let mut config_file = tempfile::NamedTempFile::new().unwrap();
+            let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+            writeln!(
+                config_file,
+                "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = localhost\nport = 5000\n\n{section}"
+            )
+            .unwrap();
+            writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+            let err = load_configuration(
+                config_file.path().to_str().unwrap(),
+                user_file.path().to_str().unwrap(),
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains(expected), "{section}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_load_configuration_rejects_unknown_timezone() {
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        let mut user_file = tempfile::NamedTempFile::new().unwrap();
+
+        writeln!(
+            config_file,
+            "[pgmoneta_mcp]\nport = 8000\ntimezone = Mars/Olympus\n\n[pgmoneta]\nhost = localhost\nport = 5000\n"
+        )
+        .unwrap();
+        writeln!(user_file, "[admins]\nadmin = encrypted-password\n").unwrap();
+
+        let err = load_configuration(
+            config_file.path().to_str().unwrap(),
+            user_file.path().to_str().unwrap(),
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("Unsupported timezone"));
+    }
+
+    #[test]
+    fn test_parse_timezone_values() {
+        assert_eq!(parse_timezone("local").unwrap(), None);
+        assert_eq!(parse_timezone("UTC").unwrap(), FixedOffset::east_opt(0));
+        assert_eq!(
+            parse_timezone("+02:00").unwrap(),
+            FixedOffset::east_opt(7200)
+        );
+        assert_eq!(
+            parse_timezone("UTC-05:30").unwrap(),
+            FixedOffset::west_opt(5 * 3600 + 1800)
+        );
+        assert!(parse_timezone("+25:00").is_err());
+    }
 }
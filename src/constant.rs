@@ -37,6 +37,8 @@ pub struct Encryption;
 pub struct ManagementError;
 /// Represents sorting directions (ascending/descending).
 pub struct Sort;
+/// Represents the files a verification reports (failed only or all).
+pub struct VerifyFiles;
 /// Represents logging verbosity levels.
 pub struct LogLevel;
 
@@ -618,6 +620,13 @@ impl Sort {
     pub const DESC: &str = "desc";
 }
 
+impl VerifyFiles {
+    /// Report the files that failed verification only.
+    pub const FAILED: &str = "failed";
+    /// Report all verified files.
+    pub const ALL: &str = "all";
+}
+
 impl LogLevel {
     /// Trace-level logging.
     pub const TRACE: &str = "trace";
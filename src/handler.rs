@@ -13,49 +13,105 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+pub mod alerts;
 pub mod annotate;
 pub mod archive;
 pub mod backup;
+pub mod bulk;
+pub mod capacity;
+pub(crate) mod catalog;
+pub mod chain;
 pub mod clear;
+pub mod compare;
+mod completion;
 pub mod compression;
 pub mod conf;
 pub mod delete;
 pub mod encryption;
+pub mod events;
 pub mod info;
 pub mod metrics;
 pub mod mode;
 pub mod ping;
+pub mod prompts;
+pub mod recovery;
+pub mod report;
 pub mod restore;
 pub mod retention;
+pub mod runbooks;
+pub mod schedule;
 pub mod shutdown;
+pub mod sla;
 pub mod status;
+mod validation;
 pub mod verify;
 
 use super::constant::*;
 use super::constant::{Command, Compression, Encryption};
+use crate::activity;
+use crate::otel;
+use crate::telemetry;
 use crate::utils::Utility;
+use opentelemetry::trace::TraceContextExt;
 use rmcp::{
     ErrorData as McpError, RoleServer, ServerHandler, handler::server::router::tool::ToolRouter,
     model::*, service::RequestContext, tool_handler,
 };
 use serde_json::Map;
 use serde_json::Value;
+use std::sync::{Arc, LazyLock};
+use std::time::Instant;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 /// The core handler for incoming Model Context Protocol (MCP) requests.
 ///
 /// This struct routes MCP tool calls from the client (like an AI model)
 /// to the appropriate internal functions that communicate with pgmoneta.
 #[derive(Clone)]
-pub struct PgmonetaHandler;
+pub struct PgmonetaHandler {
+    /// Counts the MCP session served by this handler as active.
+    _session: Option<Arc<telemetry::SessionGuard>>,
+}
 
 impl PgmonetaHandler {
     /// Creates a new instance of the `PgmonetaHandler` with an initialized tool router.
     pub fn new() -> Self {
-        Self
+        Self { _session: None }
+    }
+
+    /// Creates the handler of an MCP session, counted as active until it is dropped.
+    pub fn for_session() -> Self {
+        Self {
+            _session: Some(Arc::new(telemetry::SessionGuard::new(telemetry::metrics()))),
+        }
     }
 
-    /// Builds the tool router by registering each tool via the trait-based API.
-    pub fn tool_router() -> ToolRouter<Self> {
+    /// The tool router: the built-in tools plus one tool per runbook.
+    ///
+    /// It is built on first use; the runbook library does not change once loaded.
+    pub fn tool_router() -> &'static ToolRouter<Self> {
+        static ROUTER: LazyLock<ToolRouter<PgmonetaHandler>> = LazyLock::new(|| {
+            runbooks::routes().into_iter().fold(
+                PgmonetaHandler::build_builtin_tool_router(),
+                |mut router, route| {
+                    router.add_route(route);
+                    router
+                },
+            )
+        });
+        &ROUTER
+    }
+
+    /// The router of the built-in tools, which runbook steps can call.
+    pub fn builtin_tool_router() -> &'static ToolRouter<Self> {
+        static ROUTER: LazyLock<ToolRouter<PgmonetaHandler>> =
+            LazyLock::new(PgmonetaHandler::build_builtin_tool_router);
+        &ROUTER
+    }
+
+    /// Builds the router of the built-in tools by registering each tool via the trait-based API.
+    fn build_builtin_tool_router() -> ToolRouter<Self> {
         ToolRouter::new()
             .with_async_tool::<annotate::AnnotateBackupTool>()
             .with_async_tool::<archive::ArchiveTool>()
@@ -63,11 +119,24 @@ impl PgmonetaHandler {
             .with_async_tool::<clear::ClearTool>()
             .with_async_tool::<info::GetBackupInfoTool>()
             .with_async_tool::<info::ListBackupsTool>()
+            .with_async_tool::<compare::CompareBackupsTool>()
+            .with_async_tool::<chain::BackupChainTool>()
             .with_async_tool::<metrics::GetMetricsTool>()
             .with_async_tool::<metrics::MetricTool>()
+            .with_async_tool::<metrics::QueryMetricsTool>()
             .with_async_tool::<retention::RetainBackupTool>()
             .with_async_tool::<restore::RestoreTool>()
+            .with_async_tool::<restore::PlanRestoreTool>()
+            .with_async_tool::<recovery::FindRecoveryPointTool>()
             .with_async_tool::<retention::ExpungeBackupTool>()
+            .with_async_tool::<retention::SimulateRetentionTool>()
+            .with_async_tool::<report::BackupReportTool>()
+            .with_async_tool::<sla::CheckSlaTool>()
+            .with_async_tool::<capacity::ForecastCapacityTool>()
+            .with_async_tool::<schedule::ScheduleListTool>()
+            .with_async_tool::<schedule::ScheduleRunNowTool>()
+            .with_async_tool::<schedule::SchedulePauseTool>()
+            .with_async_tool::<runbooks::RunbookAuditTool>()
             .with_async_tool::<shutdown::ShutdownTool>()
             .with_async_tool::<conf::ConfReloadTool>()
             .with_async_tool::<conf::ConfLsTool>()
@@ -81,7 +150,11 @@ impl PgmonetaHandler {
             .with_async_tool::<encryption::DecryptFileTool>()
             .with_async_tool::<verify::VerifyBackupTool>()
             .with_async_tool::<delete::DeleteTool>()
+            .with_async_tool::<bulk::BulkDeleteTool>()
+            .with_async_tool::<bulk::BulkAnnotateTool>()
+            .with_async_tool::<bulk::BulkVerifyTool>()
             .with_async_tool::<status::StatusTool>()
+            .with_async_tool::<alerts::ActiveAlertsTool>()
     }
 }
 
@@ -256,7 +329,14 @@ impl ServerHandler for PgmonetaHandler {
         let pkg_name = env!("CARGO_PKG_NAME");
         let pkg_version = env!("CARGO_PKG_VERSION");
 
-        ServerInfo::new(ServerCapabilities::builder().enable_tools().build())
+        ServerInfo::new(
+            ServerCapabilities::builder()
+                .enable_tools()
+                .enable_completions()
+                .enable_prompts()
+                .enable_resources()
+                .build(),
+        )
             .with_server_info(Implementation::new(pkg_name, pkg_version))
             .with_instructions("This server provides capabilities to interact with pgmoneta, a backup/restore tool for PostgreSQL.")
     }
@@ -274,6 +354,111 @@ impl ServerHandler for PgmonetaHandler {
         }
         Ok(self.get_info())
     }
+
+    /// Routes a tool call, publishing its start and finish to the activity
+    /// stream and recording it in the telemetry.
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let router = Self::tool_router();
+        let tool = if router.has_route(&request.name) {
+            request.name.to_string()
+        } else {
+            telemetry::UNKNOWN_TOOL.to_string()
+        };
+        let span = tracing::info_span!(
+            target: otel::SPAN_TARGET,
+            "mcp.tool",
+            otel.name = format!("tools/call {tool}"),
+            otel.kind = "server",
+            mcp.tool.name = tool.as_str(),
+            mcp.tool.outcome = tracing::field::Empty,
+            pgmoneta.error_code = tracing::field::Empty,
+            otel.status_code = tracing::field::Empty,
+        );
+        let parent = otel::tool_parent(
+            request.meta.as_ref().unwrap_or(&context.meta),
+            context.extensions.get::<axum::http::request::Parts>(),
+        );
+        if let Some(parent) = parent {
+            let _ = span.set_parent(parent);
+        }
+
+        let call = activity::ToolCall::start(&request);
+        let start = Instant::now();
+        let tool_context =
+            rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
+        let (result, error_code) = telemetry::track_pgmoneta_error(router.call(tool_context))
+            .instrument(span.clone())
+            .await;
+        let outcome = call.finish(&result, error_code);
+        telemetry::metrics().record_tool_call(
+            &tool,
+            outcome,
+            error_code,
+            start.elapsed(),
+            Some(span.context().span().span_context()),
+        );
+
+        span.record("mcp.tool.outcome", outcome);
+        if let Some(code) = error_code {
+            span.record("pgmoneta.error_code", code);
+        }
+        span.record(
+            "otel.status_code",
+            if outcome == "success" { "ok" } else { "error" },
+        );
+        result
+    }
+
+    /// Suggests values for server, backup, username and configuration key arguments.
+    async fn complete(
+        &self,
+        request: CompleteRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, McpError> {
+        completion::complete(&request).await
+    }
+
+    /// Lists the prompt templates for common backup workflows.
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        Ok(ListPromptsResult::with_all_items(
+            prompts::library().prompts(),
+        ))
+    }
+
+    /// Renders a prompt template with the given arguments.
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        prompts::library().get(&request)
+    }
+
+    /// Lists the resources, such as the active alerts.
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        Ok(ListResourcesResult::with_all_items(alerts::resources()))
+    }
+
+    /// Reads a resource.
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        alerts::read_resource(&request.uri)
+    }
 }
 
 #[cfg(test)]
@@ -281,6 +466,22 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_tool_routers_are_built_once() {
+        assert!(std::ptr::eq(
+            PgmonetaHandler::tool_router(),
+            PgmonetaHandler::tool_router()
+        ));
+        assert!(std::ptr::eq(
+            PgmonetaHandler::builtin_tool_router(),
+            PgmonetaHandler::builtin_tool_router()
+        ));
+        assert!(
+            PgmonetaHandler::tool_router().list_all().len()
+                > PgmonetaHandler::builtin_tool_router().list_all().len()
+        );
+    }
+
     #[test]
     fn test_parse_and_check_result_valid() {
         let input = r#"{"Outcome": "success", "Server": "test"}"#;
@@ -0,0 +1,147 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The alerts of the `[alert:<name>]` rules, as the `active_alerts` tool and
+//! the `pgmoneta://alerts/active` resource.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::PgmonetaHandler;
+use super::validation;
+use crate::alerts;
+use crate::configuration::CONFIG;
+use rmcp::ErrorData as McpError;
+use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
+use rmcp::model::{
+    AnnotateAble, JsonObject, RawResource, ReadResourceResult, Resource, ResourceContents,
+};
+use rmcp::schemars;
+
+/// The URI of the active alerts resource.
+pub const ACTIVE_ALERTS_URI: &str = "pgmoneta://alerts/active";
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct ActiveAlertsRequest {
+    pub username: String,
+    /// Also list the recently resolved alerts
+    #[serde(default)]
+    pub include_resolved: Option<bool>,
+}
+
+/// Tool for listing the pending and firing alerts.
+pub struct ActiveAlertsTool;
+
+impl ToolBase for ActiveAlertsTool {
+    type Parameter = ActiveAlertsRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "active_alerts".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "List the alerts of the [alert:<name>] configuration sections, evaluated periodically \
+            against the pgmoneta metrics and the backup catalog: the firing alerts, then the pending \
+            ones that have not yet been active for the 'for' of their rule, with their labels, \
+            value, summary and since when they are active. \
+            Set include_resolved to also list the recently resolved alerts. \
+            Rules that could not be evaluated are listed with their error. \
+            The username has to be one of the pgmoneta admins."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for ActiveAlertsTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: ActiveAlertsRequest,
+    ) -> Result<String, McpError> {
+        validation::validate_admin(&request.username)?;
+        ensure_configured()?;
+        active_alerts(request.include_resolved.unwrap_or(false))
+    }
+}
+
+/// The resources of the alerts.
+pub(crate) fn resources() -> Vec<Resource> {
+    vec![
+        RawResource::new(ACTIVE_ALERTS_URI, "active_alerts")
+            .with_title("Active alerts")
+            .with_description("The firing and pending alerts of the [alert:<name>] rules")
+            .with_mime_type("application/json")
+            .no_annotation(),
+    ]
+}
+
+/// Reads a resource of the alerts.
+pub(crate) fn read_resource(uri: &str) -> Result<ReadResourceResult, McpError> {
+    if uri != ACTIVE_ALERTS_URI {
+        return Err(McpError::resource_not_found(
+            format!("Unknown resource '{uri}'"),
+            None,
+        ));
+    }
+    ensure_configured()?;
+    Ok(ReadResourceResult::new(vec![
+        ResourceContents::text(active_alerts(false)?, uri).with_mime_type("application/json"),
+    ]))
+}
+
+fn ensure_configured() -> Result<(), McpError> {
+    let configured = CONFIG
+        .get()
+        .and_then(|config| config.alerting.as_ref())
+        .is_some_and(|alerting| !alerting.rules.is_empty());
+    if !configured {
+        return Err(McpError::invalid_params(
+            "No alert rules configured: add an [alert:<name>] section",
+            None,
+        ));
+    }
+    Ok(())
+}
+
+fn active_alerts(include_resolved: bool) -> Result<String, McpError> {
+    serde_json::to_string(&alerts::snapshot(include_resolved))
+        .map_err(|e| McpError::internal_error(format!("Failed to serialize result: {:?}", e), None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handler_has_active_alerts_tool() {
+        let tools = PgmonetaHandler::tool_router().list_all();
+        assert!(tools.iter().any(|tool| tool.name == "active_alerts"));
+    }
+
+    #[test]
+    fn test_alert_resources() {
+        let resources = resources();
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].uri, ACTIVE_ALERTS_URI);
+        assert_eq!(resources[0].mime_type.as_deref(), Some("application/json"));
+        assert!(read_resource("pgmoneta://alerts/unknown").is_err());
+    }
+}
@@ -17,6 +17,8 @@ use std::borrow::Cow;
 use std::sync::Arc;
 
 use super::PgmonetaHandler;
+use super::events::{self, Event, EventKind, EventSource};
+use super::validation::{self, Keyword};
 use crate::client::PgmonetaClient;
 use rmcp::ErrorData as McpError;
 use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
@@ -27,12 +29,100 @@ use rmcp::schemars;
 pub struct AnnotateRequest {
     pub username: String,
     pub server: String,
+    /// Backup identifier: a 14 digit timestamp or "newest", "latest" or "oldest"
+    #[schemars(pattern(validation::BACKUP_ID_PATTERN))]
+    #[serde(deserialize_with = "validation::deserialize_backup_id")]
     pub backup_id: String,
-    pub action: String,
+    #[schemars(required)]
+    #[serde(deserialize_with = "validation::deserialize_optional_keyword")]
+    pub action: Option<AnnotateAction>,
     pub key: String,
     pub comment: Option<String>,
 }
 
+/// The change applied to a backup annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, schemars::JsonSchema)]
+#[schemars(rename_all = "lowercase")]
+pub enum AnnotateAction {
+    Add,
+    Remove,
+    Update,
+}
+
+impl AnnotateAction {
+    /// The keyword sent to pgmoneta.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnotateAction::Add => "add",
+            AnnotateAction::Remove => "remove",
+            AnnotateAction::Update => "update",
+        }
+    }
+}
+
+impl Keyword for AnnotateAction {
+    const ARGUMENT: &'static str = "annotate action";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("add", AnnotateAction::Add),
+        ("remove", AnnotateAction::Remove),
+        ("update", AnnotateAction::Update),
+    ];
+}
+
+impl<'de> serde::Deserialize<'de> for AnnotateAction {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        validation::deserialize_keyword(deserializer)
+    }
+}
+
+/// Checks an annotation and returns the comment to send with it.
+///
+/// The key must not be empty, and the add and update actions require a comment.
+pub(crate) fn annotation_comment<'a>(
+    action: AnnotateAction,
+    key: &str,
+    comment: Option<&'a str>,
+) -> Result<Option<&'a str>, McpError> {
+    if key.trim().is_empty() {
+        return Err(McpError::invalid_params(
+            "The annotation key must not be empty",
+            None,
+        ));
+    }
+
+    match action {
+        AnnotateAction::Add | AnnotateAction::Update => {
+            let value = comment.unwrap_or("").trim();
+            if value.is_empty() {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "The '{}' action requires a non-empty comment",
+                        action.as_str()
+                    ),
+                    None,
+                ));
+            }
+            Ok(Some(value))
+        }
+        AnnotateAction::Remove => Ok(None),
+    }
+}
+
+/// The event of an annotation of a backup.
+pub(crate) fn annotated_event(
+    username: &str,
+    server: &str,
+    backup_id: &str,
+    action: AnnotateAction,
+    key: &str,
+) -> Event {
+    Event::new(EventKind::BackupAnnotated, EventSource::Tool, server)
+        .backup(backup_id)
+        .username(username)
+        .detail("Action", action.as_str())
+        .detail("Key", key)
+}
+
 /// Tool for adding or updating backup annotations.
 pub struct AnnotateBackupTool;
 
@@ -67,33 +157,31 @@ impl AsyncTool<PgmonetaHandler> for AnnotateBackupTool {
         _service: &PgmonetaHandler,
         request: AnnotateRequest,
     ) -> Result<String, McpError> {
-        let action = normalize_action(&request.action)?;
-
-        let comment = match action.as_str() {
-            "add" | "update" => {
-                let value = request.comment.as_deref().unwrap_or("").trim();
-                if value.is_empty() {
-                    return Err(McpError::invalid_params(
-                        format!("The '{}' action requires a non-empty comment", action),
-                        None,
-                    ));
-                }
-                Some(value)
-            }
-            "remove" => None,
-            _ => None,
-        };
+        validation::validate_backup_id("backup_id", &request.backup_id)?;
+        let action = validation::require_keyword(request.action)?;
+        let comment = annotation_comment(action, &request.key, request.comment.as_deref())?;
 
-        let result: String = PgmonetaClient::request_annotate(
+        let result = PgmonetaClient::request_annotate(
             &request.username,
             &request.server,
             &request.backup_id,
-            &action,
+            action.as_str(),
             &request.key,
             comment,
         )
-        .await
-        .map_err(|e| {
+        .await;
+        events::emit_outcome(
+            annotated_event(
+                &request.username,
+                &request.server,
+                &request.backup_id,
+                action,
+                &request.key,
+            ),
+            None,
+            &result,
+        );
+        let result: String = result.map_err(|e| {
             McpError::internal_error(format!("Failed to annotate backup: {:?}", e), None)
         })?;
 
@@ -101,20 +189,6 @@ impl AsyncTool<PgmonetaHandler> for AnnotateBackupTool {
     }
 }
 
-fn normalize_action(action: &str) -> Result<String, McpError> {
-    let normalized = action.trim().to_ascii_lowercase();
-    match normalized.as_str() {
-        "add" | "update" | "remove" => Ok(normalized),
-        _ => Err(McpError::invalid_params(
-            format!(
-                "Unsupported annotate action '{}'. Supported actions: add, remove, update",
-                action
-            ),
-            None,
-        )),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -146,11 +220,31 @@ mod tests {
     }
 
     #[test]
-    fn test_normalize_action() {
-        assert_eq!(normalize_action(" add ").unwrap(), "add");
-        assert_eq!(normalize_action("UPDATE").unwrap(), "update");
-        assert_eq!(normalize_action("Remove").unwrap(), "remove");
-        assert!(normalize_action("replace").is_err());
+    fn test_parse_annotate_action() {
+        assert_eq!(AnnotateAction::parse(" add ").unwrap(), AnnotateAction::Add);
+        assert_eq!(
+            AnnotateAction::parse("UPDATE").unwrap(),
+            AnnotateAction::Update
+        );
+        assert_eq!(
+            AnnotateAction::parse("Remove").unwrap(),
+            AnnotateAction::Remove
+        );
+        assert!(AnnotateAction::parse("replace").is_err());
+    }
+
+    #[test]
+    fn test_annotate_request_schema_has_enum() {
+        let schema = serde_json::to_value(schemars::schema_for!(AnnotateRequest)).unwrap();
+        let text = schema.to_string();
+        assert!(
+            text.contains(r#""enum":["add","remove","update"]"#),
+            "{text}"
+        );
+        assert_eq!(
+            schema["properties"]["backup_id"]["pattern"],
+            validation::BACKUP_ID_PATTERN
+        );
     }
 
     #[test]
@@ -17,6 +17,8 @@ use std::borrow::Cow;
 use std::sync::Arc;
 
 use super::PgmonetaHandler;
+use super::restore::RecoveryAction;
+use super::validation;
 use crate::client::PgmonetaClient;
 use rmcp::ErrorData as McpError;
 use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
@@ -27,7 +29,11 @@ use rmcp::schemars;
 pub struct ArchiveRequest {
     pub username: String,
     pub server: String,
+    /// Backup identifier: a 14 digit timestamp or "newest", "latest" or "oldest"
+    #[schemars(pattern(validation::BACKUP_ID_PATTERN))]
+    #[serde(deserialize_with = "validation::deserialize_backup_id")]
     pub backup_id: String,
+    /// Absolute target directory on the pgmoneta host
     pub directory: String,
 
     /// Archive the backup of the first stable checkpoint
@@ -44,8 +50,8 @@ pub struct ArchiveRequest {
     pub inclusive: Option<String>,
     /// Archive the backup of the specified timeline
     pub timeline: Option<String>,
-    /// Action to execute after the archive (pause, shutdown)
-    pub action: Option<String>,
+    /// Action to execute after the archive (pause, promote, shutdown)
+    pub action: Option<RecoveryAction>,
     /// Indicates if the cluster is set up as a primary
     pub primary: Option<bool>,
     /// Indicates if the cluster is set up as a replica
@@ -79,7 +85,7 @@ impl ToolBase for ArchiveTool {
             Position \"lsn\" means archive the backup of the specified LSN. \
             Position \"inclusive\" means the archive is inclusive of the specified information. \
             Position \"timeline\" means archive the backup of the specified timeline. \
-            Position \"action\" means which action will be executed after the archive (pause, shutdown). \
+            Position \"action\" means which action will be executed after the archive (pause, promote, shutdown). \
             Choose the position that best fits. \
             The directory specifies where to archive the backup. \
             The username has to be one of the pgmoneta admins to be able to access pgmoneta."
@@ -103,6 +109,8 @@ impl AsyncTool<PgmonetaHandler> for ArchiveTool {
         _service: &PgmonetaHandler,
         request: ArchiveRequest,
     ) -> Result<String, McpError> {
+        validation::validate_backup_id("backup_id", &request.backup_id)?;
+        validation::validate_path("directory", &request.directory)?;
         let position = normalize_position(&request);
         let result: String = PgmonetaClient::request_archive(
             &request.username,
@@ -154,8 +162,8 @@ fn normalize_position(req: &ArchiveRequest) -> String {
     if let Some(timeline) = &req.timeline {
         result.push(format!("timeline={}", timeline));
     }
-    if let Some(action) = &req.action {
-        result.push(format!("action={}", action));
+    if let Some(action) = req.action {
+        result.push(format!("action={}", action.as_str()));
     }
 
     let position = result.join(",");
@@ -205,7 +213,7 @@ mod tests {
             lsn: Some("0/12345678".to_string()),
             inclusive: Some("true".to_string()),
             timeline: Some("2".to_string()),
-            action: Some("pause".to_string()),
+            action: Some(RecoveryAction::Pause),
             primary: None,
             replica: None,
         };
@@ -17,6 +17,8 @@ use std::borrow::Cow;
 use std::sync::Arc;
 
 use super::PgmonetaHandler;
+use super::events::{self, Event, EventKind, EventSource};
+use super::validation;
 use crate::client::PgmonetaClient;
 use rmcp::ErrorData as McpError;
 use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
@@ -27,6 +29,12 @@ use rmcp::schemars;
 pub struct BackupRequest {
     pub username: String,
     pub server: String,
+    /// Parent backup of an incremental backup: a 14 digit timestamp or "newest", "latest" or "oldest"
+    #[schemars(pattern(validation::BACKUP_ID_PATTERN))]
+    #[serde(
+        default,
+        deserialize_with = "validation::deserialize_optional_backup_id"
+    )]
     pub backup_id: Option<String>,
 }
 
@@ -63,25 +71,48 @@ impl AsyncTool<PgmonetaHandler> for BackupServerTool {
         _service: &PgmonetaHandler,
         request: BackupRequest,
     ) -> Result<String, McpError> {
+        if let Some(backup_id) = &request.backup_id {
+            validation::validate_backup_id("backup_id", backup_id)?;
+        }
+        let event = |kind| {
+            let event = Event::new(kind, EventSource::Tool, &request.server)
+                .username(&request.username)
+                .detail("Incremental", request.backup_id.is_some());
+            match &request.backup_id {
+                Some(backup_id) => event.detail("Parent", backup_id.as_str()),
+                None => event,
+            }
+        };
+        events::emit(event(EventKind::BackupStarted));
         let result = if let Some(backup_id) = &request.backup_id {
-            PgmonetaClient::request_incremental_backup(
+            let result = PgmonetaClient::request_incremental_backup(
                 &request.username,
                 &request.server,
                 backup_id,
             )
-            .await
-            .map_err(|e| {
+            .await;
+            events::emit_outcome(
+                event(EventKind::BackupFinished),
+                Some(EventKind::BackupFailed),
+                &result,
+            );
+            result.map_err(|e| {
                 McpError::internal_error(
                     format!("Failed to create incremental backup: {:?}", e),
                     None,
                 )
             })?
         } else {
-            PgmonetaClient::request_full_backup(&request.username, &request.server)
-                .await
-                .map_err(|e| {
-                    McpError::internal_error(format!("Failed to create full backup: {:?}", e), None)
-                })?
+            let result =
+                PgmonetaClient::request_full_backup(&request.username, &request.server).await;
+            events::emit_outcome(
+                event(EventKind::BackupFinished),
+                Some(EventKind::BackupFailed),
+                &result,
+            );
+            result.map_err(|e| {
+                McpError::internal_error(format!("Failed to create full backup: {:?}", e), None)
+            })?
         };
         PgmonetaHandler::generate_call_tool_result_string(&result)
     }
@@ -0,0 +1,955 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Bulk operations over a set of backups chosen by a selector.
+//!
+//! `bulk_delete`, `bulk_annotate` and `bulk_verify` resolve the selector
+//! against the backup catalog, and either report the selected backups
+//! (dry run) or run the operation on each of them with bounded concurrency.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::PgmonetaHandler;
+use super::annotate::{self, AnnotateAction};
+use super::catalog::{self, BackupClock, BackupEntry};
+use super::events::{self, Event, EventKind, EventSource};
+use super::recovery;
+use super::validation;
+use super::verify::{self, VerifyResult};
+use crate::client::PgmonetaClient;
+use crate::configuration::DEFAULT_VERIFY_DIRECTORY;
+use chrono::{NaiveDate, NaiveDateTime};
+use rmcp::ErrorData as McpError;
+use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
+use rmcp::model::JsonObject;
+use rmcp::schemars;
+use serde::Serialize;
+use tokio::task::JoinSet;
+
+/// The number of backups processed at once unless requested otherwise.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// The highest accepted concurrency.
+const MAX_CONCURRENCY: usize = 16;
+
+/// Chooses backups; all given criteria have to match.
+#[derive(Debug, Default, Clone, serde::Deserialize, schemars::JsonSchema)]
+pub struct BackupSelector {
+    /// Only backups taken before this time, e.g. "2026-01-01", "2026-01-01 12:00:00" or "30 days ago"
+    #[serde(default)]
+    pub older_than: Option<String>,
+    /// Only backups pgmoneta marked as invalid
+    #[serde(default)]
+    pub invalid_only: Option<bool>,
+    /// Only backups that are not marked as retained
+    #[serde(default)]
+    pub not_retained: Option<bool>,
+    /// Only backups having an annotation with this key
+    #[serde(default)]
+    pub annotation: Option<String>,
+    /// Only the N largest of the matching backups, by backup size
+    #[serde(default)]
+    pub largest: Option<usize>,
+}
+
+/// A [`BackupSelector`] with its time resolved.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Selection {
+    pub before: Option<NaiveDateTime>,
+    pub invalid_only: bool,
+    pub not_retained: bool,
+    pub annotation: Option<String>,
+    pub largest: Option<usize>,
+}
+
+impl Selection {
+    /// Resolves a selector; at least one criterion has to be given.
+    pub fn resolve(selector: &BackupSelector, clock: BackupClock) -> Result<Self, String> {
+        let before = match selector.older_than.as_deref().map(str::trim) {
+            None | Some("") => None,
+            // A negative age lies in the future and would select every backup.
+            Some(older_than) if older_than.starts_with('-') => {
+                return Err(format!(
+                    "Invalid older_than '{older_than}': the amount must not be negative"
+                ));
+            }
+            Some(older_than) => Some(
+                NaiveDate::parse_from_str(older_than, "%Y-%m-%d")
+                    .ok()
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+                    .map(Ok)
+                    .unwrap_or_else(|| {
+                        recovery::parse_human_time(older_than, clock).map(|(time, _)| time)
+                    })
+                    .map_err(|_| format!("Unable to understand older_than '{older_than}'"))?,
+            ),
+        };
+        let annotation = selector
+            .annotation
+            .as_deref()
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(str::to_string);
+        if selector.largest == Some(0) {
+            return Err("'largest' must be at least 1".to_string());
+        }
+
+        let selection = Self {
+            before,
+            invalid_only: selector.invalid_only.unwrap_or(false),
+            not_retained: selector.not_retained.unwrap_or(false),
+            annotation,
+            largest: selector.largest,
+        };
+        if selection == Self::default() {
+            return Err(
+                "Specify at least one selector: older_than, invalid_only, not_retained, annotation or largest"
+                    .to_string(),
+            );
+        }
+        Ok(selection)
+    }
+
+    /// The criteria in words, e.g. `older than 2026-01-01 00:00:00`.
+    pub fn describe(&self, clock: BackupClock) -> Vec<String> {
+        let mut criteria = Vec::new();
+        if let Some(before) = self.before {
+            criteria.push(format!("older than {}", clock.format(before)));
+        }
+        if self.invalid_only {
+            criteria.push("invalid".to_string());
+        }
+        if self.not_retained {
+            criteria.push("not retained".to_string());
+        }
+        if let Some(key) = &self.annotation {
+            criteria.push(format!("annotated with '{key}'"));
+        }
+        if let Some(largest) = self.largest {
+            criteria.push(format!("the {largest} largest"));
+        }
+        criteria
+    }
+
+    /// The selected backups, oldest first.
+    pub fn apply(&self, backups: &[BackupEntry]) -> Vec<BackupEntry> {
+        let mut selected: Vec<BackupEntry> = backups
+            .iter()
+            .filter(|backup| {
+                self.before
+                    .is_none_or(|before| backup.timestamp().is_some_and(|time| time < before))
+            })
+            .filter(|backup| !self.invalid_only || backup.valid == Some(false))
+            .filter(|backup| !self.not_retained || !backup.keep)
+            .filter(|backup| {
+                self.annotation
+                    .as_ref()
+                    .is_none_or(|key| backup.annotations().contains_key(key))
+            })
+            .cloned()
+            .collect();
+        if let Some(largest) = self.largest {
+            selected.sort_by(|a, b| {
+                b.backup_size
+                    .unwrap_or(0)
+                    .cmp(&a.backup_size.unwrap_or(0))
+                    .then_with(|| a.backup.cmp(&b.backup))
+            });
+            selected.truncate(largest);
+        }
+        selected.sort_by(|a, b| a.backup.cmp(&b.backup));
+        selected
+    }
+}
+
+/// The operation of a bulk tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Operation {
+    Delete,
+    Annotate,
+    Verify,
+}
+
+/// A backup chosen by the selector.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct SelectedBackup {
+    pub backup: String,
+    pub valid: Option<bool>,
+    pub keep: bool,
+    pub backup_size: Option<u64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+    /// Incremental backups depending on this one that are not selected; for `bulk_delete`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unselected_dependents: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ItemStatus {
+    Succeeded,
+    Failed,
+}
+
+/// The result of the operation on one backup.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct ItemResult {
+    pub backup: String,
+    pub status: ItemStatus,
+    pub seconds: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The number of files that failed verification; for `bulk_verify`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_files: Option<usize>,
+}
+
+/// The response of a bulk tool.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct BulkResult {
+    pub server: String,
+    pub operation: Operation,
+    pub dry_run: bool,
+    pub criteria: Vec<String>,
+    pub concurrency: usize,
+    pub selected: Vec<SelectedBackup>,
+    pub results: Vec<ItemResult>,
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+/// The common part of a bulk tool call.
+struct Bulk<'a> {
+    username: &'a str,
+    server: &'a str,
+    operation: Operation,
+    selector: &'a BackupSelector,
+    dry_run: Option<bool>,
+    concurrency: Option<usize>,
+}
+
+impl Bulk<'_> {
+    /// Resolves the selector and, unless this is a dry run, runs `operation`
+    /// on the selected backups in batches, one batch after the other.
+    async fn run<F, Fut>(self, operation: F) -> Result<String, McpError>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = ItemResult> + Send + 'static,
+    {
+        let clock = BackupClock::configured();
+        let selection = Selection::resolve(self.selector, clock)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+        let concurrency = self.concurrency.unwrap_or(DEFAULT_CONCURRENCY);
+        if !(1..=MAX_CONCURRENCY).contains(&concurrency) {
+            return Err(McpError::invalid_params(
+                format!("'concurrency' must be between 1 and {MAX_CONCURRENCY}"),
+                None,
+            ));
+        }
+        let dry_run = self.dry_run.unwrap_or(true);
+
+        let backups = catalog::fetch_backups(self.username, self.server).await?;
+        let chosen = selection.apply(&backups);
+        let selected = describe_selected(self.operation, &chosen, &backups);
+
+        let mut results = Vec::new();
+        if !dry_run {
+            tracing::info!(
+                "Running bulk {:?} on {} backup(s) of {}",
+                self.operation,
+                chosen.len(),
+                self.server
+            );
+            for batch in batches(self.operation, &chosen, &backups) {
+                results.extend(execute(batch, concurrency, &operation).await);
+            }
+        }
+
+        let succeeded = results
+            .iter()
+            .filter(|result| result.status == ItemStatus::Succeeded)
+            .count();
+        let bulk = BulkResult {
+            server: self.server.to_string(),
+            operation: self.operation,
+            dry_run,
+            criteria: selection.describe(clock),
+            concurrency,
+            selected,
+            failed: results.len() - succeeded,
+            succeeded,
+            results,
+        };
+        serde_json::to_string(&bulk).map_err(|e| {
+            McpError::internal_error(format!("Failed to serialize bulk result: {:?}", e), None)
+        })
+    }
+}
+
+fn describe_selected(
+    operation: Operation,
+    chosen: &[BackupEntry],
+    backups: &[BackupEntry],
+) -> Vec<SelectedBackup> {
+    chosen
+        .iter()
+        .map(|backup| SelectedBackup {
+            backup: backup.backup.clone(),
+            valid: backup.valid,
+            keep: backup.keep,
+            backup_size: backup.backup_size,
+            annotations: backup.annotations(),
+            unselected_dependents: match operation {
+                Operation::Delete => catalog::dependents(&backup.backup, backups)
+                    .into_iter()
+                    .filter(|dependent| !chosen.iter().any(|c| &c.backup == dependent))
+                    .collect(),
+                Operation::Annotate | Operation::Verify => Vec::new(),
+            },
+        })
+        .collect()
+}
+
+/// Splits the selected backups into batches that are run one after the other.
+///
+/// Deletes run the deepest incremental backups first, so that no backup is
+/// deleted while a selected incremental backup still depends on it.
+pub(crate) fn batches(
+    operation: Operation,
+    chosen: &[BackupEntry],
+    backups: &[BackupEntry],
+) -> Vec<Vec<String>> {
+    if chosen.is_empty() {
+        return Vec::new();
+    }
+    if operation != Operation::Delete {
+        return vec![chosen.iter().map(|backup| backup.backup.clone()).collect()];
+    }
+    let mut depths: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for backup in chosen {
+        depths
+            .entry(catalog::incremental_chain(backup, backups).len())
+            .or_default()
+            .push(backup.backup.clone());
+    }
+    depths.into_values().rev().collect()
+}
+
+/// Runs `operation` on the backups with at most `concurrency` at a time,
+/// returning the results in the order of the backups.
+pub(crate) async fn execute<F, Fut>(
+    backups: Vec<String>,
+    concurrency: usize,
+    operation: &F,
+) -> Vec<ItemResult>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = ItemResult> + Send + 'static,
+{
+    let mut tasks = JoinSet::new();
+    let mut results = Vec::with_capacity(backups.len());
+    for backup in &backups {
+        if tasks.len() >= concurrency
+            && let Some(result) = tasks.join_next().await
+        {
+            collect(result, &mut results);
+        }
+        tasks.spawn(operation(backup.clone()));
+    }
+    while let Some(result) = tasks.join_next().await {
+        collect(result, &mut results);
+    }
+    results.sort_by_key(|result| {
+        backups
+            .iter()
+            .position(|backup| *backup == result.backup)
+            .unwrap_or(usize::MAX)
+    });
+    results
+}
+
+fn collect(result: Result<ItemResult, tokio::task::JoinError>, results: &mut Vec<ItemResult>) {
+    match result {
+        Ok(result) => results.push(result),
+        Err(e) => tracing::error!("Bulk operation task failed: {}", e),
+    }
+}
+
+/// Runs one pgmoneta request and turns its outcome into an [`ItemResult`].
+async fn run_item(
+    backup: String,
+    request: impl Future<Output = anyhow::Result<String>>,
+) -> (ItemResult, Option<String>) {
+    let start = Instant::now();
+    let outcome = match request.await {
+        Ok(result) => PgmonetaHandler::_parse_and_check_result(&result)
+            .and_then(|response| catalog::ensure_success(&response))
+            .map(|_| result)
+            .map_err(|e| e.message.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    let item = ItemResult {
+        backup,
+        status: if outcome.is_ok() {
+            ItemStatus::Succeeded
+        } else {
+            ItemStatus::Failed
+        },
+        seconds: start.elapsed().as_secs_f64(),
+        error: outcome.as_ref().err().cloned(),
+        failed_files: None,
+    };
+    (item, outcome.ok())
+}
+
+const SELECTOR_DESCRIPTION: &str = "Backups are selected by any combination of older_than \
+    (e.g. \"2026-01-01\" or \"30 days ago\"), invalid_only, not_retained, annotation (a key) \
+    and largest (the N largest by size). \
+    By default this is a dry run listing the selected backups; set dry_run to false to run it, \
+    on up to concurrency backups at a time (default 4, at most 16), with a result per backup.";
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct BulkDeleteRequest {
+    pub username: String,
+    pub server: String,
+    #[serde(flatten)]
+    pub selector: BackupSelector,
+    /// Only list the selected backups (default true)
+    #[serde(default)]
+    pub dry_run: Option<bool>,
+    /// The number of backups processed at once (default 4)
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    #[serde(default)]
+    pub force: Option<bool>,
+}
+
+/// Tool for deleting the backups chosen by a selector.
+pub struct BulkDeleteTool;
+
+impl ToolBase for BulkDeleteTool {
+    type Parameter = BulkDeleteRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "bulk_delete".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            format!(
+                "Delete the backups of a server chosen by a selector. {SELECTOR_DESCRIPTION} \
+                Incremental backups are deleted before the backups they depend on; \
+                the dry run lists dependents that are not selected. \
+                The username has to be one of the pgmoneta admins to be able to access pgmoneta."
+            )
+            .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for BulkDeleteTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: BulkDeleteRequest,
+    ) -> Result<String, McpError> {
+        let force = request.force.unwrap_or(false);
+        let bulk = Bulk {
+            username: &request.username,
+            server: &request.server,
+            operation: Operation::Delete,
+            selector: &request.selector,
+            dry_run: request.dry_run,
+            concurrency: request.concurrency,
+        };
+        bulk.run(|backup| {
+            let username = request.username.clone();
+            let server = request.server.clone();
+            async move {
+                let delete = PgmonetaClient::request_delete(&username, &server, &backup, force);
+                let item = run_item(backup.clone(), delete).await.0;
+                if item.status == ItemStatus::Succeeded {
+                    events::emit(
+                        Event::new(EventKind::BackupDeleted, EventSource::Tool, &server)
+                            .backup(&backup)
+                            .username(&username),
+                    );
+                }
+                item
+            }
+        })
+        .await
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct BulkAnnotateRequest {
+    pub username: String,
+    pub server: String,
+    #[serde(flatten)]
+    pub selector: BackupSelector,
+    #[schemars(required)]
+    #[serde(deserialize_with = "validation::deserialize_optional_keyword")]
+    pub action: Option<AnnotateAction>,
+    pub key: String,
+    pub comment: Option<String>,
+    /// Only list the selected backups (default true)
+    #[serde(default)]
+    pub dry_run: Option<bool>,
+    /// The number of backups processed at once (default 4)
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+}
+
+/// Tool for annotating the backups chosen by a selector.
+pub struct BulkAnnotateTool;
+
+impl ToolBase for BulkAnnotateTool {
+    type Parameter = BulkAnnotateRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "bulk_annotate".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            format!(
+                "Add, update or remove an annotation on the backups of a server chosen by a selector. \
+                The add and update actions require a comment. {SELECTOR_DESCRIPTION} \
+                The username has to be one of the pgmoneta admins to be able to access pgmoneta."
+            )
+            .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for BulkAnnotateTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: BulkAnnotateRequest,
+    ) -> Result<String, McpError> {
+        let action = validation::require_keyword(request.action)?;
+        let comment =
+            annotate::annotation_comment(action, &request.key, request.comment.as_deref())?
+                .map(str::to_string);
+        let bulk = Bulk {
+            username: &request.username,
+            server: &request.server,
+            operation: Operation::Annotate,
+            selector: &request.selector,
+            dry_run: request.dry_run,
+            concurrency: request.concurrency,
+        };
+        bulk.run(|backup| {
+            let username = request.username.clone();
+            let server = request.server.clone();
+            let key = request.key.clone();
+            let comment = comment.clone();
+            async move {
+                let annotate = PgmonetaClient::request_annotate(
+                    &username,
+                    &server,
+                    &backup,
+                    action.as_str(),
+                    &key,
+                    comment.as_deref(),
+                );
+                let item = run_item(backup.clone(), annotate).await.0;
+                if item.status == ItemStatus::Succeeded {
+                    events::emit(annotate::annotated_event(
+                        &username, &server, &backup, action, &key,
+                    ));
+                }
+                item
+            }
+        })
+        .await
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct BulkVerifyRequest {
+    pub username: String,
+    pub server: String,
+    #[serde(flatten)]
+    pub selector: BackupSelector,
+    /// The directory the backups are verified in (default /tmp)
+    #[serde(default)]
+    pub directory: Option<String>,
+    /// Only list the selected backups (default true)
+    #[serde(default)]
+    pub dry_run: Option<bool>,
+    /// The number of backups processed at once (default 4)
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+}
+
+/// Tool for verifying the backups chosen by a selector.
+pub struct BulkVerifyTool;
+
+impl ToolBase for BulkVerifyTool {
+    type Parameter = BulkVerifyRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "bulk_verify".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            format!(
+                "Verify the integrity of the backups of a server chosen by a selector, \
+                in the given directory; /tmp is used by default. \
+                A backup with files that failed verification is reported as failed. \
+                {SELECTOR_DESCRIPTION} \
+                The username has to be one of the pgmoneta admins to be able to access pgmoneta."
+            )
+            .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for BulkVerifyTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: BulkVerifyRequest,
+    ) -> Result<String, McpError> {
+        let directory = request
+            .directory
+            .as_deref()
+            .unwrap_or(DEFAULT_VERIFY_DIRECTORY)
+            .to_string();
+        validation::validate_path("directory", &directory)?;
+        let bulk = Bulk {
+            username: &request.username,
+            server: &request.server,
+            operation: Operation::Verify,
+            selector: &request.selector,
+            dry_run: request.dry_run,
+            concurrency: request.concurrency,
+        };
+        bulk.run(|backup| {
+            let username = request.username.clone();
+            let server = request.server.clone();
+            let directory = directory.clone();
+            async move {
+                let verify = verify::run_verify(&username, &server, &backup, &directory, false);
+                let (mut item, result) = run_item(backup.clone(), verify).await;
+                if let Some(report) = result.and_then(|result| {
+                    verify::parse_verify(&result, &server, &backup, &directory).ok()
+                }) {
+                    events::emit(report.to_event(EventSource::Tool, &username));
+                    item.failed_files = Some(report.failed_files);
+                    if report.result == VerifyResult::Failed {
+                        item.status = ItemStatus::Failed;
+                        item.error = Some(report.summary.text);
+                    }
+                }
+                item
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn backup(id: &str, valid: bool, keep: bool, size: u64, comments: &str) -> BackupEntry {
+        BackupEntry {
+            backup: id.to_string(),
+            valid: Some(valid),
+            keep,
+            backup_size: Some(size),
+            comments: (!comments.is_empty()).then(|| comments.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn catalog() -> Vec<BackupEntry> {
+        vec![
+            backup("20260101000000", true, true, 500, ""),
+            backup("20260201000000", false, false, 300, "broken|disk full"),
+            backup("20260301000000", true, false, 900, "verified|passed"),
+            backup("20260401000000", false, false, 100, ""),
+        ]
+    }
+
+    fn ids(backups: &[BackupEntry]) -> Vec<&str> {
+        backups
+            .iter()
+            .map(|backup| backup.backup.as_str())
+            .collect()
+    }
+
+    fn clock() -> BackupClock {
+        BackupClock::new(Some(chrono::FixedOffset::east_opt(0).unwrap()))
+    }
+
+    #[test]
+    fn test_selection_resolve() {
+        let selector = BackupSelector {
+            older_than: Some("2026-03-01".to_string()),
+            annotation: Some(" verified ".to_string()),
+            ..Default::default()
+        };
+        let selection = Selection::resolve(&selector, clock()).unwrap();
+        assert_eq!(
+            selection.before,
+            NaiveDate::from_ymd_opt(2026, 3, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(selection.annotation.as_deref(), Some("verified"));
+        assert_eq!(
+            selection.describe(clock()),
+            vec![
+                "older than 2026-03-01 00:00:00+00:00",
+                "annotated with 'verified'"
+            ]
+        );
+
+        let selector = BackupSelector {
+            older_than: Some("2026-03-01 12:30:00".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Selection::resolve(&selector, clock()).unwrap().before,
+            NaiveDate::from_ymd_opt(2026, 3, 1)
+                .unwrap()
+                .and_hms_opt(12, 30, 0)
+        );
+        let selector = BackupSelector {
+            older_than: Some("30 days ago".to_string()),
+            ..Default::default()
+        };
+        assert!(
+            Selection::resolve(&selector, clock())
+                .unwrap()
+                .before
+                .is_some()
+        );
+
+        let error = Selection::resolve(&BackupSelector::default(), clock()).unwrap_err();
+        assert!(error.contains("at least one selector"));
+        let selector = BackupSelector {
+            invalid_only: Some(false),
+            annotation: Some(" ".to_string()),
+            ..Default::default()
+        };
+        assert!(Selection::resolve(&selector, clock()).is_err());
+        let selector = BackupSelector {
+            older_than: Some("someday".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Selection::resolve(&selector, clock()).unwrap_err(),
+            "Unable to understand older_than 'someday'"
+        );
+        let selector = BackupSelector {
+            largest: Some(0),
+            ..Default::default()
+        };
+        assert!(Selection::resolve(&selector, clock()).is_err());
+    }
+
+    #[test]
+    fn test_selection_resolve_rejects_negative_and_overflowing_ages() {
+        let selector = BackupSelector {
+            older_than: Some(" -1000 days ago".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Selection::resolve(&selector, clock()).unwrap_err(),
+            "Invalid older_than '-1000 days ago': the amount must not be negative"
+        );
+        let selector = BackupSelector {
+            older_than: Some("999999999999 days ago".to_string()),
+            ..Default::default()
+        };
+        assert!(Selection::resolve(&selector, clock()).is_err());
+    }
+
+    #[test]
+    fn test_selection_apply() {
+        let backups = catalog();
+        let select = |selection: Selection| selection.apply(&backups);
+
+        assert_eq!(
+            ids(&select(Selection {
+                before: NaiveDate::from_ymd_opt(2026, 3, 1)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0),
+                ..Default::default()
+            })),
+            vec!["20260101000000", "20260201000000"]
+        );
+        assert_eq!(
+            ids(&select(Selection {
+                invalid_only: true,
+                ..Default::default()
+            })),
+            vec!["20260201000000", "20260401000000"]
+        );
+        assert_eq!(
+            ids(&select(Selection {
+                not_retained: true,
+                ..Default::default()
+            })),
+            vec!["20260201000000", "20260301000000", "20260401000000"]
+        );
+        assert_eq!(
+            ids(&select(Selection {
+                annotation: Some("broken".to_string()),
+                ..Default::default()
+            })),
+            vec!["20260201000000"]
+        );
+        assert_eq!(
+            ids(&select(Selection {
+                largest: Some(2),
+                ..Default::default()
+            })),
+            vec!["20260101000000", "20260301000000"]
+        );
+        // largest applies to the backups matching the other criteria
+        assert_eq!(
+            ids(&select(Selection {
+                not_retained: true,
+                largest: Some(2),
+                ..Default::default()
+            })),
+            vec!["20260201000000", "20260301000000"]
+        );
+    }
+
+    #[test]
+    fn test_delete_batches_and_dependents() {
+        let mut backups = catalog();
+        backups[1].incremental = true;
+        backups[1].incremental_parent = Some("20260101000000".to_string());
+        backups[2].incremental = true;
+        backups[2].incremental_parent = Some("20260201000000".to_string());
+        let chosen = vec![backups[0].clone(), backups[1].clone(), backups[3].clone()];
+
+        assert_eq!(
+            batches(Operation::Delete, &chosen, &backups),
+            vec![
+                vec!["20260201000000".to_string()],
+                vec!["20260101000000".to_string(), "20260401000000".to_string()],
+            ]
+        );
+        assert_eq!(batches(Operation::Verify, &chosen, &backups).len(), 1);
+        assert!(batches(Operation::Delete, &[], &backups).is_empty());
+
+        let selected = describe_selected(Operation::Delete, &chosen, &backups);
+        assert_eq!(selected[0].unselected_dependents, vec!["20260301000000"]);
+        assert_eq!(selected[1].unselected_dependents, vec!["20260301000000"]);
+        assert!(selected[2].unselected_dependents.is_empty());
+        let selected = describe_selected(Operation::Annotate, &chosen, &backups);
+        assert!(selected[0].unselected_dependents.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_bounds_concurrency() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let backups: Vec<String> = (0..10).map(|i| format!("2026010{i}000000")).collect();
+
+        let results = execute(backups.clone(), 3, &|backup: String| {
+            let running = Arc::clone(&running);
+            let peak = Arc::clone(&peak);
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                let failed = backup.ends_with("5000000");
+                ItemResult {
+                    backup,
+                    status: if failed {
+                        ItemStatus::Failed
+                    } else {
+                        ItemStatus::Succeeded
+                    },
+                    seconds: 0.01,
+                    error: failed.then(|| "No backup found".to_string()),
+                    failed_files: None,
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        let order: Vec<String> = results.iter().map(|result| result.backup.clone()).collect();
+        assert_eq!(order, backups);
+        assert_eq!(
+            results
+                .iter()
+                .filter(|result| result.status == ItemStatus::Failed)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_bulk_tools_registered_with_flat_selector() {
+        let router = PgmonetaHandler::tool_router();
+        for name in ["bulk_delete", "bulk_annotate", "bulk_verify"] {
+            let tool = router.get(name).expect("bulk tool should be registered");
+            let properties = tool.input_schema["properties"].as_object().unwrap();
+            for argument in [
+                "older_than",
+                "invalid_only",
+                "not_retained",
+                "annotation",
+                "largest",
+                "dry_run",
+                "concurrency",
+            ] {
+                assert!(
+                    properties.contains_key(argument),
+                    "{name} should accept {argument}"
+                );
+            }
+        }
+    }
+}
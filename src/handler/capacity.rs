@@ -0,0 +1,796 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage capacity forecasting (`forecast_capacity`).
+//!
+//! The daily backup volume of each server is modelled as a linear trend plus a
+//! weekly pattern. Future backups following that model are added day by day,
+//! and the retention policy is applied to the resulting catalog, which gives
+//! the space the backups will take over the forecast horizon.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::PgmonetaHandler;
+use super::catalog::{self, BACKUP_ID_FORMAT, BackupClock, BackupEntry};
+use super::retention::RetentionPolicy;
+use crate::client::PgmonetaClient;
+use crate::utils::Utility;
+use chrono::{Datelike, Days, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use rmcp::ErrorData as McpError;
+use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
+use rmcp::model::JsonObject;
+use rmcp::schemars;
+use serde::Serialize;
+use serde_json::Value;
+
+/// The forecast horizon used when none is given, in days.
+const DEFAULT_HORIZON_DAYS: u32 = 90;
+
+/// The longest forecast horizon, in days.
+const MAX_HORIZON_DAYS: u32 = 1095;
+
+/// The history needed before a weekly pattern is fitted, in days.
+const SEASONAL_MIN_DAYS: i64 = 14;
+
+/// The distance between the forecast points, in days.
+const POINT_INTERVAL_DAYS: u32 = 7;
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct ForecastCapacityRequest {
+    pub username: String,
+    /// Only forecast the growth of this server; the backups of the others are assumed constant
+    #[serde(default)]
+    pub server: Option<String>,
+    /// Forecast horizon in days, default 90
+    #[serde(default)]
+    #[schemars(range(min = 1, max = 1095))]
+    pub horizon_days: Option<u32>,
+    /// Proposed retention policy in the pgmoneta syntax "days,weeks,months,years", e.g. "7,4"
+    #[serde(default)]
+    pub retention: Option<String>,
+}
+
+/// Tool for forecasting when the backup volume fills up.
+pub struct ForecastCapacityTool;
+
+impl ToolBase for ForecastCapacityTool {
+    type Parameter = ForecastCapacityRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "forecast_capacity".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "Forecast the space used by backups and the date the backup volume fills up. \
+            Fits a linear trend plus a weekly pattern to the daily backup volume of every server, \
+            applies the current retention policies and projects the used space over horizon_days (default 90). \
+            Optionally compares with a proposed retention policy in the pgmoneta syntax \"days,weeks,months,years\", \
+            reporting the space it would free now and at the end of the horizon. \
+            The username has to be one of the pgmoneta admins to be able to access pgmoneta."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for ForecastCapacityTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: ForecastCapacityRequest,
+    ) -> Result<String, McpError> {
+        let horizon = request.horizon_days.unwrap_or(DEFAULT_HORIZON_DAYS);
+        if horizon == 0 || horizon > MAX_HORIZON_DAYS {
+            return Err(McpError::invalid_params(
+                format!("Invalid horizon_days '{horizon}': expected 1 to {MAX_HORIZON_DAYS} days"),
+                None,
+            ));
+        }
+        let proposed = request
+            .retention
+            .as_deref()
+            .map(RetentionPolicy::parse)
+            .transpose()
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let clock = BackupClock::configured();
+        let now = clock.now();
+        let status = catalog::fetch_status(&request.username).await?;
+        let mut servers = catalog::parse_server_names(&status);
+        if let Some(server) = request.server.as_deref() {
+            if !servers.iter().any(|name| name == server) {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "Unknown server '{server}'. Known servers: {}",
+                        servers.join(", ")
+                    ),
+                    None,
+                ));
+            }
+            servers.retain(|name| name == server);
+        }
+
+        let metrics = match PgmonetaClient::request_metrics(&request.username).await {
+            Ok(metrics) => Some(metrics),
+            Err(e) => {
+                tracing::warn!("capacity forecast without pgmoneta metrics: {:?}", e);
+                None
+            }
+        };
+
+        let mut catalogs = Vec::new();
+        for server in servers {
+            let server_status = catalog::parse_servers(&status)
+                .into_iter()
+                .find(|object| object.get("Server").and_then(Value::as_str) == Some(&server));
+            let policy = RetentionPolicy::configured(&server, server_status, metrics.as_deref());
+            let backups = catalog::fetch_backups(&request.username, &server).await?;
+            catalogs.push(ServerCatalog {
+                server,
+                policy,
+                backups,
+            });
+        }
+
+        let volume = Volume {
+            total: catalog::space_bytes(&status, "TotalSpace"),
+            used: catalog::space_bytes(&status, "UsedSpace"),
+            free: catalog::space_bytes(&status, "FreeSpace"),
+        };
+        let forecast = forecast(&catalogs, volume, proposed.as_ref(), horizon, now, clock);
+        serde_json::to_string(&forecast).map_err(|e| {
+            McpError::internal_error(
+                format!("Failed to serialize capacity forecast: {:?}", e),
+                None,
+            )
+        })
+    }
+}
+
+/// The backups of a server together with its retention policy.
+struct ServerCatalog {
+    server: String,
+    policy: Option<RetentionPolicy>,
+    backups: Vec<BackupEntry>,
+}
+
+/// The space figures of the backup volume from the detailed status, in bytes.
+#[derive(Debug, Clone, Copy, Default)]
+struct Volume {
+    total: Option<u64>,
+    used: Option<u64>,
+    free: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct CapacityForecast {
+    generated: String,
+    horizon_days: u32,
+    total_space: Option<String>,
+    total_space_bytes: Option<u64>,
+    used_space: Option<String>,
+    used_space_bytes: Option<u64>,
+    servers: Vec<ServerGrowth>,
+    current: Scenario,
+    proposed: Option<Scenario>,
+    notes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ServerGrowth {
+    server: String,
+    backups: usize,
+    retention: Option<RetentionPolicy>,
+    size: String,
+    size_bytes: u64,
+    history_days: i64,
+    daily_volume: String,
+    daily_volume_bytes: u64,
+    daily_volume_change_per_30_days: String,
+    weekly_pattern: Vec<WeekdayVolume>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct WeekdayVolume {
+    day: &'static str,
+    volume: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Scenario {
+    retention: String,
+    space_freed_now: Option<String>,
+    space_freed_at_horizon: Option<String>,
+    used_at_horizon: String,
+    used_at_horizon_bytes: u64,
+    fill_date: Option<String>,
+    days_until_full: Option<u32>,
+    points: Vec<ForecastPoint>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ForecastPoint {
+    date: String,
+    used: String,
+    used_bytes: u64,
+}
+
+/// A linear trend plus weekly pattern of the daily backup volume.
+#[derive(Debug, Clone, PartialEq)]
+struct GrowthModel {
+    /// The first day of the fitted history.
+    origin: NaiveDate,
+    /// The number of days of fitted history.
+    days: i64,
+    /// The daily volume at `origin`, in bytes.
+    intercept: f64,
+    /// The change of the daily volume per day, in bytes.
+    slope: f64,
+    /// The offset of each weekday (Monday first) from the trend, in bytes.
+    seasonal: [f64; 7],
+}
+
+impl GrowthModel {
+    /// Fits the model to the backups taken from `since` up to and including `today`.
+    ///
+    /// Returns `None` when there are no sized backups in that window.
+    fn fit(backups: &[BackupEntry], since: Option<NaiveDate>, today: NaiveDate) -> Option<Self> {
+        let sized: Vec<(NaiveDate, u64)> = backups
+            .iter()
+            .filter_map(|backup| Some((backup.timestamp()?.date(), backup.backup_size?)))
+            .filter(|(date, _)| since.is_none_or(|since| *date >= since) && *date <= today)
+            .collect();
+        let origin = sized.iter().map(|(date, _)| *date).min()?;
+        let days = (today - origin).num_days() + 1;
+
+        let mut volume = vec![0.0; days as usize];
+        for (date, size) in &sized {
+            volume[(*date - origin).num_days() as usize] += *size as f64;
+        }
+
+        let n = days as f64;
+        let mean_t = (n - 1.0) / 2.0;
+        let mean_y = volume.iter().sum::<f64>() / n;
+        let (covariance, variance) =
+            volume
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(covariance, variance), (t, y)| {
+                    let dt = t as f64 - mean_t;
+                    (covariance + dt * (y - mean_y), variance + dt * dt)
+                });
+        let slope = if variance > 0.0 {
+            covariance / variance
+        } else {
+            0.0
+        };
+        let intercept = mean_y - slope * mean_t;
+
+        let mut seasonal = [0.0; 7];
+        if days >= SEASONAL_MIN_DAYS {
+            let mut sums = [0.0; 7];
+            let mut counts = [0.0; 7];
+            for (t, y) in volume.iter().enumerate() {
+                let weekday = weekday(origin + Duration::days(t as i64));
+                sums[weekday] += y - (intercept + slope * t as f64);
+                counts[weekday] += 1.0;
+            }
+            for ((offset, sum), count) in seasonal.iter_mut().zip(sums).zip(counts) {
+                *offset = sum / count;
+            }
+            let mean = seasonal.iter().sum::<f64>() / 7.0;
+            seasonal.iter_mut().for_each(|offset| *offset -= mean);
+        }
+
+        Some(Self {
+            origin,
+            days,
+            intercept,
+            slope,
+            seasonal,
+        })
+    }
+
+    /// The expected backup volume of a day, in bytes.
+    fn daily_volume(&self, date: NaiveDate) -> f64 {
+        let t = (date - self.origin).num_days() as f64;
+        (self.intercept + self.slope * t + self.seasonal[weekday(date)]).max(0.0)
+    }
+}
+
+fn weekday(date: NaiveDate) -> usize {
+    date.weekday().num_days_from_monday() as usize
+}
+
+/// The space taken by the backups a policy keeps, or by all backups without a policy.
+fn retained_size(
+    policy: Option<&RetentionPolicy>,
+    backups: &[BackupEntry],
+    now: NaiveDateTime,
+) -> u64 {
+    let size = |backup: &BackupEntry| backup.backup_size.unwrap_or(0);
+    match policy {
+        Some(policy) => {
+            let kept = policy.keep_reasons(backups, now);
+            backups
+                .iter()
+                .filter(|backup| kept.contains_key(&backup.backup))
+                .map(size)
+                .sum()
+        }
+        None => backups.iter().map(size).sum(),
+    }
+}
+
+/// The space taken by the backups of a server for each day from today to the horizon.
+///
+/// Every future day gets one backup of the modelled daily volume, taken at the
+/// time of day of the newest backup.
+fn project(
+    backups: &[BackupEntry],
+    model: Option<&GrowthModel>,
+    policy: Option<&RetentionPolicy>,
+    horizon: u32,
+    now: NaiveDateTime,
+) -> Vec<u64> {
+    let time_of_day = backups
+        .last()
+        .and_then(BackupEntry::timestamp)
+        .map_or(NaiveTime::MIN, |time| time.time());
+
+    let mut catalog = backups.to_vec();
+    let mut sizes = vec![retained_size(policy, &catalog, now)];
+    for day in 1..=i64::from(horizon) {
+        let date = now.date() + Duration::days(day);
+        if let Some(model) = model {
+            let volume = model.daily_volume(date).round() as u64;
+            if volume > 0 {
+                catalog.push(BackupEntry {
+                    backup: date
+                        .and_time(time_of_day)
+                        .format(BACKUP_ID_FORMAT)
+                        .to_string(),
+                    valid: Some(true),
+                    backup_size: Some(volume),
+                    ..Default::default()
+                });
+            }
+        }
+        sizes.push(retained_size(policy, &catalog, now + Duration::days(day)));
+    }
+    sizes
+}
+
+/// The fitted history window: the daily retention window when there is one,
+/// since older backups have already been thinned out by the weekly, monthly
+/// and yearly retention. A window reaching past the calendar covers the whole
+/// history.
+fn history_start(policy: Option<&RetentionPolicy>, today: NaiveDate) -> Option<NaiveDate> {
+    policy
+        .and_then(|policy| policy.days)
+        .filter(|days| *days > 0)
+        .and_then(|days| {
+            today.checked_sub_days(Days::new(u64::from(days.max(SEASONAL_MIN_DAYS as u32)) - 1))
+        })
+}
+
+fn forecast(
+    catalogs: &[ServerCatalog],
+    volume: Volume,
+    proposed: Option<&RetentionPolicy>,
+    horizon: u32,
+    now: NaiveDateTime,
+    clock: BackupClock,
+) -> CapacityForecast {
+    let today = now.date();
+    let mut notes = Vec::new();
+    let mut servers = Vec::new();
+    let mut current_sizes = vec![0i64; horizon as usize + 1];
+    let mut proposed_sizes = vec![0i64; horizon as usize + 1];
+    let mut actual_size = 0i64;
+
+    for catalog in catalogs {
+        let size: u64 = catalog
+            .backups
+            .iter()
+            .filter_map(|backup| backup.backup_size)
+            .sum();
+        actual_size += size as i64;
+
+        let model = GrowthModel::fit(
+            &catalog.backups,
+            history_start(catalog.policy.as_ref(), today),
+            today,
+        );
+        match &model {
+            None => notes.push(format!(
+                "{}: no backup sizes available, no growth assumed",
+                catalog.server
+            )),
+            Some(model) if model.days < SEASONAL_MIN_DAYS => notes.push(format!(
+                "{}: {} day(s) of history, too few for a weekly pattern",
+                catalog.server, model.days
+            )),
+            _ => {}
+        }
+        if catalog.policy.is_none() {
+            notes.push(format!(
+                "{}: no retention policy known, backups are assumed to be kept forever",
+                catalog.server
+            ));
+        }
+
+        let current = project(
+            &catalog.backups,
+            model.as_ref(),
+            catalog.policy.as_ref(),
+            horizon,
+            now,
+        );
+        for (total, size) in current_sizes.iter_mut().zip(&current) {
+            *total += *size as i64;
+        }
+        if let Some(proposed) = proposed {
+            let sizes = project(
+                &catalog.backups,
+                model.as_ref(),
+                Some(proposed),
+                horizon,
+                now,
+            );
+            for (total, size) in proposed_sizes.iter_mut().zip(&sizes) {
+                *total += *size as i64;
+            }
+        }
+
+        let daily = model
+            .as_ref()
+            .map_or(0.0, |model| model.daily_volume(today));
+        let weekly_pattern = match &model {
+            Some(model) if model.days >= SEASONAL_MIN_DAYS => (0..7)
+                .map(|offset| today + Duration::days(offset))
+                .map(|date| WeekdayVolume {
+                    day: WEEKDAYS[weekday(date)],
+                    volume: Utility::format_file_size(model.daily_volume(date).round() as u64),
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let change = model.as_ref().map_or(0.0, |model| model.slope * 30.0);
+        servers.push(ServerGrowth {
+            server: catalog.server.clone(),
+            backups: catalog.backups.len(),
+            retention: catalog.policy,
+            size: Utility::format_file_size(size),
+            size_bytes: size,
+            history_days: model.as_ref().map_or(0, |model| model.days),
+            daily_volume: Utility::format_file_size(daily.round() as u64),
+            daily_volume_bytes: daily.round() as u64,
+            daily_volume_change_per_30_days: format!(
+                "{}{}",
+                if change < 0.0 { "-" } else { "+" },
+                Utility::format_file_size(change.abs().round() as u64)
+            ),
+            weekly_pattern,
+        });
+    }
+
+    let used_now = match (volume.used, volume.total, volume.free) {
+        (Some(used), _, _) => used as i64,
+        (None, Some(total), Some(free)) => total.saturating_sub(free) as i64,
+        _ => {
+            notes.push("The used space is unknown, only the backups are counted".to_string());
+            actual_size
+        }
+    };
+    if volume.total.is_none() {
+        notes.push("The total space is unknown, no fill date can be predicted".to_string());
+    }
+
+    // The space used outside the forecast backups stays as it is
+    let used = |sizes: &[i64]| -> Vec<u64> {
+        sizes
+            .iter()
+            .map(|size| (used_now - actual_size + size).max(0) as u64)
+            .collect()
+    };
+    let current_used = used(&current_sizes);
+    let current = scenario(
+        "current".to_string(),
+        &current_used,
+        None,
+        volume.total,
+        now,
+        clock,
+    );
+    let proposed = proposed.map(|policy| {
+        scenario(
+            policy.to_string(),
+            &used(&proposed_sizes),
+            Some(&current_used),
+            volume.total,
+            now,
+            clock,
+        )
+    });
+
+    CapacityForecast {
+        generated: clock.format(now),
+        horizon_days: horizon,
+        total_space: volume.total.map(Utility::format_file_size),
+        total_space_bytes: volume.total,
+        used_space: Some(Utility::format_file_size(used_now.max(0) as u64)),
+        used_space_bytes: Some(used_now.max(0) as u64),
+        servers,
+        current,
+        proposed,
+        notes,
+    }
+}
+
+fn scenario(
+    retention: String,
+    used: &[u64],
+    baseline: Option<&[u64]>,
+    total: Option<u64>,
+    now: NaiveDateTime,
+    clock: BackupClock,
+) -> Scenario {
+    let last = used.len() - 1;
+    let days_until_full = total.and_then(|total| {
+        used.iter()
+            .position(|used| *used >= total)
+            .map(|day| day as u32)
+    });
+    let freed = |day: usize| {
+        baseline.map(|baseline| {
+            let freed = baseline[day] as i64 - used[day] as i64;
+            if freed < 0 {
+                format!("-{}", Utility::format_file_size(freed.unsigned_abs()))
+            } else {
+                Utility::format_file_size(freed as u64)
+            }
+        })
+    };
+    let date = |day: usize| (now.date() + Duration::days(day as i64)).to_string();
+
+    let points: Vec<ForecastPoint> = (0..=last)
+        .step_by(POINT_INTERVAL_DAYS as usize)
+        .chain((!last.is_multiple_of(POINT_INTERVAL_DAYS as usize)).then_some(last))
+        .map(|day| ForecastPoint {
+            date: date(day),
+            used: Utility::format_file_size(used[day]),
+            used_bytes: used[day],
+        })
+        .collect();
+
+    Scenario {
+        retention,
+        space_freed_now: freed(0),
+        space_freed_at_horizon: freed(last),
+        used_at_horizon: Utility::format_file_size(used[last]),
+        used_at_horizon_bytes: used[last],
+        fill_date: days_until_full.map(|day| {
+            clock.format((now.date() + Duration::days(i64::from(day))).and_time(NaiveTime::MIN))
+        }),
+        days_until_full,
+        points,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    fn backup(date: NaiveDate, size: u64) -> BackupEntry {
+        BackupEntry {
+            backup: date
+                .and_hms_opt(2, 0, 0)
+                .unwrap()
+                .format(BACKUP_ID_FORMAT)
+                .to_string(),
+            valid: Some(true),
+            backup_size: Some(size),
+            ..Default::default()
+        }
+    }
+
+    fn today() -> NaiveDate {
+        // A Monday
+        NaiveDate::from_ymd_opt(2026, 7, 13).unwrap()
+    }
+
+    fn now() -> NaiveDateTime {
+        today().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn clock() -> BackupClock {
+        BackupClock::new(chrono::FixedOffset::east_opt(0))
+    }
+
+    /// Daily backups over `days` days, growing by `growth` bytes a day.
+    fn history(days: i64, base: u64, growth: u64) -> Vec<BackupEntry> {
+        (0..days)
+            .map(|day| {
+                let date = today() - Duration::days(days - 1 - day);
+                backup(date, base + growth * day as u64)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_forecast_capacity_tool_metadata() {
+        assert_eq!(ForecastCapacityTool::name(), "forecast_capacity");
+        assert!(
+            ForecastCapacityTool::description()
+                .unwrap()
+                .contains("weekly pattern")
+        );
+        let tools = PgmonetaHandler::tool_router().list_all();
+        assert!(tools.iter().any(|tool| tool.name == "forecast_capacity"));
+    }
+
+    #[test]
+    fn test_growth_model_linear() {
+        let model = GrowthModel::fit(&history(10, 100, 10), None, today()).unwrap();
+        assert_eq!(model.days, 10);
+        assert!((model.slope - 10.0).abs() < 1e-9);
+        assert!((model.intercept - 100.0).abs() < 1e-9);
+        assert_eq!(model.seasonal, [0.0; 7]);
+        assert!((model.daily_volume(today() + Duration::days(1)) - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_growth_model_weekly_pattern() {
+        // Full backups of 700 on Sundays, incrementals of 100 on the other days
+        let backups: Vec<BackupEntry> = (0..28)
+            .map(|day| {
+                let date = today() - Duration::days(27 - day);
+                let size = if date.weekday() == chrono::Weekday::Sun {
+                    700
+                } else {
+                    100
+                };
+                backup(date, size)
+            })
+            .collect();
+        let model = GrowthModel::fit(&backups, None, today()).unwrap();
+        let sunday = today() + Duration::days(6);
+        assert!(model.daily_volume(sunday) > 600.0);
+        assert!(model.daily_volume(today() + Duration::days(1)) < 200.0);
+        assert!(model.seasonal.iter().sum::<f64>().abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_growth_model_window_and_empty() {
+        let backups = history(30, 100, 0);
+        let model = GrowthModel::fit(&backups, Some(today() - Duration::days(6)), today());
+        assert_eq!(model.unwrap().days, 7);
+        assert!(GrowthModel::fit(&[], None, today()).is_none());
+        assert!(
+            history_start(RetentionPolicy::parse("30").ok().as_ref(), today())
+                == Some(today() - Duration::days(29))
+        );
+        assert!(history_start(None, today()).is_none());
+        let unbounded = RetentionPolicy {
+            days: Some(u32::MAX),
+            ..Default::default()
+        };
+        assert!(history_start(Some(&unbounded), today()).is_none());
+        assert!(RetentionPolicy::parse("4000000000").is_err());
+    }
+
+    #[test]
+    fn test_project_applies_retention() {
+        let backups = history(7, GB, 0);
+        let model = GrowthModel::fit(&backups, None, today()).unwrap();
+        let policy = RetentionPolicy::parse("7").unwrap();
+
+        let kept = project(&backups, Some(&model), Some(&policy), 30, now());
+        // A rolling window of 7 to 8 daily backups
+        assert!(kept.iter().all(|size| *size >= 7 * GB && *size <= 8 * GB));
+
+        let forever = project(&backups, Some(&model), None, 30, now());
+        assert_eq!(forever[0], 7 * GB);
+        assert_eq!(forever[30], 37 * GB);
+    }
+
+    #[test]
+    fn test_forecast_fill_date_and_proposed_retention() {
+        let catalogs = vec![ServerCatalog {
+            server: "primary".to_string(),
+            policy: None,
+            backups: history(10, GB, 0),
+        }];
+        let volume = Volume {
+            total: Some(50 * GB),
+            used: Some(12 * GB),
+            free: Some(38 * GB),
+        };
+        let proposed = RetentionPolicy::parse("7").unwrap();
+
+        let forecast = forecast(&catalogs, volume, Some(&proposed), 60, now(), clock());
+
+        // 2 GB of other data plus 1 GB a day fills 50 GB after 38 days
+        assert_eq!(forecast.current.days_until_full, Some(38));
+        assert_eq!(
+            forecast.current.fill_date.as_deref(),
+            Some("2026-08-20 00:00:00+00:00")
+        );
+        assert_eq!(forecast.servers[0].daily_volume_bytes, GB);
+        assert!(
+            forecast
+                .notes
+                .iter()
+                .any(|note| note.contains("kept forever"))
+        );
+
+        let proposed = forecast.proposed.unwrap();
+        assert_eq!(proposed.retention, "7");
+        assert_eq!(proposed.days_until_full, None);
+        assert_eq!(proposed.space_freed_now.as_deref(), Some("3.00 GB"));
+        assert!(proposed.used_at_horizon_bytes <= 10 * GB);
+        assert_eq!(forecast.current.points.first().unwrap().date, "2026-07-13");
+        assert_eq!(forecast.current.points.last().unwrap().date, "2026-09-11");
+    }
+
+    #[test]
+    fn test_forecast_without_total_space() {
+        let catalogs = vec![ServerCatalog {
+            server: "primary".to_string(),
+            policy: RetentionPolicy::parse("7").ok(),
+            backups: Vec::new(),
+        }];
+        let forecast = forecast(&catalogs, Volume::default(), None, 7, now(), clock());
+        assert_eq!(forecast.current.days_until_full, None);
+        assert_eq!(forecast.current.used_at_horizon_bytes, 0);
+        assert!(forecast.proposed.is_none());
+        assert!(
+            forecast
+                .notes
+                .iter()
+                .any(|note| note.contains("no fill date"))
+        );
+        assert!(
+            forecast
+                .notes
+                .iter()
+                .any(|note| note.contains("no backup sizes"))
+        );
+    }
+}
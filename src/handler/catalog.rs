@@ -0,0 +1,689 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Typed access to the pgmoneta backup catalog.
+//!
+//! The tools in this module tree mostly forward raw pgmoneta responses, but the
+//! analysis tools (restore planning, comparisons, simulations) need to reason
+//! about the `LIST_BACKUP` and `INFO` responses. This module parses those raw,
+//! untranslated responses into [`BackupEntry`] values.
+
+use super::PgmonetaHandler;
+use crate::client::PgmonetaClient;
+use crate::configuration::{self, CONFIG};
+use crate::constant::{
+    Compression, Encryption, MANAGEMENT_ARGUMENT_STATUS, MANAGEMENT_CATEGORY_OUTCOME,
+    ManagementError, Sort,
+};
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
+use rmcp::ErrorData as McpError;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Format of the timestamp based backup identifiers generated by pgmoneta.
+pub(crate) const BACKUP_ID_FORMAT: &str = "%Y%m%d%H%M%S";
+
+/// A single backup as reported by pgmoneta `LIST_BACKUP` or `INFO`.
+///
+/// Every field except the identifier is optional, since `LIST_BACKUP` reports
+/// a subset of what `INFO` reports, and older pgmoneta versions omit some keys.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct BackupEntry {
+    pub backup: String,
+    pub server: Option<String>,
+    pub valid: Option<bool>,
+    pub keep: bool,
+    pub incremental: bool,
+    pub incremental_parent: Option<String>,
+    pub backup_size: Option<u64>,
+    pub restore_size: Option<u64>,
+    pub biggest_file_size: Option<u64>,
+    pub delta: Option<u64>,
+    pub compression: Option<u64>,
+    pub encryption: Option<u64>,
+    pub comments: Option<String>,
+    pub wal: Option<String>,
+    pub elapsed: Option<f64>,
+    pub start_lsn: Option<u64>,
+    pub end_lsn: Option<u64>,
+    pub checkpoint_lsn: Option<u64>,
+    pub start_timeline: Option<u32>,
+    pub end_timeline: Option<u32>,
+}
+
+impl BackupEntry {
+    /// Builds an entry from a raw (untranslated) backup object.
+    pub fn from_json(object: &Map<String, Value>) -> Option<Self> {
+        let backup = value_as_string(object.get("Backup")?)?;
+        Some(Self {
+            backup,
+            server: object.get("Server").and_then(value_as_string),
+            valid: object.get("Valid").and_then(value_as_valid),
+            keep: object.get("Keep").and_then(value_as_bool).unwrap_or(false),
+            incremental: object
+                .get("Incremental")
+                .and_then(value_as_bool)
+                .unwrap_or(false),
+            incremental_parent: object
+                .get("IncrementalParent")
+                .and_then(value_as_string)
+                .filter(|parent| !parent.is_empty()),
+            backup_size: object.get("BackupSize").and_then(Value::as_u64),
+            restore_size: object.get("RestoreSize").and_then(Value::as_u64),
+            biggest_file_size: object.get("BiggestFileSize").and_then(Value::as_u64),
+            delta: object.get("Delta").and_then(Value::as_u64),
+            compression: object.get("Compression").and_then(Value::as_u64),
+            encryption: object.get("Encryption").and_then(Value::as_u64),
+            comments: object
+                .get("Comments")
+                .and_then(value_as_string)
+                .filter(|comments| !comments.is_empty()),
+            wal: object
+                .get("WAL")
+                .and_then(value_as_string)
+                .filter(|wal| !wal.is_empty() && wal != "0"),
+            elapsed: object.get("Elapsed").and_then(value_as_f64),
+            start_lsn: split_lsn(object, "StartHiLSN", "StartLoLSN"),
+            end_lsn: split_lsn(object, "EndHiLSN", "EndLoLSN"),
+            checkpoint_lsn: split_lsn(object, "CheckpointHiLSN", "CheckpointLoLSN"),
+            start_timeline: object
+                .get("StartTimeline")
+                .and_then(Value::as_u64)
+                .map(|tli| tli as u32),
+            end_timeline: object
+                .get("EndTimeline")
+                .and_then(Value::as_u64)
+                .map(|tli| tli as u32),
+        })
+    }
+
+    /// The local start time encoded in the backup identifier.
+    pub fn timestamp(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(&self.backup, BACKUP_ID_FORMAT).ok()
+    }
+
+    /// The estimated local end time of the backup, using `Elapsed` when known.
+    pub fn end_timestamp(&self) -> Option<NaiveDateTime> {
+        let start = self.timestamp()?;
+        let elapsed = self.elapsed.unwrap_or(0.0).max(0.0);
+        Some(start + chrono::Duration::milliseconds((elapsed * 1000.0) as i64))
+    }
+
+    /// The annotations of the backup, stored by pgmoneta as `key|comment,key|comment`.
+    pub fn annotations(&self) -> BTreeMap<String, String> {
+        self.comments
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .filter(|annotation| !annotation.trim().is_empty())
+            .map(|annotation| match annotation.split_once('|') {
+                Some((key, comment)) => (key.trim().to_string(), comment.trim().to_string()),
+                None => (annotation.trim().to_string(), String::new()),
+            })
+            .collect()
+    }
+
+    /// The name of the compression algorithm, if reported.
+    pub fn compression_name(&self) -> Option<String> {
+        self.compression.map(|code| {
+            Compression::translate_compression_enum(code as u8)
+                .map(str::to_string)
+                .unwrap_or_else(|_| format!("unknown ({code})"))
+        })
+    }
+
+    /// The name of the encryption algorithm, if reported.
+    pub fn encryption_name(&self) -> Option<String> {
+        self.encryption.map(|code| {
+            Encryption::translate_encryption_enum(code as u8)
+                .map(str::to_string)
+                .unwrap_or_else(|_| format!("unknown ({code})"))
+        })
+    }
+}
+
+/// The clock in which backup identifiers and human readable times are interpreted.
+///
+/// This follows the `timezone` setting of the `[pgmoneta_mcp]` section, which
+/// should match the timezone of the pgmoneta host.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct BackupClock {
+    offset: Option<FixedOffset>,
+}
+
+impl BackupClock {
+    /// Creates a clock using a fixed offset, or the local timezone for `None`.
+    pub fn new(offset: Option<FixedOffset>) -> Self {
+        Self { offset }
+    }
+
+    /// Creates a clock from the loaded configuration, falling back to local time.
+    pub fn configured() -> Self {
+        let offset = CONFIG
+            .get()
+            .and_then(|config| configuration::parse_timezone(&config.pgmoneta_mcp.timezone).ok())
+            .flatten();
+        Self::new(offset)
+    }
+
+    /// The current wall clock time.
+    pub fn now(&self) -> NaiveDateTime {
+        match self.offset {
+            Some(offset) => Utc::now().with_timezone(&offset).naive_local(),
+            None => Local::now().naive_local(),
+        }
+    }
+
+    /// Converts a timestamp with an explicit offset into this clock.
+    pub fn localize(&self, datetime: DateTime<FixedOffset>) -> NaiveDateTime {
+        match self.offset {
+            Some(offset) => datetime.with_timezone(&offset).naive_local(),
+            None => datetime.with_timezone(&Local).naive_local(),
+        }
+    }
+
+    /// Converts a timestamp of this clock into Unix milliseconds.
+    pub fn timestamp_millis(&self, time: NaiveDateTime) -> Option<i64> {
+        let time = match self.offset {
+            Some(offset) => offset.from_local_datetime(&time).earliest()?.to_utc(),
+            None => Local.from_local_datetime(&time).earliest()?.to_utc(),
+        };
+        Some(time.timestamp_millis())
+    }
+
+    /// Formats a timestamp of this clock with its UTC offset, e.g. `2026-07-06 11:30:00+02:00`.
+    pub fn format(&self, time: NaiveDateTime) -> String {
+        let offset = match self.offset {
+            Some(offset) => Some(offset),
+            None => Local
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|local| *local.offset()),
+        };
+        match offset {
+            Some(offset) => format!("{}{}", time.format("%Y-%m-%d %H:%M:%S"), offset),
+            None => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
+/// Resolves a backup identifier against a catalog.
+///
+/// `newest`/`latest` resolve to the most recent backup and `oldest` to the
+/// first one; any other value must match a backup identifier exactly.
+pub(crate) fn resolve_backup<'a>(
+    backups: &'a [BackupEntry],
+    backup_id: &str,
+) -> Option<&'a BackupEntry> {
+    match backup_id.trim().to_lowercase().as_str() {
+        "newest" | "latest" => backups.iter().max_by(|a, b| a.backup.cmp(&b.backup)),
+        "oldest" => backups.iter().min_by(|a, b| a.backup.cmp(&b.backup)),
+        id => backups.iter().find(|backup| backup.backup == id),
+    }
+}
+
+/// Parses the backups out of a raw `LIST_BACKUP` response, sorted oldest first.
+pub(crate) fn parse_backup_list(response: &Map<String, Value>) -> Vec<BackupEntry> {
+    let backups = response
+        .get("Response")
+        .and_then(|value| value.get("Backups"))
+        .or_else(|| response.get("Backups"))
+        .and_then(Value::as_array);
+
+    let mut entries: Vec<BackupEntry> = backups
+        .map(|backups| {
+            backups
+                .iter()
+                .filter_map(Value::as_object)
+                .filter_map(BackupEntry::from_json)
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort_by(|a, b| a.backup.cmp(&b.backup));
+    entries
+}
+
+/// The server objects of a raw `STATUS_DETAILS` response.
+pub(crate) fn parse_servers(response: &Map<String, Value>) -> Vec<&Map<String, Value>> {
+    response
+        .get("Response")
+        .and_then(|value| value.get("Servers"))
+        .or_else(|| response.get("Servers"))
+        .and_then(Value::as_array)
+        .map(|servers| servers.iter().filter_map(Value::as_object).collect())
+        .unwrap_or_default()
+}
+
+/// Parses the server names out of a raw `STATUS_DETAILS` response, sorted.
+pub(crate) fn parse_server_names(response: &Map<String, Value>) -> Vec<String> {
+    let mut names: Vec<String> = parse_servers(response)
+        .into_iter()
+        .filter_map(|server| server.get("Server"))
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Parses the backup out of a raw `INFO` response.
+pub(crate) fn parse_backup_info(response: &Map<String, Value>) -> Option<BackupEntry> {
+    let object = response
+        .get("Response")
+        .and_then(Value::as_object)
+        .unwrap_or(response);
+    BackupEntry::from_json(object)
+}
+
+/// Fails with a descriptive error when pgmoneta reports an unsuccessful outcome.
+pub(crate) fn ensure_success(response: &Map<String, Value>) -> Result<(), McpError> {
+    let outcome = response.get(MANAGEMENT_CATEGORY_OUTCOME);
+    let status = outcome
+        .and_then(|outcome| outcome.get(MANAGEMENT_ARGUMENT_STATUS))
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    if status {
+        return Ok(());
+    }
+
+    let error = outcome
+        .and_then(|outcome| outcome.get("Error"))
+        .and_then(Value::as_u64)
+        .map(|code| ManagementError::translate_error_enum(code as u32))
+        .unwrap_or("unknown error");
+    Err(McpError::internal_error(
+        format!("pgmoneta reported a failure: {error}"),
+        None,
+    ))
+}
+
+/// Fetches the detailed status (`STATUS_DETAILS`) as a raw response.
+pub(crate) async fn fetch_status(username: &str) -> Result<Map<String, Value>, McpError> {
+    let result = PgmonetaClient::request_status(username, true)
+        .await
+        .map_err(|e| {
+            McpError::internal_error(format!("Failed to retrieve status: {:?}", e), None)
+        })?;
+    let response = PgmonetaHandler::_parse_and_check_result(&result)?;
+    ensure_success(&response)?;
+    Ok(response)
+}
+
+/// A space field of the detailed status, e.g. `TotalSpace`, in bytes.
+pub(crate) fn space_bytes(status: &Map<String, Value>, field: &str) -> Option<u64> {
+    status
+        .get("Response")
+        .and_then(|value| value.get(field))
+        .or_else(|| status.get(field))
+        .and_then(Value::as_u64)
+}
+
+/// The newest backup marked valid, from a catalog sorted oldest first.
+pub(crate) fn newest_valid(backups: &[BackupEntry]) -> Option<&BackupEntry> {
+    backups
+        .iter()
+        .rev()
+        .find(|backup| backup.valid == Some(true))
+}
+
+/// Fetches the backup catalog of a server, sorted oldest first.
+pub(crate) async fn fetch_backups(
+    username: &str,
+    server: &str,
+) -> Result<Vec<BackupEntry>, McpError> {
+    let result = PgmonetaClient::request_list_backups(username, server, Sort::ASC)
+        .await
+        .map_err(|e| McpError::internal_error(format!("Failed to list backups: {:?}", e), None))?;
+    let response = PgmonetaHandler::_parse_and_check_result(&result)?;
+    ensure_success(&response)?;
+    Ok(parse_backup_list(&response))
+}
+
+/// Fetches the detailed information of a single backup.
+pub(crate) async fn fetch_backup_info(
+    username: &str,
+    server: &str,
+    backup_id: &str,
+) -> Result<BackupEntry, McpError> {
+    let result = PgmonetaClient::request_backup_info(username, server, backup_id)
+        .await
+        .map_err(|e| {
+            McpError::internal_error(
+                format!("Failed to retrieve backup information: {:?}", e),
+                None,
+            )
+        })?;
+    let response = PgmonetaHandler::_parse_and_check_result(&result)?;
+    ensure_success(&response)?;
+    parse_backup_info(&response).ok_or_else(|| {
+        McpError::internal_error(
+            format!("Backup information for '{backup_id}' is missing the backup identifier"),
+            None,
+        )
+    })
+}
+
+/// Formats a number of seconds as e.g. `2d 3h 4m` or `5m 6s`.
+pub(crate) fn format_duration(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.unsigned_abs();
+    let (days, hours, minutes, seconds) = (
+        seconds / 86400,
+        seconds % 86400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+    );
+    let text = if days > 0 {
+        format!("{days}d {hours}h {minutes}m")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m {seconds}s")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    };
+    format!("{sign}{text}")
+}
+
+/// The incremental chain of a backup, from its full base backup up to the backup itself.
+pub(crate) fn incremental_chain(backup: &BackupEntry, backups: &[BackupEntry]) -> Vec<String> {
+    let mut chain = vec![backup.backup.clone()];
+    let mut current = backup.incremental_parent.clone();
+    while let Some(parent) = current {
+        if chain.contains(&parent) {
+            break;
+        }
+        current = backups
+            .iter()
+            .find(|candidate| candidate.backup == parent)
+            .and_then(|candidate| candidate.incremental_parent.clone());
+        chain.push(parent);
+    }
+    chain.reverse();
+    chain
+}
+
+/// All backups that depend on a backup through incremental parents, oldest first.
+pub(crate) fn dependents(backup_id: &str, backups: &[BackupEntry]) -> Vec<String> {
+    let mut dependents: Vec<String> = Vec::new();
+    let mut pending = vec![backup_id.to_string()];
+    while let Some(parent) = pending.pop() {
+        for child in backups
+            .iter()
+            .filter(|candidate| candidate.incremental_parent.as_deref() == Some(parent.as_str()))
+        {
+            if child.backup != backup_id && !dependents.contains(&child.backup) {
+                dependents.push(child.backup.clone());
+                pending.push(child.backup.clone());
+            }
+        }
+    }
+    dependents.sort();
+    dependents
+}
+
+/// Parses a PostgreSQL `X/Y` LSN into its 64-bit representation.
+pub(crate) fn parse_lsn(lsn: &str) -> Option<u64> {
+    let (hi, lo) = lsn.trim().split_once('/')?;
+    let hi = u64::from_str_radix(hi.trim_start_matches("0x"), 16).ok()?;
+    let lo = u64::from_str_radix(lo.trim_start_matches("0x"), 16).ok()?;
+    if hi > u32::MAX as u64 || lo > u32::MAX as u64 {
+        return None;
+    }
+    Some((hi << 32) | lo)
+}
+
+/// Formats a 64-bit LSN in the PostgreSQL `X/Y` notation.
+pub(crate) fn format_lsn(lsn: u64) -> String {
+    format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFF_FFFF)
+}
+
+fn split_lsn(object: &Map<String, Value>, hi: &str, lo: &str) -> Option<u64> {
+    let hi = object.get(hi).and_then(Value::as_u64)?;
+    let lo = object.get(lo).and_then(Value::as_u64)?;
+    Some((hi << 32) | (lo & 0xFFFF_FFFF))
+}
+
+pub(crate) fn value_as_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+fn value_as_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(boolean) => Some(*boolean),
+        Value::Number(number) => number.as_i64().map(|n| n != 0),
+        Value::String(text) => match text.to_lowercase().as_str() {
+            "true" | "1" | "yes" => Some(true),
+            "false" | "0" | "no" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn value_as_valid(value: &Value) -> Option<bool> {
+    // pgmoneta reports -1 when the validity of a backup is unknown
+    match value {
+        Value::Number(number) => match number.as_i64() {
+            Some(1) => Some(true),
+            Some(0) => Some(false),
+            _ => None,
+        },
+        other => value_as_bool(other),
+    }
+}
+
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn backup(id: &str) -> BackupEntry {
+        BackupEntry {
+            backup: id.to_string(),
+            valid: Some(true),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_backup_list_sorts_and_normalizes_entries() {
+        let response = json!({
+            "Outcome": {"Status": true},
+            "Response": {
+                "Backups": [
+                    {"Backup": 20260410142257u64, "Valid": 1, "Keep": true, "BackupSize": 1024,
+                     "Incremental": true, "IncrementalParent": "20260409120000", "WAL": 0},
+                    {"Backup": "20260409120000", "Valid": -1, "Keep": false, "Comments": null,
+                     "IncrementalParent": null}
+                ]
+            }
+        });
+
+        let backups = parse_backup_list(response.as_object().unwrap());
+
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].backup, "20260409120000");
+        assert_eq!(backups[0].valid, None);
+        assert_eq!(backups[0].incremental_parent, None);
+        assert_eq!(backups[1].valid, Some(true));
+        assert!(backups[1].keep);
+        assert!(backups[1].incremental);
+        assert_eq!(
+            backups[1].incremental_parent.as_deref(),
+            Some("20260409120000")
+        );
+        assert_eq!(backups[1].backup_size, Some(1024));
+        assert_eq!(backups[1].wal, None);
+    }
+
+    #[test]
+    fn test_parse_backup_info_combines_lsn_halves() {
+        let response = json!({
+            "Outcome": {"Status": true},
+            "Response": {
+                "Backup": "20260410142257",
+                "StartHiLSN": 1, "StartLoLSN": 0x28,
+                "EndHiLSN": 1, "EndLoLSN": 0x100,
+                "StartTimeline": 1, "EndTimeline": 2,
+                "Elapsed": 2.5
+            }
+        });
+
+        let info = parse_backup_info(response.as_object().unwrap()).unwrap();
+
+        assert_eq!(info.start_lsn, Some(0x1_0000_0028));
+        assert_eq!(info.end_lsn, Some(0x1_0000_0100));
+        assert_eq!(info.start_timeline, Some(1));
+        assert_eq!(info.end_timeline, Some(2));
+        assert_eq!(
+            info.end_timestamp().unwrap().format("%H:%M:%S").to_string(),
+            "14:22:59"
+        );
+    }
+
+    #[test]
+    fn test_resolve_backup_handles_symbolic_identifiers() {
+        let backups = vec![backup("20260101000000"), backup("20260301000000")];
+
+        assert_eq!(
+            resolve_backup(&backups, "newest").unwrap().backup,
+            "20260301000000"
+        );
+        assert_eq!(
+            resolve_backup(&backups, "Latest").unwrap().backup,
+            "20260301000000"
+        );
+        assert_eq!(
+            resolve_backup(&backups, "oldest").unwrap().backup,
+            "20260101000000"
+        );
+        assert!(resolve_backup(&backups, "20260201000000").is_none());
+        assert!(resolve_backup(&[], "newest").is_none());
+    }
+
+    #[test]
+    fn test_backup_clock_uses_configured_offset() {
+        let clock = BackupClock::new(FixedOffset::east_opt(2 * 3600));
+        let time = NaiveDateTime::parse_from_str("20260706113000", BACKUP_ID_FORMAT).unwrap();
+
+        assert_eq!(clock.format(time), "2026-07-06 11:30:00+02:00");
+        let utc = DateTime::parse_from_rfc3339("2026-07-06T09:30:00Z").unwrap();
+        assert_eq!(clock.localize(utc), time);
+    }
+
+    #[test]
+    fn test_annotations_and_algorithm_names() {
+        let entry = BackupEntry {
+            comments: Some("owner|dba team,ticket|OPS-42,flag".to_string()),
+            compression: Some(2),
+            encryption: Some(9),
+            ..backup("20260101000000")
+        };
+
+        let annotations = entry.annotations();
+        assert_eq!(annotations["owner"], "dba team");
+        assert_eq!(annotations["ticket"], "OPS-42");
+        assert_eq!(annotations["flag"], "");
+        assert_eq!(entry.compression_name().as_deref(), Some("zstd"));
+        assert_eq!(entry.encryption_name().as_deref(), Some("unknown (9)"));
+        assert!(backup("20260101000000").annotations().is_empty());
+    }
+
+    #[test]
+    fn test_incremental_chain_follows_parents() {
+        let backups = vec![
+            backup("20260101000000"),
+            BackupEntry {
+                incremental: true,
+                incremental_parent: Some("20260101000000".to_string()),
+                ..backup("20260102000000")
+            },
+            BackupEntry {
+                incremental: true,
+                incremental_parent: Some("20260102000000".to_string()),
+                ..backup("20260103000000")
+            },
+        ];
+
+        assert_eq!(
+            incremental_chain(&backups[2], &backups),
+            vec!["20260101000000", "20260102000000", "20260103000000"]
+        );
+        assert_eq!(
+            incremental_chain(&backups[0], &backups),
+            vec!["20260101000000"]
+        );
+        assert_eq!(
+            dependents("20260101000000", &backups),
+            vec!["20260102000000", "20260103000000"]
+        );
+        assert!(dependents("20260103000000", &backups).is_empty());
+    }
+
+    #[test]
+    fn test_lsn_round_trip() {
+        assert_eq!(parse_lsn("0/5000000"), Some(0x500_0000));
+        assert_eq!(parse_lsn("16/B374D848"), Some(0x16_B374_D848));
+        assert_eq!(format_lsn(0x16_B374_D848), "16/B374D848");
+        assert_eq!(parse_lsn("nonsense"), None);
+        assert_eq!(parse_lsn("1/ZZ"), None);
+    }
+
+    #[test]
+    fn test_ensure_success_translates_error_code() {
+        let response = json!({"Outcome": {"Status": false, "Error": 600}});
+        let err = ensure_success(response.as_object().unwrap()).unwrap_err();
+        assert!(err.message.contains("Restore: no backup available"));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(42), "42s");
+        assert_eq!(format_duration(3725), "1h 2m 5s");
+        assert_eq!(format_duration(180_000), "2d 2h 0m");
+        assert_eq!(format_duration(-90), "-1m 30s");
+    }
+
+    #[test]
+    fn test_parse_server_names() {
+        let response = json!({
+            "Outcome": {"Status": true},
+            "Response": {
+                "Servers": [
+                    {"Server": "replica", "Backups": 2},
+                    {"Server": "primary", "Backups": 5},
+                    {"Backups": 0}
+                ]
+            }
+        });
+        let names = parse_server_names(response.as_object().unwrap());
+        assert_eq!(names, vec!["primary", "replica"]);
+    }
+}
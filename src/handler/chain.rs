@@ -0,0 +1,502 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::PgmonetaHandler;
+use super::catalog::{self, BackupEntry};
+use super::validation::{self, Keyword};
+use crate::constant::ManagementError;
+use rmcp::ErrorData as McpError;
+use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
+use rmcp::model::JsonObject;
+use rmcp::schemars;
+use serde::Serialize;
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct BackupChainRequest {
+    pub username: String,
+    pub server: String,
+    /// Backup to evaluate for deletion ("newest", "latest" and "oldest" are accepted)
+    #[schemars(pattern(validation::BACKUP_ID_PATTERN))]
+    #[serde(
+        default,
+        deserialize_with = "validation::deserialize_optional_backup_id"
+    )]
+    pub backup_id: Option<String>,
+    /// Rendering of the tree, either "ascii" (default) or "mermaid"
+    #[serde(default, deserialize_with = "validation::deserialize_optional_keyword")]
+    pub format: Option<TreeFormat>,
+}
+
+/// The rendering of a backup tree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, schemars::JsonSchema)]
+#[schemars(rename_all = "lowercase")]
+pub enum TreeFormat {
+    #[default]
+    Ascii,
+    Mermaid,
+}
+
+impl TreeFormat {
+    /// The keyword of the format.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TreeFormat::Ascii => "ascii",
+            TreeFormat::Mermaid => "mermaid",
+        }
+    }
+}
+
+impl Keyword for TreeFormat {
+    const ARGUMENT: &'static str = "format";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("ascii", TreeFormat::Ascii),
+        ("mermaid", TreeFormat::Mermaid),
+    ];
+}
+
+impl<'de> serde::Deserialize<'de> for TreeFormat {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        validation::deserialize_keyword(deserializer)
+    }
+}
+
+/// Tool for showing the incremental backup graph of a server.
+pub struct BackupChainTool;
+
+impl ToolBase for BackupChainTool {
+    type Parameter = BackupChainRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "backup_chain".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "Show the parent/child graph of the incremental backups of a server, \
+            as JSON and as an ASCII or Mermaid tree (format \"ascii\" or \"mermaid\"). \
+            When a backup_id is given, also report which backups depend on it and would \
+            become unrestorable if it were deleted. \
+            The username has to be one of the pgmoneta admins to be able to access pgmoneta."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for BackupChainTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: BackupChainRequest,
+    ) -> Result<String, McpError> {
+        let format = request.format.unwrap_or_default();
+        if let Some(backup_id) = &request.backup_id {
+            validation::validate_backup_id("backup_id", backup_id)?;
+        }
+
+        let mut backups = catalog::fetch_backups(&request.username, &request.server).await?;
+        // Older pgmoneta versions omit the parent from LIST_BACKUP
+        for backup in backups.iter_mut() {
+            if backup.incremental && backup.incremental_parent.is_none() {
+                let info =
+                    catalog::fetch_backup_info(&request.username, &request.server, &backup.backup)
+                        .await?;
+                backup.incremental_parent = info.incremental_parent;
+            }
+        }
+
+        let target = match request.backup_id.as_deref() {
+            Some(backup_id) => Some(
+                catalog::resolve_backup(&backups, backup_id)
+                    .map(|backup| backup.backup.clone())
+                    .ok_or_else(|| {
+                        McpError::invalid_params(
+                            format!(
+                                "Backup '{}' does not exist on server '{}'",
+                                backup_id, request.server
+                            ),
+                            None,
+                        )
+                    })?,
+            ),
+            None => None,
+        };
+
+        let chain = build_chain(&request.server, &backups, target.as_deref(), format);
+        serde_json::to_string(&chain).map_err(|e| {
+            McpError::internal_error(format!("Failed to serialize backup chain: {:?}", e), None)
+        })
+    }
+}
+
+/// The backup graph of a server.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct BackupChain {
+    server: String,
+    nodes: Vec<ChainNode>,
+    roots: Vec<String>,
+    format: &'static str,
+    tree: String,
+    delete_impact: Option<DeleteImpact>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChainNode {
+    backup: String,
+    parent: Option<String>,
+    children: Vec<String>,
+    incremental: bool,
+    valid: Option<bool>,
+    keep: bool,
+    restorable: bool,
+}
+
+/// The consequences of deleting a backup.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct DeleteImpact {
+    backup: String,
+    retained: bool,
+    dependents: Vec<String>,
+    unrestorable: Vec<String>,
+    possible_errors: Vec<String>,
+    notes: Vec<String>,
+}
+
+fn build_chain(
+    server: &str,
+    backups: &[BackupEntry],
+    target: Option<&str>,
+    format: TreeFormat,
+) -> BackupChain {
+    let nodes: Vec<ChainNode> = backups
+        .iter()
+        .map(|backup| ChainNode {
+            backup: backup.backup.clone(),
+            parent: backup.incremental_parent.clone(),
+            children: children(&backup.backup, backups),
+            incremental: backup.incremental,
+            valid: backup.valid,
+            keep: backup.keep,
+            restorable: is_restorable(backup, backups),
+        })
+        .collect();
+
+    // A backup whose parent is missing from the catalog is shown as a root
+    let roots: Vec<String> = nodes
+        .iter()
+        .filter(|node| {
+            node.parent
+                .as_ref()
+                .is_none_or(|parent| !nodes.iter().any(|n| &n.backup == parent))
+        })
+        .map(|node| node.backup.clone())
+        .collect();
+
+    let tree = match format {
+        TreeFormat::Mermaid => render_mermaid(&nodes),
+        TreeFormat::Ascii => render_ascii(&nodes, &roots),
+    };
+
+    BackupChain {
+        server: server.to_string(),
+        delete_impact: target.map(|backup_id| delete_impact(backup_id, backups)),
+        nodes,
+        roots,
+        format: format.as_str(),
+        tree,
+    }
+}
+
+fn children(backup_id: &str, backups: &[BackupEntry]) -> Vec<String> {
+    backups
+        .iter()
+        .filter(|candidate| candidate.incremental_parent.as_deref() == Some(backup_id))
+        .map(|candidate| candidate.backup.clone())
+        .collect()
+}
+
+/// A backup is restorable when it and all of its ancestors exist and are not invalid.
+fn is_restorable(backup: &BackupEntry, backups: &[BackupEntry]) -> bool {
+    let chain = catalog::incremental_chain(backup, backups);
+    let complete = chain.first().is_some_and(|base| {
+        backups
+            .iter()
+            .find(|candidate| &candidate.backup == base)
+            .is_some_and(|base| base.incremental_parent.is_none())
+    });
+    complete
+        && chain.iter().all(|id| {
+            backups
+                .iter()
+                .find(|candidate| &candidate.backup == id)
+                .is_some_and(|candidate| candidate.valid != Some(false))
+        })
+}
+
+fn delete_impact(backup_id: &str, backups: &[BackupEntry]) -> DeleteImpact {
+    let backup = backups.iter().find(|backup| backup.backup == backup_id);
+    let retained = backup.is_some_and(|backup| backup.keep);
+    let dependents = catalog::dependents(backup_id, backups);
+
+    let remaining: Vec<BackupEntry> = backups
+        .iter()
+        .filter(|candidate| candidate.backup != backup_id)
+        .cloned()
+        .collect();
+    let unrestorable: Vec<String> = remaining
+        .iter()
+        .filter(|candidate| dependents.contains(&candidate.backup))
+        .filter(|candidate| !is_restorable(candidate, &remaining))
+        .map(|candidate| candidate.backup.clone())
+        .collect();
+
+    let mut possible_errors = Vec::new();
+    let mut notes = Vec::new();
+    if retained {
+        possible_errors.push(ManagementError::translate_error_enum(
+            ManagementError::MANAGEMENT_ERROR_DELETE_BACKUP_RETAINED,
+        ));
+        notes.push(format!(
+            "Backup '{backup_id}' is retained; expunge it before deleting it"
+        ));
+    }
+    if !dependents.is_empty() {
+        possible_errors.push(ManagementError::translate_error_enum(
+            ManagementError::MANAGEMENT_ERROR_DELETE_BACKUP_ROLLUP,
+        ));
+        possible_errors.push(ManagementError::translate_error_enum(
+            ManagementError::MANAGEMENT_ERROR_DELETE_BACKUP_FULL,
+        ));
+        notes.push(format!(
+            "pgmoneta has to roll backup '{backup_id}' up into its child before deleting it; \
+            if the rollup fails the delete is refused and {} dependent backup(s) stay intact",
+            dependents.len()
+        ));
+        notes.push(
+            "Without a rollup the dependent backups lose their parent and cannot be restored"
+                .to_string(),
+        );
+    } else {
+        notes.push(format!(
+            "No other backup depends on '{backup_id}'; deleting it does not affect other backups"
+        ));
+    }
+
+    DeleteImpact {
+        backup: backup_id.to_string(),
+        retained,
+        dependents,
+        unrestorable,
+        possible_errors: possible_errors.into_iter().map(str::to_string).collect(),
+        notes,
+    }
+}
+
+fn node_label(node: &ChainNode) -> String {
+    let mut flags = vec![if node.incremental {
+        "incremental"
+    } else {
+        "full"
+    }];
+    match node.valid {
+        Some(false) => flags.push("invalid"),
+        None => flags.push("validity unknown"),
+        Some(true) => {}
+    }
+    if node.keep {
+        flags.push("keep");
+    }
+    if !node.restorable {
+        flags.push("unrestorable");
+    }
+    format!("{} ({})", node.backup, flags.join(", "))
+}
+
+fn render_ascii(nodes: &[ChainNode], roots: &[String]) -> String {
+    fn walk(
+        nodes: &[ChainNode],
+        id: &str,
+        prefix: &str,
+        last: bool,
+        root: bool,
+        out: &mut Vec<String>,
+    ) {
+        let Some(node) = nodes.iter().find(|node| node.backup == id) else {
+            return;
+        };
+        if root {
+            out.push(node_label(node));
+        } else {
+            let branch = if last { "└── " } else { "├── " };
+            out.push(format!("{prefix}{branch}{}", node_label(node)));
+        }
+        let child_prefix = if root {
+            String::new()
+        } else {
+            format!("{prefix}{}", if last { "    " } else { "│   " })
+        };
+        for (index, child) in node.children.iter().enumerate() {
+            walk(
+                nodes,
+                child,
+                &child_prefix,
+                index + 1 == node.children.len(),
+                false,
+                out,
+            );
+        }
+    }
+
+    let mut out = Vec::new();
+    for root in roots {
+        walk(nodes, root, "", true, true, &mut out);
+    }
+    out.join("\n")
+}
+
+fn render_mermaid(nodes: &[ChainNode]) -> String {
+    let mut out = vec!["graph TD".to_string()];
+    for node in nodes {
+        out.push(format!("    b{}[\"{}\"]", node.backup, node_label(node)));
+    }
+    for node in nodes {
+        for child in &node.children {
+            out.push(format!("    b{} --> b{}", node.backup, child));
+        }
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::handler::server::router::tool::ToolBase;
+
+    fn backup(id: &str, parent: Option<&str>) -> BackupEntry {
+        BackupEntry {
+            backup: id.to_string(),
+            valid: Some(true),
+            incremental: parent.is_some(),
+            incremental_parent: parent.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn catalog() -> Vec<BackupEntry> {
+        vec![
+            backup("20260701000000", None),
+            backup("20260702000000", Some("20260701000000")),
+            backup("20260703000000", Some("20260702000000")),
+            backup("20260703120000", Some("20260702000000")),
+            BackupEntry {
+                keep: true,
+                ..backup("20260704000000", None)
+            },
+        ]
+    }
+
+    #[test]
+    fn test_backup_chain_tool_metadata() {
+        assert_eq!(BackupChainTool::name(), "backup_chain");
+        assert!(BackupChainTool::description().unwrap().contains("Mermaid"));
+        let tools = PgmonetaHandler::tool_router().list_all();
+        assert!(tools.iter().any(|t| t.name == "backup_chain"));
+    }
+
+    #[test]
+    fn test_parse_tree_format() {
+        assert_eq!(TreeFormat::parse(" Mermaid ").unwrap(), TreeFormat::Mermaid);
+        assert!(TreeFormat::parse("dot").is_err());
+        let request: BackupChainRequest = serde_json::from_value(serde_json::json!({
+            "username": "admin", "server": "primary"
+        }))
+        .unwrap();
+        assert_eq!(request.format.unwrap_or_default(), TreeFormat::Ascii);
+    }
+
+    #[test]
+    fn test_build_chain_graph_and_ascii_tree() {
+        let chain = build_chain("primary", &catalog(), None, TreeFormat::Ascii);
+
+        assert_eq!(chain.roots, vec!["20260701000000", "20260704000000"]);
+        assert_eq!(
+            chain.nodes[1].children,
+            vec!["20260703000000", "20260703120000"]
+        );
+        assert!(chain.nodes.iter().all(|node| node.restorable));
+        assert!(chain.delete_impact.is_none());
+        assert_eq!(
+            chain.tree,
+            "20260701000000 (full)\n\
+             └── 20260702000000 (incremental)\n    \
+             ├── 20260703000000 (incremental)\n    \
+             └── 20260703120000 (incremental)\n\
+             20260704000000 (full, keep)"
+        );
+    }
+
+    #[test]
+    fn test_build_chain_mermaid_tree() {
+        let chain = build_chain("primary", &catalog(), None, TreeFormat::Mermaid);
+        assert!(chain.tree.starts_with("graph TD"));
+        assert!(chain.tree.contains("b20260701000000 --> b20260702000000"));
+        assert!(
+            chain
+                .tree
+                .contains("b20260704000000[\"20260704000000 (full, keep)\"]")
+        );
+    }
+
+    #[test]
+    fn test_delete_impact_lists_unrestorable_dependents() {
+        let impact = delete_impact("20260702000000", &catalog());
+
+        assert!(!impact.retained);
+        assert_eq!(impact.dependents, vec!["20260703000000", "20260703120000"]);
+        assert_eq!(impact.unrestorable, impact.dependents);
+        assert!(
+            impact
+                .possible_errors
+                .contains(&"Delete backup: rollup failed".to_string())
+        );
+
+        let impact = delete_impact("20260704000000", &catalog());
+        assert!(impact.retained);
+        assert!(impact.dependents.is_empty());
+        assert_eq!(
+            impact.possible_errors,
+            vec!["Delete backup: backup is retained"]
+        );
+    }
+
+    #[test]
+    fn test_missing_parent_makes_backup_unrestorable() {
+        let backups = vec![backup("20260702000000", Some("20260701000000"))];
+        let chain = build_chain("primary", &backups, None, TreeFormat::Ascii);
+        assert_eq!(chain.roots, vec!["20260702000000"]);
+        assert!(!chain.nodes[0].restorable);
+        assert_eq!(chain.tree, "20260702000000 (incremental, unrestorable)");
+    }
+}
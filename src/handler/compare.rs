@@ -0,0 +1,528 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::PgmonetaHandler;
+use super::catalog::{self, BackupEntry, format_duration};
+use super::validation;
+use crate::utils::Utility;
+use rmcp::ErrorData as McpError;
+use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
+use rmcp::model::JsonObject;
+use rmcp::schemars;
+use serde::Serialize;
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct CompareBackupsRequest {
+    pub username: String,
+    pub server: String,
+    /// The older backup of the comparison ("newest", "latest" and "oldest" are accepted)
+    #[schemars(pattern(validation::BACKUP_ID_PATTERN))]
+    #[serde(deserialize_with = "validation::deserialize_backup_id")]
+    pub from_backup_id: String,
+    /// The newer backup of the comparison ("newest", "latest" and "oldest" are accepted)
+    #[schemars(pattern(validation::BACKUP_ID_PATTERN))]
+    #[serde(deserialize_with = "validation::deserialize_backup_id")]
+    pub to_backup_id: String,
+}
+
+/// Tool for comparing two backups of the same server.
+pub struct CompareBackupsTool;
+
+impl ToolBase for CompareBackupsTool {
+    type Parameter = CompareBackupsRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "compare_backups".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "Compare two backups of the same server and describe what changed between them. \
+            Covers size and delta, LSN range, WAL span, duration, compression, encryption, \
+            incremental parent chain, annotations, validity and retention, \
+            and ends with a short summary. \
+            \"newest\", \"latest\" or \"oldest\" are also accepted as backup identifiers. \
+            The username has to be one of the pgmoneta admins to be able to access pgmoneta."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for CompareBackupsTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: CompareBackupsRequest,
+    ) -> Result<String, McpError> {
+        validation::validate_backup_id("from_backup_id", &request.from_backup_id)?;
+        validation::validate_backup_id("to_backup_id", &request.to_backup_id)?;
+        let backups = catalog::fetch_backups(&request.username, &request.server).await?;
+        let resolve = |backup_id: &str| {
+            catalog::resolve_backup(&backups, backup_id)
+                .map(|backup| backup.backup.clone())
+                .ok_or_else(|| {
+                    McpError::invalid_params(
+                        format!(
+                            "Backup '{}' does not exist on server '{}'",
+                            backup_id, request.server
+                        ),
+                        None,
+                    )
+                })
+        };
+        let from_id = resolve(&request.from_backup_id)?;
+        let to_id = resolve(&request.to_backup_id)?;
+
+        let from = catalog::fetch_backup_info(&request.username, &request.server, &from_id).await?;
+        let to = if to_id == from_id {
+            from.clone()
+        } else {
+            catalog::fetch_backup_info(&request.username, &request.server, &to_id).await?
+        };
+
+        let comparison = compare_backups(&request.server, &from, &to, &backups);
+        serde_json::to_string(&comparison).map_err(|e| {
+            McpError::internal_error(
+                format!("Failed to serialize backup comparison: {:?}", e),
+                None,
+            )
+        })
+    }
+}
+
+/// The structured difference between two backups.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct BackupComparison {
+    server: String,
+    from: String,
+    to: String,
+    time_between: Option<String>,
+    backup_size: SizeDiff,
+    restore_size: SizeDiff,
+    delta: SizeDiff,
+    lsn_range: Change<Option<String>>,
+    wal_span: WalSpan,
+    duration: Change<Option<String>>,
+    compression: Change<Option<String>>,
+    encryption: Change<Option<String>>,
+    incremental_chain: ChainDiff,
+    annotations: AnnotationDiff,
+    valid: Change<String>,
+    retained: Change<bool>,
+    summary: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Change<T> {
+    from: T,
+    to: T,
+    changed: bool,
+}
+
+impl<T: PartialEq> Change<T> {
+    fn new(from: T, to: T) -> Self {
+        let changed = from != to;
+        Self { from, to, changed }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct SizeDiff {
+    from: Option<String>,
+    to: Option<String>,
+    difference: Option<String>,
+    percent: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct WalSpan {
+    from_wal: Option<String>,
+    to_wal: Option<String>,
+    generated_between: Option<String>,
+    timeline: Change<Option<u32>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChainDiff {
+    from: Vec<String>,
+    to: Vec<String>,
+    shared_base: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct AnnotationDiff {
+    added: BTreeMap<String, String>,
+    removed: BTreeMap<String, String>,
+    changed: BTreeMap<String, Change<String>>,
+}
+
+fn compare_backups(
+    server: &str,
+    from: &BackupEntry,
+    to: &BackupEntry,
+    backups: &[BackupEntry],
+) -> BackupComparison {
+    let time_between = match (from.timestamp(), to.timestamp()) {
+        (Some(from), Some(to)) => Some(format_duration((to - from).num_seconds())),
+        _ => None,
+    };
+
+    let wal_span = WalSpan {
+        from_wal: from.wal.clone(),
+        to_wal: to.wal.clone(),
+        generated_between: match (from.end_lsn, to.start_lsn) {
+            (Some(end), Some(start)) if start >= end => {
+                Some(Utility::format_file_size(start - end))
+            }
+            _ => None,
+        },
+        timeline: Change::new(from.end_timeline, to.start_timeline),
+    };
+
+    let from_chain = catalog::incremental_chain(from, backups);
+    let to_chain = catalog::incremental_chain(to, backups);
+    let shared_base = from_chain
+        .iter()
+        .zip(to_chain.iter())
+        .take_while(|(a, b)| a == b)
+        .last()
+        .map(|(base, _)| base.clone());
+
+    let mut comparison = BackupComparison {
+        server: server.to_string(),
+        from: from.backup.clone(),
+        to: to.backup.clone(),
+        time_between,
+        backup_size: size_diff(from.backup_size, to.backup_size),
+        restore_size: size_diff(from.restore_size, to.restore_size),
+        delta: size_diff(from.delta, to.delta),
+        lsn_range: Change::new(lsn_range(from), lsn_range(to)),
+        wal_span,
+        duration: Change::new(
+            from.elapsed.map(|e| format_duration(e.round() as i64)),
+            to.elapsed.map(|e| format_duration(e.round() as i64)),
+        ),
+        compression: Change::new(from.compression_name(), to.compression_name()),
+        encryption: Change::new(from.encryption_name(), to.encryption_name()),
+        incremental_chain: ChainDiff {
+            from: from_chain,
+            to: to_chain,
+            shared_base,
+        },
+        annotations: annotation_diff(&from.annotations(), &to.annotations()),
+        valid: Change::new(validity(from.valid), validity(to.valid)),
+        retained: Change::new(from.keep, to.keep),
+        summary: String::new(),
+    };
+    comparison.summary = summarize(&comparison, from, to);
+    comparison
+}
+
+fn size_diff(from: Option<u64>, to: Option<u64>) -> SizeDiff {
+    let (difference, percent) = match (from, to) {
+        (Some(from), Some(to)) => {
+            let difference = if to >= from {
+                format!("+{}", Utility::format_file_size(to - from))
+            } else {
+                format!("-{}", Utility::format_file_size(from - to))
+            };
+            let percent = (from > 0)
+                .then(|| ((to as f64 - from as f64) / from as f64 * 10000.0).round() / 100.0);
+            (Some(difference), percent)
+        }
+        _ => (None, None),
+    };
+    SizeDiff {
+        from: from.map(Utility::format_file_size),
+        to: to.map(Utility::format_file_size),
+        difference,
+        percent,
+    }
+}
+
+fn lsn_range(backup: &BackupEntry) -> Option<String> {
+    match (backup.start_lsn, backup.end_lsn) {
+        (Some(start), Some(end)) => Some(format!(
+            "{} - {}",
+            catalog::format_lsn(start),
+            catalog::format_lsn(end)
+        )),
+        _ => None,
+    }
+}
+
+fn validity(valid: Option<bool>) -> String {
+    match valid {
+        Some(true) => "valid",
+        Some(false) => "invalid",
+        None => "unknown",
+    }
+    .to_string()
+}
+
+fn annotation_diff(
+    from: &BTreeMap<String, String>,
+    to: &BTreeMap<String, String>,
+) -> AnnotationDiff {
+    let mut diff = AnnotationDiff::default();
+    for (key, value) in to {
+        match from.get(key) {
+            None => {
+                diff.added.insert(key.clone(), value.clone());
+            }
+            Some(previous) if previous != value => {
+                diff.changed
+                    .insert(key.clone(), Change::new(previous.clone(), value.clone()));
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, value) in from {
+        if !to.contains_key(key) {
+            diff.removed.insert(key.clone(), value.clone());
+        }
+    }
+    diff
+}
+
+fn summarize(comparison: &BackupComparison, from: &BackupEntry, to: &BackupEntry) -> String {
+    if from.backup == to.backup {
+        return format!("Both identifiers refer to backup {}.", from.backup);
+    }
+
+    let mut sentences = vec![match &comparison.time_between {
+        Some(between) => format!(
+            "Backup {} was taken {} after backup {}.",
+            to.backup, between, from.backup
+        ),
+        None => format!(
+            "Comparing backup {} with backup {}.",
+            from.backup, to.backup
+        ),
+    }];
+
+    if let (Some(difference), Some(percent)) = (
+        &comparison.backup_size.difference,
+        comparison.backup_size.percent,
+    ) {
+        sentences.push(format!(
+            "The backup size changed by {difference} ({percent:+}%) to {}.",
+            comparison
+                .backup_size
+                .to
+                .as_deref()
+                .unwrap_or("an unknown size")
+        ));
+    }
+    if let Some(generated) = &comparison.wal_span.generated_between {
+        sentences.push(format!(
+            "{generated} of WAL were generated between the two backups."
+        ));
+    }
+    if comparison.wal_span.timeline.changed {
+        sentences.push(format!(
+            "The timeline changed from {} to {}.",
+            option_text(&comparison.wal_span.timeline.from),
+            option_text(&comparison.wal_span.timeline.to)
+        ));
+    }
+    if comparison.compression.changed || comparison.encryption.changed {
+        sentences.push(format!(
+            "Compression went from {} to {} and encryption from {} to {}.",
+            option_text(&comparison.compression.from),
+            option_text(&comparison.compression.to),
+            option_text(&comparison.encryption.from),
+            option_text(&comparison.encryption.to)
+        ));
+    }
+    if to.incremental {
+        match &comparison.incremental_chain.shared_base {
+            Some(base) => sentences.push(format!(
+                "Backup {} is incremental and shares the base backup {base}.",
+                to.backup
+            )),
+            None => sentences.push(format!(
+                "Backup {} is incremental on a different chain.",
+                to.backup
+            )),
+        }
+    }
+    if comparison.valid.changed {
+        sentences.push(format!(
+            "Validity changed from {} to {}.",
+            comparison.valid.from, comparison.valid.to
+        ));
+    } else if comparison.valid.from == "invalid" {
+        sentences.push("Both backups are invalid.".to_string());
+    }
+    if comparison.retained.changed {
+        let retained = if to.keep { &to.backup } else { &from.backup };
+        sentences.push(format!("Only backup {retained} is kept by retention."));
+    }
+    let annotations = &comparison.annotations;
+    let annotation_changes =
+        annotations.added.len() + annotations.removed.len() + annotations.changed.len();
+    if annotation_changes > 0 {
+        sentences.push(format!("{annotation_changes} annotation(s) differ."));
+    }
+
+    sentences.join(" ")
+}
+
+fn option_text<T: ToString>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map(ToString::to_string)
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::handler::server::router::tool::ToolBase;
+
+    fn full() -> BackupEntry {
+        BackupEntry {
+            backup: "20260701020000".to_string(),
+            valid: Some(true),
+            keep: true,
+            backup_size: Some(1024 * 1024),
+            restore_size: Some(4 * 1024 * 1024),
+            compression: Some(2),
+            encryption: Some(0),
+            comments: Some("owner|dba,ticket|OPS-1".to_string()),
+            wal: Some("000000010000000000000002".to_string()),
+            elapsed: Some(65.0),
+            start_lsn: Some(0x200_0028),
+            end_lsn: Some(0x200_0100),
+            start_timeline: Some(1),
+            end_timeline: Some(1),
+            ..Default::default()
+        }
+    }
+
+    fn incremental() -> BackupEntry {
+        BackupEntry {
+            backup: "20260702020000".to_string(),
+            valid: Some(true),
+            incremental: true,
+            incremental_parent: Some("20260701020000".to_string()),
+            backup_size: Some(512 * 1024),
+            restore_size: Some(5 * 1024 * 1024),
+            delta: Some(256 * 1024),
+            compression: Some(3),
+            encryption: Some(0),
+            comments: Some("owner|ops,reason|nightly".to_string()),
+            wal: Some("000000010000000000000005".to_string()),
+            elapsed: Some(20.0),
+            start_lsn: Some(0x500_0028),
+            end_lsn: Some(0x500_0100),
+            start_timeline: Some(1),
+            end_timeline: Some(1),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compare_backups_tool_metadata() {
+        assert_eq!(CompareBackupsTool::name(), "compare_backups");
+        assert!(
+            CompareBackupsTool::description()
+                .unwrap()
+                .contains("newest")
+        );
+        let tools = PgmonetaHandler::tool_router().list_all();
+        assert!(tools.iter().any(|t| t.name == "compare_backups"));
+    }
+
+    #[test]
+    fn test_compare_backups_reports_structured_diff() {
+        let (from, to) = (full(), incremental());
+        let catalog = vec![from.clone(), to.clone()];
+
+        let comparison = compare_backups("primary", &from, &to, &catalog);
+
+        assert_eq!(comparison.time_between.as_deref(), Some("1d 0h 0m"));
+        assert_eq!(
+            comparison.backup_size.difference.as_deref(),
+            Some("-512.00 KB")
+        );
+        assert_eq!(comparison.backup_size.percent, Some(-50.0));
+        assert_eq!(comparison.delta.from, None);
+        assert_eq!(
+            comparison.lsn_range.to.as_deref(),
+            Some("0/5000028 - 0/5000100")
+        );
+        assert_eq!(
+            comparison.wal_span.generated_between.as_deref(),
+            Some("48.00 MB")
+        );
+        assert!(!comparison.wal_span.timeline.changed);
+        assert_eq!(comparison.duration.from.as_deref(), Some("1m 5s"));
+        assert!(comparison.compression.changed);
+        assert!(!comparison.encryption.changed);
+        assert_eq!(
+            comparison.incremental_chain.to,
+            vec!["20260701020000", "20260702020000"]
+        );
+        assert_eq!(
+            comparison.incremental_chain.shared_base.as_deref(),
+            Some("20260701020000")
+        );
+        assert_eq!(comparison.annotations.added["reason"], "nightly");
+        assert_eq!(comparison.annotations.removed["ticket"], "OPS-1");
+        assert_eq!(
+            comparison.annotations.changed["owner"],
+            Change::new("dba".to_string(), "ops".to_string())
+        );
+        assert!(!comparison.valid.changed);
+        assert!(comparison.retained.changed);
+
+        let summary = &comparison.summary;
+        assert!(summary.contains("taken 1d 0h 0m after backup 20260701020000"));
+        assert!(summary.contains("-50%"));
+        assert!(summary.contains("from zstd to lz4"));
+        assert!(summary.contains("shares the base backup 20260701020000"));
+        assert!(summary.contains("3 annotation(s) differ"));
+    }
+
+    #[test]
+    fn test_compare_same_backup() {
+        let backup = full();
+        let comparison =
+            compare_backups("primary", &backup, &backup, std::slice::from_ref(&backup));
+        assert!(!comparison.compression.changed);
+        assert_eq!(comparison.backup_size.percent, Some(0.0));
+        assert_eq!(
+            comparison.summary,
+            "Both identifiers refer to backup 20260701020000."
+        );
+    }
+}
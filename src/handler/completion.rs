@@ -0,0 +1,336 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Argument completion (`completion/complete`).
+//!
+//! Suggestions are keyed on the argument name, so the same values are offered
+//! for every prompt or resource that uses e.g. a `server` argument. Values that
+//! require a round trip to pgmoneta are cached for [`CACHE_TTL`].
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::{PgmonetaHandler, catalog};
+use crate::client::PgmonetaClient;
+use crate::configuration::CONFIG;
+use rmcp::ErrorData as McpError;
+use rmcp::model::{CompleteRequestParams, CompleteResult, CompletionInfo};
+use serde_json::{Map, Value};
+
+/// How long fetched suggestions are reused before pgmoneta is asked again.
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Symbolic backup identifiers accepted next to timestamps.
+const SYMBOLIC_BACKUP_IDS: [&str; 2] = ["newest", "oldest"];
+
+struct CachedValues {
+    fetched: Instant,
+    values: Vec<String>,
+}
+
+static COMPLETION_CACHE: Mutex<BTreeMap<String, CachedValues>> = Mutex::new(BTreeMap::new());
+
+/// The kind of value an argument holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArgumentKind {
+    Server,
+    BackupId,
+    Username,
+    ConfigKey,
+}
+
+impl ArgumentKind {
+    fn from_argument(name: &str) -> Option<Self> {
+        match name {
+            "server" => Some(Self::Server),
+            "backup_id" | "from_backup_id" | "to_backup_id" => Some(Self::BackupId),
+            "username" => Some(Self::Username),
+            "config_key" => Some(Self::ConfigKey),
+            _ => None,
+        }
+    }
+}
+
+/// Answers a `completion/complete` request.
+///
+/// pgmoneta being unreachable is not an error for completion: the client
+/// simply gets no suggestions.
+pub(crate) async fn complete(request: &CompleteRequestParams) -> Result<CompleteResult, McpError> {
+    let argument = |name: &str| {
+        request
+            .context
+            .as_ref()
+            .and_then(|context| context.get_argument(name))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let candidates = match ArgumentKind::from_argument(&request.argument.name) {
+        Some(ArgumentKind::Username) => admin_usernames(),
+        Some(kind) => match argument("username").or_else(default_username) {
+            Some(username) => fetch_candidates(kind, &username, argument("server").as_deref())
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::debug!(
+                        argument = %request.argument.name,
+                        "completion lookup failed: {}",
+                        e.message
+                    );
+                    Vec::new()
+                }),
+            None => Vec::new(),
+        },
+        None => Vec::new(),
+    };
+
+    Ok(CompleteResult::new(filter_candidates(
+        candidates,
+        &request.argument.value,
+    )))
+}
+
+async fn fetch_candidates(
+    kind: ArgumentKind,
+    username: &str,
+    server: Option<&str>,
+) -> Result<Vec<String>, McpError> {
+    match kind {
+        ArgumentKind::Server => {
+            cached(format!("servers/{username}"), || server_names(username)).await
+        }
+        ArgumentKind::BackupId => {
+            let Some(server) = server else {
+                return Ok(Vec::new());
+            };
+            let mut values = cached(format!("backups/{username}/{server}"), || async move {
+                let backups = catalog::fetch_backups(username, server).await?;
+                Ok(backup_ids_newest_first(&backups))
+            })
+            .await?;
+            values.extend(SYMBOLIC_BACKUP_IDS.iter().map(|id| id.to_string()));
+            Ok(values)
+        }
+        ArgumentKind::ConfigKey => {
+            cached(format!("config/{username}"), || config_keys(username)).await
+        }
+        ArgumentKind::Username => Ok(admin_usernames()),
+    }
+}
+
+/// Returns the cached values for `key`, fetching them when missing or expired.
+async fn cached<F, Fut>(key: String, fetch: F) -> Result<Vec<String>, McpError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Vec<String>, McpError>>,
+{
+    {
+        let cache = COMPLETION_CACHE.lock().map_err(poisoned)?;
+        if let Some(entry) = cache.get(&key)
+            && entry.fetched.elapsed() < CACHE_TTL
+        {
+            return Ok(entry.values.clone());
+        }
+    } // Lock is dropped here
+
+    let values = fetch().await?;
+
+    let mut cache = COMPLETION_CACHE.lock().map_err(poisoned)?;
+    cache.retain(|_, entry| entry.fetched.elapsed() < CACHE_TTL);
+    cache.insert(
+        key,
+        CachedValues {
+            fetched: Instant::now(),
+            values: values.clone(),
+        },
+    );
+    Ok(values)
+}
+
+fn poisoned<T>(e: std::sync::PoisonError<T>) -> McpError {
+    McpError::internal_error(format!("Completion cache Mutex poisoned: {:?}", e), None)
+}
+
+/// The configured admin usernames, sorted.
+fn admin_usernames() -> Vec<String> {
+    let mut usernames: Vec<String> = CONFIG
+        .get()
+        .map(|config| config.admins.keys().cloned().collect())
+        .unwrap_or_default();
+    usernames.sort();
+    usernames
+}
+
+/// The admin used for lookups when the client has not chosen a username yet.
+fn default_username() -> Option<String> {
+    admin_usernames().into_iter().next()
+}
+
+async fn server_names(username: &str) -> Result<Vec<String>, McpError> {
+    let response = catalog::fetch_status(username).await?;
+    Ok(catalog::parse_server_names(&response))
+}
+async fn config_keys(username: &str) -> Result<Vec<String>, McpError> {
+    let result = PgmonetaClient::request_conf_get(username)
+        .await
+        .map_err(|e| {
+            McpError::internal_error(format!("Failed to get configuration: {:?}", e), None)
+        })?;
+    let response = PgmonetaHandler::_parse_and_check_result(&result)?;
+    catalog::ensure_success(&response)?;
+    Ok(parse_config_keys(&response))
+}
+
+fn backup_ids_newest_first(backups: &[catalog::BackupEntry]) -> Vec<String> {
+    backups
+        .iter()
+        .rev()
+        .map(|backup| backup.backup.clone())
+        .collect()
+}
+
+/// Parses the configuration keys out of a raw `CONF_GET` response.
+///
+/// Nested sections are reported as `section.key`.
+fn parse_config_keys(response: &Map<String, Value>) -> Vec<String> {
+    let configuration = response
+        .get("Response")
+        .and_then(Value::as_object)
+        .unwrap_or(response);
+
+    let mut keys = Vec::new();
+    for (key, value) in configuration {
+        match value {
+            Value::Object(section) => {
+                keys.extend(section.keys().map(|nested| format!("{key}.{nested}")));
+            }
+            _ => keys.push(key.clone()),
+        }
+    }
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Keeps the candidates starting with `prefix` (case-insensitively), in order,
+/// capped at the protocol limit.
+fn filter_candidates(candidates: Vec<String>, prefix: &str) -> CompletionInfo {
+    let prefix = prefix.trim().to_lowercase();
+    let matches: Vec<String> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.to_lowercase().starts_with(&prefix))
+        .collect();
+    let total = matches.len();
+    let values: Vec<String> = matches
+        .into_iter()
+        .take(CompletionInfo::MAX_VALUES)
+        .collect();
+    CompletionInfo {
+        values,
+        total: Some(total as u32),
+        has_more: Some(total > CompletionInfo::MAX_VALUES),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(backup: &str) -> catalog::BackupEntry {
+        catalog::BackupEntry {
+            backup: backup.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_argument_kind() {
+        assert_eq!(
+            ArgumentKind::from_argument("server"),
+            Some(ArgumentKind::Server)
+        );
+        assert_eq!(
+            ArgumentKind::from_argument("to_backup_id"),
+            Some(ArgumentKind::BackupId)
+        );
+        assert_eq!(
+            ArgumentKind::from_argument("config_key"),
+            Some(ArgumentKind::ConfigKey)
+        );
+        assert_eq!(ArgumentKind::from_argument("directory"), None);
+    }
+
+    #[test]
+    fn test_backup_ids_newest_first() {
+        let backups = vec![entry("20260101000000"), entry("20260102000000")];
+        assert_eq!(
+            backup_ids_newest_first(&backups),
+            vec!["20260102000000", "20260101000000"]
+        );
+    }
+
+    #[test]
+    fn test_parse_config_keys() {
+        let response = json!({
+            "Outcome": {"Status": true},
+            "Response": {
+                "log_level": "info",
+                "retention": "7",
+                "primary": {"host": "localhost", "port": 5432}
+            }
+        });
+        let keys = parse_config_keys(response.as_object().unwrap());
+        assert_eq!(
+            keys,
+            vec!["log_level", "primary.host", "primary.port", "retention"]
+        );
+    }
+
+    #[test]
+    fn test_filter_candidates() {
+        let candidates = vec![
+            "primary".to_string(),
+            "Production".to_string(),
+            "replica".to_string(),
+        ];
+        let completion = filter_candidates(candidates.clone(), "pr");
+        assert_eq!(completion.values, vec!["primary", "Production"]);
+        assert_eq!(completion.total, Some(2));
+        assert_eq!(completion.has_more, Some(false));
+
+        let completion = filter_candidates(candidates, "");
+        assert_eq!(completion.values.len(), 3);
+
+        let many: Vec<String> = (0..150).map(|i| format!("2026{i:010}")).collect();
+        let completion = filter_candidates(many, "2026");
+        assert_eq!(completion.values.len(), CompletionInfo::MAX_VALUES);
+        assert_eq!(completion.total, Some(150));
+        assert_eq!(completion.has_more, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_cached_reuses_values() {
+        let key = "test/cached_reuses_values".to_string();
+        let first = cached(key.clone(), || async { Ok(vec!["a".to_string()]) })
+            .await
+            .unwrap();
+        let second = cached(key, || async { Ok(vec!["b".to_string()]) })
+            .await
+            .unwrap();
+        assert_eq!(first, vec!["a"]);
+        assert_eq!(second, vec!["a"]);
+    }
+}
@@ -17,6 +17,8 @@ use std::borrow::Cow;
 use std::sync::Arc;
 
 use super::PgmonetaHandler;
+use super::events::{self, Event, EventKind, EventSource};
+use super::validation;
 use crate::client::PgmonetaClient;
 use rmcp::ErrorData as McpError;
 use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
@@ -27,6 +29,9 @@ use rmcp::schemars;
 pub struct DeleteRequest {
     pub username: String,
     pub server: String,
+    /// Backup identifier: a 14 digit timestamp or "newest", "latest" or "oldest"
+    #[schemars(pattern(validation::BACKUP_ID_PATTERN))]
+    #[serde(deserialize_with = "validation::deserialize_backup_id")]
     pub backup_id: String,
     pub force: Option<bool>,
 }
@@ -69,15 +74,25 @@ impl AsyncTool<PgmonetaHandler> for DeleteTool {
         _service: &PgmonetaHandler,
         request: DeleteRequest,
     ) -> Result<String, McpError> {
+        validation::validate_backup_id("backup_id", &request.backup_id)?;
         let force = request.force.unwrap_or(false);
-        let result: String = PgmonetaClient::request_delete(
+        let result = PgmonetaClient::request_delete(
             &request.username,
             &request.server,
             &request.backup_id,
             force,
         )
-        .await
-        .map_err(|e| McpError::internal_error(format!("Failed to delete backup: {:?}", e), None))?;
+        .await;
+        events::emit_outcome(
+            Event::new(EventKind::BackupDeleted, EventSource::Tool, &request.server)
+                .backup(&request.backup_id)
+                .username(&request.username),
+            None,
+            &result,
+        );
+        let result: String = result.map_err(|e| {
+            McpError::internal_error(format!("Failed to delete backup: {:?}", e), None)
+        })?;
         PgmonetaHandler::generate_call_tool_result_string(&result)
     }
 }
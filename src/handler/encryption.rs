@@ -17,6 +17,7 @@ use std::borrow::Cow;
 use std::sync::Arc;
 
 use super::PgmonetaHandler;
+use super::validation;
 use crate::client::PgmonetaClient;
 use rmcp::ErrorData as McpError;
 use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
@@ -26,12 +27,14 @@ use rmcp::schemars;
 #[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
 pub struct EncryptRequest {
     pub username: String,
+    /// Absolute path of the file on the pgmoneta host
     pub file_path: String,
 }
 
 #[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
 pub struct DecryptRequest {
     pub username: String,
+    /// Absolute path of the file on the pgmoneta host
     pub file_path: String,
 }
 
@@ -73,6 +76,7 @@ impl AsyncTool<PgmonetaHandler> for EncryptFileTool {
         _service: &PgmonetaHandler,
         request: EncryptRequest,
     ) -> Result<String, McpError> {
+        validation::validate_path("file_path", &request.file_path)?;
         let result: String = PgmonetaClient::request_encrypt(&request.username, &request.file_path)
             .await
             .map_err(|e| {
@@ -114,6 +118,7 @@ impl AsyncTool<PgmonetaHandler> for DecryptFileTool {
         _service: &PgmonetaHandler,
         request: DecryptRequest,
     ) -> Result<String, McpError> {
+        validation::validate_path("file_path", &request.file_path)?;
         let result: String = PgmonetaClient::request_decrypt(&request.username, &request.file_path)
             .await
             .map_err(|e| {
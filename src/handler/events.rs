@@ -0,0 +1,510 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The event bus of the backup lifecycle.
+//!
+//! The mutating tools and the backup schedules emit an [`Event`] for every
+//! backup they start, finish, delete, retain, expunge, annotate or verify.
+//! The catalog poller compares the backup catalog of every server with the
+//! previous one and emits the events of the changes made outside this server,
+//! such as backups taken by pgmoneta itself or removed by its retention policy.
+//! Subscribers, such as the webhook dispatcher, receive every event.
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, PoisonError};
+
+use super::PgmonetaHandler;
+use super::catalog::{self, BackupClock, BackupEntry};
+use once_cell::sync::Lazy;
+use rmcp::ErrorData as McpError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::sync::broadcast;
+
+/// The number of events a subscriber may lag behind before it misses events.
+const CAPACITY: usize = 1024;
+
+static BUS: Lazy<broadcast::Sender<Event>> = Lazy::new(|| broadcast::channel(CAPACITY).0);
+
+static CATALOG: Mutex<Option<CatalogState>> = Mutex::new(None);
+
+/// The type of an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    BackupStarted,
+    BackupFinished,
+    BackupFailed,
+    BackupDeleted,
+    BackupRetained,
+    BackupExpunged,
+    BackupAnnotated,
+    BackupVerified,
+}
+
+impl EventKind {
+    /// Every type of event.
+    pub const ALL: [EventKind; 8] = [
+        EventKind::BackupStarted,
+        EventKind::BackupFinished,
+        EventKind::BackupFailed,
+        EventKind::BackupDeleted,
+        EventKind::BackupRetained,
+        EventKind::BackupExpunged,
+        EventKind::BackupAnnotated,
+        EventKind::BackupVerified,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::BackupStarted => "backup_started",
+            EventKind::BackupFinished => "backup_finished",
+            EventKind::BackupFailed => "backup_failed",
+            EventKind::BackupDeleted => "backup_deleted",
+            EventKind::BackupRetained => "backup_retained",
+            EventKind::BackupExpunged => "backup_expunged",
+            EventKind::BackupAnnotated => "backup_annotated",
+            EventKind::BackupVerified => "backup_verified",
+        }
+    }
+
+    /// Parses a type of event, e.g. `backup_failed`.
+    pub fn parse(kind: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == kind)
+    }
+}
+
+/// What caused an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventSource {
+    /// A tool call, including the calls of runbooks.
+    Tool,
+    /// A run of a backup schedule.
+    Scheduler,
+    /// A change of the backup catalog not made through this server.
+    Catalog,
+}
+
+/// A change of the backup lifecycle.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Event {
+    /// A unique identifier of the event.
+    pub id: String,
+    #[serde(rename = "Type")]
+    pub kind: EventKind,
+    pub time: String,
+    pub source: EventSource,
+    pub server: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup: Option<String>,
+    /// The admin that made the change; absent for catalog events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub details: Map<String, Value>,
+}
+
+impl Event {
+    pub fn new(kind: EventKind, source: EventSource, server: &str) -> Self {
+        let clock = BackupClock::configured();
+        Event {
+            id: format!("{:032x}", rand::random::<u128>()),
+            kind,
+            time: clock.format(clock.now()),
+            source,
+            server: server.to_string(),
+            backup: None,
+            username: None,
+            error: None,
+            details: Map::new(),
+        }
+    }
+
+    pub fn backup(mut self, backup: &str) -> Self {
+        self.backup = Some(backup.to_string());
+        self
+    }
+
+    pub fn username(mut self, username: &str) -> Self {
+        self.username = Some(username.to_string());
+        self
+    }
+
+    pub fn error(mut self, error: &str) -> Self {
+        self.error = Some(error.to_string());
+        self
+    }
+
+    pub fn detail(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.details.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+/// Publishes an event to every subscriber.
+pub fn emit(event: Event) {
+    record(&event);
+    tracing::debug!(
+        "Event {} of {} {:?}",
+        event.kind.as_str(),
+        event.server,
+        event.backup
+    );
+    // Without subscribers the event is dropped
+    let _ = BUS.send(event);
+}
+
+/// Subscribes to the events emitted from now on.
+pub fn subscribe() -> broadcast::Receiver<Event> {
+    BUS.subscribe()
+}
+
+/// Emits `event` if the raw pgmoneta `result` reports a success, with the
+/// backup named by the response; otherwise emits it as `failed` with the
+/// error, if `failed` is given.
+pub(crate) fn emit_outcome(
+    mut event: Event,
+    failed: Option<EventKind>,
+    result: &anyhow::Result<String>,
+) {
+    match outcome(result) {
+        Ok(backup) => {
+            if let Some(backup) = backup {
+                event.backup = Some(backup);
+            }
+            emit(event);
+        }
+        Err(error) => {
+            if let Some(failed) = failed {
+                event.kind = failed;
+                emit(event.error(&error));
+            }
+        }
+    }
+}
+
+/// The backup named by a successful raw pgmoneta response, or its error.
+fn outcome(result: &anyhow::Result<String>) -> Result<Option<String>, String> {
+    let result = result.as_ref().map_err(|e| e.to_string())?;
+    let response = PgmonetaHandler::_parse_and_check_result(result)
+        .and_then(|response| catalog::ensure_success(&response).map(|_| response))
+        .map_err(|e| e.message.to_string())?;
+    Ok(response
+        .get("Response")
+        .and_then(|value| value.get("Backup"))
+        .or_else(|| response.get("Backup"))
+        .and_then(catalog::value_as_string))
+}
+
+/// The backups of every server seen by the catalog poller, with their retain flag.
+#[derive(Debug, Default)]
+struct CatalogState {
+    servers: BTreeMap<String, BTreeMap<String, bool>>,
+    /// Counts the events applied to `servers`, so that the poller can tell
+    /// that a catalog it fetched may already be outdated.
+    changes: u64,
+}
+
+/// Applies the change of an emitted event to the catalog seen by the poller,
+/// so that the poller does not emit it a second time.
+fn record(event: &Event) {
+    if event.source == EventSource::Catalog {
+        return;
+    }
+    let Some(backup) = &event.backup else {
+        return;
+    };
+    with_catalog(|state| {
+        let Some(backups) = state.servers.get_mut(&event.server) else {
+            return;
+        };
+        match event.kind {
+            EventKind::BackupFinished => {
+                backups.entry(backup.clone()).or_insert(false);
+            }
+            EventKind::BackupDeleted => {
+                backups.remove(backup);
+            }
+            EventKind::BackupRetained => {
+                backups.insert(backup.clone(), true);
+            }
+            EventKind::BackupExpunged => {
+                backups.insert(backup.clone(), false);
+            }
+            _ => return,
+        }
+        state.changes += 1;
+    });
+}
+
+/// Fetches the backup catalog of every server and emits the events of the
+/// changes since the previous poll. The first poll of a server only records
+/// its catalog.
+pub(crate) async fn poll_catalog(username: &str) -> Result<(), McpError> {
+    let changes = with_catalog(|state| state.changes);
+    let status = catalog::fetch_status(username).await?;
+    let mut catalogs = Vec::new();
+    for server in catalog::parse_server_names(&status) {
+        match catalog::fetch_backups(username, &server).await {
+            Ok(backups) => catalogs.push((server, backups)),
+            Err(e) => tracing::warn!("Failed to poll the backups of {}: {}", server, e.message),
+        }
+    }
+
+    let events = with_catalog(|state| {
+        if state.changes != changes {
+            // A tool changed the catalog meanwhile; compare at the next poll
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        for (server, backups) in &catalogs {
+            let current = known_backups(backups);
+            if let Some(previous) = state.servers.get(server) {
+                events.extend(diff(server, previous, backups));
+            }
+            state.servers.insert(server.clone(), current);
+        }
+        events
+    });
+    for event in events {
+        emit(event);
+    }
+    Ok(())
+}
+
+/// The backups whose validity is known, with their retain flag; backups in
+/// progress are left out until pgmoneta has validated them.
+fn known_backups(backups: &[BackupEntry]) -> BTreeMap<String, bool> {
+    backups
+        .iter()
+        .filter(|backup| backup.valid.is_some())
+        .map(|backup| (backup.backup.clone(), backup.keep))
+        .collect()
+}
+
+/// The events of the changes from the `previous` catalog of a server to `backups`.
+fn diff(server: &str, previous: &BTreeMap<String, bool>, backups: &[BackupEntry]) -> Vec<Event> {
+    let mut events = Vec::new();
+    for backup in backups {
+        let Some(valid) = backup.valid else {
+            continue;
+        };
+        let event = match previous.get(&backup.backup) {
+            None if valid => EventKind::BackupFinished,
+            None => EventKind::BackupFailed,
+            Some(false) if backup.keep => EventKind::BackupRetained,
+            Some(true) if !backup.keep => EventKind::BackupExpunged,
+            Some(_) => continue,
+        };
+        let mut event = Event::new(event, EventSource::Catalog, server).backup(&backup.backup);
+        if !valid {
+            event = event.error("pgmoneta marked the backup invalid");
+        }
+        events.push(event);
+    }
+    for backup in previous.keys() {
+        if !backups.iter().any(|current| &current.backup == backup) {
+            events.push(
+                Event::new(EventKind::BackupDeleted, EventSource::Catalog, server).backup(backup),
+            );
+        }
+    }
+    events
+}
+
+fn with_catalog<T>(f: impl FnOnce(&mut CatalogState) -> T) -> T {
+    let mut guard = CATALOG.lock().unwrap_or_else(PoisonError::into_inner);
+    f(guard.get_or_insert_with(CatalogState::default))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup(id: &str, valid: Option<bool>, keep: bool) -> BackupEntry {
+        BackupEntry {
+            backup: id.to_string(),
+            valid,
+            keep,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let event = Event::new(EventKind::BackupFinished, EventSource::Tool, "primary")
+            .backup("20260301000000")
+            .username("admin")
+            .detail("Incremental", false);
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["Type"], "backup_finished");
+        assert_eq!(json["Source"], "tool");
+        assert_eq!(json["Server"], "primary");
+        assert_eq!(json["Backup"], "20260301000000");
+        assert_eq!(json["Details"]["Incremental"], false);
+        assert_eq!(json["Id"].as_str().unwrap().len(), 32);
+        assert!(json.get("Error").is_none());
+        assert_eq!(serde_json::from_value::<Event>(json).unwrap(), event);
+
+        for kind in EventKind::ALL {
+            assert_eq!(EventKind::parse(kind.as_str()), Some(kind));
+            assert_eq!(
+                serde_json::to_value(kind).unwrap(),
+                Value::from(kind.as_str())
+            );
+        }
+        assert_eq!(EventKind::parse("backup"), None);
+    }
+
+    #[test]
+    fn test_outcome() {
+        assert_eq!(
+            outcome(&Ok(
+                r#"{"Outcome": {"Status": true}, "Response": {"Backup": "20260301000000"}}"#
+                    .to_string()
+            )),
+            Ok(Some("20260301000000".to_string()))
+        );
+        assert_eq!(
+            outcome(&Ok(r#"{"Outcome": {"Status": true}}"#.to_string())),
+            Ok(None)
+        );
+        assert_eq!(
+            outcome(&Ok(
+                r#"{"Outcome": {"Status": false, "Error": 505}}"#.to_string()
+            )),
+            Err("pgmoneta reported a failure: Delete backup: backup not found".to_string())
+        );
+        assert!(outcome(&Err(anyhow::anyhow!("connection refused"))).is_err());
+    }
+
+    #[test]
+    fn test_diff() {
+        let previous = BTreeMap::from([
+            ("20260101000000".to_string(), false),
+            ("20260201000000".to_string(), false),
+            ("20260301000000".to_string(), true),
+        ]);
+        let backups = vec![
+            backup("20260201000000", Some(true), true),
+            backup("20260301000000", Some(true), false),
+            backup("20260401000000", Some(true), false),
+            backup("20260501000000", Some(false), false),
+            backup("20260601000000", None, false),
+        ];
+        let events = diff("primary", &previous, &backups)
+            .into_iter()
+            .map(|event| (event.kind, event.backup.unwrap(), event.source))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                (
+                    EventKind::BackupRetained,
+                    "20260201000000".to_string(),
+                    EventSource::Catalog
+                ),
+                (
+                    EventKind::BackupExpunged,
+                    "20260301000000".to_string(),
+                    EventSource::Catalog
+                ),
+                (
+                    EventKind::BackupFinished,
+                    "20260401000000".to_string(),
+                    EventSource::Catalog
+                ),
+                (
+                    EventKind::BackupFailed,
+                    "20260501000000".to_string(),
+                    EventSource::Catalog
+                ),
+                (
+                    EventKind::BackupDeleted,
+                    "20260101000000".to_string(),
+                    EventSource::Catalog
+                ),
+            ]
+        );
+        assert_eq!(
+            known_backups(&backups).keys().collect::<Vec<_>>(),
+            vec![
+                "20260201000000",
+                "20260301000000",
+                "20260401000000",
+                "20260501000000"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_emitted_events_update_the_catalog() {
+        with_catalog(|state| {
+            state.servers.insert(
+                "events_test".to_string(),
+                BTreeMap::from([("20260101000000".to_string(), false)]),
+            )
+        });
+        let changes = with_catalog(|state| state.changes);
+        let mut receiver = subscribe();
+
+        emit_outcome(
+            Event::new(EventKind::BackupFinished, EventSource::Tool, "events_test")
+                .username("admin"),
+            Some(EventKind::BackupFailed),
+            &Ok(
+                r#"{"Outcome": {"Status": true}, "Response": {"Backup": "20260201000000"}}"#
+                    .to_string(),
+            ),
+        );
+        emit(
+            Event::new(EventKind::BackupDeleted, EventSource::Tool, "events_test")
+                .backup("20260101000000"),
+        );
+        emit_outcome(
+            Event::new(EventKind::BackupStarted, EventSource::Tool, "events_test"),
+            Some(EventKind::BackupFailed),
+            &Err(anyhow::anyhow!("connection refused")),
+        );
+
+        let mut events = Vec::new();
+        while events.len() < 3 {
+            let event = receiver.recv().await.unwrap();
+            if event.server == "events_test" {
+                events.push(event);
+            }
+        }
+        let [finished, deleted, failed] = events.try_into().unwrap();
+        assert_eq!(finished.kind, EventKind::BackupFinished);
+        assert_eq!(finished.backup.as_deref(), Some("20260201000000"));
+        assert_eq!(deleted.kind, EventKind::BackupDeleted);
+        assert_eq!(failed.kind, EventKind::BackupFailed);
+        assert_eq!(failed.error.as_deref(), Some("connection refused"));
+
+        with_catalog(|state| {
+            assert_eq!(state.changes, changes + 2);
+            assert_eq!(
+                state.servers.remove("events_test").unwrap(),
+                BTreeMap::from([("20260201000000".to_string(), false)])
+            );
+        });
+    }
+}
@@ -17,17 +17,24 @@ use std::borrow::Cow;
 use std::sync::Arc;
 
 use super::PgmonetaHandler;
+use super::catalog;
+use super::validation::{self, Keyword};
+use super::verify;
 use crate::client::PgmonetaClient;
 use crate::constant::Sort;
 use rmcp::ErrorData as McpError;
 use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
 use rmcp::model::JsonObject;
 use rmcp::schemars;
+use serde_json::Value;
 
 #[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
 pub struct InfoRequest {
     pub username: String,
     pub server: String,
+    /// Backup identifier: a 14 digit timestamp or "newest", "latest" or "oldest"
+    #[schemars(pattern(validation::BACKUP_ID_PATTERN))]
+    #[serde(deserialize_with = "validation::deserialize_backup_id")]
     pub backup_id: String,
 }
 
@@ -35,7 +42,40 @@ pub struct InfoRequest {
 pub struct ListBackupsRequest {
     pub username: String,
     pub server: String,
-    pub sort: Option<String>,
+    /// Sort order of the backups, ascending by default
+    #[serde(default, deserialize_with = "validation::deserialize_optional_keyword")]
+    pub sort: Option<SortOrder>,
+}
+
+/// The order in which backups are listed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, schemars::JsonSchema)]
+#[schemars(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    /// The keyword sent to pgmoneta.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => Sort::ASC,
+            SortOrder::Desc => Sort::DESC,
+        }
+    }
+}
+
+impl Keyword for SortOrder {
+    const ARGUMENT: &'static str = "sort order";
+    const VARIANTS: &'static [(&'static str, Self)] =
+        &[(Sort::ASC, SortOrder::Asc), (Sort::DESC, SortOrder::Desc)];
+}
+
+impl<'de> serde::Deserialize<'de> for SortOrder {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        validation::deserialize_keyword(deserializer)
+    }
 }
 
 /// Tool for fetching detailed information about a specific backup.
@@ -54,6 +94,7 @@ impl ToolBase for GetBackupInfoTool {
         Some(
             "Get information of a backup using given backup ID and server name. \
             \"newest\", \"latest\" or \"oldest\" are also accepted as backup identifier. \
+            LastVerified holds the time and result of the last verification through this server. \
             The username has to be one of the pgmoneta admins to be able to access pgmoneta"
                 .into(),
         )
@@ -72,6 +113,7 @@ impl ToolBase for GetBackupInfoTool {
 
 impl AsyncTool<PgmonetaHandler> for GetBackupInfoTool {
     async fn invoke(_service: &PgmonetaHandler, request: InfoRequest) -> Result<String, McpError> {
+        validation::validate_backup_id("backup_id", &request.backup_id)?;
         let result: String = PgmonetaClient::request_backup_info(
             &request.username,
             &request.server,
@@ -84,10 +126,31 @@ impl AsyncTool<PgmonetaHandler> for GetBackupInfoTool {
                 None,
             )
         })?;
-        PgmonetaHandler::generate_call_tool_result_string(&result)
+        let translated = PgmonetaHandler::generate_call_tool_result_string(&result)?;
+        Ok(with_last_verification(&translated, &request.server))
     }
 }
 
+/// Adds the last verification of the backup, or `null`, as `LastVerified` to a
+/// translated backup information response.
+fn with_last_verification(translated: &str, server: &str) -> String {
+    let Ok(Value::Object(mut response)) = serde_json::from_str::<Value>(translated) else {
+        return translated.to_string();
+    };
+    let info = match response.get_mut("Response") {
+        Some(Value::Object(info)) => info,
+        _ => &mut response,
+    };
+    let Some(backup) = info.get("Backup").and_then(catalog::value_as_string) else {
+        return translated.to_string();
+    };
+    let last = verify::last_verification(server, &backup)
+        .and_then(|last| serde_json::to_value(last).ok())
+        .unwrap_or(Value::Null);
+    info.insert("LastVerified".to_string(), last);
+    serde_json::to_string(&response).unwrap_or_else(|_| translated.to_string())
+}
+
 /// Tool for listing available backups on a specified server.
 pub struct ListBackupsTool;
 
@@ -125,9 +188,9 @@ impl AsyncTool<PgmonetaHandler> for ListBackupsTool {
         _service: &PgmonetaHandler,
         request: ListBackupsRequest,
     ) -> Result<String, McpError> {
-        let sort = normalize_sort_option(request.sort);
+        let sort = request.sort.unwrap_or_default();
         let result: String =
-            PgmonetaClient::request_list_backups(&request.username, &request.server, &sort)
+            PgmonetaClient::request_list_backups(&request.username, &request.server, sort.as_str())
                 .await
                 .map_err(|e| {
                     McpError::internal_error(format!("Failed to list backups: {:?}", e), None)
@@ -136,20 +199,6 @@ impl AsyncTool<PgmonetaHandler> for ListBackupsTool {
     }
 }
 
-fn normalize_sort_option(sort: Option<String>) -> String {
-    match sort {
-        Some(value) => {
-            let trimmed = value.trim();
-            if trimmed.is_empty() || trimmed.eq_ignore_ascii_case("null") {
-                Sort::ASC.to_string()
-            } else {
-                trimmed.to_string()
-            }
-        }
-        None => Sort::ASC.to_string(),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,11 +220,50 @@ mod tests {
         assert!(desc.unwrap().contains("backups"));
     }
 
+    fn list_request(sort: serde_json::Value) -> Result<ListBackupsRequest, serde_json::Error> {
+        serde_json::from_value(serde_json::json!({
+            "username": "admin", "server": "primary", "sort": sort
+        }))
+    }
+
     #[test]
-    fn test_normalize_sort_option_defaults_invalid_values_to_asc() {
-        assert_eq!(normalize_sort_option(None), "asc");
-        assert_eq!(normalize_sort_option(Some(String::new())), "asc");
-        assert_eq!(normalize_sort_option(Some(" null ".to_string())), "asc");
-        assert_eq!(normalize_sort_option(Some("desc".to_string())), "desc");
+    fn test_sort_option_defaults_empty_values_to_asc() {
+        let request: ListBackupsRequest = serde_json::from_value(serde_json::json!({
+            "username": "admin", "server": "primary"
+        }))
+        .unwrap();
+        assert_eq!(request.sort.unwrap_or_default(), SortOrder::Asc);
+        assert_eq!(list_request(serde_json::json!("")).unwrap().sort, None);
+        assert_eq!(
+            list_request(serde_json::json!(" null ")).unwrap().sort,
+            None
+        );
+        assert_eq!(
+            list_request(serde_json::json!("DESC")).unwrap().sort,
+            Some(SortOrder::Desc)
+        );
+        assert!(list_request(serde_json::json!("newest first")).is_err());
+    }
+
+    #[test]
+    fn test_list_backups_schema_has_sort_enum() {
+        let schema = serde_json::to_value(schemars::schema_for!(ListBackupsRequest)).unwrap();
+        assert!(schema.to_string().contains(r#""enum":["asc","desc"]"#));
+    }
+
+    #[test]
+    fn test_with_last_verification() {
+        let translated =
+            r#"{"Outcome":{"Status":true},"Response":{"Backup":"20260706113000","Valid":true}}"#;
+        let output = with_last_verification(translated, "info_test_never_verified");
+        let parsed: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(parsed["Response"]["Backup"], "20260706113000");
+        assert!(parsed["Response"]["LastVerified"].is_null());
+
+        let without_backup = r#"{"Outcome":{"Status":false,"Error":"Info: no backup"}}"#;
+        assert_eq!(
+            with_last_verification(without_backup, "info_test_never_verified"),
+            without_backup
+        );
     }
 }
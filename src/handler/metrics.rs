@@ -13,13 +13,22 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+pub(crate) mod exposition;
+pub(crate) mod query;
+
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
 
 use super::PgmonetaHandler;
+use super::catalog::BackupClock;
+use super::{recovery, validation};
 use crate::client::PgmonetaClient;
-use anyhow::{Result, anyhow, bail};
+use crate::history;
+use anyhow::{Result, bail};
+use chrono::NaiveDate;
+use exposition::{Exposition, MetricFamily};
+use query::{Query, QueryValue};
 use rmcp::ErrorData as McpError;
 use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
 use rmcp::model::JsonObject;
@@ -41,18 +50,29 @@ pub struct MetricRequest {
     pub labels: HashMap<String, Value>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct MetricQuery {
-    name: String,
-    attributes: HashMap<String, String>,
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct QueryMetricsRequest {
+    pub username: String,
+    /// The PromQL-style expression, e.g. `sum by (name) (pgmoneta_backup_total_size)`.
+    pub query: String,
+    /// Evaluate at this past time against the metrics history.
+    #[serde(default)]
+    pub time: Option<String>,
+    /// Evaluate over the window from `start` against the metrics history.
+    #[serde(default)]
+    pub start: Option<String>,
+    /// The end of the window; now by default.
+    #[serde(default)]
+    pub end: Option<String>,
+    /// The step of the window, e.g. `5m`; 100 steps over the window by default.
+    #[serde(default)]
+    pub step: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct MetricSample {
+struct MetricQuery {
     name: String,
     attributes: HashMap<String, String>,
-    line: String,
-    value: String,
 }
 
 /// Tool for fetching the complete pgmoneta Prometheus metrics exposition.
@@ -93,7 +113,7 @@ impl AsyncTool<PgmonetaHandler> for GetMetricsTool {
     }
 }
 
-/// Tool for fetching a single pgmoneta Prometheus metric value.
+/// Tool for fetching a single pgmoneta Prometheus metric family.
 pub struct MetricTool;
 
 impl ToolBase for MetricTool {
@@ -107,7 +127,9 @@ impl ToolBase for MetricTool {
 
     fn description() -> Option<Cow<'static, str>> {
         Some(
-            "Fetch a single Prometheus metric value exposed by pgmoneta using a metric name and optional attributes. \
+            "Fetch a single Prometheus metric family exposed by pgmoneta using a metric name and optional attributes. \
+            Returns the family type (counter, gauge, histogram, summary, info, ...), its help text and the matching samples \
+            with their labels, timestamps and exemplars. A sample name such as a histogram '_bucket' returns only those samples. \
             The username has to be one of the pgmoneta admins to be able to access pgmoneta."
                 .into(),
         )
@@ -132,12 +154,219 @@ impl AsyncTool<PgmonetaHandler> for MetricTool {
                 McpError::internal_error(format!("Failed to fetch metrics: {:?}", e), None)
             })?;
 
-        find_metric_value(&metrics, &query.metric).map_err(|e| {
-            McpError::internal_error(format!("Failed to resolve metric value: {e}"), None)
+        let family = find_metric_family(&metrics, &query.metric).map_err(|e| {
+            McpError::internal_error(format!("Failed to resolve metric: {e}"), None)
+        })?;
+        serde_json::to_string(&family)
+            .map_err(|e| McpError::internal_error(format!("Failed to serialize metric: {e}"), None))
+    }
+}
+
+/// Tool for evaluating PromQL-style expressions over the pgmoneta metrics.
+pub struct QueryMetricsTool;
+
+impl ToolBase for QueryMetricsTool {
+    type Parameter = QueryMetricsRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "query_metrics".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "Evaluate a PromQL-style expression against the current pgmoneta Prometheus metrics. \
+            Supports selectors with label matchers (=, !=, =~, !~), arithmetic and comparisons between series and numbers \
+            with optional on/ignoring matching, sum/avg/min/max/count/topk/bottomk with by/without, and histogram_quantile. \
+            Example: 'sum by (name) (pgmoneta_backup_total_size)' for the total backup size by server. \
+            When the metrics history is enabled, range selectors with rate, increase and avg_over_time \
+            (e.g. 'increase(pgmoneta_logging_error[1d])') are supported, 'time' evaluates at a past time, \
+            and 'start', 'end' and 'step' evaluate over a time window. \
+            Times can be absolute ('2026-03-01 12:00:00') or relative ('7 days ago'). \
+            The username has to be one of the pgmoneta admins to be able to access pgmoneta."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for QueryMetricsTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: QueryMetricsRequest,
+    ) -> Result<String, McpError> {
+        let query = Query::parse(&request.query)
+            .map_err(|e| McpError::invalid_params(format!("Invalid query: {e}"), None))?;
+
+        let Some(window) = QueryWindow::resolve(&request, &query, BackupClock::configured())
+            .map_err(|e| McpError::invalid_params(e, None))?
+        else {
+            let metrics = PgmonetaClient::request_metrics(&request.username)
+                .await
+                .map_err(|e| {
+                    McpError::internal_error(format!("Failed to fetch metrics: {:?}", e), None)
+                })?;
+            let result = evaluate_query(&metrics, &request.query, &query).map_err(|e| {
+                McpError::internal_error(format!("Failed to evaluate query: {e}"), None)
+            })?;
+            return serde_json::to_string(&result).map_err(|e| {
+                McpError::internal_error(format!("Failed to serialize query result: {e}"), None)
+            });
+        };
+
+        validation::validate_admin(&request.username)?;
+        let store = history::store().ok_or_else(|| {
+            McpError::invalid_params(
+                "The metrics history is not enabled; configure the [history] section \
+                for range selectors, 'time' and 'start'"
+                    .to_string(),
+                None,
+            )
+        })?;
+        let text = request.query.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            evaluate_history_query(&store, &text, &query, &window, history::lookback())
         })
+        .await
+        .map_err(|e| McpError::internal_error(format!("Failed to evaluate query: {e}"), None))?
+        .map_err(|e| McpError::internal_error(format!("Failed to evaluate query: {e}"), None))?;
+        serde_json::to_string(&result).map_err(|e| {
+            McpError::internal_error(format!("Failed to serialize query result: {e}"), None)
+        })
+    }
+}
+
+/// When a query is evaluated against the metrics history, in Unix milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueryWindow {
+    At(i64),
+    Range { start: i64, end: i64, step: i64 },
+}
+
+/// The number of steps of a window without an explicit step.
+const DEFAULT_STEPS: i64 = 100;
+
+impl QueryWindow {
+    /// The window of a request, or `None` to evaluate against a live scrape.
+    fn resolve(
+        request: &QueryMetricsRequest,
+        query: &Query,
+        clock: BackupClock,
+    ) -> Result<Option<Self>, String> {
+        let time = |argument: &str, text: Option<&str>| -> Result<Option<i64>, String> {
+            let Some(text) = text.map(str::trim).filter(|text| !text.is_empty()) else {
+                return Ok(None);
+            };
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(Ok)
+                .unwrap_or_else(|| recovery::parse_human_time(text, clock).map(|(time, _)| time))
+                .ok()
+                .and_then(|time| clock.timestamp_millis(time))
+                .map(Some)
+                .ok_or_else(|| format!("Unable to understand {argument} '{text}'"))
+        };
+        let now = || clock.timestamp_millis(clock.now()).unwrap_or_default();
+
+        let at = time("time", request.time.as_deref())?;
+        let start = time("start", request.start.as_deref())?;
+        let end = time("end", request.end.as_deref())?;
+        let step = request
+            .step
+            .as_deref()
+            .map(str::trim)
+            .filter(|step| !step.is_empty());
+
+        let Some(start) = start else {
+            if end.is_some() || step.is_some() {
+                return Err("'end' and 'step' need a 'start'".to_string());
+            }
+            return Ok(match at {
+                Some(at) => Some(Self::At(at)),
+                None if query.needs_history() => Some(Self::At(now())),
+                None => None,
+            });
+        };
+        if at.is_some() {
+            return Err("Use either 'time' or 'start', not both".to_string());
+        }
+        let end = end.unwrap_or_else(now);
+        if end <= start {
+            return Err("'start' must be before 'end'".to_string());
+        }
+        let step = match step {
+            Some(step) => query::parse_duration(step)
+                .filter(|step| *step > 0)
+                .ok_or_else(|| format!("Invalid step '{step}': expected a duration such as 5m"))?,
+            None => ((end - start) / DEFAULT_STEPS).max(1000),
+        };
+        if (end - start) / step >= query::MAX_STEPS {
+            return Err(format!(
+                "The window has more than {} steps; use a longer step",
+                query::MAX_STEPS
+            ));
+        }
+        Ok(Some(Self::Range { start, end, step }))
     }
 }
 
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueryResult {
+    query: String,
+    /// The evaluation time of a query against the history.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time: Option<String>,
+    #[serde(flatten)]
+    value: QueryValue,
+}
+
+fn evaluate_history_query(
+    store: &history::store::Store,
+    text: &str,
+    query: &Query,
+    window: &QueryWindow,
+    lookback: i64,
+) -> Result<QueryResult> {
+    let (start, end) = match *window {
+        QueryWindow::At(time) => (time, time),
+        QueryWindow::Range { start, end, .. } => (start, end),
+    };
+    let series = store.read(
+        start - query.window().max(lookback),
+        end,
+        query.metric_names().as_ref(),
+    )?;
+    let value = match *window {
+        QueryWindow::At(time) => query.evaluate_at(&series, lookback, time)?,
+        QueryWindow::Range { start, end, step } => {
+            query.evaluate_range(&series, lookback, start, end, step)?
+        }
+    };
+    Ok(QueryResult {
+        query: text.trim().to_string(),
+        time: match *window {
+            QueryWindow::At(time) => Some(query::format_time(time)),
+            QueryWindow::Range { .. } => None,
+        },
+        value,
+    })
+}
+
+fn evaluate_query(metrics: &str, text: &str, query: &Query) -> Result<QueryResult> {
+    let exposition = Exposition::parse(metrics)?;
+    Ok(QueryResult {
+        query: text.trim().to_string(),
+        time: None,
+        value: query.evaluate(&exposition)?,
+    })
+}
+
 #[derive(Debug)]
 struct NormalizedMetricQuery {
     username: String,
@@ -196,199 +425,59 @@ fn parse_metric_query_attributes(
         .collect()
 }
 
-fn find_metric_value(metrics: &str, query: &MetricQuery) -> Result<String> {
-    let samples = parse_metric_samples(metrics)?;
-    let named_samples = samples
-        .into_iter()
-        .filter(|sample| sample.name == query.name)
-        .collect::<Vec<_>>();
-
-    if named_samples.is_empty() {
+/// The family of metric `query.name` with the samples that carry the
+/// requested attributes.
+///
+/// A family name returns all of its samples, a sample name (e.g. the
+/// `_bucket` samples of a histogram) only the samples of that name.
+fn find_metric_family(metrics: &str, query: &MetricQuery) -> Result<MetricFamily> {
+    let exposition = Exposition::parse(metrics)?;
+    let Some(family) = exposition.family(&query.name) else {
         bail!("Metric '{}' was not found", query.name);
-    }
+    };
 
-    let matching_samples = named_samples
-        .into_iter()
-        .filter(|sample| metric_attributes_match(&sample.attributes, &query.attributes))
+    let whole_family = family.name == query.name;
+    let samples = family
+        .samples
+        .iter()
+        .filter(|sample| whole_family || sample.name == query.name)
+        .filter(|sample| {
+            sample.has_labels(
+                query
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.as_str())),
+            )
+        })
+        .cloned()
         .collect::<Vec<_>>();
-
-    match matching_samples.as_slice() {
-        [] => bail!(
+    if samples.is_empty() && !family.samples.is_empty() {
+        bail!(
             "Metric '{}' did not match the requested attributes",
             query.name
-        ),
-        [sample] => Ok(sample.line.clone()),
-        _ => Ok(matching_samples
-            .into_iter()
-            .map(|sample| sample.line)
-            .collect::<Vec<_>>()
-            .join("\n")),
+        );
     }
-}
 
-fn metric_attributes_match(
-    sample_attributes: &HashMap<String, String>,
-    expected_attributes: &HashMap<String, String>,
-) -> bool {
-    expected_attributes
-        .iter()
-        .all(|(key, value)| sample_attributes.get(key) == Some(value))
+    Ok(MetricFamily {
+        samples,
+        ..family.clone()
+    })
 }
 
-fn parse_metric_samples(metrics: &str) -> Result<Vec<MetricSample>> {
+/// The values of the samples named `name` that carry all of `labels`.
+///
+/// Lines that cannot be parsed are skipped.
+pub(crate) fn metric_values(metrics: &str, name: &str, labels: &[(&str, &str)]) -> Vec<f64> {
     metrics
         .lines()
-        .filter_map(|line| {
-            let trimmed = line.trim();
-            if trimmed.is_empty() || trimmed.starts_with('#') {
-                None
-            } else {
-                Some(parse_metric_sample(trimmed))
-            }
-        })
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| exposition::parse_sample(line).ok())
+        .filter(|sample| sample.name == name && sample.has_labels(labels.iter().copied()))
+        .map(|sample| sample.value)
         .collect()
 }
 
-fn parse_metric_sample(line: &str) -> Result<MetricSample> {
-    let (identifier, raw_value) = split_metric_sample_line(line)?;
-    let (name, attributes) = parse_metric_identifier(identifier)?;
-    let value = raw_value
-        .split_whitespace()
-        .next()
-        .ok_or_else(|| anyhow!("Metric sample '{}' is missing a value", line))?;
-
-    Ok(MetricSample {
-        name,
-        attributes,
-        line: line.to_string(),
-        value: value.to_string(),
-    })
-}
-
-fn split_metric_sample_line(line: &str) -> Result<(&str, &str)> {
-    let mut in_braces = false;
-
-    for (idx, ch) in line.char_indices() {
-        match ch {
-            '{' => in_braces = true,
-            '}' => in_braces = false,
-            ch if ch.is_whitespace() && !in_braces => {
-                let identifier = line[..idx].trim();
-                let value = line[idx..].trim();
-                if identifier.is_empty() || value.is_empty() {
-                    bail!("Invalid metric sample '{}'", line);
-                }
-                return Ok((identifier, value));
-            }
-            _ => {}
-        }
-    }
-
-    bail!("Invalid metric sample '{}'", line)
-}
-
-fn parse_metric_identifier(identifier: &str) -> Result<(String, HashMap<String, String>)> {
-    if let Some(start) = identifier.find('{') {
-        if !identifier.ends_with('}') {
-            bail!("Invalid metric label set '{}'", identifier);
-        }
-
-        let name = identifier[..start].trim();
-        if name.is_empty() {
-            bail!("Metric sample is missing a metric name");
-        }
-
-        let attributes = parse_metric_attribute_set(&identifier[start + 1..identifier.len() - 1])?;
-        Ok((name.to_string(), attributes))
-    } else {
-        let name = identifier.trim();
-        if name.is_empty() {
-            bail!("Metric sample is missing a metric name");
-        }
-        Ok((name.to_string(), HashMap::new()))
-    }
-}
-
-fn parse_metric_attribute_set(input: &str) -> Result<HashMap<String, String>> {
-    let mut attributes = HashMap::new();
-    let mut idx = 0;
-
-    while idx < input.len() {
-        while idx < input.len() && input.as_bytes()[idx].is_ascii_whitespace() {
-            idx += 1;
-        }
-        if idx >= input.len() {
-            break;
-        }
-
-        let key_start = idx;
-        while idx < input.len() && input.as_bytes()[idx] != b'=' {
-            idx += 1;
-        }
-        if idx >= input.len() {
-            bail!("Invalid metric label set '{}'", input);
-        }
-
-        let key = input[key_start..idx].trim();
-        if key.is_empty() {
-            bail!("Metric attribute names must not be empty");
-        }
-
-        idx += 1;
-        if idx >= input.len() || input.as_bytes()[idx] != b'"' {
-            bail!("Metric attribute '{}' must use a quoted value", key);
-        }
-        idx += 1;
-
-        let mut value = String::new();
-        while idx < input.len() {
-            match input.as_bytes()[idx] {
-                b'\\' => {
-                    idx += 1;
-                    if idx >= input.len() {
-                        bail!("Invalid escape sequence in metric attribute '{}'", key);
-                    }
-
-                    match input.as_bytes()[idx] {
-                        b'\\' => value.push('\\'),
-                        b'"' => value.push('"'),
-                        b'n' => value.push('\n'),
-                        _ => bail!("Unsupported escape sequence in metric attribute '{}'", key),
-                    }
-                    idx += 1;
-                }
-                b'"' => {
-                    idx += 1;
-                    break;
-                }
-                _ => {
-                    let ch = input[idx..]
-                        .chars()
-                        .next()
-                        .ok_or_else(|| anyhow!("Invalid metric label set '{}'", input))?;
-                    value.push(ch);
-                    idx += ch.len_utf8();
-                }
-            }
-        }
-
-        attributes.insert(key.to_string(), value);
-
-        while idx < input.len() && input.as_bytes()[idx].is_ascii_whitespace() {
-            idx += 1;
-        }
-        if idx >= input.len() {
-            break;
-        }
-        if input.as_bytes()[idx] != b',' {
-            bail!("Invalid metric label separator in '{}'", input);
-        }
-        idx += 1;
-    }
-
-    Ok(attributes)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -408,7 +497,7 @@ mod tests {
         assert_eq!(MetricTool::name(), "metric");
         let desc = MetricTool::description();
         assert!(desc.is_some());
-        assert!(desc.unwrap().contains("single Prometheus metric value"));
+        assert!(desc.unwrap().contains("single Prometheus metric family"));
     }
 
     #[test]
@@ -425,6 +514,11 @@ mod tests {
             "metric tool should be registered, found: {:?}",
             tool_names
         );
+        assert!(
+            tool_names.contains(&"query_metrics"),
+            "query_metrics tool should be registered, found: {:?}",
+            tool_names
+        );
     }
 
     #[test]
@@ -469,73 +563,220 @@ mod tests {
         assert!(err.to_string().contains("either 'attributes' or 'labels'"));
     }
 
+    fn query(name: &str, attributes: &[(&str, &str)]) -> MetricQuery {
+        MetricQuery {
+            name: name.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
     #[test]
-    fn test_find_metric_value_matches_unique_unlabeled_sample() {
+    fn test_find_metric_family_returns_type_and_help() {
         let metrics = "# HELP pgmoneta_up Whether pgmoneta is available.\n\
                        # TYPE pgmoneta_up gauge\n\
                        pgmoneta_up 1\n";
 
+        let family = find_metric_family(metrics, &query("pgmoneta_up", &[])).unwrap();
         assert_eq!(
-            find_metric_value(
-                metrics,
-                &MetricQuery {
-                    name: "pgmoneta_up".to_string(),
-                    attributes: HashMap::new(),
-                }
-            )
-            .unwrap(),
-            "pgmoneta_up 1"
+            serde_json::to_value(&family).unwrap(),
+            serde_json::json!({
+                "Name": "pgmoneta_up",
+                "Type": "gauge",
+                "Help": "Whether pgmoneta is available.",
+                "Samples": [{"Name": "pgmoneta_up", "Labels": {}, "Value": 1.0}]
+            })
         );
     }
 
     #[test]
-    fn test_find_metric_value_matches_labeled_sample() {
-        let metrics = "pgmoneta_backup_total{server=\"primary\",type=\"full\"} 4\n\
-                       pgmoneta_backup_total{server=\"standby\",type=\"full\"} 2\n";
+    fn test_find_metric_family_filters_samples_by_attributes() {
+        let metrics = include_str!("metrics/testdata/pgmoneta.prom");
 
-        assert_eq!(
-            find_metric_value(
-                metrics,
-                &MetricQuery {
-                    name: "pgmoneta_backup_total".to_string(),
-                    attributes: HashMap::from([("server".to_string(), "primary".to_string())]),
-                }
-            )
-            .unwrap(),
-            r#"pgmoneta_backup_total{server="primary",type="full"} 4"#
-        );
+        let family = find_metric_family(
+            metrics,
+            &query(
+                "pgmoneta_retention_server",
+                &[("name", "primary"), ("parameter", "days")],
+            ),
+        )
+        .unwrap();
+        assert_eq!(family.metric_type, exposition::MetricType::Gauge);
+        assert_eq!(family.samples.len(), 1);
+        assert_eq!(family.samples[0].value, 7.0);
+
+        let err = find_metric_family(
+            metrics,
+            &query("pgmoneta_retention_server", &[("name", "unknown")]),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("did not match"));
+        let err = find_metric_family(metrics, &query("pgmoneta_missing", &[])).unwrap_err();
+        assert!(err.to_string().contains("was not found"));
     }
 
     #[test]
-    fn test_find_metric_value_returns_all_matching_samples() {
-        let metrics = "pgmoneta_backup_total{server=\"primary\"} 4\n\
-                       pgmoneta_backup_total{server=\"standby\"} 2\n";
+    fn test_find_metric_family_resolves_histogram_sample_names() {
+        let metrics = crate::telemetry::Metrics::new();
+        metrics.record_http_request(
+            "GET",
+            "/mcp",
+            axum::http::StatusCode::OK,
+            std::time::Duration::from_millis(20),
+            None,
+        );
+        let metrics = metrics.encode().unwrap();
 
-        let output = find_metric_value(
-            metrics,
-            &MetricQuery {
-                name: "pgmoneta_backup_total".to_string(),
-                attributes: HashMap::new(),
-            },
+        let family = find_metric_family(
+            &metrics,
+            &query("pgmoneta_mcp_http_request_duration_seconds", &[]),
         )
         .unwrap();
+        assert_eq!(family.metric_type, exposition::MetricType::Histogram);
+        assert!(family.samples.iter().any(|s| s.name.ends_with("_sum")));
+
+        let buckets = find_metric_family(
+            &metrics,
+            &query(
+                "pgmoneta_mcp_http_request_duration_seconds_bucket",
+                &[("le", "+Inf")],
+            ),
+        )
+        .unwrap();
+        assert_eq!(buckets.name, "pgmoneta_mcp_http_request_duration_seconds");
+        assert_eq!(buckets.samples.len(), 1);
+        assert_eq!(buckets.samples[0].value, 1.0);
+
+        let counter =
+            find_metric_family(&metrics, &query("pgmoneta_mcp_http_requests_total", &[])).unwrap();
+        assert_eq!(counter.name, "pgmoneta_mcp_http_requests");
+        assert_eq!(counter.metric_type, exposition::MetricType::Counter);
+    }
 
+    #[test]
+    fn test_evaluate_query_includes_the_query() {
+        let metrics = include_str!("metrics/testdata/pgmoneta.prom");
+        let text = " count(pgmoneta_backup) ";
+        let query = Query::parse(text).unwrap();
+
+        let result = serde_json::to_value(evaluate_query(metrics, text, &query).unwrap()).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({
+                "Query": "count(pgmoneta_backup)",
+                "ResultType": "vector",
+                "Result": [{"Labels": {}, "Value": 4.0}]
+            })
+        );
+    }
+
+    #[test]
+    fn test_metric_values_filters_by_labels() {
+        let metrics = "# HELP pgmoneta_retention_server Retention\n\
+                       pgmoneta_retention_server{name=\"primary\",parameter=\"days\"} 7\n\
+                       pgmoneta_retention_server{name=\"primary\",parameter=\"weeks\"} 4\n\
+                       pgmoneta_retention_server{name=\"standby\",parameter=\"days\"} 3\n\
+                       not a sample\n";
+
+        assert_eq!(
+            metric_values(
+                metrics,
+                "pgmoneta_retention_server",
+                &[("name", "primary"), ("parameter", "days")]
+            ),
+            vec![7.0]
+        );
         assert_eq!(
-            output,
-            "pgmoneta_backup_total{server=\"primary\"} 4\npgmoneta_backup_total{server=\"standby\"} 2"
+            metric_values(metrics, "pgmoneta_retention_server", &[]).len(),
+            3
         );
+        assert!(metric_values(metrics, "pgmoneta_version", &[]).is_empty());
     }
 
     #[test]
-    fn test_parse_metric_attribute_set_unescapes_values() {
+    fn test_query_window_resolve() {
+        let clock = BackupClock::new(Some(chrono::FixedOffset::east_opt(0).unwrap()));
+        let day = 1_772_323_200_000; // 2026-03-01
+        let resolve = |query: &str, time: Option<&str>, start: Option<&str>, step: Option<&str>| {
+            QueryWindow::resolve(
+                &QueryMetricsRequest {
+                    username: "admin".to_string(),
+                    query: query.to_string(),
+                    time: time.map(str::to_string),
+                    start: start.map(str::to_string),
+                    end: start.map(|_| "2026-03-02".to_string()),
+                    step: step.map(str::to_string),
+                },
+                &Query::parse(query).unwrap(),
+                clock,
+            )
+        };
+
+        assert_eq!(resolve("pgmoneta_state", None, None, None), Ok(None));
+        assert_eq!(
+            resolve("pgmoneta_state", Some("2026-03-01 12:00:00"), None, None),
+            Ok(Some(QueryWindow::At(day + 12 * 60 * 60 * 1000)))
+        );
+        assert!(matches!(
+            resolve("rate(pgmoneta_state[5m])", None, None, None),
+            Ok(Some(QueryWindow::At(_)))
+        ));
+        assert_eq!(
+            resolve("pgmoneta_state", None, Some("2026-03-01"), Some("1h")),
+            Ok(Some(QueryWindow::Range {
+                start: day,
+                end: day + 24 * 60 * 60 * 1000,
+                step: 60 * 60 * 1000,
+            }))
+        );
         assert_eq!(
-            parse_metric_attribute_set(r#"path="/metrics",note="line\nbreak",quote="a\"b""#)
-                .unwrap(),
-            HashMap::from([
-                ("path".to_string(), "/metrics".to_string()),
-                ("note".to_string(), "line\nbreak".to_string()),
-                ("quote".to_string(), "a\"b".to_string()),
-            ])
+            resolve("pgmoneta_state", None, Some("2026-03-01"), None),
+            Ok(Some(QueryWindow::Range {
+                start: day,
+                end: day + 24 * 60 * 60 * 1000,
+                step: 864_000,
+            }))
         );
+
+        for (time, start, step, error) in [
+            (Some("soon"), None, None, "Unable to understand time 'soon'"),
+            (
+                Some("1000000000000 weeks ago"),
+                None,
+                None,
+                "Unable to understand time '1000000000000 weeks ago'",
+            ),
+            (
+                None,
+                Some("999999999999 days ago"),
+                None,
+                "Unable to understand start '999999999999 days ago'",
+            ),
+            (
+                Some("-2 hours ago"),
+                None,
+                None,
+                "Unable to understand time '-2 hours ago'",
+            ),
+            (
+                Some("2026-03-01"),
+                Some("2026-03-01"),
+                None,
+                "either 'time' or 'start'",
+            ),
+            (
+                None,
+                Some("2026-03-03"),
+                None,
+                "'start' must be before 'end'",
+            ),
+            (None, Some("2026-03-01"), Some("5"), "Invalid step '5'"),
+            (None, Some("2026-03-01"), Some("1m"), "more than 1100 steps"),
+        ] {
+            let err = resolve("pgmoneta_state", time, start, step).unwrap_err();
+            assert!(err.contains(error), "expected {error:?}, got {err}");
+        }
     }
 }
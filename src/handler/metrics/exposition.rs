@@ -0,0 +1,705 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! A parser for the Prometheus text and OpenMetrics exposition formats.
+//!
+//! The samples are grouped into typed metric families with their help text
+//! and unit: the `_bucket`, `_sum` and `_count` samples of a histogram, the
+//! `_total` and `_created` samples of a counter, and so on. Timestamps and
+//! exemplars are kept, and an OpenMetrics `# EOF` ends the exposition.
+//!
+//! pgmoneta writes its metadata as `#HELP` and `#TYPE`, without the space
+//! after `#`, which is accepted as well.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Result, anyhow, bail};
+use serde::{Serialize, Serializer};
+
+/// The type of a metric family.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+    GaugeHistogram,
+    Summary,
+    Info,
+    StateSet,
+    /// No or an `unknown`/`untyped` type.
+    #[default]
+    Unknown,
+}
+
+impl MetricType {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "counter" => Some(Self::Counter),
+            "gauge" => Some(Self::Gauge),
+            "histogram" => Some(Self::Histogram),
+            "gaugehistogram" => Some(Self::GaugeHistogram),
+            "summary" => Some(Self::Summary),
+            "info" => Some(Self::Info),
+            "stateset" => Some(Self::StateSet),
+            "unknown" | "untyped" => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+
+    /// The suffixes the sample names of a family of this type may carry.
+    fn suffixes(&self) -> &'static [&'static str] {
+        match self {
+            Self::Counter => &["_total", "_created"],
+            Self::Histogram => &["_bucket", "_sum", "_count", "_created"],
+            Self::GaugeHistogram => &["_bucket", "_gsum", "_gcount"],
+            Self::Summary => &["_sum", "_count", "_created"],
+            Self::Info => &["_info"],
+            Self::Gauge | Self::StateSet | Self::Unknown => &[],
+        }
+    }
+}
+
+/// An exemplar attached to a sample.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Exemplar {
+    pub labels: BTreeMap<String, String>,
+    #[serde(serialize_with = "serialize_value")]
+    pub value: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<f64>,
+}
+
+/// A sample of a metric family.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Sample {
+    /// The sample name, e.g. `http_request_duration_seconds_bucket`.
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    #[serde(serialize_with = "serialize_value")]
+    pub value: f64,
+    /// The timestamp as exposed: milliseconds in the Prometheus text format,
+    /// seconds in OpenMetrics.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exemplar: Option<Exemplar>,
+}
+
+impl Sample {
+    /// Whether the sample carries all of `labels`.
+    pub fn has_labels<'a>(&self, mut labels: impl Iterator<Item = (&'a str, &'a str)>) -> bool {
+        labels.all(|(key, value)| self.labels.get(key).map(String::as_str) == Some(value))
+    }
+}
+
+/// A metric family: its metadata and samples.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MetricFamily {
+    pub name: String,
+    #[serde(rename = "Type")]
+    pub metric_type: MetricType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    pub samples: Vec<Sample>,
+}
+
+/// A parsed exposition.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Exposition {
+    /// The families in the order of their first line.
+    pub families: Vec<MetricFamily>,
+    /// Whether the exposition ended with the OpenMetrics `# EOF`.
+    pub eof: bool,
+}
+
+impl Exposition {
+    /// Parses a Prometheus text or OpenMetrics exposition.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Parser::default();
+        let mut lines = text.lines().enumerate();
+        for (index, line) in lines.by_ref() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == "# EOF" || line == "#EOF" {
+                parser.exposition.eof = true;
+                break;
+            }
+            parser
+                .line(line)
+                .map_err(|e| anyhow!("Line {}: {e}", index + 1))?;
+        }
+        if let Some((index, _)) = lines.find(|(_, line)| !line.trim().is_empty()) {
+            bail!("Line {}: content after '# EOF'", index + 1);
+        }
+        Ok(parser.exposition)
+    }
+
+    /// The family named `name`, or the family of the samples named `name`.
+    pub fn family(&self, name: &str) -> Option<&MetricFamily> {
+        self.families
+            .iter()
+            .find(|family| family.name == name)
+            .or_else(|| {
+                self.families
+                    .iter()
+                    .find(|family| family.samples.iter().any(|sample| sample.name == name))
+            })
+    }
+}
+
+#[derive(Default)]
+struct Parser {
+    exposition: Exposition,
+    /// The index of each family by name.
+    index: HashMap<String, usize>,
+}
+
+impl Parser {
+    fn line(&mut self, line: &str) -> Result<()> {
+        if let Some(comment) = line.strip_prefix('#') {
+            return self.metadata(comment.trim_start());
+        }
+        let sample = parse_sample(line)?;
+        let family = self.family_of(&sample.name);
+        self.exposition.families[family].samples.push(sample);
+        Ok(())
+    }
+
+    /// Handles `HELP`, `TYPE` and `UNIT`; other comments are ignored.
+    fn metadata(&mut self, comment: &str) -> Result<()> {
+        let mut parts = comment.splitn(3, [' ', '\t']);
+        let keyword = parts.next().unwrap_or_default();
+        if !matches!(keyword, "HELP" | "TYPE" | "UNIT") {
+            return Ok(());
+        }
+        let name = parts
+            .next()
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| anyhow!("'# {keyword}' is missing a metric name"))?;
+        let rest = parts.next().unwrap_or_default().trim();
+        let family = self.family(name);
+        let family = &mut self.exposition.families[family];
+        match keyword {
+            "HELP" => family.help = Some(unescape(rest)),
+            "UNIT" => family.unit = Some(rest.to_string()).filter(|unit| !unit.is_empty()),
+            _ => {
+                family.metric_type = MetricType::parse(rest)
+                    .ok_or_else(|| anyhow!("unsupported metric type '{rest}' of '{name}'"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The family named `name`, created when missing.
+    fn family(&mut self, name: &str) -> usize {
+        if let Some(index) = self.index.get(name) {
+            return *index;
+        }
+        self.exposition.families.push(MetricFamily {
+            name: name.to_string(),
+            ..Default::default()
+        });
+        let index = self.exposition.families.len() - 1;
+        self.index.insert(name.to_string(), index);
+        index
+    }
+
+    /// The family a sample belongs to, by its name or the name without a
+    /// suffix of the family type.
+    fn family_of(&mut self, sample: &str) -> usize {
+        if let Some(index) = self.index.get(sample) {
+            return *index;
+        }
+        for (position, _) in sample.match_indices('_') {
+            let (name, suffix) = sample.split_at(position);
+            if let Some(index) = self.index.get(name)
+                && self.exposition.families[*index]
+                    .metric_type
+                    .suffixes()
+                    .contains(&suffix)
+            {
+                return *index;
+            }
+        }
+        self.family(sample)
+    }
+}
+
+/// Parses `name{labels} value [timestamp] [# {labels} value [timestamp]]`.
+pub(crate) fn parse_sample(line: &str) -> Result<Sample> {
+    let (name, labels, rest) = parse_series(line)?;
+    let (values, exemplar) = match rest.split_once('#') {
+        Some((values, exemplar)) => (values, Some(exemplar.trim())),
+        None => (rest, None),
+    };
+    let mut values = values.split_whitespace();
+    let value = values
+        .next()
+        .ok_or_else(|| anyhow!("metric sample '{line}' is missing a value"))
+        .and_then(|value| parse_value(value, line))?;
+    let timestamp = values
+        .next()
+        .map(|timestamp| parse_value(timestamp, line))
+        .transpose()?;
+    if values.next().is_some() {
+        bail!("invalid metric sample '{line}'");
+    }
+
+    let exemplar = match exemplar {
+        Some(exemplar) => {
+            let labels_end = exemplar
+                .strip_prefix('{')
+                .and_then(|rest| find_closing_brace(rest).map(|end| end + 1))
+                .ok_or_else(|| anyhow!("invalid exemplar in '{line}'"))?;
+            let labels = parse_labels(&exemplar[1..labels_end])?;
+            let mut values = exemplar[labels_end + 1..].split_whitespace();
+            let value = values
+                .next()
+                .ok_or_else(|| anyhow!("exemplar in '{line}' is missing a value"))
+                .and_then(|value| parse_value(value, line))?;
+            let timestamp = values
+                .next()
+                .map(|timestamp| parse_value(timestamp, line))
+                .transpose()?;
+            Some(Exemplar {
+                labels,
+                value,
+                timestamp,
+            })
+        }
+        None => None,
+    };
+
+    Ok(Sample {
+        name,
+        labels,
+        value,
+        timestamp,
+        exemplar,
+    })
+}
+
+/// Splits a sample line into its name, labels and the text after them.
+fn parse_series(line: &str) -> Result<(String, BTreeMap<String, String>, &str)> {
+    let name_end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .unwrap_or(line.len());
+    let name = &line[..name_end];
+    if name.is_empty() {
+        bail!("metric sample '{line}' is missing a metric name");
+    }
+    let rest = line[name_end..].trim_start();
+    let (labels, rest) = match rest.strip_prefix('{') {
+        Some(inner) => {
+            let end = find_closing_brace(inner)
+                .ok_or_else(|| anyhow!("invalid metric label set in '{line}'"))?;
+            (parse_labels(&inner[..end])?, &inner[end + 1..])
+        }
+        None => (BTreeMap::new(), rest),
+    };
+    Ok((name.to_string(), labels, rest))
+}
+
+/// The position of the `}` closing a label set, skipping quoted values.
+fn find_closing_brace(input: &str) -> Option<usize> {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '}' if !quoted => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses the inside of a label set, e.g. `server="primary",le="0.5"`.
+pub(crate) fn parse_labels(input: &str) -> Result<BTreeMap<String, String>> {
+    let mut labels = BTreeMap::new();
+    let mut idx = 0;
+
+    while idx < input.len() {
+        while idx < input.len() && input.as_bytes()[idx].is_ascii_whitespace() {
+            idx += 1;
+        }
+        if idx >= input.len() {
+            break;
+        }
+
+        let key_start = idx;
+        while idx < input.len() && input.as_bytes()[idx] != b'=' {
+            idx += 1;
+        }
+        if idx >= input.len() {
+            bail!("Invalid metric label set '{}'", input);
+        }
+
+        let key = input[key_start..idx].trim();
+        if key.is_empty() {
+            bail!("Metric attribute names must not be empty");
+        }
+
+        idx += 1;
+        while idx < input.len() && input.as_bytes()[idx].is_ascii_whitespace() {
+            idx += 1;
+        }
+        if idx >= input.len() || input.as_bytes()[idx] != b'"' {
+            bail!("Metric attribute '{}' must use a quoted value", key);
+        }
+        idx += 1;
+
+        let mut value = String::new();
+        let mut closed = false;
+        while idx < input.len() {
+            match input.as_bytes()[idx] {
+                b'\\' => {
+                    idx += 1;
+                    if idx >= input.len() {
+                        bail!("Invalid escape sequence in metric attribute '{}'", key);
+                    }
+
+                    match input.as_bytes()[idx] {
+                        b'\\' => value.push('\\'),
+                        b'"' => value.push('"'),
+                        b'n' => value.push('\n'),
+                        _ => bail!("Unsupported escape sequence in metric attribute '{}'", key),
+                    }
+                    idx += 1;
+                }
+                b'"' => {
+                    idx += 1;
+                    closed = true;
+                    break;
+                }
+                _ => {
+                    let ch = input[idx..]
+                        .chars()
+                        .next()
+                        .ok_or_else(|| anyhow!("Invalid metric label set '{}'", input))?;
+                    value.push(ch);
+                    idx += ch.len_utf8();
+                }
+            }
+        }
+        if !closed {
+            bail!("Unterminated value of metric attribute '{}'", key);
+        }
+
+        labels.insert(key.to_string(), value);
+
+        while idx < input.len() && input.as_bytes()[idx].is_ascii_whitespace() {
+            idx += 1;
+        }
+        if idx >= input.len() {
+            break;
+        }
+        if input.as_bytes()[idx] != b',' {
+            bail!("Invalid metric label separator in '{}'", input);
+        }
+        idx += 1;
+    }
+
+    Ok(labels)
+}
+
+/// Parses a sample value, including `+Inf`, `-Inf` and `NaN`.
+fn parse_value(value: &str, line: &str) -> Result<f64> {
+    match value {
+        "+Inf" | "Inf" => Ok(f64::INFINITY),
+        "-Inf" => Ok(f64::NEG_INFINITY),
+        "NaN" => Ok(f64::NAN),
+        _ => value
+            .parse::<f64>()
+            .map_err(|_| anyhow!("invalid value '{value}' in '{line}'")),
+    }
+}
+
+/// Unescapes `\\` and `\n` in help text.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('"') => unescaped.push('"'),
+            Some(other) => {
+                if other != '\\' {
+                    unescaped.push('\\');
+                }
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Writes finite values as numbers and the others as `+Inf`, `-Inf` or `NaN`.
+pub(crate) fn serialize_value<S: Serializer>(
+    value: &f64,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if value.is_finite() {
+        serializer.serialize_f64(*value)
+    } else if value.is_nan() {
+        serializer.serialize_str("NaN")
+    } else if value.is_sign_positive() {
+        serializer.serialize_str("+Inf")
+    } else {
+        serializer.serialize_str("-Inf")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::Metrics;
+    use axum::http::StatusCode;
+    use std::time::Duration;
+
+    const PGMONETA: &str = include_str!("testdata/pgmoneta.prom");
+
+    fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_pgmoneta_scrape() {
+        let exposition = Exposition::parse(PGMONETA).unwrap();
+        assert!(!exposition.eof);
+        assert_eq!(exposition.families.len(), 21);
+        assert!(
+            exposition
+                .families
+                .iter()
+                .all(|family| family.metric_type == MetricType::Gauge && family.help.is_some())
+        );
+
+        let version = exposition.family("pgmoneta_version").unwrap();
+        assert_eq!(version.help.as_deref(), Some("The version of pgmoneta"));
+        assert_eq!(version.samples[0].labels, labels(&[("version", "0.19.0")]));
+
+        let retention = exposition.family("pgmoneta_retention_server").unwrap();
+        assert_eq!(retention.samples.len(), 8);
+        assert_eq!(
+            retention
+                .samples
+                .iter()
+                .filter(|sample| sample.has_labels([("name", "replica")].into_iter()))
+                .count(),
+            4
+        );
+        assert_eq!(
+            exposition.family("pgmoneta_free_space").unwrap().samples[0].value,
+            52706254848.0
+        );
+    }
+
+    #[test]
+    fn test_parse_telemetry_openmetrics() {
+        let metrics = Metrics::new();
+        metrics.record_http_request(
+            "GET",
+            "/mcp",
+            StatusCode::OK,
+            Duration::from_millis(20),
+            None,
+        );
+        metrics.record_http_request("POST", "/mcp", StatusCode::OK, Duration::from_secs(3), None);
+        metrics.record_sla_check("primary", 1, Some(7200.0), None);
+        let exposition = Exposition::parse(&metrics.encode().unwrap()).unwrap();
+        assert!(exposition.eof);
+
+        let requests = exposition.family("pgmoneta_mcp_http_requests").unwrap();
+        assert_eq!(requests.metric_type, MetricType::Counter);
+        assert!(
+            requests
+                .help
+                .as_deref()
+                .unwrap()
+                .starts_with("Number of HTTP requests handled by pgmoneta-mcp.")
+        );
+        assert!(
+            requests
+                .samples
+                .iter()
+                .all(|sample| sample.name == "pgmoneta_mcp_http_requests_total")
+        );
+        assert_eq!(requests.samples.len(), 2);
+
+        let duration = exposition
+            .family("pgmoneta_mcp_http_request_duration_seconds")
+            .unwrap();
+        assert_eq!(duration.metric_type, MetricType::Histogram);
+        let get = |name: &str, le: Option<&str>| {
+            duration
+                .samples
+                .iter()
+                .find(|sample| {
+                    sample.name == name
+                        && sample.labels.get("method").map(String::as_str) == Some("POST")
+                        && sample.labels.get("le").map(String::as_str) == le
+                })
+                .map(|sample| sample.value)
+        };
+        assert_eq!(
+            get("pgmoneta_mcp_http_request_duration_seconds_count", None),
+            Some(1.0)
+        );
+        assert_eq!(
+            get("pgmoneta_mcp_http_request_duration_seconds_sum", None),
+            Some(3.0)
+        );
+        assert_eq!(
+            get(
+                "pgmoneta_mcp_http_request_duration_seconds_bucket",
+                Some("+Inf")
+            ),
+            Some(1.0)
+        );
+
+        let status = exposition.family("pgmoneta_mcp_sla_status").unwrap();
+        assert_eq!(status.metric_type, MetricType::Gauge);
+        assert_eq!(status.samples[0].labels, labels(&[("server", "primary")]));
+    }
+
+    #[test]
+    fn test_parse_openmetrics_features() {
+        let text = "# TYPE build info\n\
+                    # HELP build Build information with a \\\\ and\\na new line.\n\
+                    build_info{version=\"1.0\",note=\"a } b\"} 1\n\
+                    # TYPE rpc summary\n\
+                    # UNIT rpc seconds\n\
+                    rpc{quantile=\"0.5\"} 0.05\n\
+                    rpc{quantile=\"0.99\"} NaN\n\
+                    rpc_sum 12.5 1700000000.5\n\
+                    rpc_count 250\n\
+                    # TYPE latency histogram\n\
+                    latency_bucket{le=\"0.1\"} 3 # {trace_id=\"abc\"} 0.05 1700000000\n\
+                    latency_bucket{le=\"+Inf\"} 4\n\
+                    latency_sum 1.25\n\
+                    latency_count 4\n\
+                    latency_created 1699999000\n\
+                    # TYPE temperature untyped\n\
+                    temperature -Inf\n\
+                    orphan 2\n\
+                    # EOF\n";
+
+        let exposition = Exposition::parse(text).unwrap();
+        assert!(exposition.eof);
+        let names = exposition
+            .families
+            .iter()
+            .map(|family| (family.name.as_str(), family.metric_type))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("build", MetricType::Info),
+                ("rpc", MetricType::Summary),
+                ("latency", MetricType::Histogram),
+                ("temperature", MetricType::Unknown),
+                ("orphan", MetricType::Unknown),
+            ]
+        );
+
+        let build = &exposition.families[0];
+        assert_eq!(
+            build.help.as_deref(),
+            Some("Build information with a \\ and\na new line.")
+        );
+        assert_eq!(
+            build.samples[0].labels,
+            labels(&[("version", "1.0"), ("note", "a } b")])
+        );
+
+        let rpc = &exposition.families[1];
+        assert_eq!(rpc.unit.as_deref(), Some("seconds"));
+        assert_eq!(rpc.samples.len(), 4);
+        assert!(rpc.samples[1].value.is_nan());
+        assert_eq!(rpc.samples[2].timestamp, Some(1700000000.5));
+
+        let latency = &exposition.families[2];
+        assert_eq!(latency.samples.len(), 5);
+        assert_eq!(
+            latency.samples[0].exemplar,
+            Some(Exemplar {
+                labels: labels(&[("trace_id", "abc")]),
+                value: 0.05,
+                timestamp: Some(1700000000.0),
+            })
+        );
+        assert_eq!(latency.samples[1].labels, labels(&[("le", "+Inf")]));
+        assert_eq!(
+            exposition.family("latency_created").unwrap().name,
+            "latency"
+        );
+        assert_eq!(exposition.families[3].samples[0].value, f64::NEG_INFINITY);
+
+        let json = serde_json::to_value(rpc).unwrap();
+        assert_eq!(json["Type"], "summary");
+        assert_eq!(json["Samples"][1]["Value"], "NaN");
+        assert_eq!(json["Samples"][2]["Timestamp"], 1700000000.5);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_expositions() {
+        for (text, error) in [
+            ("up 1\n# EOF\nup 2\n", "Line 3: content after '# EOF'"),
+            ("# TYPE up thing\n", "unsupported metric type 'thing'"),
+            ("up\n", "missing a value"),
+            ("up one\n", "invalid value 'one'"),
+            ("up{job=\"a\" 1\n", "invalid metric label set"),
+            ("up{job=a} 1\n", "must use a quoted value"),
+            ("up 1 2 3\n", "invalid metric sample"),
+        ] {
+            let err = Exposition::parse(text).unwrap_err();
+            assert!(
+                err.to_string().contains(error),
+                "{text:?}: expected {error:?}, got {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_labels_unescapes_values() {
+        assert_eq!(
+            parse_labels(r#"path="/metrics",note="line\nbreak",quote="a\"b""#).unwrap(),
+            labels(&[
+                ("path", "/metrics"),
+                ("note", "line\nbreak"),
+                ("quote", "a\"b"),
+            ])
+        );
+    }
+}
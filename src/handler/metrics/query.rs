@@ -0,0 +1,1908 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! A small PromQL subset evaluated against a single metrics scrape.
+//!
+//! Supported are instant vector selectors with `=`, `!=`, `=~` and `!~`
+//! label matchers, arithmetic (`+ - * / % ^`) and comparisons (with an
+//! optional `bool`) between scalars and vectors, `on`/`ignoring` one-to-one
+//! vector matching, the `sum`, `avg`, `min`, `max`, `count`, `topk` and
+//! `bottomk` aggregations with `by`/`without`, and `histogram_quantile`.
+//!
+//! Range selectors such as `x[1h]` and the `rate`, `increase` and
+//! `avg_over_time` functions need the metrics history, against which queries
+//! can also be evaluated at a past time or over a time window.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{Result, anyhow, bail};
+use chrono::DateTime;
+use regex::Regex;
+use serde::{Serialize, Serializer};
+
+use super::exposition::{Exposition, serialize_value};
+use crate::handler::catalog::BackupClock;
+use crate::history::store::{Point, SeriesPoints};
+
+type Labels = BTreeMap<String, String>;
+
+/// The most steps a range query is evaluated at.
+pub const MAX_STEPS: i64 = 1_100;
+
+/// A parsed query.
+#[derive(Debug)]
+pub struct Query {
+    expr: Expr,
+}
+
+impl Query {
+    /// Parses `query`.
+    pub fn parse(query: &str) -> Result<Self> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            end: query.len(),
+        };
+        let expr = parser.expr(0)?;
+        if let Some((offset, token)) = parser.tokens.get(parser.position) {
+            bail!("unexpected {} at position {offset}", token.describe());
+        }
+        Ok(Self { expr })
+    }
+
+    /// Evaluates the query against a scrape.
+    pub fn evaluate(&self, exposition: &Exposition) -> Result<QueryValue> {
+        let context = Context {
+            source: Source::Scrape(exposition),
+            time: 0,
+        };
+        Ok(evaluate(&self.expr, &context)?.into())
+    }
+
+    /// Evaluates the query at `time` against the history, whose instant
+    /// selectors take the latest sample up to `lookback` before `time`.
+    pub fn evaluate_at(
+        &self,
+        series: &[SeriesPoints],
+        lookback: i64,
+        time: i64,
+    ) -> Result<QueryValue> {
+        let context = Context {
+            source: Source::History { series, lookback },
+            time,
+        };
+        Ok(evaluate(&self.expr, &context)?.into())
+    }
+
+    /// Evaluates the query against the history at every `step` from `start`
+    /// to `end`.
+    pub fn evaluate_range(
+        &self,
+        series: &[SeriesPoints],
+        lookback: i64,
+        start: i64,
+        end: i64,
+        step: i64,
+    ) -> Result<QueryValue> {
+        if step <= 0 {
+            bail!("the step must be positive");
+        }
+        if end < start {
+            bail!("the end must not be before the start");
+        }
+        if (end - start) / step >= MAX_STEPS {
+            bail!("the window has more than {MAX_STEPS} steps; use a longer step");
+        }
+
+        let mut matrix: BTreeMap<(Option<String>, Labels), Vec<TimedValue>> = BTreeMap::new();
+        let mut time = start;
+        while time <= end {
+            let context = Context {
+                source: Source::History { series, lookback },
+                time,
+            };
+            let values = match evaluate(&self.expr, &context)? {
+                Value::Scalar(value) => vec![(None, Labels::new(), value)],
+                Value::Vector(series) => series
+                    .into_iter()
+                    .map(|series| (series.name, series.labels, series.value))
+                    .collect(),
+            };
+            for (name, labels, value) in values {
+                matrix
+                    .entry((name, labels))
+                    .or_default()
+                    .push(TimedValue { time, value });
+            }
+            time += step;
+        }
+
+        Ok(QueryValue::Matrix {
+            series: matrix
+                .into_iter()
+                .map(|((name, labels), values)| RangeSeries {
+                    name,
+                    labels,
+                    values,
+                })
+                .collect(),
+        })
+    }
+
+    /// Whether the query has range selectors, which need the history.
+    pub fn needs_history(&self) -> bool {
+        self.expr.longest_range() > 0
+    }
+
+    /// How far before the evaluation time the query reads samples, not
+    /// counting the lookback of instant selectors.
+    pub fn window(&self) -> i64 {
+        self.expr.longest_range()
+    }
+
+    /// The metric names the query selects, or `None` when a selector has
+    /// no metric name.
+    pub fn metric_names(&self) -> Option<BTreeSet<String>> {
+        let mut names = BTreeSet::new();
+        self.expr.metric_names(&mut names).then_some(names)
+    }
+}
+
+/// The result of a query.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "ResultType", rename_all = "lowercase")]
+pub enum QueryValue {
+    Scalar {
+        #[serde(rename = "Value", serialize_with = "serialize_value")]
+        value: f64,
+    },
+    Vector {
+        #[serde(rename = "Result")]
+        series: Vec<Series>,
+    },
+    Matrix {
+        #[serde(rename = "Result")]
+        series: Vec<RangeSeries>,
+    },
+}
+
+impl From<Value> for QueryValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Scalar(value) => Self::Scalar { value },
+            Value::Vector(series) => Self::Vector { series },
+        }
+    }
+}
+
+/// A series of an instant vector.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Series {
+    /// The metric name; dropped by arithmetic and aggregations.
+    #[serde(rename = "Metric", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub labels: Labels,
+    #[serde(serialize_with = "serialize_value")]
+    pub value: f64,
+}
+
+/// A series of a range query.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RangeSeries {
+    #[serde(rename = "Metric", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub labels: Labels,
+    pub values: Vec<TimedValue>,
+}
+
+/// A value of a range query at a step.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TimedValue {
+    /// Unix milliseconds, written as a time of the configured timezone.
+    #[serde(serialize_with = "serialize_time")]
+    pub time: i64,
+    #[serde(serialize_with = "serialize_value")]
+    pub value: f64,
+}
+
+/// Formats Unix milliseconds as a time of the configured timezone.
+pub(crate) fn format_time(time: i64) -> String {
+    let clock = BackupClock::configured();
+    match DateTime::from_timestamp_millis(time) {
+        Some(time) => clock.format(clock.localize(time.fixed_offset())),
+        None => time.to_string(),
+    }
+}
+
+fn serialize_time<S: Serializer>(time: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_time(*time))
+}
+
+#[derive(Debug)]
+enum Expr {
+    Number(f64),
+    Selector {
+        name: Option<String>,
+        matchers: Vec<Matcher>,
+    },
+    /// A range selector, only valid as the argument of a range function.
+    RangeSelector {
+        name: Option<String>,
+        matchers: Vec<Matcher>,
+        range: i64,
+    },
+    RangeFunction {
+        function: RangeFunction,
+        name: Option<String>,
+        matchers: Vec<Matcher>,
+        range: i64,
+    },
+    Negate(Box<Expr>),
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+        matching: Option<Matching>,
+        return_bool: bool,
+    },
+    Aggregate {
+        op: AggregateOp,
+        grouping: Grouping,
+        parameter: Option<Box<Expr>>,
+        expr: Box<Expr>,
+    },
+    HistogramQuantile {
+        quantile: Box<Expr>,
+        expr: Box<Expr>,
+    },
+}
+
+impl Expr {
+    /// The longest range of the range selectors, or 0 without any.
+    fn longest_range(&self) -> i64 {
+        match self {
+            Self::Number(_) | Self::Selector { .. } => 0,
+            Self::RangeSelector { range, .. } | Self::RangeFunction { range, .. } => *range,
+            Self::Negate(expr) => expr.longest_range(),
+            Self::Binary { lhs, rhs, .. } => lhs.longest_range().max(rhs.longest_range()),
+            Self::Aggregate {
+                parameter, expr, ..
+            } => parameter
+                .as_ref()
+                .map_or(0, |parameter| parameter.longest_range())
+                .max(expr.longest_range()),
+            Self::HistogramQuantile { quantile, expr } => {
+                quantile.longest_range().max(expr.longest_range())
+            }
+        }
+    }
+
+    /// Adds the selected metric names, returning false for a selector
+    /// without a metric name.
+    fn metric_names(&self, names: &mut BTreeSet<String>) -> bool {
+        match self {
+            Self::Number(_) => true,
+            Self::Selector { name, .. }
+            | Self::RangeSelector { name, .. }
+            | Self::RangeFunction { name, .. } => match name {
+                Some(name) => {
+                    names.insert(name.clone());
+                    true
+                }
+                None => false,
+            },
+            Self::Negate(expr) => expr.metric_names(names),
+            Self::Binary { lhs, rhs, .. } => lhs.metric_names(names) & rhs.metric_names(names),
+            Self::Aggregate {
+                parameter, expr, ..
+            } => {
+                parameter
+                    .as_ref()
+                    .is_none_or(|parameter| parameter.metric_names(names))
+                    & expr.metric_names(names)
+            }
+            Self::HistogramQuantile { quantile, expr } => {
+                quantile.metric_names(names) & expr.metric_names(names)
+            }
+        }
+    }
+}
+
+/// A function over the samples of a range selector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RangeFunction {
+    Rate,
+    Increase,
+    AvgOverTime,
+}
+
+impl RangeFunction {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "rate" => Some(Self::Rate),
+            "increase" => Some(Self::Increase),
+            "avg_over_time" => Some(Self::AvgOverTime),
+            _ => None,
+        }
+    }
+
+    /// Applies the function to points, oldest first.
+    ///
+    /// `rate` and `increase` treat the series as a counter: a decrease is a
+    /// reset. Unlike Prometheus, they do not extrapolate to the range edges.
+    fn apply(&self, points: &[Point]) -> Option<f64> {
+        match self {
+            Self::AvgOverTime => {
+                let count = points
+                    .iter()
+                    .map(|point| f64::from(point.count))
+                    .sum::<f64>();
+                (count > 0.0).then(|| {
+                    points
+                        .iter()
+                        .map(|point| point.mean * f64::from(point.count))
+                        .sum::<f64>()
+                        / count
+                })
+            }
+            Self::Increase | Self::Rate => {
+                let [first, .., last] = points else {
+                    return None;
+                };
+                let increase = points
+                    .windows(2)
+                    .map(|pair| {
+                        if pair[1].last >= pair[0].last {
+                            pair[1].last - pair[0].last
+                        } else {
+                            pair[1].last
+                        }
+                    })
+                    .sum::<f64>();
+                if *self == Self::Increase {
+                    Some(increase)
+                } else {
+                    let seconds = (last.timestamp - first.timestamp) as f64 / 1000.0;
+                    (seconds > 0.0).then(|| increase / seconds)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Matcher {
+    label: String,
+    op: MatchOp,
+}
+
+#[derive(Debug)]
+enum MatchOp {
+    Equal(String),
+    NotEqual(String),
+    Regex(Regex),
+    NotRegex(Regex),
+}
+
+impl Matcher {
+    fn matches(&self, value: &str) -> bool {
+        match &self.op {
+            MatchOp::Equal(expected) => value == expected,
+            MatchOp::NotEqual(expected) => value != expected,
+            MatchOp::Regex(regex) => regex.is_match(value),
+            MatchOp::NotRegex(regex) => !regex.is_match(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl BinaryOp {
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge => 1,
+            Self::Add | Self::Sub => 2,
+            Self::Mul | Self::Div | Self::Mod => 3,
+            Self::Pow => 4,
+        }
+    }
+
+    fn is_comparison(&self) -> bool {
+        self.precedence() == 1
+    }
+
+    /// Applies the operator; comparisons return 1 or 0.
+    fn apply(&self, lhs: f64, rhs: f64) -> f64 {
+        let truth = |holds: bool| if holds { 1.0 } else { 0.0 };
+        match self {
+            Self::Add => lhs + rhs,
+            Self::Sub => lhs - rhs,
+            Self::Mul => lhs * rhs,
+            Self::Div => lhs / rhs,
+            Self::Mod => lhs % rhs,
+            Self::Pow => lhs.powf(rhs),
+            Self::Eq => truth(lhs == rhs),
+            Self::Ne => truth(lhs != rhs),
+            Self::Lt => truth(lhs < rhs),
+            Self::Le => truth(lhs <= rhs),
+            Self::Gt => truth(lhs > rhs),
+            Self::Ge => truth(lhs >= rhs),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AggregateOp {
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Count,
+    Topk,
+    Bottomk,
+}
+
+impl AggregateOp {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "sum" => Some(Self::Sum),
+            "avg" => Some(Self::Avg),
+            "min" => Some(Self::Min),
+            "max" => Some(Self::Max),
+            "count" => Some(Self::Count),
+            "topk" => Some(Self::Topk),
+            "bottomk" => Some(Self::Bottomk),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Grouping {
+    By(Vec<String>),
+    Without(Vec<String>),
+}
+
+impl Grouping {
+    fn key(&self, labels: &Labels) -> Labels {
+        match self {
+            Self::By(names) => labels
+                .iter()
+                .filter(|(key, _)| names.contains(key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            Self::Without(names) => labels
+                .iter()
+                .filter(|(key, _)| !names.contains(key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// `on (labels)` or `ignoring (labels)` of a binary operation.
+#[derive(Debug)]
+struct Matching {
+    on: bool,
+    labels: Vec<String>,
+}
+
+impl Matching {
+    fn key(&self, labels: &Labels) -> Labels {
+        if self.on {
+            Grouping::By(self.labels.clone()).key(labels)
+        } else {
+            Grouping::Without(self.labels.clone()).key(labels)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Number(f64),
+    String(String),
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+    /// A duration such as `5m`, in milliseconds.
+    Duration(i64),
+    Comma,
+    Assign,
+    EqualEqual,
+    NotEqual,
+    RegexMatch,
+    RegexNotMatch,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Caret,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Self::Identifier(name) => format!("'{name}'"),
+            Self::Number(number) => format!("number {number}"),
+            Self::String(text) => format!("string \"{text}\""),
+            Self::LeftParen => "'('".to_string(),
+            Self::RightParen => "')'".to_string(),
+            Self::LeftBrace => "'{'".to_string(),
+            Self::RightBrace => "'}'".to_string(),
+            Self::LeftBracket => "'['".to_string(),
+            Self::RightBracket => "']'".to_string(),
+            Self::Duration(duration) => format!("duration {duration}ms"),
+            Self::Comma => "','".to_string(),
+            Self::Assign => "'='".to_string(),
+            Self::EqualEqual => "'=='".to_string(),
+            Self::NotEqual => "'!='".to_string(),
+            Self::RegexMatch => "'=~'".to_string(),
+            Self::RegexNotMatch => "'!~'".to_string(),
+            Self::Less => "'<'".to_string(),
+            Self::LessEqual => "'<='".to_string(),
+            Self::Greater => "'>'".to_string(),
+            Self::GreaterEqual => "'>='".to_string(),
+            Self::Plus => "'+'".to_string(),
+            Self::Minus => "'-'".to_string(),
+            Self::Star => "'*'".to_string(),
+            Self::Slash => "'/'".to_string(),
+            Self::Percent => "'%'".to_string(),
+            Self::Caret => "'^'".to_string(),
+        }
+    }
+
+    fn binary_op(&self) -> Option<BinaryOp> {
+        match self {
+            Self::Plus => Some(BinaryOp::Add),
+            Self::Minus => Some(BinaryOp::Sub),
+            Self::Star => Some(BinaryOp::Mul),
+            Self::Slash => Some(BinaryOp::Div),
+            Self::Percent => Some(BinaryOp::Mod),
+            Self::Caret => Some(BinaryOp::Pow),
+            Self::EqualEqual => Some(BinaryOp::Eq),
+            Self::NotEqual => Some(BinaryOp::Ne),
+            Self::Less => Some(BinaryOp::Lt),
+            Self::LessEqual => Some(BinaryOp::Le),
+            Self::Greater => Some(BinaryOp::Gt),
+            Self::GreaterEqual => Some(BinaryOp::Ge),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a query into tokens with their byte offsets.
+fn tokenize(query: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        let mut next_is = |expected: char| chars.next_if(|(_, next)| *next == expected).is_some();
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '{' => Token::LeftBrace,
+            '}' => Token::RightBrace,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            ',' => Token::Comma,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '%' => Token::Percent,
+            '^' => Token::Caret,
+            '=' if next_is('=') => Token::EqualEqual,
+            '=' if next_is('~') => Token::RegexMatch,
+            '=' => Token::Assign,
+            '!' if next_is('=') => Token::NotEqual,
+            '!' if next_is('~') => Token::RegexNotMatch,
+            '<' if next_is('=') => Token::LessEqual,
+            '<' => Token::Less,
+            '>' if next_is('=') => Token::GreaterEqual,
+            '>' => Token::Greater,
+            '"' | '\'' | '`' => {
+                let mut text = String::new();
+                let mut closed = false;
+                while let Some((_, next)) = chars.next() {
+                    match next {
+                        _ if next == c => {
+                            closed = true;
+                            break;
+                        }
+                        '\\' if c != '`' => match chars.next() {
+                            Some((_, 'n')) => text.push('\n'),
+                            Some((_, 't')) => text.push('\t'),
+                            Some((_, escaped)) => text.push(escaped),
+                            None => break,
+                        },
+                        _ => text.push(next),
+                    }
+                }
+                if !closed {
+                    bail!("unterminated string at position {offset}");
+                }
+                Token::String(text)
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut end = offset + c.len_utf8();
+                let mut previous = c;
+                while let Some((index, next)) = chars.next_if(|(_, next)| {
+                    next.is_ascii_alphanumeric()
+                        || *next == '.'
+                        || (matches!(*next, '+' | '-') && matches!(previous, 'e' | 'E'))
+                }) {
+                    previous = next;
+                    end = index + next.len_utf8();
+                }
+                let number = &query[offset..end];
+                match (number.parse(), parse_duration(number)) {
+                    (Ok(number), _) => Token::Number(number),
+                    (_, Some(duration)) => Token::Duration(duration),
+                    _ => bail!("invalid number '{number}' at position {offset}"),
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' || c == ':' => {
+                let mut end = offset + c.len_utf8();
+                while let Some((index, next)) = chars
+                    .next_if(|(_, next)| next.is_ascii_alphanumeric() || matches!(next, '_' | ':'))
+                {
+                    end = index + next.len_utf8();
+                }
+                Token::Identifier(query[offset..end].to_string())
+            }
+            _ => bail!("unexpected character '{c}' at position {offset}"),
+        };
+        tokens.push((offset, token));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    /// The length of the query, reported for errors at its end.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let (_, token) = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| anyhow!("unexpected end of query at position {}", self.end))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn error(&self, expected: &str) -> anyhow::Error {
+        match self.tokens.get(self.position) {
+            Some((offset, token)) => anyhow!(
+                "expected {expected} at position {offset}, found {}",
+                token.describe()
+            ),
+            None => anyhow!("expected {expected} at position {}", self.end),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        if self.peek() == Some(&expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&expected.describe()))
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Identifier(name)) if name == keyword) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parses an expression whose operators bind at least as tight as
+    /// `precedence`.
+    fn expr(&mut self, precedence: u8) -> Result<Expr> {
+        let mut lhs = self.unary()?;
+
+        while let Some(op) = self.peek().and_then(Token::binary_op) {
+            if op.precedence() < precedence {
+                break;
+            }
+            self.position += 1;
+
+            let return_bool = op.is_comparison() && self.keyword("bool");
+            let matching = if self.keyword("on") {
+                Some(Matching {
+                    on: true,
+                    labels: self.label_list()?,
+                })
+            } else if self.keyword("ignoring") {
+                Some(Matching {
+                    on: false,
+                    labels: self.label_list()?,
+                })
+            } else {
+                None
+            };
+
+            // `^` is right associative, the others left associative.
+            let next = if op == BinaryOp::Pow {
+                op.precedence()
+            } else {
+                op.precedence() + 1
+            };
+            let rhs = self.expr(next)?;
+            lhs = Expr::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+                matching,
+                return_bool,
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        match self.peek() {
+            Some(Token::Minus) => {
+                self.position += 1;
+                Ok(Expr::Negate(Box::new(
+                    self.expr(BinaryOp::Pow.precedence())?,
+                )))
+            }
+            Some(Token::Plus) => {
+                self.position += 1;
+                self.expr(BinaryOp::Pow.precedence())
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        match self.peek().cloned() {
+            Some(Token::Number(number)) => {
+                self.position += 1;
+                Ok(Expr::Number(number))
+            }
+            Some(Token::LeftParen) => {
+                self.position += 1;
+                let expr = self.expr(0)?;
+                self.expect(Token::RightParen)?;
+                Ok(expr)
+            }
+            Some(Token::LeftBrace) => self.selector(None),
+            Some(Token::Identifier(name)) => {
+                self.position += 1;
+                let called = matches!(self.peek(), Some(Token::LeftParen));
+                if let Some(op) = AggregateOp::parse(&name)
+                    && (called
+                        || matches!(self.peek(), Some(Token::Identifier(word)) if word == "by" || word == "without"))
+                {
+                    return self.aggregate(op);
+                }
+                if name == "histogram_quantile" && called {
+                    self.position += 1;
+                    let quantile = self.expr(0)?;
+                    self.expect(Token::Comma)?;
+                    let expr = self.expr(0)?;
+                    self.expect(Token::RightParen)?;
+                    return Ok(Expr::HistogramQuantile {
+                        quantile: Box::new(quantile),
+                        expr: Box::new(expr),
+                    });
+                }
+                if let Some(function) = RangeFunction::parse(&name)
+                    && called
+                {
+                    self.position += 1;
+                    let argument = self.expr(0)?;
+                    self.expect(Token::RightParen)?;
+                    let Expr::RangeSelector {
+                        name: selected,
+                        matchers,
+                        range,
+                    } = argument
+                    else {
+                        bail!("{name} needs a range selector such as 'metric[5m]'");
+                    };
+                    return Ok(Expr::RangeFunction {
+                        function,
+                        name: selected,
+                        matchers,
+                        range,
+                    });
+                }
+                if called {
+                    bail!("unsupported function '{name}'");
+                }
+                match name.to_ascii_lowercase().as_str() {
+                    "inf" => Ok(Expr::Number(f64::INFINITY)),
+                    "nan" => Ok(Expr::Number(f64::NAN)),
+                    _ => self.selector(Some(name)),
+                }
+            }
+            _ => Err(self.error("an expression")),
+        }
+    }
+
+    fn selector(&mut self, name: Option<String>) -> Result<Expr> {
+        let mut matchers = Vec::new();
+        if matches!(self.peek(), Some(Token::LeftBrace)) {
+            self.position += 1;
+            while !matches!(self.peek(), Some(Token::RightBrace)) {
+                let label = match self.next()? {
+                    Token::Identifier(label) => label,
+                    _ => {
+                        self.position -= 1;
+                        return Err(self.error("a label name"));
+                    }
+                };
+                let op = self.next()?;
+                let value = match self.next()? {
+                    Token::String(value) => value,
+                    _ => {
+                        self.position -= 1;
+                        return Err(self.error("a quoted label value"));
+                    }
+                };
+                let regex = |value: &str| {
+                    Regex::new(&format!("^(?:{value})$"))
+                        .map_err(|e| anyhow!("invalid regex '{value}' for label '{label}': {e}"))
+                };
+                let op = match op {
+                    Token::Assign => MatchOp::Equal(value),
+                    Token::NotEqual => MatchOp::NotEqual(value),
+                    Token::RegexMatch => MatchOp::Regex(regex(&value)?),
+                    Token::RegexNotMatch => MatchOp::NotRegex(regex(&value)?),
+                    other => bail!(
+                        "expected a label matcher ('=', '!=', '=~' or '!~') for label '{label}', found {}",
+                        other.describe()
+                    ),
+                };
+                matchers.push(Matcher { label, op });
+                if matches!(self.peek(), Some(Token::Comma)) {
+                    self.position += 1;
+                } else if !matches!(self.peek(), Some(Token::RightBrace)) {
+                    return Err(self.error("',' or '}'"));
+                }
+            }
+            self.position += 1;
+        }
+
+        if name.is_none() && !matchers.iter().any(|matcher| !matcher.matches("")) {
+            bail!(
+                "a selector without a metric name needs a matcher that does not match empty labels"
+            );
+        }
+        if matches!(self.peek(), Some(Token::LeftBracket)) {
+            self.position += 1;
+            let range = match self.next()? {
+                Token::Duration(range) if range > 0 => range,
+                _ => {
+                    self.position -= 1;
+                    return Err(self.error("a duration such as 5m"));
+                }
+            };
+            self.expect(Token::RightBracket)?;
+            return Ok(Expr::RangeSelector {
+                name,
+                matchers,
+                range,
+            });
+        }
+        Ok(Expr::Selector { name, matchers })
+    }
+
+    fn aggregate(&mut self, op: AggregateOp) -> Result<Expr> {
+        let mut grouping = self.grouping()?;
+        self.expect(Token::LeftParen)?;
+        let parameter = if matches!(op, AggregateOp::Topk | AggregateOp::Bottomk) {
+            let parameter = self.expr(0)?;
+            self.expect(Token::Comma)?;
+            Some(Box::new(parameter))
+        } else {
+            None
+        };
+        let expr = self.expr(0)?;
+        self.expect(Token::RightParen)?;
+        if grouping.is_none() {
+            grouping = self.grouping()?;
+        }
+
+        Ok(Expr::Aggregate {
+            op,
+            grouping: grouping.unwrap_or(Grouping::By(Vec::new())),
+            parameter,
+            expr: Box::new(expr),
+        })
+    }
+
+    fn grouping(&mut self) -> Result<Option<Grouping>> {
+        if self.keyword("by") {
+            Ok(Some(Grouping::By(self.label_list()?)))
+        } else if self.keyword("without") {
+            Ok(Some(Grouping::Without(self.label_list()?)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parses `(label, ...)`.
+    fn label_list(&mut self) -> Result<Vec<String>> {
+        self.expect(Token::LeftParen)?;
+        let mut labels = Vec::new();
+        loop {
+            match self.peek().cloned() {
+                Some(Token::RightParen) => break,
+                Some(Token::Identifier(label)) => {
+                    self.position += 1;
+                    labels.push(label);
+                    if matches!(self.peek(), Some(Token::Comma)) {
+                        self.position += 1;
+                    } else if !matches!(self.peek(), Some(Token::RightParen)) {
+                        return Err(self.error("',' or ')'"));
+                    }
+                }
+                _ => return Err(self.error("a label name")),
+            }
+        }
+        self.position += 1;
+        Ok(labels)
+    }
+}
+
+enum Value {
+    Scalar(f64),
+    Vector(Vec<Series>),
+}
+
+/// What the selectors read.
+enum Source<'a> {
+    Scrape(&'a Exposition),
+    History {
+        series: &'a [SeriesPoints],
+        lookback: i64,
+    },
+}
+
+struct Context<'a> {
+    source: Source<'a>,
+    /// The evaluation time, in Unix milliseconds, for the history.
+    time: i64,
+}
+
+fn evaluate(expr: &Expr, context: &Context) -> Result<Value> {
+    match expr {
+        Expr::Number(number) => Ok(Value::Scalar(*number)),
+        Expr::Selector { name, matchers } => {
+            Ok(Value::Vector(select(context, name.as_deref(), matchers)))
+        }
+        Expr::RangeSelector { .. } => {
+            bail!("a range selector can only be the argument of rate, increase or avg_over_time")
+        }
+        Expr::RangeFunction {
+            function,
+            name,
+            matchers,
+            range,
+        } => {
+            let Source::History { series, .. } = context.source else {
+                bail!("range selectors need the metrics history");
+            };
+            let start = context.time - range;
+            Ok(Value::Vector(
+                series
+                    .iter()
+                    .filter(|series| {
+                        selects(name.as_deref(), matchers, &series.name, &series.labels)
+                    })
+                    .filter_map(|series| {
+                        let points = series
+                            .points
+                            .iter()
+                            .filter(|point| {
+                                point.timestamp > start && point.timestamp <= context.time
+                            })
+                            .copied()
+                            .collect::<Vec<_>>();
+                        function.apply(&points).map(|value| Series {
+                            name: None,
+                            labels: series.labels.clone(),
+                            value,
+                        })
+                    })
+                    .collect(),
+            ))
+        }
+        Expr::Negate(expr) => Ok(match evaluate(expr, context)? {
+            Value::Scalar(value) => Value::Scalar(-value),
+            Value::Vector(series) => Value::Vector(
+                series
+                    .into_iter()
+                    .map(|series| Series {
+                        name: None,
+                        value: -series.value,
+                        ..series
+                    })
+                    .collect(),
+            ),
+        }),
+        Expr::Binary {
+            op,
+            lhs,
+            rhs,
+            matching,
+            return_bool,
+        } => binary(
+            *op,
+            evaluate(lhs, context)?,
+            evaluate(rhs, context)?,
+            matching.as_ref(),
+            *return_bool,
+        ),
+        Expr::Aggregate {
+            op,
+            grouping,
+            parameter,
+            expr,
+        } => {
+            let parameter = match parameter {
+                Some(parameter) => Some(scalar(evaluate(parameter, context)?, "k")?),
+                None => None,
+            };
+            let series = vector(evaluate(expr, context)?, "the aggregation")?;
+            Ok(Value::Vector(aggregate(*op, grouping, parameter, series)))
+        }
+        Expr::HistogramQuantile { quantile, expr } => {
+            let quantile = scalar(evaluate(quantile, context)?, "the quantile")?;
+            let series = vector(evaluate(expr, context)?, "histogram_quantile")?;
+            Ok(Value::Vector(histogram_quantile(quantile, series)))
+        }
+    }
+}
+
+fn scalar(value: Value, what: &str) -> Result<f64> {
+    match value {
+        Value::Scalar(value) => Ok(value),
+        Value::Vector(_) => bail!("{what} must be a scalar, not a vector"),
+    }
+}
+
+fn vector(value: Value, what: &str) -> Result<Vec<Series>> {
+    match value {
+        Value::Vector(series) => Ok(series),
+        Value::Scalar(_) => bail!("{what} needs a vector, not a scalar"),
+    }
+}
+
+/// The samples matching a selector: in exposition order from a scrape, or
+/// the latest sample within the lookback of each series from the history.
+fn select(context: &Context, name: Option<&str>, matchers: &[Matcher]) -> Vec<Series> {
+    match context.source {
+        Source::Scrape(exposition) => exposition
+            .families
+            .iter()
+            .flat_map(|family| &family.samples)
+            .filter(|sample| selects(name, matchers, &sample.name, &sample.labels))
+            .map(|sample| Series {
+                name: Some(sample.name.clone()),
+                labels: sample.labels.clone(),
+                value: sample.value,
+            })
+            .collect(),
+        Source::History { series, lookback } => series
+            .iter()
+            .filter(|series| selects(name, matchers, &series.name, &series.labels))
+            .filter_map(|series| {
+                let point = series
+                    .points
+                    .iter()
+                    .rev()
+                    .find(|point| point.timestamp <= context.time)
+                    .filter(|point| point.timestamp > context.time - lookback)?;
+                Some(Series {
+                    name: Some(series.name.clone()),
+                    labels: series.labels.clone(),
+                    value: point.last,
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Whether a selector selects the series `series_name{labels}`.
+fn selects(name: Option<&str>, matchers: &[Matcher], series_name: &str, labels: &Labels) -> bool {
+    name.is_none_or(|name| series_name == name)
+        && matchers.iter().all(|matcher| {
+            let value = if matcher.label == "__name__" {
+                series_name
+            } else {
+                labels
+                    .get(&matcher.label)
+                    .map(String::as_str)
+                    .unwrap_or_default()
+            };
+            matcher.matches(value)
+        })
+}
+
+fn binary(
+    op: BinaryOp,
+    lhs: Value,
+    rhs: Value,
+    matching: Option<&Matching>,
+    return_bool: bool,
+) -> Result<Value> {
+    // Comparisons filter vectors unless `bool` asks for 0 or 1, in which
+    // case the metric name is dropped like for arithmetic.
+    let filter = op.is_comparison() && !return_bool;
+    let combine = |series: Series, lhs: f64, rhs: f64| -> Option<Series> {
+        let value = op.apply(lhs, rhs);
+        if filter {
+            (value == 1.0).then_some(series)
+        } else {
+            Some(Series {
+                name: None,
+                value,
+                ..series
+            })
+        }
+    };
+
+    match (lhs, rhs) {
+        (Value::Scalar(lhs), Value::Scalar(rhs)) => {
+            if filter {
+                bail!("comparisons between scalars must use the 'bool' modifier");
+            }
+            Ok(Value::Scalar(op.apply(lhs, rhs)))
+        }
+        (Value::Vector(lhs), Value::Scalar(rhs)) => Ok(Value::Vector(
+            lhs.into_iter()
+                .filter_map(|series| {
+                    let value = series.value;
+                    combine(series, value, rhs)
+                })
+                .collect(),
+        )),
+        (Value::Scalar(lhs), Value::Vector(rhs)) => Ok(Value::Vector(
+            rhs.into_iter()
+                .filter_map(|series| {
+                    let value = series.value;
+                    combine(series, lhs, value)
+                })
+                .collect(),
+        )),
+        (Value::Vector(lhs), Value::Vector(rhs)) => {
+            let key = |series: &Series| match matching {
+                Some(matching) => matching.key(&series.labels),
+                None => series.labels.clone(),
+            };
+            let mut right = BTreeMap::new();
+            for series in rhs {
+                if right.insert(key(&series), series.value).is_some() {
+                    bail!(
+                        "found duplicate series on the right-hand side of the operation; use 'on' or 'ignoring' to match one-to-one"
+                    );
+                }
+            }
+
+            let mut matched = BTreeMap::new();
+            let mut result = Vec::new();
+            for series in lhs {
+                let key = key(&series);
+                let Some(rhs) = right.get(&key) else {
+                    continue;
+                };
+                if matched.insert(key.clone(), ()).is_some() {
+                    bail!(
+                        "found duplicate series on the left-hand side of the operation; use 'on' or 'ignoring' to match one-to-one"
+                    );
+                }
+                let value = series.value;
+                if let Some(mut series) = combine(series, value, *rhs) {
+                    if !filter && let Some(matching) = matching {
+                        series.labels = matching.key(&series.labels);
+                    }
+                    result.push(series);
+                }
+            }
+            Ok(Value::Vector(result))
+        }
+    }
+}
+
+fn aggregate(
+    op: AggregateOp,
+    grouping: &Grouping,
+    parameter: Option<f64>,
+    series: Vec<Series>,
+) -> Vec<Series> {
+    let mut groups: BTreeMap<Labels, Vec<Series>> = BTreeMap::new();
+    for series in series {
+        groups
+            .entry(grouping.key(&series.labels))
+            .or_default()
+            .push(series);
+    }
+
+    if matches!(op, AggregateOp::Topk | AggregateOp::Bottomk) {
+        let k = parameter.unwrap_or_default().max(0.0) as usize;
+        return groups
+            .into_values()
+            .flat_map(|mut group| {
+                // NaN values sort last either way.
+                group.sort_by(|a, b| {
+                    let (a, b) = if op == AggregateOp::Topk {
+                        (b.value, a.value)
+                    } else {
+                        (a.value, b.value)
+                    };
+                    a.partial_cmp(&b)
+                        .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+                });
+                group.truncate(k);
+                group
+            })
+            .collect();
+    }
+
+    groups
+        .into_iter()
+        .map(|(labels, group)| {
+            let values = group.iter().map(|series| series.value);
+            let value = match op {
+                AggregateOp::Sum => values.sum(),
+                AggregateOp::Avg => values.sum::<f64>() / group.len() as f64,
+                AggregateOp::Min => values.fold(f64::NAN, f64::min),
+                AggregateOp::Max => values.fold(f64::NAN, f64::max),
+                AggregateOp::Count => group.len() as f64,
+                AggregateOp::Topk | AggregateOp::Bottomk => unreachable!(),
+            };
+            Series {
+                name: None,
+                labels,
+                value,
+            }
+        })
+        .collect()
+}
+
+/// Parses a duration such as `90s`, `5m` or `1h30m` into milliseconds.
+pub(crate) fn parse_duration(text: &str) -> Option<i64> {
+    let mut total = 0i64;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let amount = rest[..digits].parse::<i64>().ok()?;
+        rest = &rest[digits..];
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let milliseconds = match &rest[..unit] {
+            "ms" => 1,
+            "s" => 1_000,
+            "m" => 60_000,
+            "h" => 3_600_000,
+            "d" => 86_400_000,
+            "w" => 604_800_000,
+            "y" => 31_536_000_000,
+            _ => return None,
+        };
+        total = total.checked_add(amount.checked_mul(milliseconds)?)?;
+        rest = &rest[unit..];
+    }
+    (!text.is_empty()).then_some(total)
+}
+
+/// Estimates the `quantile` of each histogram in `series` from its `le`
+/// buckets, interpolating linearly within the bucket like Prometheus.
+fn histogram_quantile(quantile: f64, series: Vec<Series>) -> Vec<Series> {
+    let mut histograms: BTreeMap<Labels, Vec<(f64, f64)>> = BTreeMap::new();
+    for series in series {
+        let Some(bound) = series.labels.get("le").and_then(|le| match le.as_str() {
+            "+Inf" | "Inf" => Some(f64::INFINITY),
+            le => le.parse::<f64>().ok(),
+        }) else {
+            continue;
+        };
+        let mut labels = series.labels;
+        labels.remove("le");
+        histograms
+            .entry(labels)
+            .or_default()
+            .push((bound, series.value));
+    }
+
+    histograms
+        .into_iter()
+        .map(|(labels, buckets)| Series {
+            name: None,
+            labels,
+            value: bucket_quantile(quantile, buckets),
+        })
+        .collect()
+}
+
+fn bucket_quantile(quantile: f64, mut buckets: Vec<(f64, f64)>) -> f64 {
+    if quantile.is_nan() {
+        return f64::NAN;
+    }
+    if quantile < 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if quantile > 1.0 {
+        return f64::INFINITY;
+    }
+    buckets.sort_by(|a, b| a.0.total_cmp(&b.0));
+    if buckets.len() < 2 || buckets.last().is_some_and(|(bound, _)| bound.is_finite()) {
+        return f64::NAN;
+    }
+    // Counts may decrease due to scrape races; keep them monotonic.
+    for index in 1..buckets.len() {
+        buckets[index].1 = buckets[index].1.max(buckets[index - 1].1);
+    }
+
+    let total = buckets[buckets.len() - 1].1;
+    if total == 0.0 {
+        return f64::NAN;
+    }
+    let rank = quantile * total;
+    let index = buckets
+        .iter()
+        .position(|(_, count)| *count >= rank)
+        .unwrap_or(buckets.len() - 1);
+    if index == buckets.len() - 1 {
+        return buckets[buckets.len() - 2].0;
+    }
+    if index == 0 && buckets[0].0 <= 0.0 {
+        return buckets[0].0;
+    }
+
+    let (start, below) = if index == 0 {
+        (0.0, 0.0)
+    } else {
+        buckets[index - 1]
+    };
+    let (end, count) = buckets[index];
+    start + (end - start) * ((rank - below) / (count - below))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PGMONETA: &str = include_str!("testdata/pgmoneta.prom");
+
+    fn query(exposition: &str, query: &str) -> QueryValue {
+        let exposition = Exposition::parse(exposition).unwrap();
+        Query::parse(query)
+            .unwrap_or_else(|e| panic!("{query}: {e}"))
+            .evaluate(&exposition)
+            .unwrap_or_else(|e| panic!("{query}: {e}"))
+    }
+
+    /// The result series as `labels => value`.
+    fn series(value: QueryValue) -> Vec<(String, f64)> {
+        let QueryValue::Vector { series } = value else {
+            panic!("expected a vector, got {value:?}");
+        };
+        series
+            .into_iter()
+            .map(|series| {
+                let labels = series
+                    .labels
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                (labels, series.value)
+            })
+            .collect()
+    }
+
+    fn pairs(expected: &[(&str, f64)]) -> Vec<(String, f64)> {
+        expected
+            .iter()
+            .map(|(labels, value)| (labels.to_string(), *value))
+            .collect()
+    }
+
+    #[test]
+    fn test_selectors_and_matchers() {
+        assert_eq!(
+            series(query(PGMONETA, "pgmoneta_wal_streaming")),
+            pairs(&[("name=primary", 1.0), ("name=replica", 0.0)])
+        );
+        assert_eq!(
+            series(query(
+                PGMONETA,
+                r#"pgmoneta_retention_server{name!="primary", parameter=~"d.*|weeks"}"#
+            )),
+            pairs(&[
+                ("name=replica,parameter=days", 3.0),
+                ("name=replica,parameter=weeks", 0.0)
+            ])
+        );
+        assert_eq!(
+            series(query(
+                PGMONETA,
+                r#"pgmoneta_backup{label!~"202609(12|15).*"}"#
+            )),
+            pairs(&[
+                ("label=20260918080000,name=primary", 1.0),
+                ("label=20260914093000,name=replica", 1.0)
+            ])
+        );
+        // The regex is anchored and the metric name is a label too.
+        assert_eq!(
+            series(query(
+                PGMONETA,
+                r#"{__name__=~"pgmoneta_logging_(warn|error)"}"#
+            )),
+            pairs(&[("", 3.0), ("", 0.0)])
+        );
+        assert!(series(query(PGMONETA, r#"pgmoneta_backup{name=~"prim"}"#)).is_empty());
+    }
+
+    #[test]
+    fn test_aggregations() {
+        assert_eq!(
+            series(query(
+                PGMONETA,
+                "sum by (name) (pgmoneta_backup_total_size)"
+            )),
+            pairs(&[("name=primary", 251658240.0), ("name=replica", 50331648.0)])
+        );
+        assert_eq!(
+            series(query(PGMONETA, "sum(pgmoneta_backup_total_size)")),
+            pairs(&[("", 301989888.0)])
+        );
+        assert_eq!(
+            series(query(
+                PGMONETA,
+                "avg(pgmoneta_backup_elapsed_time) without (label)"
+            )),
+            pairs(&[("name=primary", 62.0 / 3.0), ("name=replica", 38.0)])
+        );
+        assert_eq!(
+            series(query(
+                PGMONETA,
+                "max by (name) (pgmoneta_backup_elapsed_time) - min by (name) (pgmoneta_backup_elapsed_time)"
+            )),
+            pairs(&[("name=primary", 32.0), ("name=replica", 0.0)])
+        );
+        assert_eq!(
+            series(query(PGMONETA, "count by (name) (pgmoneta_backup)")),
+            pairs(&[("name=primary", 3.0), ("name=replica", 1.0)])
+        );
+        assert_eq!(
+            series(query(PGMONETA, "topk(2, pgmoneta_backup_elapsed_time)")),
+            pairs(&[
+                ("label=20260912080000,name=primary", 41.0),
+                ("label=20260914093000,name=replica", 38.0)
+            ])
+        );
+        assert_eq!(
+            series(query(
+                PGMONETA,
+                "bottomk by (name) (1, pgmoneta_backup_elapsed_time)"
+            )),
+            pairs(&[
+                ("label=20260918080000,name=primary", 9.0),
+                ("label=20260914093000,name=replica", 38.0)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_arithmetic_and_comparisons() {
+        assert_eq!(
+            query(PGMONETA, "2 + 3 * 2 ^ 2 ^ 0.5 * -1"),
+            QueryValue::Scalar {
+                value: 2.0 - 3.0 * 2f64.powf(2f64.powf(0.5))
+            }
+        );
+        assert_eq!(
+            query(PGMONETA, "(1 + 1) > bool 1"),
+            QueryValue::Scalar { value: 1.0 }
+        );
+
+        let used = query(PGMONETA, "pgmoneta_used_space / pgmoneta_total_space * 100");
+        let QueryValue::Vector { series: used } = used else {
+            panic!("expected a vector");
+        };
+        assert_eq!(used.len(), 1, "the metric name is not part of the matching");
+
+        assert_eq!(
+            series(query(
+                PGMONETA,
+                "pgmoneta_backup_total_size + ignoring(unit) pgmoneta_wal_total_size"
+            )),
+            pairs(&[("name=primary", 268435456.0), ("name=replica", 50331648.0)])
+        );
+        assert_eq!(
+            series(query(
+                PGMONETA,
+                "sum by (name) (pgmoneta_backup_elapsed_time) / on (name) pgmoneta_backup_valid"
+            )),
+            pairs(&[("name=primary", 62.0 / 3.0), ("name=replica", 38.0)])
+        );
+
+        let QueryValue::Vector { series: filtered } =
+            query(PGMONETA, "pgmoneta_backup_elapsed_time > 30")
+        else {
+            panic!("expected a vector");
+        };
+        assert_eq!(filtered.len(), 2);
+        assert_eq!(
+            filtered[0].name.as_deref(),
+            Some("pgmoneta_backup_elapsed_time")
+        );
+        assert_eq!(filtered[0].value, 41.0);
+        assert_eq!(
+            series(query(PGMONETA, "pgmoneta_wal_streaming == bool 0")),
+            pairs(&[("name=primary", 0.0), ("name=replica", 1.0)])
+        );
+        assert_eq!(
+            series(query(
+                PGMONETA,
+                "10 < pgmoneta_backup_elapsed_time{name=\"primary\"}"
+            )),
+            pairs(&[
+                ("label=20260912080000,name=primary", 41.0),
+                ("label=20260915080000,name=primary", 12.0)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_histogram_quantile() {
+        let histogram = "# TYPE latency histogram\n\
+                         latency_bucket{path=\"/a\",le=\"0.1\"} 10\n\
+                         latency_bucket{path=\"/a\",le=\"0.5\"} 30\n\
+                         latency_bucket{path=\"/a\",le=\"1\"} 40\n\
+                         latency_bucket{path=\"/a\",le=\"+Inf\"} 40\n\
+                         latency_bucket{path=\"/b\",le=\"0.1\"} 0\n\
+                         latency_bucket{path=\"/b\",le=\"0.5\"} 0\n\
+                         latency_bucket{path=\"/b\",le=\"1\"} 1\n\
+                         latency_bucket{path=\"/b\",le=\"+Inf\"} 2\n\
+                         latency_sum{path=\"/a\"} 12\n\
+                         latency_count{path=\"/a\"} 40\n";
+
+        assert_eq!(
+            series(query(histogram, "histogram_quantile(0.75, latency_bucket)")),
+            pairs(&[("path=/a", 0.5), ("path=/b", 1.0)])
+        );
+        // 10.5 of the 42 observations fall 0.5/20 into the (0.1, 0.5] bucket.
+        let summed = series(query(
+            histogram,
+            "histogram_quantile(0.25, sum by (le) (latency_bucket))",
+        ));
+        assert_eq!(summed[0].0, "");
+        assert!((summed[0].1 - 0.11).abs() < 1e-9, "{summed:?}");
+        assert_eq!(
+            series(query(
+                histogram,
+                "histogram_quantile(2, latency_bucket{path=\"/a\"})"
+            )),
+            pairs(&[("path=/a", f64::INFINITY)])
+        );
+
+        let metrics = crate::telemetry::Metrics::new();
+        for millis in [2, 3, 4, 40] {
+            metrics.record_http_request(
+                "GET",
+                "/mcp",
+                axum::http::StatusCode::OK,
+                std::time::Duration::from_millis(millis),
+                None,
+            );
+        }
+        let value = series(query(
+            &metrics.encode().unwrap(),
+            "histogram_quantile(0.5, pgmoneta_mcp_http_request_duration_seconds_bucket)",
+        ));
+        assert_eq!(value.len(), 1);
+        assert!(value[0].1 > 0.0 && value[0].1 <= 0.005, "{value:?}");
+    }
+
+    #[test]
+    fn test_result_serialization() {
+        let json =
+            serde_json::to_value(query(PGMONETA, "pgmoneta_wal_streaming{name=\"primary\"}"))
+                .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "ResultType": "vector",
+                "Result": [{
+                    "Metric": "pgmoneta_wal_streaming",
+                    "Labels": {"name": "primary"},
+                    "Value": 1.0
+                }]
+            })
+        );
+
+        let json = serde_json::to_value(query(PGMONETA, "1 / 0")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"ResultType": "scalar", "Value": "+Inf"})
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (text, error) in [
+            ("", "expected an expression at position 0"),
+            ("sum(", "expected an expression at position 4"),
+            ("sum by name (x)", "expected '(' at position 7"),
+            ("x{a=\"b\"", "expected ',' or '}' at position 7"),
+            ("x{a~\"b\"}", "unexpected character '~' at position 3"),
+            ("x{a=b}", "expected a quoted label value at position 4"),
+            ("x{a=~\"(\"}", "invalid regex '('"),
+            ("x{a=\"b}", "unterminated string at position 4"),
+            ("irate(x[5m])", "unsupported function 'irate'"),
+            (
+                "rate(x)",
+                "rate needs a range selector such as 'metric[5m]'",
+            ),
+            ("x[5]", "expected a duration such as 5m at position 2"),
+            ("x y", "unexpected 'y' at position 2"),
+            (
+                "{a=\"\"}",
+                "needs a matcher that does not match empty labels",
+            ),
+            ("1 +", "expected an expression at position 3"),
+        ] {
+            let err = Query::parse(text).unwrap_err();
+            assert!(
+                err.to_string().contains(error),
+                "{text:?}: expected {error:?}, got {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_evaluation_errors() {
+        let exposition = Exposition::parse(PGMONETA).unwrap();
+        for (text, error) in [
+            ("1 > 0", "must use the 'bool' modifier"),
+            ("topk(pgmoneta_state, pgmoneta_state)", "k must be a scalar"),
+            ("sum(1)", "the aggregation needs a vector"),
+            (
+                "pgmoneta_backup + on (name) pgmoneta_backup_valid",
+                "duplicate series on the left-hand side",
+            ),
+            (
+                "pgmoneta_backup_valid + on (name) pgmoneta_backup",
+                "duplicate series on the right-hand side",
+            ),
+        ] {
+            let err = Query::parse(text)
+                .unwrap()
+                .evaluate(&exposition)
+                .unwrap_err();
+            assert!(
+                err.to_string().contains(error),
+                "{text:?}: expected {error:?}, got {err}"
+            );
+        }
+    }
+
+    const MINUTE: i64 = 60_000;
+
+    /// A counter of errors per server, sampled every minute, that is reset
+    /// on `replica` after 3 minutes.
+    fn history() -> Vec<SeriesPoints> {
+        let server = |name: &str, values: &[f64]| SeriesPoints {
+            name: "pgmoneta_logging_error".to_string(),
+            labels: Labels::from([("name".to_string(), name.to_string())]),
+            points: values
+                .iter()
+                .enumerate()
+                .map(|(minute, value)| Point::sample(minute as i64 * MINUTE, *value))
+                .collect(),
+        };
+        vec![
+            server("primary", &[0.0, 2.0, 4.0, 6.0, 8.0, 10.0]),
+            server("replica", &[5.0, 6.0, 7.0, 1.0, 2.0, 3.0]),
+        ]
+    }
+
+    fn query_at(text: &str, time: i64) -> Vec<(String, f64)> {
+        series(
+            Query::parse(text)
+                .unwrap_or_else(|e| panic!("{text}: {e}"))
+                .evaluate_at(&history(), 5 * MINUTE, time)
+                .unwrap_or_else(|e| panic!("{text}: {e}")),
+        )
+    }
+
+    #[test]
+    fn test_range_functions() {
+        assert_eq!(
+            query_at("increase(pgmoneta_logging_error[5m])", 5 * MINUTE),
+            vec![
+                ("name=primary".to_string(), 8.0),
+                ("name=replica".to_string(), 4.0)
+            ]
+        );
+        assert_eq!(
+            query_at(
+                "rate(pgmoneta_logging_error{name=\"primary\"}[10m])",
+                5 * MINUTE
+            ),
+            vec![("name=primary".to_string(), 10.0 / 300.0)]
+        );
+        assert_eq!(
+            query_at("avg_over_time(pgmoneta_logging_error[3m])", 2 * MINUTE),
+            vec![
+                ("name=primary".to_string(), 2.0),
+                ("name=replica".to_string(), 6.0)
+            ]
+        );
+        assert_eq!(
+            query_at("sum(increase(pgmoneta_logging_error[1h]))", 5 * MINUTE),
+            vec![(String::new(), 15.0)]
+        );
+        // A single point has no increase
+        assert!(query_at("increase(pgmoneta_logging_error[1m])", 5 * MINUTE).is_empty());
+        // Instant selectors take the latest point within the lookback
+        assert_eq!(
+            query_at(
+                "pgmoneta_logging_error{name=\"replica\"}",
+                3 * MINUTE + 30_000
+            ),
+            vec![("name=replica".to_string(), 1.0)]
+        );
+        assert!(query_at("pgmoneta_logging_error", 11 * MINUTE).is_empty());
+
+        // Three samples with a mean of 2, then a sample of 6
+        let downsampled = Point {
+            timestamp: 2 * MINUTE,
+            count: 4,
+            mean: 3.0,
+            last: 6.0,
+        };
+        let single = Point::sample(MINUTE, 11.0);
+        let series = [SeriesPoints {
+            name: "pgmoneta_state".to_string(),
+            labels: Labels::new(),
+            points: vec![single, downsampled],
+        }];
+        let value = Query::parse("avg_over_time(pgmoneta_state[5m])")
+            .unwrap()
+            .evaluate_at(&series, 5 * MINUTE, 2 * MINUTE)
+            .unwrap();
+        assert_eq!(self::series(value), vec![(String::new(), 4.6)]);
+    }
+
+    #[test]
+    fn test_evaluate_range() {
+        let query = Query::parse("increase(pgmoneta_logging_error{name=\"replica\"}[2m])").unwrap();
+        let QueryValue::Matrix { series } = query
+            .evaluate_range(&history(), 5 * MINUTE, MINUTE, 5 * MINUTE, 2 * MINUTE)
+            .unwrap()
+        else {
+            panic!("expected a matrix");
+        };
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].name, None);
+        assert_eq!(
+            series[0]
+                .values
+                .iter()
+                .map(|value| (value.time, value.value))
+                .collect::<Vec<_>>(),
+            vec![(MINUTE, 1.0), (3 * MINUTE, 1.0), (5 * MINUTE, 1.0)]
+        );
+        assert!(query.needs_history());
+        assert_eq!(query.window(), 2 * MINUTE);
+        assert_eq!(
+            query.metric_names(),
+            Some(BTreeSet::from(["pgmoneta_logging_error".to_string()]))
+        );
+        assert_eq!(
+            Query::parse("{name=\"primary\"}").unwrap().metric_names(),
+            None
+        );
+
+        for (start, end, step, error) in [
+            (0, MINUTE, 0, "the step must be positive"),
+            (MINUTE, 0, MINUTE, "the end must not be before the start"),
+            (0, 2000 * MINUTE, MINUTE, "more than 1100 steps"),
+        ] {
+            let err = query
+                .evaluate_range(&history(), 5 * MINUTE, start, end, step)
+                .unwrap_err();
+            assert!(
+                err.to_string().contains(error),
+                "expected {error:?}, got {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_range_errors() {
+        let exposition = Exposition::parse(PGMONETA).unwrap();
+        let err = Query::parse("rate(pgmoneta_state[5m])")
+            .unwrap()
+            .evaluate(&exposition)
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("range selectors need the metrics history")
+        );
+        let err = Query::parse("pgmoneta_state[5m]")
+            .unwrap()
+            .evaluate_at(&history(), 5 * MINUTE, 0)
+            .unwrap_err();
+        assert!(err.to_string().contains("can only be the argument of"));
+        assert!(!Query::parse("pgmoneta_state").unwrap().needs_history());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s"), Some(90_000));
+        assert_eq!(parse_duration("5m"), Some(5 * MINUTE));
+        assert_eq!(parse_duration("1h30m"), Some(90 * MINUTE));
+        assert_eq!(parse_duration("250ms"), Some(250));
+        assert_eq!(parse_duration("2w"), Some(14 * 24 * 60 * MINUTE));
+        for invalid in ["", "5", "m", "5x", "-5m", "1.5h"] {
+            assert_eq!(parse_duration(invalid), None, "{invalid:?}");
+        }
+    }
+}
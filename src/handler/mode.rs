@@ -17,6 +17,7 @@ use std::borrow::Cow;
 use std::sync::Arc;
 
 use super::PgmonetaHandler;
+use super::validation::{self, Keyword};
 use crate::client::PgmonetaClient;
 use rmcp::ErrorData as McpError;
 use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
@@ -27,7 +28,41 @@ use rmcp::schemars;
 pub struct ModeRequest {
     pub username: String,
     pub server: String,
-    pub action: String,
+    #[schemars(required)]
+    #[serde(deserialize_with = "validation::deserialize_optional_keyword")]
+    pub action: Option<ModeAction>,
+}
+
+/// The mode a server can be switched to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, schemars::JsonSchema)]
+#[schemars(rename_all = "lowercase")]
+pub enum ModeAction {
+    Online,
+    Offline,
+}
+
+impl ModeAction {
+    /// The keyword sent to pgmoneta.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModeAction::Online => "online",
+            ModeAction::Offline => "offline",
+        }
+    }
+}
+
+impl Keyword for ModeAction {
+    const ARGUMENT: &'static str = "mode action";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("online", ModeAction::Online),
+        ("offline", ModeAction::Offline),
+    ];
+}
+
+impl<'de> serde::Deserialize<'de> for ModeAction {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        validation::deserialize_keyword(deserializer)
+    }
 }
 
 /// Tool for switching a server between online and offline mode.
@@ -64,8 +99,9 @@ impl ToolBase for SetModeTool {
 
 impl AsyncTool<PgmonetaHandler> for SetModeTool {
     async fn invoke(_service: &PgmonetaHandler, request: ModeRequest) -> Result<String, McpError> {
+        let action = validation::require_keyword(request.action)?;
         let result: String =
-            PgmonetaClient::request_mode(&request.username, &request.server, &request.action)
+            PgmonetaClient::request_mode(&request.username, &request.server, action.as_str())
                 .await
                 .map_err(|e| {
                     McpError::internal_error(format!("Failed to switch server mode: {:?}", e), None)
@@ -88,6 +124,56 @@ mod tests {
         assert!(desc.unwrap().contains("online"));
     }
 
+    #[test]
+    fn test_mode_request_rejects_unknown_action() {
+        let request: ModeRequest = serde_json::from_value(serde_json::json!({
+            "username": "admin", "server": "primary", "action": "Offline"
+        }))
+        .unwrap();
+        assert_eq!(request.action, Some(ModeAction::Offline));
+
+        let error = serde_json::from_value::<ModeRequest>(serde_json::json!({
+            "username": "admin", "server": "primary", "action": "maintenance"
+        }))
+        .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Supported values: online, offline")
+        );
+
+        assert!(
+            serde_json::from_value::<ModeRequest>(serde_json::json!({
+                "username": "admin", "server": "primary"
+            }))
+            .is_err()
+        );
+        let schema = serde_json::to_value(schemars::schema_for!(ModeRequest)).unwrap();
+        assert!(
+            schema["required"]
+                .as_array()
+                .unwrap()
+                .contains(&serde_json::json!("action"))
+        );
+    }
+
+    #[test]
+    fn test_mode_request_without_action_is_rejected() {
+        let error = validation::require_keyword(ModeRequest::default().action).unwrap_err();
+        assert!(
+            error.message.contains("Missing mode action"),
+            "{}",
+            error.message
+        );
+    }
+
+    #[test]
+    fn test_mode_request_schema_has_enum() {
+        let schema = serde_json::to_value(schemars::schema_for!(ModeRequest)).unwrap();
+        let text = schema.to_string();
+        assert!(text.contains(r#""enum":["online","offline"]"#), "{text}");
+    }
+
     #[test]
     fn test_parse_mode_success_response() {
         let response = r#"{"Outcome": {"Command": 24, "Status": "OK"}, "Server": "primary", "Mode": "online"}"#;
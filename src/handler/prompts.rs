@@ -0,0 +1,516 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! MCP prompts for common backup workflows.
+//!
+//! Prompts are YAML templates whose messages contain `{{argument}}`
+//! placeholders. The built-in templates live next to this module; templates
+//! found in `prompts_directory` are added to them and replace built-in
+//! templates of the same name.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{anyhow, bail};
+use config::{Config, FileFormat};
+use once_cell::sync::OnceCell;
+use rmcp::ErrorData as McpError;
+use rmcp::model::{
+    GetPromptRequestParams, GetPromptResult, JsonObject, Prompt, PromptArgument, PromptMessage,
+    PromptMessageRole,
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// The prompt library, loaded once at startup by [`init`].
+static PROMPTS: OnceCell<PromptLibrary> = OnceCell::new();
+
+const BUILTIN_PROMPTS: [(&str, &str); 5] = [
+    (
+        "daily_backup_health_check.yaml",
+        include_str!("prompts/daily_backup_health_check.yaml"),
+    ),
+    (
+        "investigate_failed_backup.yaml",
+        include_str!("prompts/investigate_failed_backup.yaml"),
+    ),
+    (
+        "plan_point_in_time_recovery.yaml",
+        include_str!("prompts/plan_point_in_time_recovery.yaml"),
+    ),
+    (
+        "capacity_review.yaml",
+        include_str!("prompts/capacity_review.yaml"),
+    ),
+    (
+        "pre_upgrade_safety_backup.yaml",
+        include_str!("prompts/pre_upgrade_safety_backup.yaml"),
+    ),
+];
+
+/// A prompt template as written in a YAML file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgumentTemplate>,
+    pub messages: Vec<PromptMessageTemplate>,
+}
+
+/// An argument of a [`PromptTemplate`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptArgumentTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    /// The value used when the argument is not given.
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+/// A message of a [`PromptTemplate`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptMessageTemplate {
+    /// `user` or `assistant`.
+    pub role: String,
+    pub text: String,
+}
+
+impl PromptTemplate {
+    /// Parses and validates a YAML template.
+    pub fn parse(yaml: &str) -> anyhow::Result<Self> {
+        let template = Config::builder()
+            .add_source(config::File::from_str(yaml, FileFormat::Yaml))
+            .build()?
+            .try_deserialize::<PromptTemplate>()?;
+        template.validate()?;
+        Ok(template)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !is_identifier(&self.name) {
+            bail!(
+                "Invalid prompt name '{}': use letters, digits and underscores",
+                self.name
+            );
+        }
+        if self.messages.is_empty() {
+            bail!("Prompt '{}' has no messages", self.name);
+        }
+
+        let mut names = Vec::new();
+        for argument in &self.arguments {
+            if !is_identifier(&argument.name) {
+                bail!(
+                    "Invalid argument name '{}' in prompt '{}'",
+                    argument.name,
+                    self.name
+                );
+            }
+            if names.contains(&argument.name.as_str()) {
+                bail!(
+                    "Duplicate argument '{}' in prompt '{}'",
+                    argument.name,
+                    self.name
+                );
+            }
+            names.push(argument.name.as_str());
+        }
+
+        for message in &self.messages {
+            parse_role(&message.role).map_err(|e| anyhow!("Prompt '{}': {e}", self.name))?;
+            for placeholder in placeholders(&message.text) {
+                if !names.contains(&placeholder) {
+                    bail!(
+                        "Prompt '{}' uses the undeclared argument '{{{{{placeholder}}}}}'",
+                        self.name
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The MCP description of this prompt.
+    pub fn to_prompt(&self) -> Prompt {
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|argument| {
+                let mut prompt_argument =
+                    PromptArgument::new(&argument.name).with_required(argument.required);
+                if let Some(description) = argument.description.as_ref() {
+                    prompt_argument = prompt_argument.with_description(description);
+                }
+                prompt_argument
+            })
+            .collect();
+        let prompt = Prompt::new(&self.name, self.description.as_ref(), Some(arguments));
+        match self.title.as_ref() {
+            Some(title) => prompt.with_title(title),
+            None => prompt,
+        }
+    }
+
+    /// Renders the messages with the given arguments.
+    pub fn render(&self, arguments: Option<&JsonObject>) -> Result<GetPromptResult, McpError> {
+        let mut values = BTreeMap::new();
+        for argument in &self.arguments {
+            let given = arguments
+                .and_then(|arguments| arguments.get(&argument.name))
+                .and_then(argument_as_string)
+                .filter(|value| !value.trim().is_empty());
+            let value = match (given, argument.default.as_ref()) {
+                (Some(value), _) => value,
+                (None, Some(default)) => default.clone(),
+                (None, None) if argument.required => {
+                    return Err(McpError::invalid_params(
+                        format!(
+                            "Prompt '{}' requires the argument '{}'",
+                            self.name, argument.name
+                        ),
+                        None,
+                    ));
+                }
+                (None, None) => String::new(),
+            };
+            values.insert(argument.name.as_str(), value);
+        }
+
+        let messages = self
+            .messages
+            .iter()
+            .map(|message| {
+                let role = parse_role(&message.role).unwrap_or(PromptMessageRole::User);
+                PromptMessage::new_text(role, substitute(&message.text, &values))
+            })
+            .collect();
+        let result = GetPromptResult::new(messages);
+        Ok(match self.description.as_ref() {
+            Some(description) => result.with_description(description),
+            None => result,
+        })
+    }
+}
+
+/// The available prompt templates, by name.
+#[derive(Debug, Clone, Default)]
+pub struct PromptLibrary {
+    templates: BTreeMap<String, PromptTemplate>,
+}
+
+impl PromptLibrary {
+    /// The built-in templates only.
+    pub fn builtin() -> Self {
+        let mut library = Self::default();
+        for (file, yaml) in BUILTIN_PROMPTS {
+            let template = PromptTemplate::parse(yaml)
+                .unwrap_or_else(|e| panic!("Invalid built-in prompt {file}: {e:?}"));
+            library.insert(template);
+        }
+        library
+    }
+
+    /// The built-in templates plus the `*.yaml` and `*.yml` files of `directory`.
+    pub fn load(directory: Option<&Path>) -> anyhow::Result<Self> {
+        let mut library = Self::builtin();
+        let Some(directory) = directory else {
+            return Ok(library);
+        };
+
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(directory).map_err(|e| {
+            anyhow!(
+                "Unable to read prompts directory {}: {e}",
+                directory.display()
+            )
+        })? {
+            let path = entry?.path();
+            let is_yaml = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| matches!(extension, "yaml" | "yml"));
+            if is_yaml && path.is_file() {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        for path in files {
+            let yaml = std::fs::read_to_string(&path)?;
+            let template = PromptTemplate::parse(&yaml)
+                .map_err(|e| anyhow!("Invalid prompt template {}: {e}", path.display()))?;
+            tracing::debug!(prompt = %template.name, path = %path.display(), "loaded prompt template");
+            library.insert(template);
+        }
+        Ok(library)
+    }
+
+    fn insert(&mut self, template: PromptTemplate) {
+        self.templates.insert(template.name.clone(), template);
+    }
+
+    /// The MCP descriptions of all prompts, sorted by name.
+    pub fn prompts(&self) -> Vec<Prompt> {
+        self.templates
+            .values()
+            .map(PromptTemplate::to_prompt)
+            .collect()
+    }
+
+    /// Renders the prompt requested by `request`.
+    pub fn get(&self, request: &GetPromptRequestParams) -> Result<GetPromptResult, McpError> {
+        let template = self.templates.get(&request.name).ok_or_else(|| {
+            McpError::invalid_params(format!("Unknown prompt '{}'", request.name), None)
+        })?;
+        template.render(request.arguments.as_ref())
+    }
+}
+
+/// Loads the prompt library from `directory`; returns the number of prompts.
+pub fn init(directory: Option<&Path>) -> anyhow::Result<usize> {
+    let library = PromptLibrary::load(directory)?;
+    let count = library.templates.len();
+    PROMPTS
+        .set(library)
+        .map_err(|_| anyhow!("Prompt library already initialized"))?;
+    Ok(count)
+}
+
+/// The prompt library, or the built-in templates when [`init`] was not called.
+pub fn library() -> &'static PromptLibrary {
+    PROMPTS.get_or_init(PromptLibrary::builtin)
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_role(role: &str) -> anyhow::Result<PromptMessageRole> {
+    match role.trim().to_ascii_lowercase().as_str() {
+        "user" => Ok(PromptMessageRole::User),
+        "assistant" => Ok(PromptMessageRole::Assistant),
+        _ => bail!("Unsupported message role '{role}'. Supported values: user, assistant"),
+    }
+}
+
+fn argument_as_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+/// The argument names used as `{{name}}` in `text`.
+fn placeholders(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        names.push(after[..end].trim());
+        rest = &after[end + 2..];
+    }
+    names
+}
+
+fn substitute(text: &str, values: &BTreeMap<&str, String>) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        match values.get(after[..end].trim()) {
+            Some(value) => rendered.push_str(value),
+            None => rendered.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::PromptMessageContent;
+    use serde_json::json;
+
+    fn text(message: &PromptMessage) -> &str {
+        match &message.content {
+            PromptMessageContent::Text { text } => text,
+            other => panic!("unexpected content {other:?}"),
+        }
+    }
+
+    fn request(name: &str, arguments: Value) -> GetPromptRequestParams {
+        let mut request = GetPromptRequestParams::new(name);
+        request.arguments = arguments.as_object().cloned();
+        request
+    }
+
+    #[test]
+    fn test_builtin_prompts() {
+        let library = PromptLibrary::builtin();
+        let names: Vec<String> = library.prompts().into_iter().map(|p| p.name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "capacity_review",
+                "daily_backup_health_check",
+                "investigate_failed_backup",
+                "plan_point_in_time_recovery",
+                "pre_upgrade_safety_backup",
+            ]
+        );
+        for prompt in library.prompts() {
+            let arguments = prompt.arguments.unwrap();
+            assert!(
+                arguments
+                    .iter()
+                    .any(|argument| argument.name == "server" && argument.required == Some(true))
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_uses_arguments_and_defaults() {
+        let library = PromptLibrary::builtin();
+        let result = library
+            .get(&request(
+                "daily_backup_health_check",
+                json!({"server": "primary"}),
+            ))
+            .unwrap();
+        let message = text(&result.messages[0]);
+        assert!(message.contains("pgmoneta server 'primary' for the last 24 hours"));
+        assert!(message.contains("`list_backups`"));
+        assert!(!message.contains("{{"));
+
+        let result = library
+            .get(&request(
+                "daily_backup_health_check",
+                json!({"server": "replica", "window": "3 days"}),
+            ))
+            .unwrap();
+        assert!(text(&result.messages[0]).contains("for the last 3 days"));
+    }
+
+    #[test]
+    fn test_render_requires_arguments() {
+        let library = PromptLibrary::builtin();
+        let error = library
+            .get(&request(
+                "plan_point_in_time_recovery",
+                json!({"server": "primary"}),
+            ))
+            .unwrap_err();
+        assert!(error.message.contains("requires the argument 'target'"));
+        assert!(library.get(&request("unknown", json!({}))).is_err());
+    }
+
+    #[test]
+    fn test_render_keeps_message_roles() {
+        let library = PromptLibrary::builtin();
+        let result = library
+            .get(&request(
+                "investigate_failed_backup",
+                json!({"server": "primary"}),
+            ))
+            .unwrap();
+        let roles: Vec<PromptMessageRole> =
+            result.messages.iter().map(|m| m.role.clone()).collect();
+        assert_eq!(
+            roles,
+            vec![
+                PromptMessageRole::User,
+                PromptMessageRole::Assistant,
+                PromptMessageRole::User
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_templates() {
+        let undeclared = "name: custom\nmessages:\n  - role: user\n    text: Check {{server}}\n";
+        let error = PromptTemplate::parse(undeclared).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("undeclared argument '{{server}}'")
+        );
+
+        let role = "name: custom\nmessages:\n  - role: system\n    text: Hello\n";
+        assert!(PromptTemplate::parse(role).is_err());
+
+        let name = "name: my prompt\nmessages:\n  - role: user\n    text: Hello\n";
+        assert!(PromptTemplate::parse(name).is_err());
+    }
+
+    #[test]
+    fn test_load_directory_overrides_builtin() {
+        let directory =
+            std::env::temp_dir().join(format!("pgmoneta-prompts-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(
+            directory.join("capacity_review.yaml"),
+            "name: capacity_review\narguments:\n  - name: server\n    required: true\nmessages:\n  - role: user\n    text: Our own review of {{ server }}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            directory.join("weekly_restore_test.yml"),
+            "name: weekly_restore_test\ntitle: Weekly restore test\nmessages:\n  - role: user\n    text: Restore the newest backup\n",
+        )
+        .unwrap();
+        std::fs::write(directory.join("notes.txt"), "not a template").unwrap();
+
+        let library = PromptLibrary::load(Some(&directory)).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(library.prompts().len(), 6);
+        let result = library
+            .get(&request("capacity_review", json!({"server": "primary"})))
+            .unwrap();
+        assert_eq!(text(&result.messages[0]), "Our own review of primary");
+        let custom = library
+            .prompts()
+            .into_iter()
+            .find(|prompt| prompt.name == "weekly_restore_test")
+            .unwrap();
+        assert_eq!(custom.title.as_deref(), Some("Weekly restore test"));
+    }
+
+    #[test]
+    fn test_substitute() {
+        let values = BTreeMap::from([("server", "primary".to_string())]);
+        assert_eq!(
+            substitute("a {{server}} b {{ server }}", &values),
+            "a primary b primary"
+        );
+        assert_eq!(substitute("{{other}} {{", &values), "{{other}} {{");
+    }
+}
@@ -0,0 +1,771 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::PgmonetaHandler;
+use super::catalog::{self, BackupClock, BackupEntry};
+use super::restore::{RestoreRequest, normalize_position, parse_restore_time};
+use chrono::{Duration, NaiveDateTime, NaiveTime, TimeDelta};
+use rmcp::ErrorData as McpError;
+use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
+use rmcp::model::JsonObject;
+use rmcp::schemars;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct RecoveryPointRequest {
+    pub username: String,
+    pub server: String,
+    /// Human readable target time, e.g. "just before 14:32 yesterday" or "2026-07-06 11:30:00"
+    pub target: Option<String>,
+    /// Target transaction ID
+    pub xid: Option<String>,
+    /// Target LSN in the X/Y notation
+    pub lsn: Option<String>,
+}
+
+/// Tool for choosing the base backup and restore positions for a point-in-time recovery.
+pub struct FindRecoveryPointTool;
+
+impl ToolBase for FindRecoveryPointTool {
+    type Parameter = RecoveryPointRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "find_recovery_point".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "Find the best base backup and restore positions for a point-in-time recovery. \
+            Provide exactly one of target, xid or lsn. \
+            The target is a human readable time such as \"just before 14:32 yesterday\", \"2 hours ago\" \
+            or \"2026-07-06 11:30:00\", interpreted in the configured timezone. \
+            Returns the backup to restore, the time/lsn/xid, inclusive and timeline positions, \
+            whether the WAL covers the target, and ready-to-use arguments for plan_restore and restore. \
+            The username has to be one of the pgmoneta admins to be able to access pgmoneta."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for FindRecoveryPointTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: RecoveryPointRequest,
+    ) -> Result<String, McpError> {
+        let clock = BackupClock::configured();
+        let target = parse_recovery_target(&request, clock)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let backups = catalog::fetch_backups(&request.username, &request.server).await?;
+        let mut detailed = Vec::with_capacity(backups.len());
+        for backup in candidate_backups(&backups, &target) {
+            detailed.push(
+                catalog::fetch_backup_info(&request.username, &request.server, &backup.backup)
+                    .await?,
+            );
+        }
+
+        let point = choose_recovery_point(&request.server, &target, &detailed, clock);
+        serde_json::to_string(&point).map_err(|e| {
+            McpError::internal_error(format!("Failed to serialize recovery point: {:?}", e), None)
+        })
+    }
+}
+
+/// A resolved recovery target.
+#[derive(Debug, Clone, PartialEq)]
+enum RecoveryTarget {
+    Time {
+        input: String,
+        time: NaiveDateTime,
+        inclusive: bool,
+    },
+    Xid(String),
+    Lsn(u64),
+}
+
+/// The recommendation returned by `find_recovery_point`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct RecoveryPoint {
+    server: String,
+    target: String,
+    backup: Option<String>,
+    backup_end: Option<String>,
+    timeline: Option<String>,
+    wal_coverage: &'static str,
+    position: Option<String>,
+    restore_arguments: Option<Map<String, Value>>,
+    alternatives: Vec<String>,
+    reasons: Vec<String>,
+}
+
+fn parse_recovery_target(
+    request: &RecoveryPointRequest,
+    clock: BackupClock,
+) -> Result<RecoveryTarget, String> {
+    let provided = [&request.target, &request.xid, &request.lsn]
+        .iter()
+        .filter(|value| value.as_deref().is_some_and(|v| !v.trim().is_empty()))
+        .count();
+    if provided != 1 {
+        return Err("Provide exactly one of 'target', 'xid' or 'lsn'".to_string());
+    }
+
+    if let Some(xid) = request.xid.as_deref().map(str::trim)
+        && !xid.is_empty()
+    {
+        if xid.parse::<u64>().is_err() {
+            return Err(format!(
+                "'xid' must be a numeric transaction ID, found '{xid}'"
+            ));
+        }
+        return Ok(RecoveryTarget::Xid(xid.to_string()));
+    }
+
+    if let Some(lsn) = request.lsn.as_deref().map(str::trim)
+        && !lsn.is_empty()
+    {
+        return catalog::parse_lsn(lsn)
+            .map(RecoveryTarget::Lsn)
+            .ok_or_else(|| format!("'lsn' must use the X/Y notation, found '{lsn}'"));
+    }
+
+    let input = request.target.as_deref().unwrap_or("").trim();
+    let (time, inclusive) = parse_human_time(input, clock)?;
+    Ok(RecoveryTarget::Time {
+        input: input.to_string(),
+        time,
+        inclusive,
+    })
+}
+
+/// Parses a human readable point in time in the configured timezone.
+///
+/// Supports absolute timestamps, `now`, `N minutes/hours/days ago` and
+/// `today`/`yesterday` combined with a `HH:MM[:SS]` time. A leading
+/// `before`/`just before` makes the target exclusive.
+pub(super) fn parse_human_time(
+    input: &str,
+    clock: BackupClock,
+) -> Result<(NaiveDateTime, bool), String> {
+    let mut text = input.trim().to_lowercase();
+    let mut inclusive = true;
+    for (prefix, is_inclusive) in [
+        ("just before ", false),
+        ("right before ", false),
+        ("before ", false),
+        ("just after ", true),
+        ("after ", true),
+        ("at ", true),
+        ("around ", true),
+    ] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            inclusive = is_inclusive;
+            text = rest.trim().to_string();
+            break;
+        }
+    }
+
+    if text.is_empty() {
+        return Err("'target' must not be empty".to_string());
+    }
+
+    let now = clock.now();
+    if text == "now" {
+        return Ok((now, inclusive));
+    }
+
+    if let Some(ago) = text.strip_suffix(" ago") {
+        let mut parts = ago.split_whitespace();
+        let amount = parts
+            .next()
+            .and_then(|amount| amount.parse::<i64>().ok())
+            .ok_or_else(|| format!("Unable to understand target '{input}'"))?;
+        if amount < 0 {
+            return Err(format!(
+                "Invalid target '{input}': the amount must not be negative"
+            ));
+        }
+        let duration = match parts.next().unwrap_or("").trim_end_matches('s') {
+            "second" | "sec" => TimeDelta::try_seconds(amount),
+            "minute" | "min" => TimeDelta::try_minutes(amount),
+            "hour" => TimeDelta::try_hours(amount),
+            "day" => TimeDelta::try_days(amount),
+            "week" => TimeDelta::try_weeks(amount),
+            _ => return Err(format!("Unable to understand target '{input}'")),
+        };
+        return duration
+            .and_then(|duration| now.checked_sub_signed(duration))
+            .map(|time| (time, inclusive))
+            .ok_or_else(|| format!("Invalid target '{input}': it lies too far in the past"));
+    }
+
+    if let Some(time) = parse_restore_time(&text, clock) {
+        return Ok((time, inclusive));
+    }
+
+    let mut date = None;
+    let mut clock_time = None;
+    for word in text.split_whitespace().filter(|word| *word != "at") {
+        match word {
+            "today" => date = Some(now.date()),
+            "yesterday" => date = Some(now.date() - Duration::days(1)),
+            other => {
+                clock_time = Some(
+                    NaiveTime::parse_from_str(other, "%H:%M:%S")
+                        .or_else(|_| NaiveTime::parse_from_str(other, "%H:%M"))
+                        .map_err(|_| format!("Unable to understand target '{input}'"))?,
+                )
+            }
+        }
+    }
+
+    match (date, clock_time) {
+        (Some(date), Some(time)) => Ok((date.and_time(time), inclusive)),
+        (None, Some(time)) => {
+            // A bare time refers to its most recent occurrence
+            let today = now.date().and_time(time);
+            if today > now {
+                Ok((today - Duration::days(1), inclusive))
+            } else {
+                Ok((today, inclusive))
+            }
+        }
+        _ => Err(format!("Unable to understand target '{input}'")),
+    }
+}
+
+/// Selects the backups for which `INFO` is needed to evaluate the target.
+fn candidate_backups<'a>(
+    backups: &'a [BackupEntry],
+    target: &RecoveryTarget,
+) -> Vec<&'a BackupEntry> {
+    match target {
+        RecoveryTarget::Time { time, .. } => {
+            // Every backup started before the target, plus the first one after it
+            let mut candidates: Vec<&BackupEntry> = backups
+                .iter()
+                .filter(|backup| backup.timestamp().is_some_and(|start| start <= *time))
+                .collect();
+            if let Some(next) = backups
+                .iter()
+                .find(|backup| backup.timestamp().is_some_and(|start| start > *time))
+            {
+                candidates.push(next);
+            }
+            candidates
+        }
+        RecoveryTarget::Xid(_) | RecoveryTarget::Lsn(_) => backups.iter().collect(),
+    }
+}
+
+fn choose_recovery_point(
+    server: &str,
+    target: &RecoveryTarget,
+    backups: &[BackupEntry],
+    clock: BackupClock,
+) -> RecoveryPoint {
+    let mut reasons = Vec::new();
+    let valid: Vec<&BackupEntry> = backups
+        .iter()
+        .filter(|backup| backup.valid != Some(false))
+        .collect();
+    if valid.len() < backups.len() {
+        reasons.push(format!(
+            "Skipped {} invalid backup(s)",
+            backups.len() - valid.len()
+        ));
+    }
+
+    let (best, coverage) = match target {
+        RecoveryTarget::Time { time, .. } => {
+            let best = valid
+                .iter()
+                .filter(|backup| backup.end_timestamp().is_some_and(|end| end <= *time))
+                .max_by(|a, b| a.backup.cmp(&b.backup))
+                .copied();
+            let coverage = match best {
+                Some(_) if *time > clock.now() => {
+                    reasons.push("The target time is in the future".to_string());
+                    "not covered"
+                }
+                Some(backup) => {
+                    reasons.push(format!(
+                        "Backup '{}' is the most recent valid backup that ended before the target",
+                        backup.backup
+                    ));
+                    "covered"
+                }
+                None => {
+                    reasons.push(
+                        "No valid backup ended before the target time, so the target is outside the WAL range"
+                            .to_string(),
+                    );
+                    "not covered"
+                }
+            };
+            (best, coverage)
+        }
+        RecoveryTarget::Lsn(lsn) => {
+            let best = valid
+                .iter()
+                .filter(|backup| backup.end_lsn.is_some_and(|end| end <= *lsn))
+                .max_by(|a, b| a.backup.cmp(&b.backup))
+                .copied();
+            let coverage = match best {
+                Some(backup) => {
+                    reasons.push(format!(
+                        "Backup '{}' is the most recent valid backup that ended before LSN {}",
+                        backup.backup,
+                        catalog::format_lsn(*lsn)
+                    ));
+                    "covered"
+                }
+                None if valid.iter().all(|backup| backup.end_lsn.is_none()) => {
+                    reasons.push("The LSN range of the backups is unknown".to_string());
+                    "unknown"
+                }
+                None => {
+                    reasons.push(format!(
+                        "No valid backup ended before LSN {}",
+                        catalog::format_lsn(*lsn)
+                    ));
+                    "not covered"
+                }
+            };
+            (best, coverage)
+        }
+        RecoveryTarget::Xid(_) => {
+            // Transaction IDs cannot be mapped onto the catalog, so prefer the
+            // oldest backup of the current timeline, which covers the widest range
+            let current_timeline = valid.iter().filter_map(|backup| backup.end_timeline).max();
+            let best = valid
+                .iter()
+                .filter(|backup| {
+                    current_timeline.is_none() || backup.end_timeline == current_timeline
+                })
+                .min_by(|a, b| a.backup.cmp(&b.backup))
+                .copied();
+            if let Some(backup) = best {
+                reasons.push(format!(
+                    "Transaction IDs cannot be located in the catalog; backup '{}' is the oldest valid backup of the current timeline and covers the widest range",
+                    backup.backup
+                ));
+            }
+            (
+                best,
+                if best.is_some() {
+                    "unknown"
+                } else {
+                    "not covered"
+                },
+            )
+        }
+    };
+
+    let timeline = best.map(|backup| recovery_timeline(backup, backups, target, &mut reasons));
+    let coverage = match best {
+        Some(backup) if coverage == "covered" && has_wal_gap(backup, backups, target) => {
+            reasons.push(
+                "A later backup starts on an older timeline, so the WAL may not be continuous"
+                    .to_string(),
+            );
+            "gap"
+        }
+        _ => coverage,
+    };
+
+    let alternatives = valid
+        .iter()
+        .filter(|backup| Some(backup.backup.as_str()) != best.map(|b| b.backup.as_str()))
+        .filter(|backup| match target {
+            RecoveryTarget::Time { time, .. } => {
+                backup.end_timestamp().is_some_and(|end| end <= *time)
+            }
+            RecoveryTarget::Lsn(lsn) => backup.end_lsn.is_some_and(|end| end <= *lsn),
+            RecoveryTarget::Xid(_) => true,
+        })
+        .rev()
+        .map(|backup| backup.backup.clone())
+        .collect();
+
+    let restore_request =
+        best.map(|backup| restore_request(server, backup, target, &timeline, clock));
+    let position = restore_request.as_ref().map(normalize_position);
+    let restore_arguments = restore_request.as_ref().map(restore_arguments);
+
+    RecoveryPoint {
+        server: server.to_string(),
+        target: describe_target(target, clock),
+        backup: best.map(|backup| backup.backup.clone()),
+        backup_end: best
+            .and_then(BackupEntry::end_timestamp)
+            .map(|end| clock.format(end)),
+        timeline: timeline.clone(),
+        wal_coverage: coverage,
+        position,
+        restore_arguments,
+        alternatives,
+        reasons,
+    }
+}
+
+fn recovery_timeline(
+    best: &BackupEntry,
+    backups: &[BackupEntry],
+    target: &RecoveryTarget,
+    reasons: &mut Vec<String>,
+) -> String {
+    let base = best.end_timeline.or(best.start_timeline);
+    let switched = backups
+        .iter()
+        .filter(|backup| backup.backup > best.backup)
+        .filter(|backup| match target {
+            RecoveryTarget::Time { time, .. } => {
+                backup.timestamp().is_some_and(|start| start <= *time)
+            }
+            RecoveryTarget::Lsn(lsn) => backup.start_lsn.is_some_and(|start| start <= *lsn),
+            RecoveryTarget::Xid(_) => true,
+        })
+        .filter_map(|backup| backup.start_timeline)
+        .any(|timeline| base.is_some_and(|base| timeline > base));
+
+    match base {
+        Some(_) if switched => {
+            reasons.push(
+                "The timeline changed between the base backup and the target; following the latest timeline"
+                    .to_string(),
+            );
+            "latest".to_string()
+        }
+        Some(base) => base.to_string(),
+        None => "latest".to_string(),
+    }
+}
+
+fn has_wal_gap(best: &BackupEntry, backups: &[BackupEntry], target: &RecoveryTarget) -> bool {
+    let Some(base) = best.end_timeline else {
+        return false;
+    };
+    backups
+        .iter()
+        .filter(|backup| backup.backup > best.backup)
+        .filter(|backup| match target {
+            RecoveryTarget::Time { time, .. } => {
+                backup.timestamp().is_some_and(|start| start <= *time)
+            }
+            _ => false,
+        })
+        .filter_map(|backup| backup.start_timeline)
+        .any(|timeline| timeline < base)
+}
+
+fn restore_request(
+    server: &str,
+    backup: &BackupEntry,
+    target: &RecoveryTarget,
+    timeline: &Option<String>,
+    clock: BackupClock,
+) -> RestoreRequest {
+    let mut request = RestoreRequest {
+        server: server.to_string(),
+        backup_id: backup.backup.clone(),
+        timeline: timeline.clone(),
+        ..Default::default()
+    };
+    match target {
+        RecoveryTarget::Time {
+            time, inclusive, ..
+        } => {
+            request.time = Some(clock.format(*time));
+            request.inclusive = Some(inclusive.to_string());
+        }
+        RecoveryTarget::Xid(xid) => {
+            request.xid = Some(xid.clone());
+            request.inclusive = Some("true".to_string());
+        }
+        RecoveryTarget::Lsn(lsn) => {
+            request.lsn = Some(catalog::format_lsn(*lsn));
+            request.inclusive = Some("true".to_string());
+        }
+    }
+    request
+}
+
+fn restore_arguments(request: &RestoreRequest) -> Map<String, Value> {
+    let mut arguments = Map::new();
+    arguments.insert("server".to_string(), Value::from(request.server.clone()));
+    arguments.insert(
+        "backup_id".to_string(),
+        Value::from(request.backup_id.clone()),
+    );
+    for (key, value) in [
+        ("time", &request.time),
+        ("xid", &request.xid),
+        ("lsn", &request.lsn),
+        ("inclusive", &request.inclusive),
+        ("timeline", &request.timeline),
+    ] {
+        if let Some(value) = value {
+            arguments.insert(key.to_string(), Value::from(value.clone()));
+        }
+    }
+    arguments
+}
+
+fn describe_target(target: &RecoveryTarget, clock: BackupClock) -> String {
+    match target {
+        RecoveryTarget::Time {
+            input,
+            time,
+            inclusive,
+        } => format!(
+            "{} ({}, from '{input}')",
+            clock.format(*time),
+            if *inclusive { "inclusive" } else { "exclusive" }
+        ),
+        RecoveryTarget::Xid(xid) => format!("xid {xid}"),
+        RecoveryTarget::Lsn(lsn) => format!("lsn {}", catalog::format_lsn(*lsn)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    use rmcp::handler::server::router::tool::ToolBase;
+
+    fn utc() -> BackupClock {
+        BackupClock::new(FixedOffset::east_opt(0))
+    }
+
+    fn time(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn backups() -> Vec<BackupEntry> {
+        vec![
+            BackupEntry {
+                backup: "20260701000000".to_string(),
+                valid: Some(true),
+                elapsed: Some(30.0),
+                start_lsn: Some(0x100),
+                end_lsn: Some(0x200),
+                start_timeline: Some(1),
+                end_timeline: Some(1),
+                ..Default::default()
+            },
+            BackupEntry {
+                backup: "20260702000000".to_string(),
+                valid: Some(false),
+                elapsed: Some(30.0),
+                start_timeline: Some(1),
+                end_timeline: Some(1),
+                ..Default::default()
+            },
+            BackupEntry {
+                backup: "20260703000000".to_string(),
+                valid: Some(true),
+                elapsed: Some(30.0),
+                start_lsn: Some(0x1_0000_0000),
+                end_lsn: Some(0x1_0000_0100),
+                start_timeline: Some(2),
+                end_timeline: Some(2),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_find_recovery_point_tool_metadata() {
+        assert_eq!(FindRecoveryPointTool::name(), "find_recovery_point");
+        assert!(
+            FindRecoveryPointTool::description()
+                .unwrap()
+                .contains("point-in-time recovery")
+        );
+        let tools = PgmonetaHandler::tool_router().list_all();
+        assert!(tools.iter().any(|t| t.name == "find_recovery_point"));
+    }
+
+    #[test]
+    fn test_parse_human_time_relative_expressions() {
+        let clock = utc();
+        let now = clock.now();
+
+        let (yesterday, inclusive) =
+            parse_human_time("just before 14:32 yesterday", clock).unwrap();
+        assert!(!inclusive);
+        assert_eq!(yesterday.date(), now.date() - Duration::days(1));
+        assert_eq!(
+            yesterday.time(),
+            NaiveTime::from_hms_opt(14, 32, 0).unwrap()
+        );
+
+        let (same, _) = parse_human_time("yesterday at 14:32", clock).unwrap();
+        assert_eq!(same, yesterday);
+
+        let (ago, inclusive) = parse_human_time("2 hours ago", clock).unwrap();
+        assert!(inclusive);
+        assert!((now - ago - Duration::hours(2)).num_seconds().abs() <= 1);
+
+        let (absolute, _) = parse_human_time("2026-07-06 11:30:00", clock).unwrap();
+        assert_eq!(absolute, time("2026-07-06 11:30:00"));
+
+        assert!(parse_human_time("whenever", clock).is_err());
+    }
+
+    #[test]
+    fn test_parse_human_time_rejects_out_of_range_amounts() {
+        let clock = utc();
+
+        let error = parse_human_time("999999999999 days ago", clock).unwrap_err();
+        assert!(error.contains("too far in the past"), "{error}");
+        assert!(parse_human_time("1000000000000 weeks ago", clock).is_err());
+
+        let error = parse_human_time("-3 days ago", clock).unwrap_err();
+        assert!(error.contains("must not be negative"), "{error}");
+    }
+
+    #[test]
+    fn test_parse_recovery_target_requires_exactly_one_target() {
+        let request = RecoveryPointRequest {
+            target: Some("now".to_string()),
+            xid: Some("42".to_string()),
+            ..Default::default()
+        };
+        assert!(parse_recovery_target(&request, utc()).is_err());
+
+        let request = RecoveryPointRequest {
+            xid: Some("forty-two".to_string()),
+            ..Default::default()
+        };
+        assert!(parse_recovery_target(&request, utc()).is_err());
+
+        let request = RecoveryPointRequest {
+            lsn: Some("0/5000000".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_recovery_target(&request, utc()).unwrap(),
+            RecoveryTarget::Lsn(0x500_0000)
+        );
+    }
+
+    #[test]
+    fn test_choose_recovery_point_for_time_skips_invalid_backups() {
+        let target = RecoveryTarget::Time {
+            input: "2026-07-02 12:00:00".to_string(),
+            time: time("2026-07-02 12:00:00"),
+            inclusive: false,
+        };
+
+        let point = choose_recovery_point("primary", &target, &backups(), utc());
+
+        assert_eq!(point.backup.as_deref(), Some("20260701000000"));
+        assert_eq!(point.wal_coverage, "covered");
+        assert_eq!(point.timeline.as_deref(), Some("1"));
+        assert_eq!(
+            point.position.as_deref(),
+            Some("time=2026-07-02 12:00:00+00:00,inclusive=false,timeline=1")
+        );
+        let arguments = point.restore_arguments.unwrap();
+        assert_eq!(arguments["backup_id"], "20260701000000");
+        assert_eq!(arguments["inclusive"], "false");
+    }
+
+    #[test]
+    fn test_choose_recovery_point_follows_timeline_switch() {
+        let mut catalog = backups();
+        catalog.pop();
+        catalog.push(BackupEntry {
+            backup: "20260702100000".to_string(),
+            valid: Some(false),
+            start_timeline: Some(2),
+            ..Default::default()
+        });
+        let target = RecoveryTarget::Time {
+            input: "2026-07-02 12:00:00".to_string(),
+            time: time("2026-07-02 12:00:00"),
+            inclusive: true,
+        };
+
+        let point = choose_recovery_point("primary", &target, &catalog, utc());
+
+        assert_eq!(point.backup.as_deref(), Some("20260701000000"));
+        assert_eq!(point.timeline.as_deref(), Some("latest"));
+    }
+
+    #[test]
+    fn test_choose_recovery_point_reports_uncovered_targets() {
+        let target = RecoveryTarget::Time {
+            input: "2026-06-01 00:00:00".to_string(),
+            time: time("2026-06-01 00:00:00"),
+            inclusive: true,
+        };
+        let point = choose_recovery_point("primary", &target, &backups(), utc());
+        assert_eq!(point.backup, None);
+        assert_eq!(point.wal_coverage, "not covered");
+        assert!(point.restore_arguments.is_none());
+    }
+
+    #[test]
+    fn test_choose_recovery_point_for_lsn_and_xid() {
+        let point = choose_recovery_point(
+            "primary",
+            &RecoveryTarget::Lsn(0x1_0000_0200),
+            &backups(),
+            utc(),
+        );
+        assert_eq!(point.backup.as_deref(), Some("20260703000000"));
+        assert_eq!(
+            point.position.as_deref(),
+            Some("lsn=1/200,inclusive=true,timeline=2")
+        );
+
+        let point = choose_recovery_point(
+            "primary",
+            &RecoveryTarget::Xid("734560".to_string()),
+            &backups(),
+            utc(),
+        );
+        assert_eq!(point.backup.as_deref(), Some("20260703000000"));
+        assert_eq!(point.wal_coverage, "unknown");
+    }
+
+    #[test]
+    fn test_candidate_backups_for_time_include_next_backup() {
+        let catalog = backups();
+        let target = RecoveryTarget::Time {
+            input: String::new(),
+            time: time("2026-07-01 12:00:00"),
+            inclusive: true,
+        };
+        let candidates = candidate_backups(&catalog, &target);
+        let ids: Vec<&str> = candidates.iter().map(|b| b.backup.as_str()).collect();
+        assert_eq!(ids, vec!["20260701000000", "20260702000000"]);
+    }
+}
@@ -0,0 +1,1041 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use super::catalog::{self, BackupClock, BackupEntry, format_duration};
+use super::metrics::metric_values;
+use super::validation::{self, Keyword};
+use super::{PgmonetaHandler, retention::RetentionPolicy, verify};
+use crate::client::PgmonetaClient;
+use crate::configuration::{CONFIG, DEFAULT_VERIFY_DIRECTORY};
+use crate::utils::{SafeFileWriter, Utility};
+use chrono::NaiveDateTime;
+use rmcp::ErrorData as McpError;
+use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
+use rmcp::model::JsonObject;
+use rmcp::schemars;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// The recovery point objective used when none is given.
+const DEFAULT_RPO_HOURS: u32 = 24;
+
+/// The period compared by the space trend.
+const TREND_DAYS: i64 = 7;
+
+/// Output format of a backup report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, schemars::JsonSchema)]
+#[schemars(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ReportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Markdown => "markdown",
+            Self::Html => "html",
+            Self::Json => "json",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Json => "json",
+        }
+    }
+}
+
+impl Keyword for ReportFormat {
+    const ARGUMENT: &'static str = "format";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("markdown", Self::Markdown),
+        ("html", Self::Html),
+        ("json", Self::Json),
+    ];
+}
+
+impl<'de> serde::Deserialize<'de> for ReportFormat {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        validation::deserialize_keyword(deserializer)
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct BackupReportRequest {
+    pub username: String,
+    /// Limit the report to one server; all servers are reported by default
+    #[serde(default)]
+    pub server: Option<String>,
+    /// Report format: markdown (default), html or json
+    #[serde(default, deserialize_with = "validation::deserialize_optional_keyword")]
+    pub format: Option<ReportFormat>,
+    /// Recovery point objective in hours; the newest valid backup has to be younger. Default: 24
+    #[serde(default)]
+    pub rpo_hours: Option<u32>,
+    /// Verify the newest valid backup of each server. This reads the whole backup and can take a long time
+    #[serde(default)]
+    pub verify: Option<bool>,
+    /// Write the report to this file under report_directory instead of returning it
+    #[serde(default)]
+    pub file: Option<String>,
+}
+
+/// Tool for generating a backup health report of all servers.
+pub struct BackupReportTool;
+
+impl ToolBase for BackupReportTool {
+    type Parameter = BackupReportRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "backup_report".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "Generate a backup health report in Markdown, HTML or JSON. \
+            For every server it covers the last successful backup, its age against the \
+            recovery point objective (rpo_hours), invalid backups, retention coverage, \
+            the space trend and WAL gaps, based on the detailed status, the backup lists \
+            and the pgmoneta metrics. Set verify to also verify the newest valid backup of each server. \
+            The report is returned inline, or written to file under the configured report_directory. \
+            The username has to be one of the pgmoneta admins to be able to access pgmoneta."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for BackupReportTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: BackupReportRequest,
+    ) -> Result<String, McpError> {
+        let format = request.format.unwrap_or_default();
+        let writer = match request.file.as_deref() {
+            Some(file) => Some((file, report_writer(format)?)),
+            None => None,
+        };
+        let rpo_hours = request.rpo_hours.unwrap_or(DEFAULT_RPO_HOURS);
+        if rpo_hours == 0 {
+            return Err(McpError::invalid_params(
+                "Invalid rpo_hours '0': expected a positive number of hours",
+                None,
+            ));
+        }
+
+        let clock = BackupClock::configured();
+        let now = clock.now();
+        let status = catalog::fetch_status(&request.username).await?;
+        let mut servers = catalog::parse_server_names(&status);
+        if let Some(server) = request.server.as_deref() {
+            if !servers.iter().any(|name| name == server) {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "Unknown server '{server}'. Known servers: {}",
+                        servers.join(", ")
+                    ),
+                    None,
+                ));
+            }
+            servers.retain(|name| name == server);
+        }
+
+        let metrics = match PgmonetaClient::request_metrics(&request.username).await {
+            Ok(metrics) => Some(metrics),
+            Err(e) => {
+                tracing::warn!("backup report without pgmoneta metrics: {:?}", e);
+                None
+            }
+        };
+
+        let mut reports = Vec::new();
+        for server in &servers {
+            let server_status = catalog::parse_servers(&status)
+                .into_iter()
+                .find(|object| object.get("Server").and_then(Value::as_str) == Some(server));
+            let (backups, error) = match catalog::fetch_backups(&request.username, server).await {
+                Ok(backups) => (backups, None),
+                Err(e) => (Vec::new(), Some(e.message.to_string())),
+            };
+            let verification = match (
+                request.verify.unwrap_or(false),
+                catalog::newest_valid(&backups),
+            ) {
+                (true, Some(backup)) => {
+                    Some(verify_backup(&request.username, server, &backup.backup).await)
+                }
+                _ => None,
+            };
+            let mut report = server_report(
+                server,
+                server_status,
+                &backups,
+                metrics.as_deref(),
+                verification,
+                rpo_hours,
+                now,
+                clock,
+            );
+            if let Some(error) = error {
+                report.health = Health::Critical;
+                report
+                    .findings
+                    .insert(0, format!("Unable to list backups: {error}"));
+            }
+            reports.push(report);
+        }
+
+        let report = BackupReport {
+            generated: clock.format(now),
+            rpo_hours,
+            total_space: space(&status, "TotalSpace"),
+            used_space: space(&status, "UsedSpace"),
+            free_space: space(&status, "FreeSpace"),
+            metrics_available: metrics.is_some(),
+            health: reports
+                .iter()
+                .map(|report| report.health)
+                .max()
+                .unwrap_or(Health::Ok),
+            servers: reports,
+        };
+        let rendered = render(&report, format)?;
+
+        match writer {
+            Some((file, writer)) => {
+                let path = writer.write(file, &rendered).map_err(|e| {
+                    McpError::invalid_params(format!("Failed to write report: {e}"), None)
+                })?;
+                let written = serde_json::json!({
+                    "File": path.display().to_string(),
+                    "Format": format.as_str(),
+                    "Size": Utility::format_file_size(rendered.len() as u64),
+                    "Health": report.health,
+                });
+                Ok(written.to_string())
+            }
+            None => Ok(rendered),
+        }
+    }
+}
+
+/// Overall state of a server, ordered from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Health {
+    Ok,
+    Warning,
+    Critical,
+}
+
+impl Health {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Warning => "warning",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct BackupReport {
+    generated: String,
+    rpo_hours: u32,
+    total_space: Option<String>,
+    used_space: Option<String>,
+    free_space: Option<String>,
+    metrics_available: bool,
+    health: Health,
+    servers: Vec<ServerReport>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct ServerReport {
+    server: String,
+    health: Health,
+    backups: usize,
+    valid_backups: usize,
+    last_successful_backup: Option<String>,
+    last_successful_backup_end: Option<String>,
+    backup_age: Option<String>,
+    backup_age_seconds: Option<i64>,
+    rpo_met: bool,
+    invalid_backups: Vec<String>,
+    retention: RetentionCoverage,
+    space_trend: SpaceTrend,
+    wal_gaps: Vec<WalGap>,
+    wal_streaming: Option<bool>,
+    verification: Option<Verification>,
+    findings: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct RetentionCoverage {
+    /// The policy in the pgmoneta syntax, when known.
+    policy: Option<String>,
+    /// How far back the policy keeps backups.
+    required_days: Option<u32>,
+    /// The age of the oldest valid backup.
+    covered_days: Option<u32>,
+    covered: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct SpaceTrend {
+    total: String,
+    total_bytes: u64,
+    last_period: String,
+    previous_period: String,
+    period_days: i64,
+    direction: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct WalGap {
+    after: String,
+    before: String,
+    reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct Verification {
+    backup: String,
+    /// `passed`, `failed` or `error`.
+    result: String,
+    failed_files: usize,
+    error: Option<String>,
+}
+
+fn report_writer(format: ReportFormat) -> Result<SafeFileWriter, McpError> {
+    let directory = CONFIG
+        .get()
+        .and_then(|config| config.pgmoneta_mcp.report_directory.as_deref())
+        .ok_or_else(|| {
+            McpError::invalid_params(
+                "Writing a report to a file requires report_directory in the [pgmoneta_mcp] section",
+                None,
+            )
+        })?;
+    Ok(SafeFileWriter::new(directory).allowed_extensions(vec![format.extension()]))
+}
+
+async fn verify_backup(username: &str, server: &str, backup: &str) -> Verification {
+    let result = verify::run_verify(username, server, backup, DEFAULT_VERIFY_DIRECTORY, false)
+        .await
+        .map_err(|e| McpError::internal_error(format!("{:?}", e), None))
+        .and_then(|result| verify::parse_verify(&result, server, backup, DEFAULT_VERIFY_DIRECTORY));
+    match result {
+        Ok(report) => Verification {
+            backup: backup.to_string(),
+            result: if report.failed_files == 0 {
+                "passed"
+            } else {
+                "failed"
+            }
+            .to_string(),
+            failed_files: report.failed_files,
+            error: None,
+        },
+        Err(e) => Verification {
+            backup: backup.to_string(),
+            result: "error".to_string(),
+            failed_files: 0,
+            error: Some(e.message.to_string()),
+        },
+    }
+}
+
+fn space(status: &Map<String, Value>, field: &str) -> Option<String> {
+    catalog::space_bytes(status, field).map(Utility::format_file_size)
+}
+
+fn retention_coverage(
+    policy: Option<RetentionPolicy>,
+    backups: &[BackupEntry],
+    now: NaiveDateTime,
+) -> RetentionCoverage {
+    let Some(policy) = policy else {
+        return RetentionCoverage::default();
+    };
+    let required_days = [
+        policy.days,
+        policy.weeks.map(|weeks| weeks * 7),
+        policy.months.map(|months| months * 30),
+        policy.years.map(|years| years * 365),
+    ]
+    .into_iter()
+    .flatten()
+    .max();
+    let covered_days = backups
+        .iter()
+        .find(|backup| backup.valid == Some(true))
+        .and_then(BackupEntry::timestamp)
+        .map(|oldest| (now - oldest).num_days().max(0) as u32);
+
+    RetentionCoverage {
+        policy: Some(policy.to_string()),
+        required_days,
+        covered: match (required_days, covered_days) {
+            (Some(required), Some(covered)) => Some(covered >= required),
+            (Some(_), None) => Some(false),
+            _ => None,
+        },
+        covered_days,
+    }
+}
+
+fn space_trend(backups: &[BackupEntry], now: NaiveDateTime) -> SpaceTrend {
+    let period = chrono::Duration::days(TREND_DAYS);
+    let size_between = |from: NaiveDateTime, to: NaiveDateTime| -> u64 {
+        backups
+            .iter()
+            .filter(|backup| {
+                backup
+                    .timestamp()
+                    .is_some_and(|time| time > from && time <= to)
+            })
+            .filter_map(|backup| backup.backup_size)
+            .sum()
+    };
+    let last = size_between(now - period, now);
+    let previous = size_between(now - period - period, now - period);
+    let total = backups.iter().filter_map(|backup| backup.backup_size).sum();
+
+    let direction = if last == 0 && previous == 0 {
+        "no new backups"
+    } else if (last as f64) > previous as f64 * 1.1 {
+        "growing"
+    } else if (last as f64) < previous as f64 * 0.9 {
+        "shrinking"
+    } else {
+        "stable"
+    };
+
+    SpaceTrend {
+        total: Utility::format_file_size(total),
+        total_bytes: total,
+        last_period: Utility::format_file_size(last),
+        previous_period: Utility::format_file_size(previous),
+        period_days: TREND_DAYS,
+        direction: direction.to_string(),
+    }
+}
+
+/// Places between consecutive valid backups where the WAL is unlikely to be continuous.
+fn wal_gaps(backups: &[BackupEntry]) -> Vec<WalGap> {
+    let valid: Vec<&BackupEntry> = backups
+        .iter()
+        .filter(|backup| backup.valid == Some(true))
+        .collect();
+    valid
+        .windows(2)
+        .filter_map(|pair| {
+            let (before, after) = (pair[0], pair[1]);
+            let reason = match (
+                before.end_timeline,
+                after.start_timeline,
+                before.end_lsn,
+                after.start_lsn,
+            ) {
+                (Some(end), Some(start), _, _) if start < end => format!(
+                    "the later backup starts on timeline {start}, older than timeline {end}"
+                ),
+                (Some(end), Some(start), Some(end_lsn), Some(start_lsn))
+                    if start == end && start_lsn < end_lsn =>
+                {
+                    format!(
+                        "the later backup starts at {}, before the end {} of the earlier backup",
+                        catalog::format_lsn(start_lsn),
+                        catalog::format_lsn(end_lsn)
+                    )
+                }
+                _ => return None,
+            };
+            Some(WalGap {
+                after: before.backup.clone(),
+                before: after.backup.clone(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn server_report(
+    server: &str,
+    status: Option<&Map<String, Value>>,
+    backups: &[BackupEntry],
+    metrics: Option<&str>,
+    verification: Option<Verification>,
+    rpo_hours: u32,
+    now: NaiveDateTime,
+    clock: BackupClock,
+) -> ServerReport {
+    let mut findings = Vec::new();
+    let mut health = Health::Ok;
+    let mut raise = |level: Health, finding: String, findings: &mut Vec<String>| {
+        health = health.max(level);
+        findings.push(finding);
+    };
+
+    let newest = catalog::newest_valid(backups);
+    let end = newest.and_then(BackupEntry::end_timestamp);
+    let age = end.map(|end| (now - end).num_seconds().max(0));
+    let rpo_met = age.is_some_and(|age| age <= i64::from(rpo_hours) * 3600);
+    match age {
+        None => raise(
+            Health::Critical,
+            "No valid backup available".to_string(),
+            &mut findings,
+        ),
+        Some(age) if !rpo_met => raise(
+            Health::Critical,
+            format!(
+                "The newest valid backup is {} old, beyond the RPO of {rpo_hours}h",
+                format_duration(age)
+            ),
+            &mut findings,
+        ),
+        _ => {}
+    }
+
+    let invalid_backups: Vec<String> = backups
+        .iter()
+        .filter(|backup| backup.valid == Some(false))
+        .map(|backup| backup.backup.clone())
+        .collect();
+    if !invalid_backups.is_empty() {
+        raise(
+            Health::Warning,
+            format!("{} invalid backup(s)", invalid_backups.len()),
+            &mut findings,
+        );
+    }
+
+    let retention = retention_coverage(
+        RetentionPolicy::configured(server, status, metrics),
+        backups,
+        now,
+    );
+    if let (Some(false), Some(required)) = (retention.covered, retention.required_days) {
+        raise(
+            Health::Warning,
+            format!(
+                "Valid backups cover {} of the {required} days required by the retention policy",
+                retention.covered_days.unwrap_or(0)
+            ),
+            &mut findings,
+        );
+    }
+
+    let wal_gaps = wal_gaps(backups);
+    for gap in &wal_gaps {
+        raise(
+            Health::Warning,
+            format!(
+                "Possible WAL gap between {} and {}: {}",
+                gap.after, gap.before, gap.reason
+            ),
+            &mut findings,
+        );
+    }
+
+    let wal_streaming = metrics.and_then(|metrics| {
+        metric_values(metrics, "pgmoneta_wal_streaming", &[("name", server)])
+            .first()
+            .map(|value| *value > 0.0)
+    });
+    if wal_streaming == Some(false) {
+        raise(
+            Health::Warning,
+            "WAL streaming is not active".to_string(),
+            &mut findings,
+        );
+    }
+
+    if let Some(verification) = verification.as_ref() {
+        match verification.result.as_str() {
+            "passed" => {}
+            "failed" => raise(
+                Health::Critical,
+                format!(
+                    "Verification of {} found {} failed file(s)",
+                    verification.backup, verification.failed_files
+                ),
+                &mut findings,
+            ),
+            _ => raise(
+                Health::Warning,
+                format!(
+                    "Verification of {} did not complete: {}",
+                    verification.backup,
+                    verification.error.as_deref().unwrap_or("unknown error")
+                ),
+                &mut findings,
+            ),
+        }
+    }
+
+    ServerReport {
+        server: server.to_string(),
+        health,
+        backups: backups.len(),
+        valid_backups: backups
+            .iter()
+            .filter(|backup| backup.valid == Some(true))
+            .count(),
+        last_successful_backup: newest.map(|backup| backup.backup.clone()),
+        last_successful_backup_end: end.map(|end| clock.format(end)),
+        backup_age: age.map(format_duration),
+        backup_age_seconds: age,
+        rpo_met,
+        invalid_backups,
+        retention,
+        space_trend: space_trend(backups, now),
+        wal_gaps,
+        wal_streaming,
+        verification,
+        findings,
+    }
+}
+
+fn render(report: &BackupReport, format: ReportFormat) -> Result<String, McpError> {
+    match format {
+        ReportFormat::Json => serde_json::to_string_pretty(report).map_err(|e| {
+            McpError::internal_error(format!("Failed to serialize backup report: {:?}", e), None)
+        }),
+        ReportFormat::Markdown => Ok(render_markdown(report)),
+        ReportFormat::Html => Ok(render_html(report)),
+    }
+}
+
+/// The cells of the summary table, one row per server.
+fn summary_rows(report: &BackupReport) -> Vec<[String; 8]> {
+    report
+        .servers
+        .iter()
+        .map(|server| {
+            [
+                server.server.clone(),
+                server.health.as_str().to_string(),
+                server
+                    .last_successful_backup
+                    .clone()
+                    .unwrap_or_else(|| "none".to_string()),
+                server.backup_age.clone().unwrap_or_else(|| "-".to_string()),
+                if server.rpo_met { "met" } else { "missed" }.to_string(),
+                format!("{}/{}", server.valid_backups, server.backups),
+                match (
+                    server.retention.covered_days,
+                    server.retention.required_days,
+                ) {
+                    (Some(covered), Some(required)) => format!("{covered}/{required} days"),
+                    (None, Some(required)) => format!("0/{required} days"),
+                    _ => "unknown".to_string(),
+                },
+                server.wal_gaps.len().to_string(),
+            ]
+        })
+        .collect()
+}
+
+const SUMMARY_HEADER: [&str; 8] = [
+    "Server",
+    "Health",
+    "Last successful backup",
+    "Age",
+    "RPO",
+    "Valid",
+    "Retention coverage",
+    "WAL gaps",
+];
+
+fn server_details(server: &ServerReport) -> Vec<(&'static str, String)> {
+    let mut details = vec![
+        (
+            "Last successful backup",
+            match (
+                &server.last_successful_backup,
+                &server.last_successful_backup_end,
+            ) {
+                (Some(backup), Some(end)) => format!("{backup} (ended {end})"),
+                (Some(backup), None) => backup.clone(),
+                _ => "none".to_string(),
+            },
+        ),
+        (
+            "Invalid backups",
+            if server.invalid_backups.is_empty() {
+                "none".to_string()
+            } else {
+                server.invalid_backups.join(", ")
+            },
+        ),
+        (
+            "Retention policy",
+            server
+                .retention
+                .policy
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string()),
+        ),
+        (
+            "Space",
+            format!(
+                "{} in total, {} in the last {} days, {} in the {} days before ({})",
+                server.space_trend.total,
+                server.space_trend.last_period,
+                server.space_trend.period_days,
+                server.space_trend.previous_period,
+                server.space_trend.period_days,
+                server.space_trend.direction
+            ),
+        ),
+        (
+            "WAL streaming",
+            match server.wal_streaming {
+                Some(true) => "active",
+                Some(false) => "inactive",
+                None => "unknown",
+            }
+            .to_string(),
+        ),
+    ];
+    if let Some(verification) = server.verification.as_ref() {
+        details.push((
+            "Verification",
+            format!(
+                "{}: {} ({} failed file(s))",
+                verification.backup, verification.result, verification.failed_files
+            ),
+        ));
+    }
+    details
+}
+
+fn render_markdown(report: &BackupReport) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# pgmoneta backup report\n");
+    let _ = writeln!(out, "- Generated: {}", report.generated);
+    let _ = writeln!(out, "- Overall health: {}", report.health.as_str());
+    let _ = writeln!(out, "- RPO: {}h", report.rpo_hours);
+    if let (Some(used), Some(free), Some(total)) =
+        (&report.used_space, &report.free_space, &report.total_space)
+    {
+        let _ = writeln!(out, "- Space: {used} used, {free} free of {total}");
+    }
+    if !report.metrics_available {
+        let _ = writeln!(out, "- Metrics: unavailable");
+    }
+
+    let _ = writeln!(out, "\n| {} |", SUMMARY_HEADER.join(" | "));
+    let _ = writeln!(out, "|{}", " --- |".repeat(SUMMARY_HEADER.len()));
+    for row in summary_rows(report) {
+        let cells: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+        let _ = writeln!(out, "| {} |", cells.join(" | "));
+    }
+
+    for server in &report.servers {
+        let _ = writeln!(out, "\n## {}\n", server.server);
+        for (label, value) in server_details(server) {
+            let _ = writeln!(out, "- {label}: {value}");
+        }
+        if !server.findings.is_empty() {
+            let _ = writeln!(out, "\n### Findings\n");
+            for finding in &server.findings {
+                let _ = writeln!(out, "- {finding}");
+            }
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_html(report: &BackupReport) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>pgmoneta backup report</title>\n</head>\n<body>"
+    );
+    let _ = writeln!(out, "<h1>pgmoneta backup report</h1>\n<ul>");
+    let _ = writeln!(
+        out,
+        "<li>Generated: {}</li>",
+        escape_html(&report.generated)
+    );
+    let _ = writeln!(out, "<li>Overall health: {}</li>", report.health.as_str());
+    let _ = writeln!(out, "<li>RPO: {}h</li>", report.rpo_hours);
+    if let (Some(used), Some(free), Some(total)) =
+        (&report.used_space, &report.free_space, &report.total_space)
+    {
+        let _ = writeln!(
+            out,
+            "<li>Space: {} used, {} free of {}</li>",
+            escape_html(used),
+            escape_html(free),
+            escape_html(total)
+        );
+    }
+    if !report.metrics_available {
+        let _ = writeln!(out, "<li>Metrics: unavailable</li>");
+    }
+    let _ = writeln!(out, "</ul>\n<table>\n<tr>");
+    for header in SUMMARY_HEADER {
+        let _ = writeln!(out, "<th>{header}</th>");
+    }
+    let _ = writeln!(out, "</tr>");
+    for row in summary_rows(report) {
+        let cells: String = row
+            .iter()
+            .map(|cell| format!("<td>{}</td>", escape_html(cell)))
+            .collect();
+        let _ = writeln!(out, "<tr>{cells}</tr>");
+    }
+    let _ = writeln!(out, "</table>");
+
+    for server in &report.servers {
+        let _ = writeln!(out, "<h2>{}</h2>\n<ul>", escape_html(&server.server));
+        for (label, value) in server_details(server) {
+            let _ = writeln!(out, "<li>{label}: {}</li>", escape_html(&value));
+        }
+        let _ = writeln!(out, "</ul>");
+        if !server.findings.is_empty() {
+            let _ = writeln!(out, "<h3>Findings</h3>\n<ul>");
+            for finding in &server.findings {
+                let _ = writeln!(out, "<li>{}</li>", escape_html(finding));
+            }
+            let _ = writeln!(out, "</ul>");
+        }
+    }
+    let _ = writeln!(out, "</body>\n</html>");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn backup(id: &str, valid: bool, size: u64) -> BackupEntry {
+        BackupEntry {
+            backup: id.to_string(),
+            valid: Some(valid),
+            backup_size: Some(size),
+            elapsed: Some(60.0),
+            start_timeline: Some(1),
+            end_timeline: Some(1),
+            ..Default::default()
+        }
+    }
+
+    fn time(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, catalog::BACKUP_ID_FORMAT).unwrap()
+    }
+
+    fn clock() -> BackupClock {
+        BackupClock::new(chrono::FixedOffset::east_opt(0))
+    }
+
+    #[test]
+    fn test_backup_report_tool_metadata() {
+        assert_eq!(BackupReportTool::name(), "backup_report");
+        let description = BackupReportTool::description().unwrap();
+        assert!(description.contains("rpo_hours"));
+        assert!(description.contains("report_directory"));
+    }
+
+    #[test]
+    fn test_backup_report_request_format() {
+        let request: BackupReportRequest =
+            serde_json::from_value(json!({"username": "admin", "format": "HTML"})).unwrap();
+        assert_eq!(request.format, Some(ReportFormat::Html));
+        let request: BackupReportRequest =
+            serde_json::from_value(json!({"username": "admin"})).unwrap();
+        assert_eq!(request.format, None);
+        assert!(
+            serde_json::from_value::<BackupReportRequest>(
+                json!({"username": "admin", "format": "pdf"})
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_server_report_healthy() {
+        let backups = vec![
+            backup("20260101000000", true, 100),
+            backup("20260108000000", true, 200),
+        ];
+        let status = json!({"Server": "primary", "RetentionDays": 7});
+        let report = server_report(
+            "primary",
+            status.as_object(),
+            &backups,
+            Some("pgmoneta_wal_streaming{name=\"primary\"} 1\n"),
+            None,
+            24,
+            time("20260108060000"),
+            clock(),
+        );
+
+        assert_eq!(report.health, Health::Ok, "{:?}", report.findings);
+        assert_eq!(
+            report.last_successful_backup.as_deref(),
+            Some("20260108000000")
+        );
+        assert_eq!(report.backup_age.as_deref(), Some("5h 59m 0s"));
+        assert!(report.rpo_met);
+        assert_eq!(report.retention.policy.as_deref(), Some("7"));
+        assert_eq!(report.retention.covered, Some(true));
+        assert_eq!(report.wal_streaming, Some(true));
+        assert_eq!(report.space_trend.total_bytes, 300);
+    }
+
+    #[test]
+    fn test_server_report_findings() {
+        let mut gap = backup("20260107000000", true, 100);
+        gap.start_timeline = Some(1);
+        let mut before = backup("20260106000000", true, 100);
+        before.end_timeline = Some(2);
+        let backups = vec![before, backup("20260106120000", false, 10), gap];
+        let metrics = "pgmoneta_retention_server{name=\"primary\",parameter=\"days\"} 30\n\
+                       pgmoneta_wal_streaming{name=\"primary\"} 0\n";
+        let verification = Verification {
+            backup: "20260107000000".to_string(),
+            result: "failed".to_string(),
+            failed_files: 2,
+            error: None,
+        };
+        let report = server_report(
+            "primary",
+            None,
+            &backups,
+            Some(metrics),
+            Some(verification),
+            24,
+            time("20260109000000"),
+            clock(),
+        );
+
+        assert_eq!(report.health, Health::Critical);
+        assert!(!report.rpo_met);
+        assert_eq!(report.invalid_backups, vec!["20260106120000"]);
+        assert_eq!(report.retention.required_days, Some(30));
+        assert_eq!(report.retention.covered, Some(false));
+        assert_eq!(report.wal_gaps.len(), 1);
+        assert_eq!(report.wal_gaps[0].after, "20260106000000");
+        assert_eq!(report.wal_streaming, Some(false));
+        let findings = report.findings.join("\n");
+        assert!(findings.contains("beyond the RPO of 24h"));
+        assert!(findings.contains("1 invalid backup(s)"));
+        assert!(findings.contains("Possible WAL gap"));
+        assert!(findings.contains("WAL streaming is not active"));
+        assert!(findings.contains("2 failed file(s)"));
+    }
+
+    #[test]
+    fn test_server_report_without_backups() {
+        let report = server_report(
+            "primary",
+            None,
+            &[],
+            None,
+            None,
+            24,
+            time("20260109000000"),
+            clock(),
+        );
+        assert_eq!(report.health, Health::Critical);
+        assert_eq!(report.findings, vec!["No valid backup available"]);
+        assert_eq!(report.retention.policy, None);
+        assert_eq!(report.space_trend.direction, "no new backups");
+    }
+
+    #[test]
+    fn test_space_trend() {
+        let backups = vec![
+            backup("20260101000000", true, 100),
+            backup("20260105000000", true, 100),
+            backup("20260110000000", true, 300),
+        ];
+        let trend = space_trend(&backups, time("20260112000000"));
+        assert_eq!(trend.total_bytes, 500);
+        assert_eq!(trend.direction, "growing");
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let report = BackupReport {
+            generated: "2026-01-09 00:00:00+00:00".to_string(),
+            rpo_hours: 24,
+            total_space: Some("10.00 GB".to_string()),
+            used_space: Some("4.00 GB".to_string()),
+            free_space: Some("6.00 GB".to_string()),
+            metrics_available: false,
+            health: Health::Critical,
+            servers: vec![server_report(
+                "<primary>",
+                None,
+                &[backup("20260108000000", true, 100)],
+                None,
+                None,
+                1,
+                time("20260109000000"),
+                clock(),
+            )],
+        };
+
+        let markdown = render(&report, ReportFormat::Markdown).unwrap();
+        assert!(markdown.starts_with("# pgmoneta backup report"));
+        assert!(markdown.contains("| <primary> | critical | 20260108000000 |"));
+        assert!(markdown.contains("- Metrics: unavailable"));
+
+        let html = render(&report, ReportFormat::Html).unwrap();
+        assert!(html.contains("<h2>&lt;primary&gt;</h2>"));
+        assert!(!html.contains("<primary>"));
+
+        let json: Value =
+            serde_json::from_str(&render(&report, ReportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["Health"], "critical");
+        assert_eq!(json["Servers"][0]["LastSuccessfulBackup"], "20260108000000");
+    }
+}
@@ -15,19 +15,30 @@
 
 use std::borrow::Cow;
 use std::sync::Arc;
+use std::time::Instant;
 
 use super::PgmonetaHandler;
+use super::catalog::{self, BackupClock, BackupEntry};
+use super::sla;
+use super::validation::{self, Keyword};
 use crate::client::PgmonetaClient;
+use crate::utils::Utility;
+use chrono::{DateTime, NaiveDateTime};
 use rmcp::ErrorData as McpError;
 use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
 use rmcp::model::JsonObject;
 use rmcp::schemars;
+use serde::Serialize;
 
 #[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
 pub struct RestoreRequest {
     pub username: String,
     pub server: String,
+    /// Backup identifier: a 14 digit timestamp or "newest", "latest" or "oldest"
+    #[schemars(pattern(validation::BACKUP_ID_PATTERN))]
+    #[serde(deserialize_with = "validation::deserialize_backup_id")]
     pub backup_id: String,
+    /// Absolute target directory on the pgmoneta host
     pub directory: String,
 
     /// Restore to the first stable checkpoint
@@ -44,14 +55,49 @@ pub struct RestoreRequest {
     pub inclusive: Option<String>,
     /// Restore to the specified timeline
     pub timeline: Option<String>,
-    /// Action to execute after the restore (pause, shutdown)
-    pub action: Option<String>,
+    /// Action to execute after the restore (pause, promote, shutdown)
+    pub action: Option<RecoveryAction>,
     /// Indicates if the cluster is set up as a primary
     pub primary: Option<bool>,
     /// Indicates if the cluster is set up as a replica
     pub replica: Option<bool>,
 }
 
+/// The action PostgreSQL executes once the recovery target is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, schemars::JsonSchema)]
+#[schemars(rename_all = "lowercase")]
+pub enum RecoveryAction {
+    Pause,
+    Promote,
+    Shutdown,
+}
+
+impl RecoveryAction {
+    /// The keyword sent to pgmoneta.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecoveryAction::Pause => "pause",
+            RecoveryAction::Promote => "promote",
+            RecoveryAction::Shutdown => "shutdown",
+        }
+    }
+}
+
+impl Keyword for RecoveryAction {
+    const ARGUMENT: &'static str = "recovery action";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("pause", RecoveryAction::Pause),
+        ("promote", RecoveryAction::Promote),
+        ("shutdown", RecoveryAction::Shutdown),
+    ];
+}
+
+impl<'de> serde::Deserialize<'de> for RecoveryAction {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        validation::deserialize_keyword(deserializer)
+    }
+}
+
 /// Tool for restoring a backup to a PostgreSQL server.
 pub struct RestoreTool;
 
@@ -79,7 +125,7 @@ impl ToolBase for RestoreTool {
             Position \"lsn\" means copy the WAL and restore to the specified LSN. \
             Position \"inclusive\" means the restore is inclusive of the specified information. \
             Position \"timeline\" means copy the WAL and restore to the specified timeline. \
-            Position \"action\" means which action will be executed after the restore (pause, shutdown). \
+            Position \"action\" means which action will be executed after the restore (pause, promote, shutdown). \
             Choose the position that best fits. \
             The directory specifies where to restore the backup. \
             The username has to be one of the pgmoneta admins to be able to access pgmoneta."
@@ -103,7 +149,9 @@ impl AsyncTool<PgmonetaHandler> for RestoreTool {
         _service: &PgmonetaHandler,
         request: RestoreRequest,
     ) -> Result<String, McpError> {
+        validate_request(&request)?;
         let position = normalize_position(&request);
+        let start = Instant::now();
         let result: String = PgmonetaClient::request_restore(
             &request.username,
             &request.server,
@@ -115,11 +163,500 @@ impl AsyncTool<PgmonetaHandler> for RestoreTool {
         .map_err(|e| {
             McpError::internal_error(format!("Failed to restore backup: {:?}", e), None)
         })?;
+        sla::record_duration(
+            &request.server,
+            sla::Operation::Restore,
+            &request.backup_id,
+            &result,
+            start.elapsed(),
+        );
         PgmonetaHandler::generate_call_tool_result_string(&result)
     }
 }
 
-fn normalize_position(req: &RestoreRequest) -> String {
+/// Tool for validating a restore request and describing it without executing it.
+pub struct PlanRestoreTool;
+
+impl ToolBase for PlanRestoreTool {
+    type Parameter = RestoreRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "plan_restore".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "Plan a restore without executing it (dry run). \
+            Accepts exactly the same arguments as the restore tool. \
+            Resolves \"newest\", \"latest\" or \"oldest\" to a concrete backup, \
+            checks that the requested time, lsn, xid or timeline falls within the available backup and WAL range, \
+            detects conflicting positions (for example both primary and replica), \
+            compares the free space pgmoneta reports against the restore size, \
+            and returns a step-by-step plan together with any errors and warnings. \
+            Use this before calling restore. \
+            The username has to be one of the pgmoneta admins to be able to access pgmoneta."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for PlanRestoreTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: RestoreRequest,
+    ) -> Result<String, McpError> {
+        validate_request(&request)?;
+        let backups = catalog::fetch_backups(&request.username, &request.server).await?;
+        let info = match catalog::resolve_backup(&backups, &request.backup_id) {
+            Some(backup) => Some(
+                catalog::fetch_backup_info(&request.username, &request.server, &backup.backup)
+                    .await?,
+            ),
+            None => None,
+        };
+        // The restore runs on the host of pgmoneta, so its view of the disk
+        // is the one that counts
+        let free_space = catalog::fetch_status(&request.username)
+            .await
+            .ok()
+            .and_then(|status| catalog::space_bytes(&status, "FreeSpace"));
+
+        let plan = build_restore_plan(
+            &request,
+            &backups,
+            info.as_ref(),
+            free_space,
+            BackupClock::configured(),
+        );
+        serde_json::to_string(&plan).map_err(|e| {
+            McpError::internal_error(format!("Failed to serialize restore plan: {:?}", e), None)
+        })
+    }
+}
+
+fn validate_request(request: &RestoreRequest) -> Result<(), McpError> {
+    validation::validate_backup_id("backup_id", &request.backup_id)?;
+    validation::validate_path("directory", &request.directory)
+}
+
+/// The outcome of a restore dry run.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct RestorePlan {
+    server: String,
+    requested_backup: String,
+    backup: Option<String>,
+    position: String,
+    directory: String,
+    restore_size: Option<String>,
+    free_space: Option<String>,
+    executable: bool,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+    steps: Vec<String>,
+}
+
+fn build_restore_plan(
+    request: &RestoreRequest,
+    backups: &[BackupEntry],
+    info: Option<&BackupEntry>,
+    free_space: Option<u64>,
+    clock: BackupClock,
+) -> RestorePlan {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    check_position_conflicts(request, &mut errors, &mut warnings);
+
+    let resolved = catalog::resolve_backup(backups, &request.backup_id);
+    let backup = match (resolved, info) {
+        (_, Some(info)) => Some(info),
+        (Some(resolved), None) => Some(resolved),
+        (None, _) => {
+            if backups.is_empty() {
+                errors.push(format!(
+                    "Server '{}' has no backups to restore from",
+                    request.server
+                ));
+            } else {
+                errors.push(format!(
+                    "Backup '{}' was not found on server '{}'",
+                    request.backup_id, request.server
+                ));
+            }
+            None
+        }
+    };
+
+    if let Some(backup) = backup {
+        match backup.valid {
+            Some(false) => errors.push(format!("Backup '{}' is marked invalid", backup.backup)),
+            None => warnings.push(format!(
+                "The validity of backup '{}' is unknown; consider running verify first",
+                backup.backup
+            )),
+            Some(true) => {}
+        }
+
+        check_time_target(request, backup, backups, clock, &mut errors, &mut warnings);
+        check_lsn_target(request, backup, backups, &mut errors, &mut warnings);
+        check_timeline_target(request, backup, &mut errors);
+        check_free_space(backup, free_space, &mut warnings);
+    }
+
+    let position = normalize_position(request);
+    let steps = match backup {
+        Some(backup) => plan_steps(request, backup, backups, &position),
+        None => Vec::new(),
+    };
+
+    RestorePlan {
+        server: request.server.clone(),
+        requested_backup: request.backup_id.clone(),
+        backup: backup.map(|backup| backup.backup.clone()),
+        position,
+        directory: request.directory.clone(),
+        restore_size: backup
+            .and_then(|backup| backup.restore_size)
+            .map(Utility::format_file_size),
+        free_space: free_space.map(Utility::format_file_size),
+        executable: errors.is_empty(),
+        errors,
+        warnings,
+        steps,
+    }
+}
+
+fn check_position_conflicts(
+    request: &RestoreRequest,
+    errors: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) {
+    if request.primary == Some(true) && request.replica == Some(true) {
+        errors.push("Positions 'primary' and 'replica' are mutually exclusive".to_string());
+    }
+
+    let targets = recovery_targets(request);
+    if targets.len() > 1 {
+        errors.push(format!(
+            "Only one recovery target can be specified, found: {}",
+            targets.join(", ")
+        ));
+    }
+    if request.current == Some(true) && !targets.is_empty() {
+        errors.push(format!(
+            "Position 'current' restores to the first stable checkpoint and cannot be combined with {}",
+            targets.join(", ")
+        ));
+    }
+
+    let has_inclusive_target =
+        request.xid.is_some() || request.time.is_some() || request.lsn.is_some();
+    if let Some(inclusive) = &request.inclusive {
+        if !matches!(
+            inclusive.trim().to_lowercase().as_str(),
+            "true" | "false" | "on" | "off"
+        ) {
+            errors.push(format!(
+                "Position 'inclusive' must be true or false, found '{inclusive}'"
+            ));
+        } else if !has_inclusive_target {
+            warnings.push(
+                "Position 'inclusive' only applies to xid, time or lsn targets and will be ignored"
+                    .to_string(),
+            );
+        }
+    }
+
+    if request.action.is_some() && targets.is_empty() {
+        warnings
+            .push("Position 'action' only applies when a recovery target is specified".to_string());
+    }
+
+    if let Some(xid) = &request.xid {
+        if xid.trim().parse::<u64>().is_err() {
+            errors.push(format!(
+                "Position 'xid' must be a numeric transaction ID, found '{xid}'"
+            ));
+        } else {
+            warnings.push(
+                "Transaction IDs cannot be checked against the backup catalog; make sure the xid was committed after the backup ended"
+                    .to_string(),
+            );
+        }
+    }
+}
+
+fn recovery_targets(request: &RestoreRequest) -> Vec<&'static str> {
+    let mut targets = Vec::new();
+    if request.name.is_some() {
+        targets.push("name");
+    }
+    if request.xid.is_some() {
+        targets.push("xid");
+    }
+    if request.time.is_some() {
+        targets.push("time");
+    }
+    if request.lsn.is_some() {
+        targets.push("lsn");
+    }
+    targets
+}
+
+fn check_time_target(
+    request: &RestoreRequest,
+    backup: &BackupEntry,
+    backups: &[BackupEntry],
+    clock: BackupClock,
+    errors: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) {
+    let Some(time) = &request.time else {
+        return;
+    };
+    let Some(target) = parse_restore_time(time, clock) else {
+        errors.push(format!(
+            "Position 'time' could not be parsed, found '{time}'; use YYYY-MM-DD HH:MM:SS"
+        ));
+        return;
+    };
+
+    if target > clock.now() {
+        errors.push(format!("Target time {target} is in the future"));
+        return;
+    }
+
+    let Some(end) = backup.end_timestamp() else {
+        return;
+    };
+    // The best base backup is the most recent valid one that ended before the target
+    let best = backups
+        .iter()
+        .filter(|candidate| candidate.valid != Some(false))
+        .filter(|candidate| {
+            candidate
+                .end_timestamp()
+                .is_some_and(|candidate_end| candidate_end <= target)
+        })
+        .max_by(|a, b| a.backup.cmp(&b.backup));
+
+    if target < end {
+        match best {
+            Some(best) => errors.push(format!(
+                "Target time {target} precedes the end of backup '{}' ({end}); use backup '{}' instead",
+                backup.backup, best.backup
+            )),
+            None => errors.push(format!(
+                "Target time {target} precedes the end of the oldest available backup"
+            )),
+        }
+    } else if let Some(best) = best
+        && best.backup != backup.backup
+    {
+        warnings.push(format!(
+            "Backup '{}' is closer to the target time and needs less WAL replay",
+            best.backup
+        ));
+    }
+
+    // The WAL is known to reach the end of the newest backup only
+    if let Some((newest, newest_end)) = backups
+        .iter()
+        .filter_map(|candidate| Some((candidate, candidate.end_timestamp()?)))
+        .max_by_key(|(_, end)| *end)
+        && target > newest_end
+    {
+        warnings.push(format!(
+            "Target time {target} is after the end of the newest backup '{}' ({newest_end}); \
+             pgmoneta does not report its newest archived WAL, so the WAL up to the target is unchecked",
+            newest.backup
+        ));
+    }
+}
+
+fn check_lsn_target(
+    request: &RestoreRequest,
+    backup: &BackupEntry,
+    backups: &[BackupEntry],
+    errors: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) {
+    let Some(lsn) = &request.lsn else {
+        return;
+    };
+    let Some(target) = catalog::parse_lsn(lsn) else {
+        errors.push(format!(
+            "Position 'lsn' could not be parsed, found '{lsn}'; use the X/Y notation"
+        ));
+        return;
+    };
+
+    match backup.end_lsn.or(backup.start_lsn) {
+        Some(end) if target < end => errors.push(format!(
+            "Target LSN {} precedes the end of backup '{}' ({})",
+            catalog::format_lsn(target),
+            backup.backup,
+            catalog::format_lsn(end)
+        )),
+        Some(_) => {}
+        None => warnings.push(format!(
+            "The LSN range of backup '{}' is unknown; the target LSN could not be checked",
+            backup.backup
+        )),
+    }
+
+    // The WAL is known to reach the end of the newest backup only
+    if let Some((newest, newest_end)) = backups
+        .iter()
+        .filter_map(|candidate| Some((candidate, candidate.end_lsn?)))
+        .max_by_key(|(_, end)| *end)
+        && target > newest_end
+    {
+        warnings.push(format!(
+            "Target LSN {} is after the end of the newest backup '{}' ({}); \
+             pgmoneta does not report its newest archived WAL, so the WAL up to the target is unchecked",
+            catalog::format_lsn(target),
+            newest.backup,
+            catalog::format_lsn(newest_end)
+        ));
+    }
+}
+
+fn check_timeline_target(request: &RestoreRequest, backup: &BackupEntry, errors: &mut Vec<String>) {
+    let Some(timeline) = &request.timeline else {
+        return;
+    };
+    let normalized = timeline.trim().to_lowercase();
+    if normalized == "latest" || normalized == "current" {
+        return;
+    }
+
+    match normalized.parse::<u32>() {
+        Ok(target) => {
+            if let Some(start) = backup.start_timeline
+                && target < start
+            {
+                errors.push(format!(
+                    "Target timeline {target} precedes timeline {start} of backup '{}'",
+                    backup.backup
+                ));
+            }
+        }
+        Err(_) => errors.push(format!(
+            "Position 'timeline' must be latest, current or a number, found '{timeline}'"
+        )),
+    }
+}
+
+/// Compares the restore size against the free space pgmoneta reports for its
+/// backup volume. The target directory may be on another filesystem of the
+/// pgmoneta host, so a shortfall is only a warning.
+fn check_free_space(backup: &BackupEntry, free_space: Option<u64>, warnings: &mut Vec<String>) {
+    match (backup.restore_size, free_space) {
+        (Some(required), Some(free)) if free < required => warnings.push(format!(
+            "pgmoneta reports {} free on its backup volume but the restore needs {}; \
+             check the free space of the target directory on the pgmoneta host",
+            Utility::format_file_size(free),
+            Utility::format_file_size(required)
+        )),
+        (Some(_), Some(_)) => {}
+        (None, _) => warnings.push(format!(
+            "The restore size of backup '{}' is unknown",
+            backup.backup
+        )),
+        (Some(_), None) => {
+            warnings.push("pgmoneta did not report the free space of its host".to_string())
+        }
+    }
+}
+
+fn plan_steps(
+    request: &RestoreRequest,
+    backup: &BackupEntry,
+    backups: &[BackupEntry],
+    position: &str,
+) -> Vec<String> {
+    let mut steps = vec![format!(
+        "Resolve backup '{}' of server '{}' to '{}'",
+        request.backup_id, request.server, backup.backup
+    )];
+
+    if backup.incremental {
+        let chain = catalog::incremental_chain(backup, backups);
+        steps.push(format!(
+            "Combine incremental backup chain {} into a full data directory",
+            chain.join(" -> ")
+        ));
+    }
+
+    steps.push(format!(
+        "Copy the backup ({}) into '{}'",
+        backup
+            .restore_size
+            .map(Utility::format_file_size)
+            .unwrap_or_else(|| "unknown size".to_string()),
+        request.directory
+    ));
+
+    let targets = recovery_targets(request);
+    if targets.is_empty() {
+        steps.push("Copy the WAL and recover to the first stable checkpoint".to_string());
+    } else {
+        steps.push(format!(
+            "Copy the WAL and configure recovery with position '{position}'"
+        ));
+    }
+
+    if request.primary == Some(true) {
+        steps.push("Configure the restored cluster as a primary".to_string());
+    } else if request.replica == Some(true) {
+        steps.push("Configure the restored cluster as a replica".to_string());
+    }
+
+    if let Some(action) = request.action {
+        steps.push(format!(
+            "Run '{}' once the recovery target is reached",
+            action.as_str()
+        ));
+    }
+
+    steps.push(format!(
+        "Start PostgreSQL on '{}' to replay the WAL",
+        request.directory
+    ));
+    steps
+}
+
+/// Parses a restore target time in the configured timezone.
+///
+/// Accepts `YYYY-MM-DD HH:MM:SS` (optionally with `T` and fractional seconds)
+/// as well as RFC 3339 timestamps carrying an explicit offset.
+pub(super) fn parse_restore_time(time: &str, clock: BackupClock) -> Option<NaiveDateTime> {
+    let trimmed = time.trim();
+    if let Ok(with_offset) = DateTime::parse_from_rfc3339(trimmed)
+        .or_else(|_| DateTime::parse_from_str(trimmed, "%Y-%m-%d %H:%M:%S%#z"))
+    {
+        return Some(clock.localize(with_offset));
+    }
+
+    [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+        catalog::BACKUP_ID_FORMAT,
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(trimmed, format).ok())
+}
+
+pub(super) fn normalize_position(req: &RestoreRequest) -> String {
     let mut result = Vec::new();
     if let Some(current) = req.current
         && current
@@ -154,8 +691,8 @@ fn normalize_position(req: &RestoreRequest) -> String {
     if let Some(timeline) = &req.timeline {
         result.push(format!("timeline={}", timeline));
     }
-    if let Some(action) = &req.action {
-        result.push(format!("action={}", action));
+    if let Some(action) = req.action {
+        result.push(format!("action={}", action.as_str()));
     }
 
     let position = result.join(",");
@@ -205,7 +742,7 @@ mod tests {
             lsn: Some("0/12345678".to_string()),
             inclusive: Some("true".to_string()),
             timeline: Some("2".to_string()),
-            action: Some("pause".to_string()),
+            action: Some(RecoveryAction::Pause),
             primary: None,
             replica: None,
         };
@@ -216,6 +753,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_restore_request_normalizes_backup_id_and_action() {
+        let request: RestoreRequest = serde_json::from_value(serde_json::json!({
+            "username": "user",
+            "server": "server",
+            "backup_id": " LATEST ",
+            "directory": "/tmp/restore",
+            "action": "Promote",
+        }))
+        .unwrap();
+        assert_eq!(request.backup_id, "latest");
+        assert_eq!(request.action, Some(RecoveryAction::Promote));
+        assert!(normalize_position(&request).ends_with("action=promote"));
+    }
+
     #[test]
     fn test_normalize_position_empty() {
         let req = RestoreRequest {
@@ -260,6 +812,211 @@ mod tests {
         assert_eq!(position, "primary,replica");
     }
 
+    fn plan_request() -> RestoreRequest {
+        RestoreRequest {
+            username: "user".to_string(),
+            server: "primary".to_string(),
+            backup_id: "newest".to_string(),
+            directory: "/tmp/restore".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn plan_backups() -> Vec<BackupEntry> {
+        vec![
+            BackupEntry {
+                backup: "20260101000000".to_string(),
+                valid: Some(true),
+                restore_size: Some(1024),
+                elapsed: Some(60.0),
+                start_lsn: Some(0x100),
+                end_lsn: Some(0x200),
+                start_timeline: Some(1),
+                ..Default::default()
+            },
+            BackupEntry {
+                backup: "20260201000000".to_string(),
+                valid: Some(true),
+                restore_size: Some(2048),
+                elapsed: Some(60.0),
+                incremental: true,
+                incremental_parent: Some("20260101000000".to_string()),
+                start_lsn: Some(0x1_0000_0000),
+                end_lsn: Some(0x1_0000_0100),
+                start_timeline: Some(2),
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn plan_clock() -> BackupClock {
+        BackupClock::new(chrono::FixedOffset::east_opt(0))
+    }
+
+    #[test]
+    fn test_plan_restore_tool_metadata() {
+        assert_eq!(PlanRestoreTool::name(), "plan_restore");
+        assert!(PlanRestoreTool::description().unwrap().contains("dry run"));
+        let tools = PgmonetaHandler::tool_router().list_all();
+        assert!(tools.iter().any(|t| t.name == "plan_restore"));
+    }
+
+    #[test]
+    fn test_plan_restore_resolves_symbolic_backup_and_builds_steps() {
+        let backups = plan_backups();
+        let plan = build_restore_plan(&plan_request(), &backups, None, Some(1 << 30), plan_clock());
+
+        assert!(plan.executable, "unexpected errors: {:?}", plan.errors);
+        assert_eq!(plan.backup.as_deref(), Some("20260201000000"));
+        assert_eq!(plan.position, "current");
+        assert!(
+            plan.steps
+                .iter()
+                .any(|step| step.contains("20260101000000 -> 20260201000000"))
+        );
+    }
+
+    #[test]
+    fn test_plan_restore_detects_conflicting_positions() {
+        let request = RestoreRequest {
+            primary: Some(true),
+            replica: Some(true),
+            current: Some(true),
+            xid: Some("123".to_string()),
+            lsn: Some("1/0".to_string()),
+            ..plan_request()
+        };
+        let plan = build_restore_plan(&request, &plan_backups(), None, Some(1 << 30), plan_clock());
+
+        assert!(!plan.executable);
+        let errors = plan.errors.join("\n");
+        assert!(errors.contains("'primary' and 'replica' are mutually exclusive"));
+        assert!(errors.contains("Only one recovery target"));
+        assert!(errors.contains("cannot be combined"));
+    }
+
+    #[test]
+    fn test_plan_restore_rejects_time_before_backup_and_suggests_older_backup() {
+        let request = RestoreRequest {
+            time: Some("2026-01-15 12:00:00".to_string()),
+            ..plan_request()
+        };
+        let plan = build_restore_plan(&request, &plan_backups(), None, Some(1 << 30), plan_clock());
+
+        assert!(!plan.executable);
+        assert!(plan.errors[0].contains("use backup '20260101000000' instead"));
+    }
+
+    #[test]
+    fn test_plan_restore_rejects_future_time_and_early_lsn() {
+        let request = RestoreRequest {
+            time: Some("2099-01-01 00:00:00".to_string()),
+            ..plan_request()
+        };
+        let plan = build_restore_plan(&request, &plan_backups(), None, Some(1 << 30), plan_clock());
+        assert!(plan.errors[0].contains("in the future"));
+
+        let request = RestoreRequest {
+            lsn: Some("0/300".to_string()),
+            ..plan_request()
+        };
+        let plan = build_restore_plan(&request, &plan_backups(), None, Some(1 << 30), plan_clock());
+        assert!(plan.errors[0].contains("precedes the end of backup '20260201000000'"));
+    }
+
+    #[test]
+    fn test_plan_restore_warns_about_targets_past_the_newest_backup() {
+        let request = RestoreRequest {
+            time: Some("2026-03-01 00:00:00".to_string()),
+            ..plan_request()
+        };
+        let plan = build_restore_plan(&request, &plan_backups(), None, Some(1 << 30), plan_clock());
+        assert!(plan.executable, "unexpected errors: {:?}", plan.errors);
+        assert!(plan.warnings.iter().any(|warning| {
+            warning.contains("after the end of the newest backup '20260201000000'")
+                && warning.contains("unchecked")
+        }));
+
+        let request = RestoreRequest {
+            lsn: Some("2/0".to_string()),
+            ..plan_request()
+        };
+        let plan = build_restore_plan(&request, &plan_backups(), None, Some(1 << 30), plan_clock());
+        assert!(plan.executable, "unexpected errors: {:?}", plan.errors);
+        assert!(plan.warnings.iter().any(|warning| {
+            warning.contains("Target LSN 2/0 is after the end of the newest backup")
+                && warning.contains("unchecked")
+        }));
+
+        // Within the newest backup the WAL is known to be available
+        let request = RestoreRequest {
+            lsn: Some("1/100".to_string()),
+            ..plan_request()
+        };
+        let plan = build_restore_plan(&request, &plan_backups(), None, Some(1 << 30), plan_clock());
+        assert!(
+            !plan
+                .warnings
+                .iter()
+                .any(|warning| warning.contains("unchecked"))
+        );
+    }
+
+    #[test]
+    fn test_plan_restore_checks_free_space_and_timeline() {
+        let request = RestoreRequest {
+            timeline: Some("1".to_string()),
+            ..plan_request()
+        };
+        let plan = build_restore_plan(&request, &plan_backups(), None, Some(100), plan_clock());
+
+        assert!(!plan.executable);
+        assert_eq!(plan.errors.len(), 1);
+        assert!(plan.errors[0].contains("Target timeline 1 precedes timeline 2"));
+        assert!(plan.warnings.join("\n").contains("needs 2.00 KB"));
+
+        // pgmoneta restores on its own host, so a shortfall does not block the restore
+        let plan = build_restore_plan(
+            &plan_request(),
+            &plan_backups(),
+            None,
+            Some(100),
+            plan_clock(),
+        );
+        assert!(plan.executable, "unexpected errors: {:?}", plan.errors);
+        assert_eq!(plan.free_space.as_deref(), Some("100 B"));
+    }
+
+    #[test]
+    fn test_plan_restore_reports_missing_backup() {
+        let request = RestoreRequest {
+            backup_id: "20250101000000".to_string(),
+            ..plan_request()
+        };
+        let plan = build_restore_plan(&request, &plan_backups(), None, None, plan_clock());
+        assert!(!plan.executable);
+        assert!(plan.errors[0].contains("was not found"));
+
+        let plan = build_restore_plan(&plan_request(), &[], None, None, plan_clock());
+        assert!(plan.errors[0].contains("has no backups"));
+    }
+
+    #[test]
+    fn test_parse_restore_time_formats() {
+        let clock = plan_clock();
+        assert!(parse_restore_time("2026-07-06 11:30:00", clock).is_some());
+        assert!(parse_restore_time("2026-07-06T11:30:00.123", clock).is_some());
+        assert_eq!(
+            parse_restore_time("2026-07-06T11:30:00+02:00", clock),
+            parse_restore_time("2026-07-06 09:30:00", clock)
+        );
+        assert_eq!(
+            parse_restore_time("2026-07-06 11:30:00+02:00", clock),
+            parse_restore_time("2026-07-06 09:30:00", clock)
+        );
+        assert!(parse_restore_time("yesterday", clock).is_none());
+    }
+
     #[test]
     fn test_parse_restore_success_response() {
         let response = r#"{"Outcome": {"Command": 3, "Status": "OK"}, "Server": "primary", "Position": "current,primary"}"#;
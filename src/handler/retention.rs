@@ -14,19 +14,31 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use super::PgmonetaHandler;
+use super::catalog::{self, BackupClock, BackupEntry};
+use super::events::{self, Event, EventKind, EventSource};
+use super::metrics::metric_values;
+use super::validation;
 use crate::client::PgmonetaClient;
+use crate::utils::Utility;
+use chrono::{Datelike, NaiveDateTime, TimeDelta};
 use rmcp::ErrorData as McpError;
 use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
 use rmcp::model::JsonObject;
 use rmcp::schemars;
+use serde::Serialize;
+use serde_json::{Map, Value};
 
 #[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
 pub struct RetentionRequest {
     pub username: String,
     pub server: String,
+    /// Backup identifier: a 14 digit timestamp or "newest", "latest" or "oldest"
+    #[schemars(pattern(validation::BACKUP_ID_PATTERN))]
+    #[serde(deserialize_with = "validation::deserialize_backup_id")]
     pub backup_id: String,
     pub cascade: Option<bool>,
 }
@@ -70,15 +82,30 @@ impl AsyncTool<PgmonetaHandler> for RetainBackupTool {
         _service: &PgmonetaHandler,
         request: RetentionRequest,
     ) -> Result<String, McpError> {
+        validation::validate_backup_id("backup_id", &request.backup_id)?;
         let cascade = request.cascade.unwrap_or(false);
-        let result: String = PgmonetaClient::request_retain(
+        let result = PgmonetaClient::request_retain(
             &request.username,
             &request.server,
             &request.backup_id,
             cascade,
         )
-        .await
-        .map_err(|e| McpError::internal_error(format!("Failed to retain backup: {:?}", e), None))?;
+        .await;
+        events::emit_outcome(
+            Event::new(
+                EventKind::BackupRetained,
+                EventSource::Tool,
+                &request.server,
+            )
+            .backup(&request.backup_id)
+            .username(&request.username)
+            .detail("Cascade", cascade),
+            None,
+            &result,
+        );
+        let result: String = result.map_err(|e| {
+            McpError::internal_error(format!("Failed to retain backup: {:?}", e), None)
+        })?;
         PgmonetaHandler::generate_call_tool_result_string(&result)
     }
 }
@@ -116,21 +143,388 @@ impl AsyncTool<PgmonetaHandler> for ExpungeBackupTool {
         _service: &PgmonetaHandler,
         request: RetentionRequest,
     ) -> Result<String, McpError> {
+        validation::validate_backup_id("backup_id", &request.backup_id)?;
         let cascade = request.cascade.unwrap_or(false);
-        let result: String = PgmonetaClient::request_expunge(
+        let result = PgmonetaClient::request_expunge(
             &request.username,
             &request.server,
             &request.backup_id,
             cascade,
         )
-        .await
-        .map_err(|e| {
+        .await;
+        events::emit_outcome(
+            Event::new(
+                EventKind::BackupExpunged,
+                EventSource::Tool,
+                &request.server,
+            )
+            .backup(&request.backup_id)
+            .username(&request.username)
+            .detail("Cascade", cascade),
+            None,
+            &result,
+        );
+        let result: String = result.map_err(|e| {
             McpError::internal_error(format!("Failed to expunge backup: {:?}", e), None)
         })?;
         PgmonetaHandler::generate_call_tool_result_string(&result)
     }
 }
 
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct SimulateRetentionRequest {
+    pub username: String,
+    pub server: String,
+    /// Candidate policy in the pgmoneta syntax "days,weeks,months,years", e.g. "7" or "7,4,12,5"
+    pub retention: String,
+}
+
+/// Tool for previewing the effect of a retention policy without changing anything.
+pub struct SimulateRetentionTool;
+
+impl ToolBase for SimulateRetentionTool {
+    type Parameter = SimulateRetentionRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "simulate_retention".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "Simulate a candidate retention policy against the current backups of a server \
+            without changing anything on the server. \
+            The policy uses the pgmoneta retention syntax \"days,weeks,months,years\", e.g. \"7\" or \"7,4,12,5\", \
+            where empty fields are disabled. \
+            Reports which backups would be kept or expunged, the space freed \
+            and the incremental chains that would break. \
+            The username has to be one of the pgmoneta admins to be able to access pgmoneta."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for SimulateRetentionTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: SimulateRetentionRequest,
+    ) -> Result<String, McpError> {
+        let policy = RetentionPolicy::parse(&request.retention)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+        let backups = catalog::fetch_backups(&request.username, &request.server).await?;
+        let simulation = simulate_retention(
+            &request.server,
+            &policy,
+            &backups,
+            BackupClock::configured().now(),
+        );
+        serde_json::to_string(&simulation).map_err(|e| {
+            McpError::internal_error(
+                format!("Failed to serialize retention simulation: {:?}", e),
+                None,
+            )
+        })
+    }
+}
+
+/// The longest retention a policy field may describe.
+const MAX_RETENTION_YEARS: u32 = 100;
+
+/// A pgmoneta retention policy: `days,weeks,months,years`, where empty fields are disabled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct RetentionPolicy {
+    pub days: Option<u32>,
+    pub weeks: Option<u32>,
+    pub months: Option<u32>,
+    pub years: Option<u32>,
+}
+
+impl RetentionPolicy {
+    /// The largest value of each field, as the number of its unit in `MAX_RETENTION_YEARS`.
+    const LIMITS: [(u32, &'static str); 4] = [
+        (MAX_RETENTION_YEARS * 366, "days"),
+        (MAX_RETENTION_YEARS * 53, "weeks"),
+        (MAX_RETENTION_YEARS * 12, "months"),
+        (MAX_RETENTION_YEARS, "years"),
+    ];
+
+    /// Parses a policy in the pgmoneta `retention` syntax, e.g. `7` or `7, 4, 12, 5`.
+    pub fn parse(retention: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = retention.split(',').map(str::trim).collect();
+        if fields.len() > 4 {
+            return Err(format!(
+                "Invalid retention '{retention}': expected at most 4 fields (days,weeks,months,years)"
+            ));
+        }
+
+        let mut values = [None; 4];
+        for (index, field) in fields.iter().enumerate() {
+            if field.is_empty() {
+                continue;
+            }
+            let value = field.parse::<u32>().map_err(|_| {
+                format!("Invalid retention '{retention}': '{field}' is not a non-negative number")
+            })?;
+            let (limit, unit) = Self::LIMITS[index];
+            if value > limit {
+                return Err(format!(
+                    "Invalid retention '{retention}': '{field}' exceeds {limit} {unit} ({MAX_RETENTION_YEARS} years)"
+                ));
+            }
+            values[index] = Some(value);
+        }
+
+        let policy = Self {
+            days: values[0],
+            weeks: values[1],
+            months: values[2],
+            years: values[3],
+        };
+        if policy == Self::default() {
+            return Err(format!(
+                "Invalid retention '{retention}': at least one field has to be set"
+            ));
+        }
+        Ok(policy)
+    }
+
+    /// The retention policy of a server, from its detailed status or the metrics.
+    pub fn configured(
+        server: &str,
+        status: Option<&Map<String, Value>>,
+        metrics: Option<&str>,
+    ) -> Option<Self> {
+        let field = |status_field: &str, parameter: &str| -> Option<u32> {
+            let from_status = status
+                .and_then(|status| status.get(status_field))
+                .and_then(Value::as_i64);
+            let from_metrics = || {
+                metrics.and_then(|metrics| {
+                    metric_values(
+                        metrics,
+                        "pgmoneta_retention_server",
+                        &[("name", server), ("parameter", parameter)],
+                    )
+                    .first()
+                    .map(|value| *value as i64)
+                })
+            };
+            from_status
+                .or_else(from_metrics)
+                .filter(|value| *value > 0)
+                .map(|value| value as u32)
+        };
+
+        let policy = Self {
+            days: field("RetentionDays", "days"),
+            weeks: field("RetentionWeeks", "weeks"),
+            months: field("RetentionMonths", "months"),
+            years: field("RetentionYears", "years"),
+        };
+        (policy != Self::default()).then_some(policy)
+    }
+
+    /// The reasons a policy keeps each backup, keyed by backup identifier.
+    ///
+    /// Backups newer than `days` are kept, and so is the first backup of each of
+    /// the last `weeks` weeks, `months` months and `years` years. Backups marked
+    /// as retained are always kept.
+    pub fn keep_reasons(
+        &self,
+        backups: &[BackupEntry],
+        now: NaiveDateTime,
+    ) -> BTreeMap<String, Vec<String>> {
+        let mut reasons: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut keep = |backup: &BackupEntry, reason: String| {
+            reasons
+                .entry(backup.backup.clone())
+                .or_default()
+                .push(reason);
+        };
+
+        for backup in backups.iter().filter(|backup| backup.keep) {
+            keep(backup, "Marked as retained".to_string());
+        }
+
+        if let Some(days) = self.days {
+            // A configured policy is not bounded by `parse`; past the
+            // representable range every backup lies within the window.
+            let cutoff = TimeDelta::try_days(i64::from(days))
+                .and_then(|window| now.checked_sub_signed(window))
+                .unwrap_or(NaiveDateTime::MIN);
+            for backup in backups
+                .iter()
+                .filter(|backup| backup.timestamp().is_some_and(|time| time >= cutoff))
+            {
+                keep(backup, format!("Within the last {days} day(s)"));
+            }
+        }
+
+        for (count, unit) in [
+            (self.weeks, "week"),
+            (self.months, "month"),
+            (self.years, "year"),
+        ] {
+            let Some(count) = count else {
+                continue;
+            };
+            let current = period_index(unit, now);
+            let mut seen: Vec<i64> = Vec::new();
+            let mut ordered: Vec<&BackupEntry> = backups.iter().collect();
+            ordered.sort_by(|a, b| a.backup.cmp(&b.backup));
+            for backup in ordered {
+                let Some(period) = backup.timestamp().map(|time| period_index(unit, time)) else {
+                    continue;
+                };
+                let distance = current - period;
+                if seen.contains(&period) || distance < 0 || distance >= count as i64 {
+                    continue;
+                }
+                seen.push(period);
+                keep(
+                    backup,
+                    format!("First backup of its {unit} within the last {count} {unit}(s)"),
+                );
+            }
+        }
+
+        reasons
+    }
+}
+
+impl std::fmt::Display for RetentionPolicy {
+    /// Formats the policy in the pgmoneta `retention` syntax, e.g. `7,4`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields = [self.days, self.weeks, self.months, self.years]
+            .iter()
+            .map(|value| value.map(|value| value.to_string()).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join(",");
+        f.write_str(fields.trim_end_matches(','))
+    }
+}
+
+/// A consecutive index of the week (starting on Monday), month or year of a timestamp.
+fn period_index(unit: &str, time: NaiveDateTime) -> i64 {
+    let date = time.date();
+    match unit {
+        "week" => {
+            (date.num_days_from_ce() - date.weekday().num_days_from_monday() as i32) as i64 / 7
+        }
+        "month" => date.year() as i64 * 12 + date.month0() as i64,
+        _ => date.year() as i64,
+    }
+}
+
+/// The outcome of applying a retention policy to a backup catalog.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct RetentionSimulation {
+    server: String,
+    policy: RetentionPolicy,
+    keep: Vec<KeptBackup>,
+    expunge: Vec<ExpungedBackup>,
+    space_freed: String,
+    space_freed_bytes: u64,
+    broken_chains: Vec<BrokenChain>,
+    summary: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct KeptBackup {
+    backup: String,
+    reasons: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ExpungedBackup {
+    backup: String,
+    size: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct BrokenChain {
+    backup: String,
+    missing_parents: Vec<String>,
+}
+
+fn simulate_retention(
+    server: &str,
+    policy: &RetentionPolicy,
+    backups: &[BackupEntry],
+    now: NaiveDateTime,
+) -> RetentionSimulation {
+    let mut reasons = policy.keep_reasons(backups, now);
+
+    let mut keep = Vec::new();
+    let mut expunge = Vec::new();
+    let mut space_freed_bytes = 0;
+    for backup in backups {
+        match reasons.remove(&backup.backup) {
+            Some(reasons) => keep.push(KeptBackup {
+                backup: backup.backup.clone(),
+                reasons,
+            }),
+            None => {
+                space_freed_bytes += backup.backup_size.unwrap_or(0);
+                expunge.push(ExpungedBackup {
+                    backup: backup.backup.clone(),
+                    size: backup.backup_size.map(Utility::format_file_size),
+                });
+            }
+        }
+    }
+
+    let broken_chains: Vec<BrokenChain> = keep
+        .iter()
+        .filter_map(|kept| {
+            let backup = backups.iter().find(|backup| backup.backup == kept.backup)?;
+            let missing_parents: Vec<String> = catalog::incremental_chain(backup, backups)
+                .into_iter()
+                .filter(|id| expunge.iter().any(|expunged| &expunged.backup == id))
+                .collect();
+            (!missing_parents.is_empty()).then(|| BrokenChain {
+                backup: kept.backup.clone(),
+                missing_parents,
+            })
+        })
+        .collect();
+
+    let mut summary = format!(
+        "The policy keeps {} of {} backup(s) and expunges {}, freeing {}.",
+        keep.len(),
+        backups.len(),
+        expunge.len(),
+        Utility::format_file_size(space_freed_bytes)
+    );
+    if !broken_chains.is_empty() {
+        summary.push_str(&format!(
+            " {} kept incremental backup(s) would lose a parent and become unrestorable.",
+            broken_chains.len()
+        ));
+    }
+
+    RetentionSimulation {
+        server: server.to_string(),
+        policy: *policy,
+        keep,
+        expunge,
+        space_freed: Utility::format_file_size(space_freed_bytes),
+        space_freed_bytes,
+        broken_chains,
+        summary,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -215,4 +609,157 @@ mod tests {
         let outcome = parsed["Outcome"].as_object().unwrap();
         assert_eq!(outcome["Error"], "Expunge: no backup available");
     }
+
+    fn backup(id: &str, size: u64) -> BackupEntry {
+        BackupEntry {
+            backup: id.to_string(),
+            valid: Some(true),
+            backup_size: Some(size),
+            ..Default::default()
+        }
+    }
+
+    fn now() -> NaiveDateTime {
+        NaiveDateTime::parse_from_str("20260715120000", catalog::BACKUP_ID_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn test_simulate_retention_tool_metadata() {
+        assert_eq!(SimulateRetentionTool::name(), "simulate_retention");
+        assert!(
+            SimulateRetentionTool::description()
+                .unwrap()
+                .contains("without changing anything")
+        );
+        let tools = PgmonetaHandler::tool_router().list_all();
+        assert!(tools.iter().any(|t| t.name == "simulate_retention"));
+    }
+
+    #[test]
+    fn test_parse_retention_policy() {
+        assert_eq!(
+            RetentionPolicy::parse("7").unwrap(),
+            RetentionPolicy {
+                days: Some(7),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            RetentionPolicy::parse("7, 4, ,2").unwrap(),
+            RetentionPolicy {
+                days: Some(7),
+                weeks: Some(4),
+                months: None,
+                years: Some(2),
+            }
+        );
+        assert!(RetentionPolicy::parse("").is_err());
+        assert!(RetentionPolicy::parse("7,x").is_err());
+        assert!(RetentionPolicy::parse("1,2,3,4,5").is_err());
+    }
+
+    #[test]
+    fn test_retention_policy_bounds_its_fields() {
+        let error = RetentionPolicy::parse("4000000000").unwrap_err();
+        assert!(error.contains("100 years"), "{error}");
+        assert!(RetentionPolicy::parse(",,1201").is_err());
+        assert!(RetentionPolicy::parse(",,,100").is_ok());
+
+        let backups = vec![backup("20260701080000", 1)];
+        let policy = RetentionPolicy {
+            days: Some(u32::MAX),
+            ..Default::default()
+        };
+        let reasons = policy.keep_reasons(&backups, now());
+        assert!(reasons.contains_key("20260701080000"));
+    }
+
+    #[test]
+    fn test_retention_policy_display() {
+        for retention in ["7", "7,4", "7,4,,2", ",,12"] {
+            assert_eq!(
+                RetentionPolicy::parse(retention).unwrap().to_string(),
+                retention
+            );
+        }
+    }
+
+    #[test]
+    fn test_simulate_retention_by_days() {
+        let backups = vec![
+            BackupEntry {
+                keep: true,
+                ..backup("20260601000000", 100)
+            },
+            backup("20260705000000", 200),
+            backup("20260709000000", 300),
+            backup("20260714000000", 400),
+        ];
+        let policy = RetentionPolicy::parse("7").unwrap();
+
+        let simulation = simulate_retention("primary", &policy, &backups, now());
+
+        let kept: Vec<&str> = simulation.keep.iter().map(|k| k.backup.as_str()).collect();
+        assert_eq!(
+            kept,
+            vec!["20260601000000", "20260709000000", "20260714000000"]
+        );
+        assert_eq!(simulation.keep[0].reasons, vec!["Marked as retained"]);
+        assert_eq!(simulation.expunge.len(), 1);
+        assert_eq!(simulation.expunge[0].backup, "20260705000000");
+        assert_eq!(simulation.space_freed_bytes, 200);
+        assert!(simulation.broken_chains.is_empty());
+        assert!(simulation.summary.contains("keeps 3 of 4 backup(s)"));
+    }
+
+    #[test]
+    fn test_simulate_retention_by_weeks_and_months() {
+        let backups = vec![
+            backup("20260415000000", 1),
+            backup("20260501000000", 1),
+            backup("20260520000000", 1),
+            backup("20260629000000", 1),
+            backup("20260701000000", 1),
+            backup("20260707000000", 1),
+        ];
+        let policy = RetentionPolicy::parse(",2,3").unwrap();
+
+        let reasons = policy.keep_reasons(&backups, now());
+
+        let kept: Vec<&str> = reasons.keys().map(String::as_str).collect();
+        // Weeks keep 07-07 (week of 07-06); months keep the first backup of May, June and July
+        assert_eq!(
+            kept,
+            vec![
+                "20260501000000",
+                "20260629000000",
+                "20260701000000",
+                "20260707000000"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_simulate_retention_reports_broken_chains() {
+        let backups = vec![
+            backup("20260701000000", 100),
+            BackupEntry {
+                incremental: true,
+                incremental_parent: Some("20260701000000".to_string()),
+                ..backup("20260712000000", 10)
+            },
+        ];
+        let policy = RetentionPolicy::parse("7").unwrap();
+
+        let simulation = simulate_retention("primary", &policy, &backups, now());
+
+        assert_eq!(
+            simulation.broken_chains,
+            vec![BrokenChain {
+                backup: "20260712000000".to_string(),
+                missing_parents: vec!["20260701000000".to_string()],
+            }]
+        );
+        assert!(simulation.summary.contains("become unrestorable"));
+    }
 }
@@ -0,0 +1,1767 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Runbooks: declarative multi-step procedures executed by the server.
+//!
+//! A runbook is a YAML definition of typed parameters and steps, each calling
+//! one of the tools of this server. Step arguments may use `{{parameter}}` and
+//! `{{steps.<step>.output.<path>}}` placeholders, `when` conditions skip a
+//! step, `fail_if` conditions fail it, and `on_failure` either aborts the run,
+//! continues it, or runs a branch of steps before stopping.
+//!
+//! Every runbook is exposed as an MCP tool of the same name. The steps are
+//! reported as MCP progress notifications when the client asks for them, and
+//! every run is appended to the audit trail listed by `runbook_audit`.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+
+use super::PgmonetaHandler;
+use super::catalog::BackupClock;
+use super::validation;
+use crate::configuration::CONFIG;
+use crate::utils::{SafeFileWriter, SnapshotWriter};
+use anyhow::{anyhow, bail};
+use config::{Config, FileFormat};
+use once_cell::sync::OnceCell;
+use rmcp::ErrorData as McpError;
+use rmcp::handler::server::router::tool::{AsyncTool, ToolBase, ToolRoute, ToolRouter};
+use rmcp::handler::server::tool::ToolCallContext;
+use rmcp::model::{
+    CallToolRequestParams, CallToolResult, Content, JsonObject, ProgressNotificationParam,
+    ProgressToken, Tool,
+};
+use rmcp::schemars;
+use rmcp::service::{RequestContext, RoleServer};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+/// The runbook library, loaded once at startup by [`init`].
+static RUNBOOKS: OnceCell<RunbookLibrary> = OnceCell::new();
+
+/// The audit trail, newest last, loaded by [`init`].
+static AUDIT: Mutex<Vec<RunbookRun>> = Mutex::new(Vec::new());
+
+/// Writes the snapshots of [`AUDIT`] taken by [`record`].
+static AUDIT_SNAPSHOTS: SnapshotWriter = SnapshotWriter::new();
+
+const BUILTIN_RUNBOOKS: [(&str, &str); 1] = [(
+    "verified_backup.yaml",
+    include_str!("runbooks/verified_backup.yaml"),
+)];
+
+/// The file in `state_directory` holding the audit trail.
+const AUDIT_FILE: &str = "runbook_audit.json";
+
+/// The number of runs kept in the audit trail.
+const AUDIT_LIMIT: usize = 200;
+
+/// The number of runs listed by `runbook_audit` unless requested otherwise.
+const DEFAULT_AUDIT_RUNS: usize = 20;
+
+/// A runbook as written in a YAML file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Runbook {
+    pub name: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub parameters: Vec<RunbookParameter>,
+    pub steps: Vec<RunbookStep>,
+}
+
+/// A typed parameter of a [`Runbook`], exposed as a tool argument.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RunbookParameter {
+    pub name: String,
+    #[serde(default, rename = "type")]
+    pub kind: ParameterType,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    /// The value used when the argument is not given.
+    #[serde(default)]
+    pub default: Option<Value>,
+    /// The accepted values of a string parameter; any value when empty.
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+/// The JSON type of a [`RunbookParameter`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+}
+
+impl ParameterType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Integer => "integer",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+        }
+    }
+}
+
+/// A step of a [`Runbook`]: a call of one tool.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RunbookStep {
+    pub name: String,
+    pub tool: String,
+    #[serde(default)]
+    pub arguments: Map<String, Value>,
+    /// Conditions that all have to hold for the step to run.
+    #[serde(default)]
+    pub when: Conditions,
+    /// Conditions any of which fails the step once it ran.
+    #[serde(default)]
+    pub fail_if: Conditions,
+    #[serde(default)]
+    pub on_failure: OnFailure,
+}
+
+/// One condition or a list of conditions.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(from = "OneOrMany")]
+pub struct Conditions(pub Vec<String>);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl From<OneOrMany> for Conditions {
+    fn from(value: OneOrMany) -> Self {
+        match value {
+            OneOrMany::One(condition) => Self(vec![condition]),
+            OneOrMany::Many(conditions) => Self(conditions),
+        }
+    }
+}
+
+/// What happens when a step fails.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum OnFailure {
+    Action(FailureAction),
+    /// Run these steps, then stop the run.
+    Branch(Vec<RunbookStep>),
+}
+
+impl Default for OnFailure {
+    fn default() -> Self {
+        Self::Action(FailureAction::Abort)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureAction {
+    /// Stop the run.
+    Abort,
+    /// Go on with the next step; the failure does not fail the run.
+    Continue,
+}
+
+/// The outcome of a step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+impl StepStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// The result of a step of a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StepResult {
+    pub step: String,
+    pub tool: String,
+    /// The failed step whose `on_failure` branch this step belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    pub status: StepStatus,
+    pub seconds: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The tool output; not kept in the audit trail.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Value>,
+}
+
+/// A run of a runbook.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RunbookRun {
+    pub runbook: String,
+    pub username: String,
+    /// `succeeded` or `failed`.
+    pub status: StepStatus,
+    pub started: String,
+    pub finished: String,
+    pub parameters: Map<String, Value>,
+    pub steps: Vec<StepResult>,
+}
+
+/// Calls the tools of the steps and reports the progress of a run.
+pub(crate) trait StepRunner {
+    /// Calls `tool`, returning its output or error message.
+    async fn call(&self, tool: &str, arguments: Map<String, Value>) -> Result<String, String>;
+
+    /// Reports that `progress` out of `total` steps are done.
+    async fn progress(&self, progress: usize, total: usize, message: String);
+}
+
+impl Runbook {
+    /// Parses and validates a YAML runbook.
+    pub fn parse(yaml: &str) -> anyhow::Result<Self> {
+        let runbook = Config::builder()
+            .add_source(config::File::from_str(yaml, FileFormat::Yaml))
+            .build()?
+            .try_deserialize::<Runbook>()?;
+        runbook.validate()?;
+        Ok(runbook)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        let tools: BTreeSet<String> = PgmonetaHandler::builtin_tool_router()
+            .list_all()
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect();
+        if !is_identifier(&self.name) {
+            bail!(
+                "Invalid runbook name '{}': use letters, digits and underscores",
+                self.name
+            );
+        }
+        if tools.contains(&self.name) {
+            bail!("Runbook '{}' has the name of a built-in tool", self.name);
+        }
+        if self.steps.is_empty() {
+            bail!("Runbook '{}' has no steps", self.name);
+        }
+
+        let mut parameters = Vec::new();
+        for parameter in &self.parameters {
+            if !is_identifier(&parameter.name) || parameter.name == "username" {
+                bail!(
+                    "Invalid parameter name '{}' in runbook '{}'",
+                    parameter.name,
+                    self.name
+                );
+            }
+            if parameters.contains(&parameter.name.as_str()) {
+                bail!(
+                    "Duplicate parameter '{}' in runbook '{}'",
+                    parameter.name,
+                    self.name
+                );
+            }
+            if let Some(default) = &parameter.default {
+                parameter
+                    .coerce(default)
+                    .map_err(|e| anyhow!("Runbook '{}': default {e}", self.name))?;
+            }
+            if !parameter.values.is_empty() && parameter.kind != ParameterType::String {
+                bail!(
+                    "Runbook '{}': only string parameters can list values, not '{}'",
+                    self.name,
+                    parameter.name
+                );
+            }
+            parameters.push(parameter.name.as_str());
+        }
+
+        let mut names = BTreeSet::new();
+        let mut earlier: Vec<&str> = Vec::new();
+        for step in &self.steps {
+            self.validate_step(step, &tools, &parameters, &earlier, &mut names)?;
+            earlier.push(&step.name);
+            if let OnFailure::Branch(branch) = &step.on_failure {
+                let mut branch_earlier = earlier.clone();
+                for branch_step in branch {
+                    if matches!(branch_step.on_failure, OnFailure::Branch(_)) {
+                        bail!(
+                            "Runbook '{}': the on_failure step '{}' cannot have its own on_failure steps",
+                            self.name,
+                            branch_step.name
+                        );
+                    }
+                    self.validate_step(
+                        branch_step,
+                        &tools,
+                        &parameters,
+                        &branch_earlier,
+                        &mut names,
+                    )?;
+                    branch_earlier.push(&branch_step.name);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_step<'a>(
+        &self,
+        step: &'a RunbookStep,
+        tools: &BTreeSet<String>,
+        parameters: &[&str],
+        earlier: &[&str],
+        names: &mut BTreeSet<&'a str>,
+    ) -> anyhow::Result<()> {
+        let context =
+            |e: anyhow::Error| anyhow!("Runbook '{}', step '{}': {e}", self.name, step.name);
+        if !is_identifier(&step.name) {
+            bail!(
+                "Invalid step name '{}' in runbook '{}'",
+                step.name,
+                self.name
+            );
+        }
+        if !names.insert(&step.name) {
+            bail!("Duplicate step '{}' in runbook '{}'", step.name, self.name);
+        }
+        if !tools.contains(&step.tool) {
+            return Err(context(anyhow!("unknown tool '{}'", step.tool)));
+        }
+
+        for reference in references(&Value::Object(step.arguments.clone())) {
+            check_reference(reference.0, parameters, earlier).map_err(context)?;
+        }
+        for condition in &step.when.0 {
+            let condition = Condition::parse(condition).map_err(context)?;
+            check_reference(&condition.reference, parameters, earlier).map_err(context)?;
+        }
+        let mut with_self = earlier.to_vec();
+        with_self.push(&step.name);
+        for condition in &step.fail_if.0 {
+            let condition = Condition::parse(condition).map_err(context)?;
+            check_reference(&condition.reference, parameters, &with_self).map_err(context)?;
+        }
+        Ok(())
+    }
+
+    /// The MCP description of this runbook as a tool.
+    pub fn to_tool(&self) -> Tool {
+        let mut properties = Map::new();
+        properties.insert("username".to_string(), json!({"type": "string"}));
+        let mut required = vec![Value::from("username")];
+        for parameter in &self.parameters {
+            let mut property = Map::new();
+            property.insert("type".to_string(), Value::from(parameter.kind.as_str()));
+            if let Some(description) = &parameter.description {
+                property.insert("description".to_string(), Value::from(description.clone()));
+            }
+            if let Some(default) = &parameter.default {
+                property.insert("default".to_string(), default.clone());
+            }
+            if !parameter.values.is_empty() {
+                property.insert("enum".to_string(), Value::from(parameter.values.clone()));
+            }
+            properties.insert(parameter.name.clone(), Value::Object(property));
+            if parameter.required {
+                required.push(Value::from(parameter.name.clone()));
+            }
+        }
+        let mut schema = JsonObject::new();
+        schema.insert("type".to_string(), Value::from("object"));
+        schema.insert("properties".to_string(), Value::Object(properties));
+        schema.insert("required".to_string(), Value::Array(required));
+
+        let steps: Vec<String> = self
+            .steps
+            .iter()
+            .map(|step| match &step.on_failure {
+                OnFailure::Branch(branch) => format!(
+                    "{} (on failure: {})",
+                    step.name,
+                    branch
+                        .iter()
+                        .map(|step| step.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                OnFailure::Action(_) => step.name.clone(),
+            })
+            .collect();
+        let description = format!(
+            "{} Runbook steps: {}. Step progress is reported as progress notifications, \
+            and the run is recorded in the audit trail listed by runbook_audit. \
+            The username has to be one of the pgmoneta admins to be able to access pgmoneta.",
+            self.description
+                .as_deref()
+                .map(|description| description.trim_end_matches('.').to_string() + ".")
+                .unwrap_or_default(),
+            steps.join(", ")
+        );
+        let tool = Tool::new(
+            self.name.clone(),
+            description.trim_start().to_string(),
+            schema,
+        );
+        match &self.title {
+            Some(title) => tool.with_title(title),
+            None => tool,
+        }
+    }
+
+    /// Checks the arguments of a call against the parameters, applying defaults.
+    pub fn bind(&self, arguments: Option<&JsonObject>) -> Result<Map<String, Value>, McpError> {
+        let mut values = Map::new();
+        for parameter in &self.parameters {
+            let given = arguments
+                .and_then(|arguments| arguments.get(&parameter.name))
+                .filter(|value| !value.is_null());
+            let value = match (given, &parameter.default) {
+                (Some(value), _) => parameter
+                    .coerce(value)
+                    .map_err(|e| McpError::invalid_params(e, None))?,
+                (None, Some(default)) => parameter
+                    .coerce(default)
+                    .map_err(|e| McpError::invalid_params(e, None))?,
+                (None, None) if parameter.required => {
+                    return Err(McpError::invalid_params(
+                        format!(
+                            "Runbook '{}' requires the argument '{}'",
+                            self.name, parameter.name
+                        ),
+                        None,
+                    ));
+                }
+                (None, None) => continue,
+            };
+            values.insert(parameter.name.clone(), value);
+        }
+        Ok(values)
+    }
+
+    /// Runs the steps as `username` with the bound `parameters`.
+    pub(crate) async fn run<R: StepRunner>(
+        &self,
+        username: &str,
+        parameters: Map<String, Value>,
+        runner: &R,
+    ) -> RunbookRun {
+        let clock = BackupClock::configured();
+        let started = clock.format(clock.now());
+        let total = self.steps.len();
+        let mut steps: Vec<StepResult> = Vec::new();
+        let mut failed = false;
+        tracing::info!("Starting runbook {} as {}", self.name, username);
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let result = run_step(step, None, username, &parameters, &steps, runner).await;
+            let status = result.status;
+            runner
+                .progress(
+                    index + 1,
+                    total,
+                    format!("{}: {}", step.name, status.as_str()),
+                )
+                .await;
+            steps.push(result);
+            if status != StepStatus::Failed {
+                continue;
+            }
+            match &step.on_failure {
+                OnFailure::Action(FailureAction::Continue) => continue,
+                OnFailure::Action(FailureAction::Abort) => {}
+                OnFailure::Branch(branch) => {
+                    let total = index + 1 + branch.len();
+                    for (offset, branch_step) in branch.iter().enumerate() {
+                        let result = run_step(
+                            branch_step,
+                            Some(&step.name),
+                            username,
+                            &parameters,
+                            &steps,
+                            runner,
+                        )
+                        .await;
+                        let status = result.status;
+                        runner
+                            .progress(
+                                index + 2 + offset,
+                                total,
+                                format!("{}: {}", branch_step.name, status.as_str()),
+                            )
+                            .await;
+                        steps.push(result);
+                        if status == StepStatus::Failed
+                            && matches!(
+                                branch_step.on_failure,
+                                OnFailure::Action(FailureAction::Abort)
+                            )
+                        {
+                            break;
+                        }
+                    }
+                }
+            }
+            failed = true;
+            break;
+        }
+
+        let run = RunbookRun {
+            runbook: self.name.clone(),
+            username: username.to_string(),
+            status: if failed {
+                StepStatus::Failed
+            } else {
+                StepStatus::Succeeded
+            },
+            started,
+            finished: clock.format(clock.now()),
+            parameters,
+            steps,
+        };
+        tracing::info!("Runbook {} {}", self.name, run.status.as_str());
+        run
+    }
+}
+
+impl RunbookParameter {
+    /// Converts a value to the type of the parameter, e.g. `"7"` to `7`.
+    fn coerce(&self, value: &Value) -> Result<Value, String> {
+        let invalid = || {
+            format!(
+                "Invalid {} '{}': expected {}",
+                self.name,
+                scalar_text(value),
+                match self.kind {
+                    ParameterType::Integer => "an integer",
+                    ParameterType::Number => "a number",
+                    ParameterType::Boolean => "true or false",
+                    ParameterType::String => "a string",
+                }
+            )
+        };
+        let coerced = match (self.kind, value) {
+            (ParameterType::String, Value::String(_)) => value.clone(),
+            (ParameterType::String, Value::Number(_) | Value::Bool(_)) => {
+                Value::from(scalar_text(value))
+            }
+            (ParameterType::Integer, Value::Number(number)) if number.is_i64() => value.clone(),
+            (ParameterType::Integer, Value::String(text)) => text
+                .trim()
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| invalid())?,
+            (ParameterType::Number, Value::Number(_)) => value.clone(),
+            (ParameterType::Number, Value::String(text)) => text
+                .trim()
+                .parse::<f64>()
+                .map(Value::from)
+                .map_err(|_| invalid())?,
+            (ParameterType::Boolean, Value::Bool(_)) => value.clone(),
+            (ParameterType::Boolean, Value::String(text)) => {
+                match text.trim().to_ascii_lowercase().as_str() {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(invalid()),
+        };
+        if let Value::String(text) = &coerced
+            && !self.values.is_empty()
+            && !self.values.contains(text)
+        {
+            return Err(format!(
+                "Unsupported {} '{}'. Supported values: {}",
+                self.name,
+                text,
+                self.values.join(", ")
+            ));
+        }
+        Ok(coerced)
+    }
+}
+
+/// Runs one step, unless its `when` conditions do not hold.
+async fn run_step<R: StepRunner>(
+    step: &RunbookStep,
+    branch: Option<&str>,
+    username: &str,
+    parameters: &Map<String, Value>,
+    earlier: &[StepResult],
+    runner: &R,
+) -> StepResult {
+    let mut result = StepResult {
+        step: step.name.clone(),
+        tool: step.tool.clone(),
+        branch: branch.map(str::to_string),
+        status: StepStatus::Skipped,
+        seconds: 0.0,
+        error: None,
+        output: None,
+    };
+    let scope = Scope {
+        parameters,
+        steps: earlier,
+    };
+    if !step.when.0.iter().all(|condition| scope.holds(condition)) {
+        tracing::debug!("Skipping runbook step {}", step.name);
+        return result;
+    }
+
+    let mut arguments = match render(&Value::Object(step.arguments.clone()), &scope) {
+        Value::Object(arguments) => arguments,
+        _ => Map::new(),
+    };
+    arguments.retain(|_, value| !value.is_null());
+    arguments
+        .entry("username")
+        .or_insert_with(|| Value::from(username));
+
+    let start = Instant::now();
+    let outcome = runner.call(&step.tool, arguments).await;
+    result.seconds = start.elapsed().as_secs_f64();
+    match outcome {
+        Ok(text) => {
+            let output = serde_json::from_str(&text).unwrap_or(Value::String(text));
+            result.error = pgmoneta_failure(&output);
+            result.output = Some(output);
+        }
+        Err(message) => result.error = Some(message),
+    }
+
+    if result.error.is_none() {
+        let mut with_self = earlier.to_vec();
+        with_self.push(result.clone());
+        let scope = Scope {
+            parameters,
+            steps: &with_self,
+        };
+        if let Some(condition) = step
+            .fail_if
+            .0
+            .iter()
+            .find(|condition| scope.holds(condition))
+        {
+            result.error = Some(format!("fail_if '{condition}' holds"));
+        }
+    }
+    result.status = if result.error.is_some() {
+        StepStatus::Failed
+    } else {
+        StepStatus::Succeeded
+    };
+    tracing::info!(
+        "Runbook step {} ({}) {}{}",
+        step.name,
+        step.tool,
+        result.status.as_str(),
+        result
+            .error
+            .as_deref()
+            .map(|error| format!(": {error}"))
+            .unwrap_or_default()
+    );
+    result
+}
+
+/// The failure pgmoneta reported in a translated tool output, if any.
+fn pgmoneta_failure(output: &Value) -> Option<String> {
+    let outcome = output.get("Outcome")?;
+    if outcome.get("Status").and_then(Value::as_bool) != Some(false) {
+        return None;
+    }
+    Some(format!(
+        "pgmoneta reported a failure: {}",
+        outcome
+            .get("Error")
+            .map(scalar_text)
+            .unwrap_or_else(|| "unknown error".to_string())
+    ))
+}
+
+/// The values placeholders and conditions refer to.
+struct Scope<'a> {
+    parameters: &'a Map<String, Value>,
+    steps: &'a [StepResult],
+}
+
+impl Scope<'_> {
+    /// Resolves `parameter` or `steps.<step>.<status|error|seconds|output[.path]>`.
+    fn resolve(&self, reference: &str) -> Option<Value> {
+        let mut segments = reference.split('.');
+        let first = segments.next()?;
+        if first != "steps" {
+            return self.parameters.get(first).cloned();
+        }
+        let name = segments.next()?;
+        let step = self.steps.iter().rev().find(|step| step.step == name)?;
+        match segments.next()? {
+            "status" => Some(Value::from(step.status.as_str())),
+            "error" => step.error.clone().map(Value::from),
+            "seconds" => Some(Value::from(step.seconds)),
+            "output" => {
+                let mut value = step.output.as_ref()?;
+                for segment in segments {
+                    value = match value {
+                        Value::Object(object) => object.get(segment)?,
+                        Value::Array(array) => array.get(segment.parse::<usize>().ok()?)?,
+                        _ => return None,
+                    };
+                }
+                Some(value.clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn holds(&self, condition: &str) -> bool {
+        match Condition::parse(condition) {
+            Ok(condition) => condition.holds(self.resolve(&condition.reference).as_ref()),
+            Err(e) => {
+                tracing::warn!("Ignoring invalid condition '{}': {}", condition, e);
+                false
+            }
+        }
+    }
+}
+
+/// A condition: `reference`, `!reference` or `reference <operator> literal`.
+#[derive(Debug, Clone, PartialEq)]
+struct Condition {
+    negated: bool,
+    reference: String,
+    comparison: Option<(Operator, Value)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Contains,
+}
+
+impl Condition {
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        let text = text.trim();
+        let (reference, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let (negated, reference) = match reference.strip_prefix('!') {
+            Some(reference) => (true, reference),
+            None => (false, reference),
+        };
+        if reference.is_empty() {
+            bail!("Invalid condition '{text}': missing reference");
+        }
+        let rest = rest.trim();
+        if rest.is_empty() {
+            return Ok(Self {
+                negated,
+                reference: reference.to_string(),
+                comparison: None,
+            });
+        }
+        if negated {
+            bail!("Invalid condition '{text}': '!' cannot be combined with an operator");
+        }
+        let (operator, literal) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let operator = match operator {
+            "==" => Operator::Equal,
+            "!=" => Operator::NotEqual,
+            "<" => Operator::Less,
+            "<=" => Operator::LessOrEqual,
+            ">" => Operator::Greater,
+            ">=" => Operator::GreaterOrEqual,
+            "contains" => Operator::Contains,
+            other => bail!(
+                "Invalid condition '{text}': unsupported operator '{other}'. \
+                Supported operators: ==, !=, <, <=, >, >=, contains"
+            ),
+        };
+        let literal = literal.trim();
+        if literal.is_empty() {
+            bail!("Invalid condition '{text}': missing value");
+        }
+        Ok(Self {
+            negated,
+            reference: reference.to_string(),
+            comparison: Some((operator, parse_literal(literal))),
+        })
+    }
+
+    fn holds(&self, value: Option<&Value>) -> bool {
+        let value = value.unwrap_or(&Value::Null);
+        let Some((operator, literal)) = &self.comparison else {
+            return is_truthy(value) != self.negated;
+        };
+        let number = |value: &Value| match value {
+            Value::Number(number) => number.as_f64(),
+            Value::String(text) => text.trim().parse::<f64>().ok(),
+            _ => None,
+        };
+        let ordering = number(value)
+            .zip(number(literal))
+            .and_then(|(a, b)| a.partial_cmp(&b));
+        match operator {
+            Operator::Equal => values_equal(value, literal),
+            Operator::NotEqual => !values_equal(value, literal),
+            Operator::Less => ordering.is_some_and(|ordering| ordering.is_lt()),
+            Operator::LessOrEqual => ordering.is_some_and(|ordering| ordering.is_le()),
+            Operator::Greater => ordering.is_some_and(|ordering| ordering.is_gt()),
+            Operator::GreaterOrEqual => ordering.is_some_and(|ordering| ordering.is_ge()),
+            Operator::Contains => match value {
+                Value::String(text) => text.contains(&scalar_text(literal)),
+                Value::Array(items) => items.iter().any(|item| values_equal(item, literal)),
+                Value::Object(object) => object.contains_key(&scalar_text(literal)),
+                _ => false,
+            },
+        }
+    }
+}
+
+fn parse_literal(literal: &str) -> Value {
+    for quote in ['"', '\''] {
+        if let Some(text) = literal
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return Value::from(text);
+        }
+    }
+    match literal {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        "null" => Value::Null,
+        _ => serde_json::from_str::<serde_json::Number>(literal)
+            .map(Value::Number)
+            .unwrap_or_else(|_| Value::from(literal)),
+    }
+}
+
+fn values_equal(value: &Value, literal: &Value) -> bool {
+    match (value, literal) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Null, Value::Null) => true,
+        (_, Value::Null) | (Value::Null, _) => false,
+        (a, b) => scalar_text(a) == scalar_text(b),
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(boolean) => *boolean,
+        Value::Number(number) => number.as_f64() != Some(0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(object) => !object.is_empty(),
+    }
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A `{{reference | default}}` placeholder: the reference and the default.
+type Placeholder<'a> = (&'a str, Option<&'a str>);
+
+/// The `{{reference | default}}` placeholders of the strings in `value`.
+fn references(value: &Value) -> Vec<Placeholder<'_>> {
+    match value {
+        Value::String(text) => placeholders(text)
+            .into_iter()
+            .map(|(_, _, placeholder)| placeholder)
+            .collect(),
+        Value::Array(items) => items.iter().flat_map(references).collect(),
+        Value::Object(object) => object.values().flat_map(references).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The placeholders of `text`: start, end and `(reference, default)`.
+fn placeholders(text: &str) -> Vec<(usize, usize, Placeholder<'_>)> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find("{{") {
+        let start = offset + start;
+        let Some(length) = text[start + 2..].find("}}") else {
+            break;
+        };
+        let inner = &text[start + 2..start + 2 + length];
+        let placeholder = match inner.split_once('|') {
+            Some((reference, default)) => (reference.trim(), Some(default.trim())),
+            None => (inner.trim(), None),
+        };
+        let end = start + length + 4;
+        found.push((start, end, placeholder));
+        offset = end;
+    }
+    found
+}
+
+/// Replaces the placeholders in the strings of `value`.
+///
+/// A string that is a single placeholder takes the referenced value with its
+/// type; otherwise the values are inserted as text.
+fn render(value: &Value, scope: &Scope) -> Value {
+    match value {
+        Value::String(text) => {
+            let found = placeholders(text);
+            let resolve = |(reference, default): Placeholder| {
+                scope
+                    .resolve(reference)
+                    .filter(|value| !value.is_null())
+                    .or_else(|| default.map(parse_literal))
+            };
+            if let [(0, end, placeholder)] = found[..]
+                && end == text.len()
+            {
+                return resolve(placeholder).unwrap_or(Value::Null);
+            }
+            let mut rendered = String::with_capacity(text.len());
+            let mut last = 0;
+            for (start, end, placeholder) in found {
+                rendered.push_str(&text[last..start]);
+                if let Some(value) = resolve(placeholder) {
+                    rendered.push_str(&scalar_text(&value));
+                }
+                last = end;
+            }
+            rendered.push_str(&text[last..]);
+            Value::String(rendered)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| render(item, scope)).collect()),
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), render(value, scope)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Checks that a reference names a parameter or an earlier step.
+fn check_reference(reference: &str, parameters: &[&str], earlier: &[&str]) -> anyhow::Result<()> {
+    let segments: Vec<&str> = reference.split('.').collect();
+    match segments[..] {
+        [parameter] if parameters.contains(&parameter) || parameter == "username" => Ok(()),
+        ["steps", step, field, ..] if earlier.contains(&step) => match (field, segments.len()) {
+            ("output", _) | ("status" | "error" | "seconds", 3) => Ok(()),
+            _ => bail!(
+                "invalid reference '{reference}': use steps.{step}.status, error, seconds or output"
+            ),
+        },
+        ["steps", step, ..] => bail!("'{reference}' does not refer to an earlier step '{step}'"),
+        _ => bail!("'{reference}' is not a parameter"),
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The available runbooks, by name.
+#[derive(Debug, Clone, Default)]
+pub struct RunbookLibrary {
+    runbooks: BTreeMap<String, Arc<Runbook>>,
+}
+
+impl RunbookLibrary {
+    /// The built-in runbooks only.
+    pub fn builtin() -> Self {
+        let mut library = Self::default();
+        for (file, yaml) in BUILTIN_RUNBOOKS {
+            let runbook = Runbook::parse(yaml)
+                .unwrap_or_else(|e| panic!("Invalid built-in runbook {file}: {e:?}"));
+            library.insert(runbook);
+        }
+        library
+    }
+
+    /// The built-in runbooks plus the `*.yaml` and `*.yml` files of `directory`.
+    pub fn load(directory: Option<&Path>) -> anyhow::Result<Self> {
+        let mut library = Self::builtin();
+        let Some(directory) = directory else {
+            return Ok(library);
+        };
+
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(directory).map_err(|e| {
+            anyhow!(
+                "Unable to read runbooks directory {}: {e}",
+                directory.display()
+            )
+        })? {
+            let path = entry?.path();
+            let is_yaml = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| matches!(extension, "yaml" | "yml"));
+            if is_yaml && path.is_file() {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        for path in files {
+            let yaml = std::fs::read_to_string(&path)?;
+            let runbook = Runbook::parse(&yaml)
+                .map_err(|e| anyhow!("Invalid runbook {}: {e}", path.display()))?;
+            tracing::debug!(runbook = %runbook.name, path = %path.display(), "loaded runbook");
+            library.insert(runbook);
+        }
+        Ok(library)
+    }
+
+    fn insert(&mut self, runbook: Runbook) {
+        self.runbooks
+            .insert(runbook.name.clone(), Arc::new(runbook));
+    }
+
+    /// The runbooks, sorted by name.
+    pub fn runbooks(&self) -> impl Iterator<Item = &Arc<Runbook>> {
+        self.runbooks.values()
+    }
+}
+
+/// Loads the runbook library from `directory` and the audit trail from
+/// `state_directory`; returns the number of runbooks.
+pub fn init(directory: Option<&Path>) -> anyhow::Result<usize> {
+    let library = RunbookLibrary::load(directory)?;
+    let count = library.runbooks.len();
+    RUNBOOKS
+        .set(library)
+        .map_err(|_| anyhow!("Runbook library already initialized"))?;
+    *AUDIT.lock().unwrap_or_else(PoisonError::into_inner) =
+        load_audit(state_directory().as_deref());
+    Ok(count)
+}
+
+/// The runbook library, or the built-in runbooks when [`init`] was not called.
+pub fn library() -> &'static RunbookLibrary {
+    RUNBOOKS.get_or_init(RunbookLibrary::builtin)
+}
+
+/// A tool route per runbook.
+pub(crate) fn routes() -> Vec<ToolRoute<PgmonetaHandler>> {
+    library()
+        .runbooks()
+        .map(|runbook| {
+            let runbook = Arc::clone(runbook);
+            ToolRoute::new_dyn(runbook.to_tool(), move |context| {
+                let runbook = Arc::clone(&runbook);
+                Box::pin(async move { invoke(&runbook, context).await })
+            })
+        })
+        .collect()
+}
+
+async fn invoke(
+    runbook: &Runbook,
+    context: ToolCallContext<'_, PgmonetaHandler>,
+) -> Result<CallToolResult, McpError> {
+    let username = context
+        .arguments
+        .as_ref()
+        .and_then(|arguments| arguments.get("username"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    validation::validate_admin(&username)?;
+    let parameters = runbook.bind(context.arguments.as_ref())?;
+
+    let runner = ToolRunner {
+        service: context.service,
+        progress_token: context.request_context.meta.get_progress_token(),
+        context: context.request_context,
+        router: PgmonetaHandler::builtin_tool_router(),
+    };
+    let run = runbook.run(&username, parameters, &runner).await;
+    record(&run);
+    let output = serde_json::to_string(&run).map_err(|e| {
+        McpError::internal_error(format!("Failed to serialize runbook run: {:?}", e), None)
+    })?;
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
+/// Runs the steps through the tool router of the current MCP request.
+struct ToolRunner<'a> {
+    service: &'a PgmonetaHandler,
+    context: RequestContext<RoleServer>,
+    router: &'static ToolRouter<PgmonetaHandler>,
+    progress_token: Option<ProgressToken>,
+}
+
+impl StepRunner for ToolRunner<'_> {
+    async fn call(&self, tool: &str, mut arguments: Map<String, Value>) -> Result<String, String> {
+        if let Some(schema) = self.router.get(tool) {
+            coerce_arguments(&mut arguments, &schema.input_schema);
+        }
+        let request = CallToolRequestParams::new(tool.to_string()).with_arguments(arguments);
+        let result = self
+            .router
+            .call(ToolCallContext::new(
+                self.service,
+                request,
+                self.context.clone(),
+            ))
+            .await
+            .map_err(|e| e.message.to_string())?;
+        let text = result
+            .content
+            .iter()
+            .filter_map(|content| content.as_text())
+            .map(|content| content.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if result.is_error == Some(true) {
+            Err(text)
+        } else {
+            Ok(text)
+        }
+    }
+
+    async fn progress(&self, progress: usize, total: usize, message: String) {
+        let Some(token) = &self.progress_token else {
+            return;
+        };
+        let notification = ProgressNotificationParam::new(token.clone(), progress as f64)
+            .with_total(total as f64)
+            .with_message(message);
+        if let Err(e) = self.context.peer.notify_progress(notification).await {
+            tracing::debug!("Failed to send runbook progress: {}", e);
+        }
+    }
+}
+
+/// Converts rendered values to the types of the tool arguments, e.g. a numeric
+/// backup identifier to a string.
+fn coerce_arguments(arguments: &mut Map<String, Value>, schema: &JsonObject) {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    for (name, value) in arguments.iter_mut() {
+        let types: Vec<&str> = match properties.get(name).and_then(|p| p.get("type")) {
+            Some(Value::String(kind)) => vec![kind.as_str()],
+            Some(Value::Array(kinds)) => kinds.iter().filter_map(Value::as_str).collect(),
+            _ => continue,
+        };
+        let coerced = match &*value {
+            Value::Number(_) | Value::Bool(_) if types.contains(&"string") => {
+                Some(Value::from(scalar_text(value)))
+            }
+            Value::String(text) if types.contains(&"boolean") => match text.as_str() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            Value::String(text) if types.contains(&"integer") => {
+                text.trim().parse::<i64>().ok().map(Value::from)
+            }
+            _ => None,
+        };
+        if let Some(coerced) = coerced {
+            *value = coerced;
+        }
+    }
+}
+
+/// Appends a run, without the tool outputs, to the audit trail.
+fn record(run: &RunbookRun) {
+    let mut entry = run.clone();
+    for step in &mut entry.steps {
+        step.output = None;
+    }
+    let (generation, snapshot) = {
+        let mut audit = AUDIT.lock().unwrap_or_else(PoisonError::into_inner);
+        audit.push(entry);
+        let excess = audit.len().saturating_sub(AUDIT_LIMIT);
+        audit.drain(..excess);
+        (AUDIT_SNAPSHOTS.next(), audit.clone())
+    };
+    if let Some(directory) = state_directory() {
+        AUDIT_SNAPSHOTS.write(generation, move || save_audit(&directory, &snapshot));
+    }
+}
+
+/// The audited runs, newest first, optionally of one runbook only.
+fn audit(runbook: Option<&str>, limit: usize) -> Vec<RunbookRun> {
+    AUDIT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .rev()
+        .filter(|run| runbook.is_none_or(|runbook| run.runbook == runbook))
+        .take(limit)
+        .cloned()
+        .collect()
+}
+
+fn state_directory() -> Option<String> {
+    CONFIG
+        .get()
+        .and_then(|config| config.pgmoneta_mcp.state_directory.clone())
+}
+
+fn load_audit(directory: Option<&str>) -> Vec<RunbookRun> {
+    let Some(directory) = directory else {
+        return Vec::new();
+    };
+    let path = Path::new(directory).join(AUDIT_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            tracing::warn!(
+                "Ignoring unreadable runbook audit trail {}: {}",
+                path.display(),
+                e
+            );
+            Vec::new()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to read runbook audit trail {}: {}",
+                path.display(),
+                e
+            );
+            Vec::new()
+        }
+    }
+}
+
+fn save_audit(directory: &str, audit: &[RunbookRun]) {
+    let result = serde_json::to_string_pretty(audit)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| {
+            SafeFileWriter::new(directory)
+                .allowed_extensions(vec!["json"])
+                .write(AUDIT_FILE, &contents)
+        });
+    if let Err(e) = result {
+        tracing::warn!("Failed to save runbook audit trail in {}: {}", directory, e);
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct RunbookAuditRequest {
+    pub username: String,
+    /// Only list the runs of this runbook
+    #[serde(default)]
+    pub runbook: Option<String>,
+    /// The number of runs to list, newest first (default 20)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct RunbookAudit {
+    runs: Vec<RunbookRun>,
+}
+
+/// Tool for listing the audit trail of the runbook runs.
+pub struct RunbookAuditTool;
+
+impl ToolBase for RunbookAuditTool {
+    type Parameter = RunbookAuditRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "runbook_audit".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "List the audit trail of the runbook runs, newest first: who ran which runbook \
+            with which parameters, and the status, duration and error of every step. \
+            The username has to be one of the pgmoneta admins."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for RunbookAuditTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: RunbookAuditRequest,
+    ) -> Result<String, McpError> {
+        validation::validate_admin(&request.username)?;
+        let runs = audit(
+            request.runbook.as_deref(),
+            request.limit.unwrap_or(DEFAULT_AUDIT_RUNS),
+        );
+        serde_json::to_string(&RunbookAudit { runs }).map_err(|e| {
+            McpError::internal_error(format!("Failed to serialize runbook audit: {:?}", e), None)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Answers tool calls from canned outputs and records the calls.
+    #[derive(Default)]
+    struct FakeRunner {
+        outputs: BTreeMap<String, Result<Value, String>>,
+        calls: RefCell<Vec<(String, Map<String, Value>)>>,
+        progress: RefCell<Vec<(usize, usize, String)>>,
+    }
+
+    impl FakeRunner {
+        fn with(mut self, tool: &str, output: Result<Value, &str>) -> Self {
+            self.outputs
+                .insert(tool.to_string(), output.map_err(str::to_string));
+            self
+        }
+    }
+
+    impl StepRunner for FakeRunner {
+        async fn call(&self, tool: &str, arguments: Map<String, Value>) -> Result<String, String> {
+            self.calls.borrow_mut().push((tool.to_string(), arguments));
+            match self.outputs.get(tool) {
+                Some(Ok(output)) => Ok(output.to_string()),
+                Some(Err(error)) => Err(error.clone()),
+                None => Ok(json!({"Outcome": {"Status": true}}).to_string()),
+            }
+        }
+
+        async fn progress(&self, progress: usize, total: usize, message: String) {
+            self.progress.borrow_mut().push((progress, total, message));
+        }
+    }
+
+    fn verified_backup() -> Runbook {
+        Runbook::parse(BUILTIN_RUNBOOKS[0].1).expect("builtin runbook should parse")
+    }
+
+    fn parameters() -> Map<String, Value> {
+        json!({"server": "primary", "directory": "/archive", "verify_directory": "/tmp"})
+            .as_object()
+            .cloned()
+            .unwrap()
+    }
+
+    fn statuses(run: &RunbookRun) -> Vec<(&str, StepStatus)> {
+        run.steps
+            .iter()
+            .map(|step| (step.step.as_str(), step.status))
+            .collect()
+    }
+
+    #[test]
+    fn test_builtin_runbooks() {
+        let library = RunbookLibrary::builtin();
+        let names: Vec<&str> = library
+            .runbooks()
+            .map(|runbook| runbook.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["verified_backup"]);
+
+        let runbook = verified_backup();
+        assert_eq!(runbook.parameters.len(), 3);
+        assert_eq!(runbook.parameters[2].default, Some(json!("/tmp")));
+        assert_eq!(runbook.steps[1].fail_if.0.len(), 1);
+        match &runbook.steps[1].on_failure {
+            OnFailure::Branch(branch) => {
+                assert_eq!(branch[0].name, "annotate_failed");
+                assert!(matches!(
+                    branch[0].on_failure,
+                    OnFailure::Action(FailureAction::Continue)
+                ));
+            }
+            other => panic!("unexpected on_failure {other:?}"),
+        }
+        assert!(matches!(
+            runbook.steps[0].on_failure,
+            OnFailure::Action(FailureAction::Abort)
+        ));
+        assert_eq!(runbook.steps[4].arguments["current"], json!(true));
+
+        let tool = runbook.to_tool();
+        assert_eq!(tool.name, "verified_backup");
+        let schema = Value::Object((*tool.input_schema).clone());
+        assert_eq!(
+            schema["required"],
+            json!(["username", "server", "directory"])
+        );
+        assert_eq!(schema["properties"]["verify_directory"]["default"], "/tmp");
+        let description = tool.description.unwrap();
+        assert!(description.contains("verify (on failure: annotate_failed)"));
+    }
+
+    #[test]
+    fn test_runbook_validation() {
+        let runbook = |steps: &str| {
+            format!(
+                "name: check\nparameters:\n  - name: server\n    required: true\nsteps:\n{steps}"
+            )
+        };
+        let error = |yaml: String| format!("{:#}", Runbook::parse(&yaml).unwrap_err());
+
+        assert!(
+            error(runbook("  - name: one\n    tool: no_such_tool\n"))
+                .contains("unknown tool 'no_such_tool'")
+        );
+        assert!(
+            error(runbook(
+                "  - name: one\n    tool: ping\n  - name: one\n    tool: ping\n"
+            ))
+            .contains("Duplicate step 'one'")
+        );
+        assert!(
+            error(runbook(
+                "  - name: one\n    tool: status\n    arguments:\n      server: \"{{steps.two.output}}\"\n  - name: two\n    tool: ping\n"
+            ))
+            .contains("does not refer to an earlier step 'two'")
+        );
+        assert!(
+            error(runbook(
+                "  - name: one\n    tool: ping\n    when: missing == 1\n"
+            ))
+            .contains("'missing' is not a parameter")
+        );
+        assert!(
+            error(runbook(
+                "  - name: one\n    tool: ping\n    when: server ~ 1\n"
+            ))
+            .contains("unsupported operator '~'")
+        );
+        assert!(
+            error("name: ping\nsteps:\n  - name: one\n    tool: ping\n".to_string())
+                .contains("has the name of a built-in tool")
+        );
+        assert!(
+            error(
+                "name: check\nparameters:\n  - name: count\n    type: integer\n    default: many\nsteps:\n  - name: one\n    tool: ping\n"
+                    .to_string()
+            )
+            .contains("Invalid count 'many': expected an integer")
+        );
+        assert!(
+            error(runbook(
+                "  - name: one\n    tool: ping\n    on_failure:\n      - name: two\n        tool: ping\n        on_failure:\n          - name: three\n            tool: ping\n"
+            ))
+            .contains("cannot have its own on_failure steps")
+        );
+
+        let yaml =
+            runbook("  - name: one\n    tool: ping\n    fail_if: steps.one.status != succeeded\n");
+        assert!(Runbook::parse(&yaml).is_ok());
+    }
+
+    #[test]
+    fn test_conditions() {
+        let steps = vec![StepResult {
+            step: "verify".to_string(),
+            tool: "verify".to_string(),
+            branch: None,
+            status: StepStatus::Succeeded,
+            seconds: 2.5,
+            error: None,
+            output: Some(json!({
+                "Outcome": {"Status": true},
+                "Response": {"Failed": [], "AllFiles": ["a", "b"], "Total": 12, "Server": "primary"}
+            })),
+        }];
+        let parameters = parameters();
+        let scope = Scope {
+            parameters: &parameters,
+            steps: &steps,
+        };
+
+        assert!(!scope.holds("steps.verify.output.Response.Failed"));
+        assert!(scope.holds("!steps.verify.output.Response.Failed"));
+        assert!(scope.holds("steps.verify.output.Response.AllFiles"));
+        assert!(scope.holds("steps.verify.output.Response.AllFiles.1 == b"));
+        assert!(scope.holds("steps.verify.output.Response.AllFiles contains 'a'"));
+        assert!(scope.holds("steps.verify.output.Response.Total >= 12"));
+        assert!(scope.holds("steps.verify.output.Response.Total < 12.5"));
+        assert!(scope.holds("steps.verify.status == succeeded"));
+        assert!(scope.holds("steps.verify.seconds > 2"));
+        assert!(scope.holds("steps.verify.error == null"));
+        assert!(scope.holds("server == \"primary\""));
+        assert!(scope.holds("server != replica"));
+        assert!(!scope.holds("steps.verify.output.Response.Missing"));
+        assert!(!scope.holds("server > 1"));
+
+        assert!(Condition::parse("!server == 1").is_err());
+        assert!(Condition::parse("server ==").is_err());
+        assert_eq!(
+            Condition::parse("steps.a.output.Count <= 3").unwrap(),
+            Condition {
+                negated: false,
+                reference: "steps.a.output.Count".to_string(),
+                comparison: Some((Operator::LessOrEqual, json!(3))),
+            }
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let steps = vec![StepResult {
+            step: "backup".to_string(),
+            tool: "backup".to_string(),
+            branch: None,
+            status: StepStatus::Succeeded,
+            seconds: 1.0,
+            error: None,
+            output: Some(json!({"Response": {"Backup": 20260101120000_u64}})),
+        }];
+        let parameters = parameters();
+        let scope = Scope {
+            parameters: &parameters,
+            steps: &steps,
+        };
+        let arguments = json!({
+            "server": "{{server}}",
+            "backup_id": "{{ steps.backup.output.Response.Backup | newest }}",
+            "label": "{{server}}-{{steps.backup.output.Response.Backup}}",
+            "missing": "{{steps.backup.output.Response.Missing}}",
+            "fallback": "{{steps.backup.output.Response.Missing | newest}}",
+            "count": 3,
+        });
+
+        assert_eq!(
+            render(&arguments, &scope),
+            json!({
+                "server": "primary",
+                "backup_id": 20260101120000_u64,
+                "label": "primary-20260101120000",
+                "missing": null,
+                "fallback": "newest",
+                "count": 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_bind_parameters() {
+        let runbook = Runbook::parse(
+            "name: check\nparameters:\n  - name: server\n    required: true\n    values: [primary, replica]\n  - name: limit\n    type: integer\n    default: 5\n  - name: force\n    type: boolean\nsteps:\n  - name: one\n    tool: ping\n",
+        )
+        .unwrap();
+        let arguments = |value: Value| value.as_object().cloned().unwrap();
+
+        let bound = runbook
+            .bind(Some(&arguments(
+                json!({"username": "admin", "server": "primary", "force": "true"}),
+            )))
+            .unwrap();
+        assert_eq!(
+            Value::Object(bound),
+            json!({"server": "primary", "limit": 5, "force": true})
+        );
+
+        let bound = runbook
+            .bind(Some(&arguments(json!({"server": "replica", "limit": "7"}))))
+            .unwrap();
+        assert_eq!(bound["limit"], json!(7));
+
+        let error = runbook.bind(Some(&arguments(json!({})))).unwrap_err();
+        assert!(error.message.contains("requires the argument 'server'"));
+        let error = runbook
+            .bind(Some(&arguments(json!({"server": "other"}))))
+            .unwrap_err();
+        assert!(error.message.contains("Supported values: primary, replica"));
+        let error = runbook
+            .bind(Some(&arguments(json!({"server": "primary", "limit": "x"}))))
+            .unwrap_err();
+        assert!(error.message.contains("Invalid limit 'x'"));
+    }
+
+    #[tokio::test]
+    async fn test_run_succeeds() {
+        let runner = FakeRunner::default().with(
+            "backup",
+            Ok(json!({"Outcome": {"Status": true}, "Response": {"Backup": "20260101120000"}})),
+        );
+        let run = verified_backup().run("admin", parameters(), &runner).await;
+
+        assert_eq!(run.status, StepStatus::Succeeded);
+        assert_eq!(
+            statuses(&run),
+            vec![
+                ("backup", StepStatus::Succeeded),
+                ("verify", StepStatus::Succeeded),
+                ("annotate", StepStatus::Succeeded),
+                ("retain", StepStatus::Succeeded),
+                ("archive", StepStatus::Succeeded),
+            ]
+        );
+        let calls = runner.calls.borrow();
+        let (tool, arguments) = &calls[4];
+        assert_eq!(tool, "archive");
+        assert_eq!(
+            Value::Object(arguments.clone()),
+            json!({
+                "username": "admin",
+                "server": "primary",
+                "backup_id": "20260101120000",
+                "directory": "/archive",
+                "current": true,
+            })
+        );
+        assert_eq!(
+            runner.progress.borrow()[1],
+            (2, 5, "verify: succeeded".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_failure_branch() {
+        let runner = FakeRunner::default()
+            .with(
+                "verify",
+                Ok(json!({"Outcome": {"Status": true}, "Response": {"Failed": ["base/1"]}})),
+            )
+            .with("annotate_backup", Err("annotation failed"));
+        let run = verified_backup().run("admin", parameters(), &runner).await;
+
+        assert_eq!(run.status, StepStatus::Failed);
+        assert_eq!(
+            statuses(&run),
+            vec![
+                ("backup", StepStatus::Succeeded),
+                ("verify", StepStatus::Failed),
+                ("annotate_failed", StepStatus::Failed),
+            ]
+        );
+        assert_eq!(
+            run.steps[1].error.as_deref(),
+            Some("fail_if 'steps.verify.output.Response.Failed' holds")
+        );
+        assert_eq!(run.steps[2].branch.as_deref(), Some("verify"));
+        assert_eq!(run.steps[2].error.as_deref(), Some("annotation failed"));
+        // Without a backup identifier in the output the default is used
+        assert_eq!(runner.calls.borrow()[1].1["backup_id"], json!("newest"));
+        assert_eq!(
+            runner.progress.borrow()[2],
+            (3, 3, "annotate_failed: failed".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_skip_continue_and_abort() {
+        let runbook = Runbook::parse(
+            "name: check\nparameters:\n  - name: deep\n    type: boolean\n    default: false\nsteps:\n  - name: ping\n    tool: ping\n    on_failure: continue\n  - name: details\n    tool: status\n    when: deep\n  - name: status\n    tool: status\n    when: steps.ping.status == failed\n  - name: info\n    tool: get_info\n",
+        )
+        .unwrap();
+        let runner = FakeRunner::default()
+            .with("ping", Err("connection refused"))
+            .with(
+                "status",
+                Ok(json!({"Outcome": {"Status": false, "Error": "Network error"}})),
+            );
+        let parameters = runbook.bind(None).unwrap();
+        let run = runbook.run("admin", parameters, &runner).await;
+
+        assert_eq!(run.status, StepStatus::Failed);
+        assert_eq!(
+            statuses(&run),
+            vec![
+                ("ping", StepStatus::Failed),
+                ("details", StepStatus::Skipped),
+                ("status", StepStatus::Failed),
+            ]
+        );
+        assert_eq!(
+            run.steps[2].error.as_deref(),
+            Some("pgmoneta reported a failure: Network error")
+        );
+        assert_eq!(runner.calls.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_coerce_arguments() {
+        let schema = json!({
+            "properties": {
+                "backup_id": {"type": "string"},
+                "current": {"type": ["boolean", "null"]},
+                "limit": {"type": ["integer", "null"]},
+            }
+        });
+        let mut arguments =
+            json!({"backup_id": 20260101120000_u64, "current": "true", "limit": "3", "other": 1})
+                .as_object()
+                .cloned()
+                .unwrap();
+        coerce_arguments(&mut arguments, schema.as_object().unwrap());
+        assert_eq!(
+            Value::Object(arguments),
+            json!({"backup_id": "20260101120000", "current": true, "limit": 3, "other": 1})
+        );
+    }
+
+    #[test]
+    fn test_load_runbooks_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        std::fs::write(
+            directory.path().join("check.yml"),
+            "name: check\nsteps:\n  - name: ping\n    tool: ping\n",
+        )
+        .unwrap();
+        std::fs::write(directory.path().join("notes.txt"), "ignored").unwrap();
+        let library = RunbookLibrary::load(Some(directory.path())).unwrap();
+        let names: Vec<&str> = library
+            .runbooks()
+            .map(|runbook| runbook.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["check", "verified_backup"]);
+
+        std::fs::write(
+            directory.path().join("broken.yaml"),
+            "name: broken\nsteps: []\n",
+        )
+        .unwrap();
+        let error = RunbookLibrary::load(Some(directory.path())).unwrap_err();
+        assert!(format!("{error:#}").contains("has no steps"));
+    }
+}
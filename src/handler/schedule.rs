@@ -0,0 +1,317 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::PgmonetaHandler;
+use super::catalog::BackupClock;
+use super::validation;
+use crate::configuration::ScheduleConfiguration;
+use crate::scheduler::{self, RunRecord};
+use rmcp::ErrorData as McpError;
+use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
+use rmcp::model::JsonObject;
+use rmcp::schemars;
+use serde::Serialize;
+
+/// The number of past runs listed per schedule unless requested otherwise.
+const DEFAULT_HISTORY: usize = 5;
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct ScheduleListRequest {
+    pub username: String,
+    /// The number of past runs to list per schedule, newest first (default 5)
+    #[serde(default)]
+    pub history: Option<usize>,
+}
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct ScheduleRunNowRequest {
+    pub username: String,
+    pub server: String,
+}
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct SchedulePauseRequest {
+    pub username: String,
+    pub server: String,
+    /// true to pause the schedule (default), false to resume it
+    #[serde(default)]
+    pub paused: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ScheduleList {
+    schedules: Vec<ScheduleStatus>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ScheduleStatus {
+    server: String,
+    cron: String,
+    verify: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verify_directory: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    archive_directory: Option<String>,
+    jitter: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    paused: bool,
+    running: bool,
+    next_run: Option<String>,
+    history: Vec<RunRecord>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct RunStarted {
+    server: String,
+    status: &'static str,
+    message: String,
+}
+
+/// Tool for listing the backup schedules and their recent runs.
+pub struct ScheduleListTool;
+
+impl ToolBase for ScheduleListTool {
+    type Parameter = ScheduleListRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "schedule_list".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "List the backup schedules of the [schedule:<server>] configuration sections: \
+            the cron expression, whether the backup is verified and archived, whether the schedule \
+            is paused or running, the next run and the most recent runs with the outcome of every \
+            step (backup, verify, archive). \
+            The username has to be one of the pgmoneta admins."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for ScheduleListTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: ScheduleListRequest,
+    ) -> Result<String, McpError> {
+        validation::validate_admin(&request.username)?;
+        let history = request.history.unwrap_or(DEFAULT_HISTORY);
+        let schedules = scheduler::schedules()
+            .iter()
+            .map(|schedule| schedule_status(schedule, history))
+            .collect();
+        to_json(&ScheduleList { schedules })
+    }
+}
+
+/// Tool for starting a run of a backup schedule immediately.
+pub struct ScheduleRunNowTool;
+
+impl ToolBase for ScheduleRunNowTool {
+    type Parameter = ScheduleRunNowRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "schedule_run_now".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "Start a run of the backup schedule of a server now, even if the schedule is paused: \
+            a full backup, followed by the verification and archive when configured. \
+            The run continues in the background; follow its outcome with schedule_list. \
+            Fails if a run of the server is already in progress. \
+            The username has to be one of the pgmoneta admins, and the run is made as that admin."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for ScheduleRunNowTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: ScheduleRunNowRequest,
+    ) -> Result<String, McpError> {
+        validation::validate_admin(&request.username)?;
+        let schedule = find_schedule(&request.server)?;
+        scheduler::run_now(&schedule, &request.username)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        to_json(&RunStarted {
+            server: schedule.server,
+            status: "started",
+            message: "The run continues in the background; use schedule_list to follow it"
+                .to_string(),
+        })
+    }
+}
+
+/// Tool for pausing or resuming a backup schedule.
+pub struct SchedulePauseTool;
+
+impl ToolBase for SchedulePauseTool {
+    type Parameter = SchedulePauseRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "schedule_pause".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "Pause the backup schedule of a server, or resume it with paused set to false. \
+            A paused schedule does not run on its own but can still be started with schedule_run_now, \
+            and a run in progress is not interrupted. The paused state survives restarts when \
+            state_directory is configured. \
+            The username has to be one of the pgmoneta admins."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for SchedulePauseTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: SchedulePauseRequest,
+    ) -> Result<String, McpError> {
+        validation::validate_admin(&request.username)?;
+        let schedule = find_schedule(&request.server)?;
+        let paused = request.paused.unwrap_or(true);
+        if scheduler::set_paused(&schedule.server, paused) {
+            tracing::info!(
+                "{} {} the schedule of {}",
+                request.username,
+                if paused { "paused" } else { "resumed" },
+                schedule.server
+            );
+        }
+        to_json(&schedule_status(&schedule, DEFAULT_HISTORY))
+    }
+}
+
+fn find_schedule(server: &str) -> Result<ScheduleConfiguration, McpError> {
+    let schedules = scheduler::schedules();
+    if let Some(schedule) = schedules.iter().find(|schedule| schedule.server == server) {
+        return Ok(schedule.clone());
+    }
+    let configured: Vec<&str> = schedules
+        .iter()
+        .map(|schedule| schedule.server.as_str())
+        .collect();
+    Err(McpError::invalid_params(
+        if configured.is_empty() {
+            format!("No schedule for '{server}': no [schedule:<server>] section is configured")
+        } else {
+            format!(
+                "No schedule for '{server}'. Scheduled servers: {}",
+                configured.join(", ")
+            )
+        },
+        None,
+    ))
+}
+
+fn schedule_status(schedule: &ScheduleConfiguration, history: usize) -> ScheduleStatus {
+    let clock = BackupClock::configured();
+    let mut state = scheduler::state(&schedule.server);
+    state.history.truncate(history);
+    ScheduleStatus {
+        server: schedule.server.clone(),
+        cron: schedule.cron.clone(),
+        verify: schedule.verify,
+        verify_directory: schedule.verify.then(|| schedule.verify_directory.clone()),
+        archive_directory: schedule.archive_directory.clone(),
+        jitter: schedule.jitter,
+        username: schedule.username.clone(),
+        paused: state.paused,
+        running: state.running,
+        next_run: state
+            .next_run
+            .filter(|_| !state.paused)
+            .map(|next| clock.format(next)),
+        history: state.history,
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, McpError> {
+    serde_json::to_string(value).map_err(|e| {
+        McpError::internal_error(format!("Failed to serialize schedules: {:?}", e), None)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    #[test]
+    fn test_handler_has_schedule_tools() {
+        let tools = PgmonetaHandler::tool_router().list_all();
+        let tool_names: Vec<&str> = tools.iter().map(|t| t.name.as_ref()).collect();
+        for name in ["schedule_list", "schedule_run_now", "schedule_pause"] {
+            assert!(tool_names.contains(&name), "{name} missing: {tool_names:?}");
+        }
+    }
+
+    #[test]
+    fn test_schedule_pause_request_defaults() {
+        let request: SchedulePauseRequest = serde_json::from_value(json!({
+            "username": "admin",
+            "server": "primary"
+        }))
+        .unwrap();
+        assert_eq!(request.paused, None);
+    }
+
+    #[test]
+    fn test_schedule_status_hides_next_run_when_paused() {
+        let schedule = ScheduleConfiguration {
+            server: "status-test".to_string(),
+            cron: "0 2 * * *".to_string(),
+            verify: true,
+            verify_directory: "/tmp".to_string(),
+            ..Default::default()
+        };
+        scheduler::set_paused("status-test", true);
+        let status = schedule_status(&schedule, DEFAULT_HISTORY);
+        let value: Value = serde_json::to_value(&status).unwrap();
+        assert_eq!(value["Paused"], true);
+        assert_eq!(value["NextRun"], Value::Null);
+        assert_eq!(value["VerifyDirectory"], "/tmp");
+        assert!(value.get("ArchiveDirectory").is_none());
+    }
+}
@@ -0,0 +1,713 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Backup service level agreements (`check_sla`).
+//!
+//! Every server matching a `[sla]` or `[sla:<pattern>]` section is checked
+//! against two limits: the age of its newest valid backup, and the time a
+//! restore is expected to take. Restore times are estimated from the durations
+//! of the restores and verifications run through this server, which are kept
+//! in `state_directory` when configured.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use super::PgmonetaHandler;
+use super::catalog::{self, BackupClock, BackupEntry, format_duration};
+use crate::configuration::{CONFIG, SlaPolicy};
+use crate::telemetry;
+use crate::utils::{SafeFileWriter, SnapshotWriter};
+use chrono::{NaiveDateTime, Utc};
+use rmcp::ErrorData as McpError;
+use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
+use rmcp::model::JsonObject;
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+/// The percentage of a limit from which a server is reported as `warn`.
+const DEFAULT_WARNING_THRESHOLD: u32 = 80;
+
+/// The file in `state_directory` holding the restore and verify durations.
+const HISTORY_FILE: &str = "restore_history.json";
+
+/// The number of durations kept per server.
+const HISTORY_LIMIT: usize = 20;
+
+/// The number of recent durations the restore estimate is based on.
+const ESTIMATE_SAMPLES: usize = 5;
+
+/// Restore and verify durations per server, loaded by [`init`].
+static HISTORY: Mutex<BTreeMap<String, Vec<DurationSample>>> = Mutex::new(BTreeMap::new());
+
+/// Saves the snapshots of [`HISTORY`] off the runtime.
+static HISTORY_SNAPSHOTS: SnapshotWriter = SnapshotWriter::new();
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct CheckSlaRequest {
+    pub username: String,
+    /// Only check this server
+    #[serde(default)]
+    pub server: Option<String>,
+}
+
+/// Tool for checking every server against its backup SLA.
+pub struct CheckSlaTool;
+
+impl ToolBase for CheckSlaTool {
+    type Parameter = CheckSlaRequest;
+    type Output = String;
+    type Error = McpError;
+
+    fn name() -> Cow<'static, str> {
+        "check_sla".into()
+    }
+
+    fn description() -> Option<Cow<'static, str>> {
+        Some(
+            "Check the servers against the backup SLA policies of the [sla] and [sla:<pattern>] \
+            configuration sections: the newest valid backup must be younger than max_backup_age hours, \
+            and a restore must be expected to finish within max_restore_time minutes, estimated from \
+            the durations of earlier restores and verifications. \
+            Returns pass, warn or fail for every server together with the reasons, \
+            and updates the SLA gauges on /metrics. \
+            The username has to be one of the pgmoneta admins to be able to access pgmoneta."
+                .into(),
+        )
+    }
+
+    fn output_schema() -> Option<Arc<JsonObject>> {
+        None
+    }
+}
+
+impl AsyncTool<PgmonetaHandler> for CheckSlaTool {
+    async fn invoke(
+        _service: &PgmonetaHandler,
+        request: CheckSlaRequest,
+    ) -> Result<String, McpError> {
+        let policies = CONFIG
+            .get()
+            .map(|config| config.sla.clone())
+            .unwrap_or_default();
+        if policies.is_empty() {
+            return Err(McpError::invalid_params(
+                "No SLA policies configured: add a [sla] or [sla:<pattern>] section",
+                None,
+            ));
+        }
+
+        let clock = BackupClock::configured();
+        let now = clock.now();
+        let status = catalog::fetch_status(&request.username).await?;
+        let mut servers = catalog::parse_server_names(&status);
+        if let Some(server) = request.server.as_deref() {
+            if !servers.iter().any(|name| name == server) {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "Unknown server '{server}'. Known servers: {}",
+                        servers.join(", ")
+                    ),
+                    None,
+                ));
+            }
+            servers.retain(|name| name == server);
+        }
+
+        let mut results = Vec::new();
+        let mut unmatched = Vec::new();
+        for server in &servers {
+            let Some(policy) = effective_policy(server, &policies) else {
+                unmatched.push(server.clone());
+                continue;
+            };
+            let samples = durations(server);
+            let result = match catalog::fetch_backups(&request.username, server).await {
+                Ok(backups) => evaluate(server, &policy, &backups, &samples, now),
+                Err(e) => {
+                    let mut result = evaluate(server, &policy, &[], &samples, now);
+                    result.status = SlaStatus::Fail;
+                    result
+                        .reasons
+                        .insert(0, format!("Unable to list backups: {}", e.message));
+                    result
+                }
+            };
+            results.push(result);
+        }
+
+        let metrics = telemetry::metrics();
+        if request.server.is_none() {
+            metrics.clear_sla_checks();
+        }
+        for result in &results {
+            metrics.record_sla_check(
+                &result.server,
+                result.status.gauge_value(),
+                result.backup_age_seconds.map(|age| age as f64),
+                result.restore_estimate_seconds,
+            );
+        }
+
+        let check = SlaCheck {
+            checked_at: clock.format(now),
+            status: results
+                .iter()
+                .map(|result| result.status)
+                .max()
+                .unwrap_or(SlaStatus::Pass),
+            servers: results,
+            unmatched,
+        };
+        serde_json::to_string(&check).map_err(|e| {
+            McpError::internal_error(format!("Failed to serialize result: {:?}", e), None)
+        })
+    }
+}
+
+/// The outcome of an SLA check, ordered from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SlaStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl SlaStatus {
+    /// The value of the `pgmoneta_mcp_sla_status` gauge.
+    fn gauge_value(&self) -> i64 {
+        match self {
+            Self::Pass => 0,
+            Self::Warn => 1,
+            Self::Fail => 2,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct SlaCheck {
+    checked_at: String,
+    status: SlaStatus,
+    servers: Vec<ServerSla>,
+    unmatched: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ServerSla {
+    server: String,
+    policy: String,
+    status: SlaStatus,
+    max_backup_age_hours: Option<u32>,
+    max_restore_time_minutes: Option<u32>,
+    newest_valid_backup: Option<String>,
+    backup_age: Option<String>,
+    backup_age_seconds: Option<i64>,
+    restore_estimate: Option<String>,
+    restore_estimate_seconds: Option<f64>,
+    restore_samples: usize,
+    reasons: Vec<String>,
+}
+
+/// The SLA that applies to a server, with `[sla]` filling in missing settings.
+#[derive(Debug, Clone, PartialEq)]
+struct EffectivePolicy {
+    section: String,
+    max_backup_age: Option<u32>,
+    max_restore_time: Option<u32>,
+    warning_threshold: u32,
+}
+
+/// Resolves the SLA of a server.
+///
+/// An exact server name wins over patterns, and a pattern with more literal
+/// characters wins over a broader one. Returns `None` when no section applies.
+fn effective_policy(server: &str, policies: &[SlaPolicy]) -> Option<EffectivePolicy> {
+    let default = policies.iter().find(|policy| policy.pattern.is_none());
+    let specific = policies
+        .iter()
+        .filter_map(|policy| Some((policy, policy.pattern.as_deref()?)))
+        .filter(|(_, pattern)| glob_match(pattern, server))
+        .min_by_key(|(_, pattern)| std::cmp::Reverse(specificity(pattern)))
+        .map(|(policy, _)| policy);
+
+    let policy = specific.or(default)?;
+    let setting =
+        |get: fn(&SlaPolicy) -> Option<u32>| get(policy).or_else(|| default.and_then(get));
+    Some(EffectivePolicy {
+        section: policy.section(),
+        max_backup_age: setting(|policy| policy.max_backup_age),
+        max_restore_time: setting(|policy| policy.max_restore_time),
+        warning_threshold: setting(|policy| policy.warning_threshold)
+            .unwrap_or(DEFAULT_WARNING_THRESHOLD),
+    })
+}
+
+/// Ranks patterns: exact names first, then by the number of literal characters.
+fn specificity(pattern: &str) -> (bool, usize) {
+    let literals = pattern.chars().filter(|c| !matches!(c, '*' | '?')).count();
+    (literals == pattern.chars().count(), literals)
+}
+
+/// Matches a server name against a glob pattern with `*` and `?` wildcards.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Checks a server against its SLA.
+fn evaluate(
+    server: &str,
+    policy: &EffectivePolicy,
+    backups: &[BackupEntry],
+    samples: &[DurationSample],
+    now: NaiveDateTime,
+) -> ServerSla {
+    let mut status = SlaStatus::Pass;
+    let mut reasons = Vec::new();
+    let mut raise = |level: SlaStatus, reason: String| {
+        status = status.max(level);
+        reasons.push(reason);
+    };
+    let threshold = f64::from(policy.warning_threshold) / 100.0;
+
+    let newest = catalog::newest_valid(backups);
+    let age = newest
+        .and_then(BackupEntry::end_timestamp)
+        .map(|end| (now - end).num_seconds().max(0));
+    if let Some(hours) = policy.max_backup_age {
+        let limit = i64::from(hours) * 3600;
+        match (newest, age) {
+            (Some(backup), Some(age)) if age > limit => raise(
+                SlaStatus::Fail,
+                format!(
+                    "The newest valid backup {} is {} old, beyond the limit of {hours}h",
+                    backup.backup,
+                    format_duration(age)
+                ),
+            ),
+            (Some(backup), Some(age)) if age as f64 >= limit as f64 * threshold => raise(
+                SlaStatus::Warn,
+                format!(
+                    "The newest valid backup {} is {} old, {}% of the limit of {hours}h",
+                    backup.backup,
+                    format_duration(age),
+                    age * 100 / limit
+                ),
+            ),
+            (Some(backup), Some(age)) => raise(
+                SlaStatus::Pass,
+                format!(
+                    "The newest valid backup {} is {} old, within the limit of {hours}h",
+                    backup.backup,
+                    format_duration(age)
+                ),
+            ),
+            _ => raise(SlaStatus::Fail, "No valid backup available".to_string()),
+        }
+    }
+
+    let estimate = restore_estimate(samples);
+    if let Some(minutes) = policy.max_restore_time {
+        let limit = f64::from(minutes) * 60.0;
+        match estimate {
+            None => raise(
+                SlaStatus::Warn,
+                format!(
+                    "No restore or verify duration recorded, so a restore within {minutes}m is unproven"
+                ),
+            ),
+            Some(estimate) if estimate > limit => raise(
+                SlaStatus::Fail,
+                format!(
+                    "A restore is estimated to take {}, beyond the limit of {minutes}m",
+                    format_duration(estimate.round() as i64)
+                ),
+            ),
+            Some(estimate) if estimate >= limit * threshold => raise(
+                SlaStatus::Warn,
+                format!(
+                    "A restore is estimated to take {}, {}% of the limit of {minutes}m",
+                    format_duration(estimate.round() as i64),
+                    (estimate * 100.0 / limit) as u64
+                ),
+            ),
+            Some(estimate) => raise(
+                SlaStatus::Pass,
+                format!(
+                    "A restore is estimated to take {}, within the limit of {minutes}m",
+                    format_duration(estimate.round() as i64)
+                ),
+            ),
+        }
+    }
+
+    ServerSla {
+        server: server.to_string(),
+        policy: policy.section.clone(),
+        status,
+        max_backup_age_hours: policy.max_backup_age,
+        max_restore_time_minutes: policy.max_restore_time,
+        newest_valid_backup: newest.map(|backup| backup.backup.clone()),
+        backup_age: age.map(format_duration),
+        backup_age_seconds: age,
+        restore_estimate: estimate.map(|estimate| format_duration(estimate.round() as i64)),
+        restore_estimate_seconds: estimate,
+        restore_samples: samples.len().min(ESTIMATE_SAMPLES),
+        reasons,
+    }
+}
+
+/// The slowest of the recent restore and verify durations, in seconds.
+fn restore_estimate(samples: &[DurationSample]) -> Option<f64> {
+    samples
+        .iter()
+        .rev()
+        .take(ESTIMATE_SAMPLES)
+        .map(|sample| sample.seconds)
+        .reduce(f64::max)
+}
+
+/// The operation a duration was measured for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Operation {
+    Restore,
+    Verify,
+}
+
+/// A measured restore or verify duration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct DurationSample {
+    operation: Operation,
+    backup: String,
+    seconds: f64,
+    recorded: String,
+}
+
+/// Records the duration of a restore or verify request when pgmoneta
+/// reported success.
+pub(crate) fn record_duration(
+    server: &str,
+    operation: Operation,
+    backup: &str,
+    result: &str,
+    duration: Duration,
+) {
+    let succeeded = PgmonetaHandler::_parse_and_check_result(result)
+        .and_then(|response| catalog::ensure_success(&response))
+        .is_ok();
+    if !succeeded {
+        return;
+    }
+
+    let sample = DurationSample {
+        operation,
+        backup: backup.to_string(),
+        seconds: duration.as_secs_f64(),
+        recorded: Utc::now().to_rfc3339(),
+    };
+    let (generation, snapshot) = {
+        let mut history = HISTORY.lock().unwrap_or_else(PoisonError::into_inner);
+        let samples = history.entry(server.to_string()).or_default();
+        samples.push(sample);
+        if samples.len() > HISTORY_LIMIT {
+            samples.drain(..samples.len() - HISTORY_LIMIT);
+        }
+        (HISTORY_SNAPSHOTS.next(), history.clone())
+    };
+    if let Some(directory) = state_directory() {
+        HISTORY_SNAPSHOTS.write(generation, move || save_history(&directory, &snapshot));
+    }
+}
+
+/// The recorded durations of a server, oldest first.
+fn durations(server: &str) -> Vec<DurationSample> {
+    HISTORY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(server)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Loads the recorded durations from `state_directory`; returns the number of
+/// servers with durations.
+///
+/// Called once at startup, so no request waits for the file.
+pub fn init() -> usize {
+    let history = load_history(state_directory().as_deref());
+    let count = history.len();
+    *HISTORY.lock().unwrap_or_else(PoisonError::into_inner) = history;
+    count
+}
+
+fn state_directory() -> Option<String> {
+    CONFIG
+        .get()
+        .and_then(|config| config.pgmoneta_mcp.state_directory.clone())
+}
+
+fn load_history(directory: Option<&str>) -> BTreeMap<String, Vec<DurationSample>> {
+    let Some(directory) = directory else {
+        return BTreeMap::new();
+    };
+    let path = Path::new(directory).join(HISTORY_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            tracing::warn!(
+                "Ignoring unreadable restore history {}: {}",
+                path.display(),
+                e
+            );
+            BTreeMap::new()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+        Err(e) => {
+            tracing::warn!("Failed to read restore history {}: {}", path.display(), e);
+            BTreeMap::new()
+        }
+    }
+}
+
+fn save_history(directory: &str, history: &BTreeMap<String, Vec<DurationSample>>) {
+    let result = serde_json::to_string_pretty(history)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| {
+            SafeFileWriter::new(directory)
+                .allowed_extensions(vec!["json"])
+                .write(HISTORY_FILE, &contents)
+        });
+    if let Err(e) = result {
+        tracing::warn!("Failed to save restore history in {}: {}", directory, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(pattern: Option<&str>, age: Option<u32>, restore: Option<u32>) -> SlaPolicy {
+        SlaPolicy {
+            pattern: pattern.map(str::to_string),
+            max_backup_age: age,
+            max_restore_time: restore,
+            warning_threshold: None,
+        }
+    }
+
+    fn backup(id: &str, valid: bool) -> BackupEntry {
+        BackupEntry {
+            backup: id.to_string(),
+            valid: Some(valid),
+            elapsed: Some(0.0),
+            ..Default::default()
+        }
+    }
+
+    fn sample(seconds: f64) -> DurationSample {
+        DurationSample {
+            operation: Operation::Verify,
+            backup: "newest".to_string(),
+            seconds,
+            recorded: "2026-07-06T10:00:00+00:00".to_string(),
+        }
+    }
+
+    fn now() -> NaiveDateTime {
+        NaiveDateTime::parse_from_str("20260706120000", catalog::BACKUP_ID_FORMAT).unwrap()
+    }
+
+    fn effective(age: Option<u32>, restore: Option<u32>) -> EffectivePolicy {
+        EffectivePolicy {
+            section: "sla".to_string(),
+            max_backup_age: age,
+            max_restore_time: restore,
+            warning_threshold: DEFAULT_WARNING_THRESHOLD,
+        }
+    }
+
+    #[test]
+    fn test_check_sla_tool_metadata() {
+        assert_eq!(CheckSlaTool::name(), "check_sla");
+        assert!(
+            CheckSlaTool::description()
+                .unwrap()
+                .contains("max_backup_age")
+        );
+        let tools = PgmonetaHandler::tool_router().list_all();
+        assert!(tools.iter().any(|tool| tool.name == "check_sla"));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("prod-*", "prod-eu"));
+        assert!(glob_match("prod-*", "prod-"));
+        assert!(glob_match("*-eu", "prod-eu"));
+        assert!(glob_match("db?", "db1"));
+        assert!(glob_match("*a*b", "xaxxab"));
+        assert!(!glob_match("prod-*", "staging"));
+        assert!(!glob_match("db?", "db10"));
+        assert!(glob_match("primary", "primary"));
+        assert!(!glob_match("primary", "primary2"));
+    }
+
+    #[test]
+    fn test_effective_policy() {
+        let policies = vec![
+            policy(None, Some(24), Some(60)),
+            policy(Some("prod-*"), Some(12), None),
+            policy(Some("prod-eu"), Some(4), None),
+        ];
+
+        let policy = effective_policy("prod-eu", &policies).unwrap();
+        assert_eq!(policy.section, "sla:prod-eu");
+        assert_eq!(policy.max_backup_age, Some(4));
+        assert_eq!(policy.max_restore_time, Some(60));
+        assert_eq!(policy.warning_threshold, DEFAULT_WARNING_THRESHOLD);
+
+        let policy = effective_policy("prod-us", &policies).unwrap();
+        assert_eq!(policy.section, "sla:prod-*");
+        assert_eq!(policy.max_backup_age, Some(12));
+
+        let policy = effective_policy("staging", &policies).unwrap();
+        assert_eq!(policy.section, "sla");
+
+        assert!(effective_policy("staging", &policies[1..]).is_none());
+    }
+
+    #[test]
+    fn test_evaluate_backup_age() {
+        let policy = effective(Some(10), None);
+
+        let result = evaluate(
+            "primary",
+            &policy,
+            &[backup("20260706100000", true)],
+            &[],
+            now(),
+        );
+        assert_eq!(result.status, SlaStatus::Pass);
+        assert_eq!(result.backup_age_seconds, Some(7200));
+        assert!(result.reasons[0].contains("within the limit of 10h"));
+
+        let result = evaluate(
+            "primary",
+            &policy,
+            &[backup("20260706030000", true)],
+            &[],
+            now(),
+        );
+        assert_eq!(result.status, SlaStatus::Warn);
+        assert!(result.reasons[0].contains("90% of the limit"));
+
+        let backups = [
+            backup("20260705000000", true),
+            backup("20260706110000", false),
+        ];
+        let result = evaluate("primary", &policy, &backups, &[], now());
+        assert_eq!(result.status, SlaStatus::Fail);
+        assert_eq!(
+            result.newest_valid_backup.as_deref(),
+            Some("20260705000000")
+        );
+
+        let result = evaluate(
+            "primary",
+            &policy,
+            &[backup("20260706110000", false)],
+            &[],
+            now(),
+        );
+        assert_eq!(result.status, SlaStatus::Fail);
+        assert_eq!(result.reasons, vec!["No valid backup available"]);
+    }
+
+    #[test]
+    fn test_evaluate_restore_time() {
+        let policy = effective(None, Some(10));
+
+        let result = evaluate("primary", &policy, &[], &[], now());
+        assert_eq!(result.status, SlaStatus::Warn);
+        assert!(result.reasons[0].contains("unproven"));
+
+        let result = evaluate(
+            "primary",
+            &policy,
+            &[],
+            &[sample(120.0), sample(300.0)],
+            now(),
+        );
+        assert_eq!(result.status, SlaStatus::Pass);
+        assert_eq!(result.restore_estimate_seconds, Some(300.0));
+        assert_eq!(result.restore_samples, 2);
+
+        let result = evaluate("primary", &policy, &[], &[sample(540.0)], now());
+        assert_eq!(result.status, SlaStatus::Warn);
+
+        let result = evaluate("primary", &policy, &[], &[sample(900.0)], now());
+        assert_eq!(result.status, SlaStatus::Fail);
+        assert!(result.reasons[0].contains("beyond the limit of 10m"));
+    }
+
+    #[test]
+    fn test_restore_estimate_uses_recent_samples() {
+        let mut samples = vec![sample(5000.0)];
+        samples.extend((0..ESTIMATE_SAMPLES).map(|i| sample(60.0 + i as f64)));
+        assert_eq!(restore_estimate(&samples), Some(64.0));
+        assert_eq!(restore_estimate(&[]), None);
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = directory.path().to_str().unwrap();
+        let history = BTreeMap::from([("primary".to_string(), vec![sample(42.0)])]);
+
+        save_history(directory, &history);
+        save_history(directory, &history);
+        assert_eq!(load_history(Some(directory)), history);
+        assert!(load_history(None).is_empty());
+    }
+}
@@ -0,0 +1,311 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Validation of tool arguments.
+//!
+//! Arguments are checked before any request is sent to pgmoneta, so that
+//! invalid values are reported as `invalid_params` instead of failing inside
+//! pgmoneta.
+
+use std::path::{Component, Path};
+
+use crate::configuration::CONFIG;
+use rmcp::ErrorData as McpError;
+use serde::Deserializer;
+use serde::de::{Deserialize, Error};
+
+/// JSON Schema pattern of a backup identifier: a pgmoneta timestamp or a symbolic name.
+///
+/// JSON Schema regexes have no case-insensitive flag, so the symbolic names
+/// spell out both cases to accept what [`validate_backup_id`] accepts.
+pub(crate) const BACKUP_ID_PATTERN: &str = r"^\s*([0-9]{14}|[Nn][Ee][Ww][Ee][Ss][Tt]|[Ll][Aa][Tt][Ee][Ss][Tt]|[Oo][Ll][Dd][Ee][Ss][Tt])\s*$";
+
+/// Longest path accepted for files and directories on the pgmoneta host.
+const MAX_PATH_LENGTH: usize = 4096;
+
+/// A string argument restricted to a fixed set of lowercase keywords.
+///
+/// Keywords are matched case-insensitively after trimming, since LLMs often
+/// send `"Online"` or `" add "`.
+pub(crate) trait Keyword: Sized + Copy + 'static {
+    /// The argument name used in error messages.
+    const ARGUMENT: &'static str;
+    /// The accepted keywords and their values.
+    const VARIANTS: &'static [(&'static str, Self)];
+
+    /// Parses a keyword.
+    fn parse(value: &str) -> Result<Self, String> {
+        let normalized = value.trim().to_ascii_lowercase();
+        Self::VARIANTS
+            .iter()
+            .find(|(keyword, _)| *keyword == normalized)
+            .map(|(_, variant)| *variant)
+            .ok_or_else(|| {
+                format!(
+                    "Unsupported {} '{}'. Supported values: {}",
+                    Self::ARGUMENT,
+                    value,
+                    Self::VARIANTS
+                        .iter()
+                        .map(|(keyword, _)| *keyword)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+/// Deserializes a [`Keyword`], reporting unsupported values as a deserialization error.
+pub(crate) fn deserialize_keyword<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Keyword,
+{
+    let value = String::deserialize(deserializer)?;
+    T::parse(&value).map_err(D::Error::custom)
+}
+
+/// Deserializes an optional [`Keyword`], treating `""` and `"null"` as absent.
+pub(crate) fn deserialize_optional_keyword<'de, D, T>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Keyword,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) if !value.trim().is_empty() && !value.trim().eq_ignore_ascii_case("null") => {
+            T::parse(&value).map(Some).map_err(D::Error::custom)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Unwraps a required [`Keyword`] argument.
+///
+/// Action keywords have no sensible default, so their requests hold them as
+/// `Option`s that are only `None` when the argument was empty or `null`.
+pub(crate) fn require_keyword<T: Keyword>(value: Option<T>) -> Result<T, McpError> {
+    value.ok_or_else(|| {
+        McpError::invalid_params(
+            format!(
+                "Missing {}. Supported values: {}",
+                T::ARGUMENT,
+                T::VARIANTS
+                    .iter()
+                    .map(|(keyword, _)| *keyword)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            None,
+        )
+    })
+}
+
+/// Trims and lowercases a backup identifier, as pgmoneta only knows the
+/// lowercase symbolic names.
+pub(crate) fn normalize_backup_id(backup_id: &str) -> String {
+    backup_id.trim().to_ascii_lowercase()
+}
+
+/// Deserializes a backup identifier with [`normalize_backup_id`].
+pub(crate) fn deserialize_backup_id<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    String::deserialize(deserializer).map(|backup_id| normalize_backup_id(&backup_id))
+}
+
+/// Deserializes an optional backup identifier with [`normalize_backup_id`].
+pub(crate) fn deserialize_optional_backup_id<'de, D>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)
+        .map(|backup_id| backup_id.map(|backup_id| normalize_backup_id(&backup_id)))
+}
+
+/// Checks a backup identifier against [`BACKUP_ID_PATTERN`], case-insensitively
+/// like the keywords.
+pub(crate) fn validate_backup_id(argument: &str, backup_id: &str) -> Result<(), McpError> {
+    let normalized = normalize_backup_id(backup_id);
+    let is_timestamp = normalized.len() == 14 && normalized.bytes().all(|b| b.is_ascii_digit());
+    if is_timestamp || matches!(normalized.as_str(), "newest" | "latest" | "oldest") {
+        return Ok(());
+    }
+    Err(McpError::invalid_params(
+        format!(
+            "Invalid {argument} '{backup_id}': expected a 14 digit backup identifier \
+            (YYYYMMDDHHMMSS) or one of newest, latest, oldest"
+        ),
+        None,
+    ))
+}
+
+/// Checks that a username is one of the configured pgmoneta admins.
+///
+/// Used by tools that act on the MCP server itself rather than on pgmoneta,
+/// where pgmoneta would otherwise reject unknown users.
+pub(crate) fn validate_admin(username: &str) -> Result<(), McpError> {
+    let known = CONFIG
+        .get()
+        .is_some_and(|config| config.admins.contains_key(username));
+    if known {
+        return Ok(());
+    }
+    Err(McpError::invalid_params(
+        format!("Unknown admin '{username}'"),
+        None,
+    ))
+}
+
+/// Checks that a path on the pgmoneta host is absolute and free of traversal.
+pub(crate) fn validate_path(argument: &str, path: &str) -> Result<(), McpError> {
+    let invalid = |reason: &str| {
+        Err(McpError::invalid_params(
+            format!("Invalid {argument} '{path}': {reason}"),
+            None,
+        ))
+    };
+
+    if path.trim().is_empty() {
+        return invalid("the path must not be empty");
+    }
+    if path.len() > MAX_PATH_LENGTH {
+        return invalid("the path is too long");
+    }
+    if path.chars().any(char::is_control) {
+        return invalid("the path must not contain control characters");
+    }
+    let path_ref = Path::new(path);
+    if !path_ref.has_root() {
+        return invalid("the path must be absolute");
+    }
+    if path_ref
+        .components()
+        .any(|component| component == Component::ParentDir)
+    {
+        return invalid("the path must not contain '..'");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Color {
+        Red,
+        Blue,
+    }
+
+    impl Keyword for Color {
+        const ARGUMENT: &'static str = "color";
+        const VARIANTS: &'static [(&'static str, Self)] =
+            &[("red", Color::Red), ("blue", Color::Blue)];
+    }
+
+    #[test]
+    fn test_keyword_parse_is_case_insensitive() {
+        assert_eq!(Color::parse(" Red ").unwrap(), Color::Red);
+        assert_eq!(Color::parse("blue").unwrap(), Color::Blue);
+        let error = Color::parse("green").unwrap_err();
+        assert_eq!(
+            error,
+            "Unsupported color 'green'. Supported values: red, blue"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_keyword() {
+        let value = serde_json::json!("RED");
+        let color: Color = deserialize_keyword(value).unwrap();
+        assert_eq!(color, Color::Red);
+        assert!(deserialize_keyword::<_, Color>(serde_json::json!("green")).is_err());
+        assert!(deserialize_keyword::<_, Color>(serde_json::json!(1)).is_err());
+
+        let absent: Option<Color> =
+            deserialize_optional_keyword(serde_json::json!(" null ")).unwrap();
+        assert_eq!(absent, None);
+        let absent: Option<Color> = deserialize_optional_keyword(serde_json::Value::Null).unwrap();
+        assert_eq!(absent, None);
+        let blue: Option<Color> = deserialize_optional_keyword(serde_json::json!("Blue")).unwrap();
+        assert_eq!(blue, Some(Color::Blue));
+    }
+
+    #[test]
+    fn test_validate_backup_id() {
+        assert!(validate_backup_id("backup_id", "20260410142257").is_ok());
+        assert!(validate_backup_id("backup_id", "newest").is_ok());
+        assert!(validate_backup_id("backup_id", "latest").is_ok());
+        assert!(validate_backup_id("backup_id", "oldest").is_ok());
+        assert!(validate_backup_id("backup_id", "LATEST").is_ok());
+        assert!(validate_backup_id("backup_id", " Newest ").is_ok());
+        assert!(validate_backup_id("backup_id", " 20260410142257\n").is_ok());
+        assert!(validate_backup_id("backup_id", "2026041014225").is_err());
+        assert!(validate_backup_id("backup_id", "yesterday").is_err());
+        assert!(validate_backup_id("backup_id", "").is_err());
+        let error = validate_backup_id("backup_id", "20260410-142257").unwrap_err();
+        assert!(error.message.contains("14 digit"));
+    }
+
+    #[test]
+    fn test_backup_id_pattern_agrees_with_validation() {
+        let pattern = regex::Regex::new(BACKUP_ID_PATTERN).unwrap();
+        for backup_id in [
+            "20260410142257",
+            "newest",
+            "LATEST",
+            " Oldest ",
+            " 20260410142257\n",
+            "2026041014225",
+            "yesterday",
+            "",
+            "20260410-142257",
+        ] {
+            assert_eq!(
+                pattern.is_match(backup_id),
+                validate_backup_id("backup_id", backup_id).is_ok(),
+                "{backup_id:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_deserialize_backup_id() {
+        let backup_id = deserialize_backup_id(serde_json::json!(" LATEST ")).unwrap();
+        assert_eq!(backup_id, "latest");
+        let backup_id = deserialize_optional_backup_id(serde_json::json!("Oldest")).unwrap();
+        assert_eq!(backup_id.as_deref(), Some("oldest"));
+        assert_eq!(
+            deserialize_optional_backup_id(serde_json::Value::Null).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate_path() {
+        assert!(validate_path("directory", "/tmp/restore").is_ok());
+        assert!(validate_path("directory", "/").is_ok());
+        assert!(validate_path("directory", "").is_err());
+        assert!(validate_path("directory", "tmp/restore").is_err());
+        assert!(validate_path("directory", "/tmp/../etc").is_err());
+        assert!(validate_path("directory", "/tmp/re\nstore").is_err());
+        assert!(validate_path("directory", &format!("/{}", "a".repeat(5000))).is_err());
+    }
+}
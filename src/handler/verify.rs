@@ -13,23 +13,476 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+//! Backup verification (`verify`) and the last verification of each backup.
+//!
+//! The pgmoneta verify response is modeled as a [`VerifyReport`]: the number
+//! of verified and failed files, the checksum mismatches with the expected and
+//! actual hashes, and a summary ranking the directories with failures. The
+//! last verification of every backup is kept, in `state_directory` when
+//! configured, and shown by `get_info`.
+
 use std::borrow::Cow;
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
 
 use super::PgmonetaHandler;
+use super::catalog::{self, BackupClock};
+use super::events::{self, Event, EventKind, EventSource};
+use super::sla;
+use super::validation;
 use crate::client::PgmonetaClient;
+use crate::configuration::{CONFIG, DEFAULT_VERIFY_DIRECTORY};
+use crate::constant::VerifyFiles;
+use crate::utils::{SafeFileWriter, SnapshotWriter};
 use rmcp::ErrorData as McpError;
 use rmcp::handler::server::router::tool::{AsyncTool, ToolBase};
 use rmcp::model::JsonObject;
 use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The file in `state_directory` holding the last verification of each backup.
+const VERIFICATIONS_FILE: &str = "verifications.json";
+
+/// The number of backups per server whose last verification is kept.
+const VERIFICATIONS_LIMIT: usize = 200;
+
+/// The number of directories ranked in the summary.
+const SUMMARY_DIRECTORIES: usize = 10;
+
+/// The last verification of each backup, per server, loaded by [`init`].
+static VERIFICATIONS: Mutex<BTreeMap<String, BTreeMap<String, LastVerification>>> =
+    Mutex::new(BTreeMap::new());
+
+/// Saves the snapshots of [`VERIFICATIONS`] off the runtime.
+static VERIFICATION_SNAPSHOTS: SnapshotWriter = SnapshotWriter::new();
 
 #[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
 pub struct VerifyRequest {
     pub username: String,
     pub server: String,
+    /// Backup identifier: a 14 digit timestamp or "newest", "latest" or "oldest"
+    #[schemars(pattern(validation::BACKUP_ID_PATTERN))]
+    #[serde(deserialize_with = "validation::deserialize_backup_id")]
     pub backup_id: String,
     #[serde(default)]
     pub directory: Option<String>,
+    /// Only ask pgmoneta for the failed files; the total number of files is then unknown
+    #[serde(default)]
+    pub failed_only: Option<bool>,
+}
+
+/// The result of a verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyResult {
+    /// No file failed.
+    Passed,
+    /// Files failed verification.
+    Failed,
+    /// pgmoneta could not verify the backup.
+    Error,
+}
+
+/// Why a file failed verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureKind {
+    /// The checksum differs from the one in the backup manifest.
+    Mismatch,
+    /// No checksum could be calculated, e.g. the file is missing.
+    Missing,
+}
+
+/// A file that failed verification.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct FailedFile {
+    /// The path of the file within the backup.
+    pub file_name: String,
+    /// The directory of the file within the backup.
+    pub directory: String,
+    pub kind: FailureKind,
+    pub hash_algorithm: Option<String>,
+    /// The checksum in the backup manifest.
+    pub expected: Option<String>,
+    /// The checksum of the file.
+    pub actual: Option<String>,
+}
+
+/// The number of failed files in a directory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DirectoryFailures {
+    pub directory: String,
+    pub failed_files: usize,
+}
+
+/// The failures of a verification, ranked.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct VerifySummary {
+    pub text: String,
+    /// The share of the verified files that failed, when the total is known.
+    pub failed_percent: Option<f64>,
+    pub mismatches: usize,
+    pub missing: usize,
+    /// The directories with the most failed files first.
+    pub directories: Vec<DirectoryFailures>,
+}
+
+/// The modeled response of a verification.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct VerifyReport {
+    pub server: String,
+    pub backup: String,
+    pub directory: String,
+    pub result: VerifyResult,
+    /// The number of verified files; unknown when only failed files were requested.
+    pub total_files: Option<usize>,
+    pub failed_files: usize,
+    /// The failed files, checksum mismatches first.
+    pub failed: Vec<FailedFile>,
+    pub summary: VerifySummary,
+}
+
+/// The last verification of a backup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LastVerification {
+    pub time: String,
+    pub result: VerifyResult,
+    pub total_files: Option<usize>,
+    pub failed_files: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl VerifyReport {
+    /// Models a successful pgmoneta verify response; `server`, `backup` and
+    /// `directory` are used when the response lacks them.
+    pub fn from_response(
+        response: &Map<String, Value>,
+        server: &str,
+        backup: &str,
+        directory: &str,
+    ) -> Self {
+        let body = response
+            .get("Response")
+            .and_then(Value::as_object)
+            .unwrap_or(response);
+        let text = |field: &str, fallback: &str| {
+            body.get(field)
+                .and_then(catalog::value_as_string)
+                .unwrap_or_else(|| fallback.to_string())
+        };
+
+        let mut failed: Vec<FailedFile> = body
+            .get("Failed")
+            .and_then(Value::as_array)
+            .map(|files| files.iter().filter_map(failed_file).collect())
+            .unwrap_or_default();
+        failed.sort_by(|a, b| {
+            a.kind
+                .cmp(&b.kind)
+                .then_with(|| a.file_name.cmp(&b.file_name))
+        });
+        let total_files = body
+            .get("All")
+            .and_then(Value::as_array)
+            .map(|files| files.len().max(failed.len()));
+
+        Self {
+            server: text("Server", server),
+            backup: text("Backup", backup),
+            directory: text("Directory", directory),
+            result: if failed.is_empty() {
+                VerifyResult::Passed
+            } else {
+                VerifyResult::Failed
+            },
+            total_files,
+            failed_files: failed.len(),
+            summary: summarize(&failed, total_files),
+            failed,
+        }
+    }
+
+    /// The report as the last verification of the backup.
+    fn to_last_verification(&self, time: String) -> LastVerification {
+        LastVerification {
+            time,
+            result: self.result,
+            total_files: self.total_files,
+            failed_files: self.failed_files,
+            error: None,
+        }
+    }
+
+    /// The `backup_verified` event of the report.
+    pub(crate) fn to_event(&self, source: EventSource, username: &str) -> Event {
+        let result = match self.result {
+            VerifyResult::Passed => "passed",
+            VerifyResult::Failed => "failed",
+            VerifyResult::Error => "error",
+        };
+        Event::new(EventKind::BackupVerified, source, &self.server)
+            .backup(&self.backup)
+            .username(username)
+            .detail("Result", result)
+            .detail("FailedFiles", self.failed_files)
+            .detail("Directory", self.directory.as_str())
+    }
+}
+
+fn failed_file(value: &Value) -> Option<FailedFile> {
+    let file = value.as_object()?;
+    let checksum = |field: &str| {
+        file.get(field)
+            .and_then(catalog::value_as_string)
+            .map(|checksum| checksum.trim().to_string())
+            .filter(|checksum| !checksum.is_empty())
+    };
+    let file_name = file.get("FileName").and_then(catalog::value_as_string)?;
+    let actual = checksum("Calculated");
+    Some(FailedFile {
+        directory: match file_name.rsplit_once('/') {
+            Some((directory, _)) => directory.to_string(),
+            None => ".".to_string(),
+        },
+        file_name,
+        kind: if actual.is_some() {
+            FailureKind::Mismatch
+        } else {
+            FailureKind::Missing
+        },
+        hash_algorithm: checksum("HashAlgorithm"),
+        expected: checksum("Original"),
+        actual,
+    })
+}
+
+fn summarize(failed: &[FailedFile], total_files: Option<usize>) -> VerifySummary {
+    let mismatches = failed
+        .iter()
+        .filter(|file| file.kind == FailureKind::Mismatch)
+        .count();
+    let missing = failed.len() - mismatches;
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for file in failed {
+        *counts.entry(file.directory.as_str()).or_default() += 1;
+    }
+    let mut directories: Vec<DirectoryFailures> = counts
+        .into_iter()
+        .map(|(directory, failed_files)| DirectoryFailures {
+            directory: directory.to_string(),
+            failed_files,
+        })
+        .collect();
+    directories.sort_by(|a, b| {
+        b.failed_files
+            .cmp(&a.failed_files)
+            .then_with(|| a.directory.cmp(&b.directory))
+    });
+    directories.truncate(SUMMARY_DIRECTORIES);
+
+    let failed_percent = total_files
+        .filter(|total| *total > 0)
+        .map(|total| (failed.len() as f64 * 10000.0 / total as f64).round() / 100.0);
+    let text = if failed.is_empty() {
+        match total_files {
+            Some(total) => format!("All {total} files passed verification"),
+            None => "No file failed verification".to_string(),
+        }
+    } else {
+        let of_total = total_files
+            .map(|total| format!(" of {total}"))
+            .unwrap_or_default();
+        let top = &directories[0];
+        format!(
+            "{}{of_total} files failed verification ({mismatches} checksum mismatches, {missing} missing); \
+            most failures are in {} ({})",
+            failed.len(),
+            top.directory,
+            top.failed_files
+        )
+    };
+
+    VerifySummary {
+        text,
+        failed_percent,
+        mismatches,
+        missing,
+        directories,
+    }
+}
+
+/// Verifies a backup, records the verify duration for `check_sla` and the
+/// last verification of the backup, and returns the raw pgmoneta response.
+pub(crate) async fn run_verify(
+    username: &str,
+    server: &str,
+    backup_id: &str,
+    directory: &str,
+    failed_only: bool,
+) -> anyhow::Result<String> {
+    let files = if failed_only {
+        VerifyFiles::FAILED
+    } else {
+        VerifyFiles::ALL
+    };
+    let start = Instant::now();
+    let result =
+        PgmonetaClient::request_verify(username, server, backup_id, directory, files).await?;
+    sla::record_duration(
+        server,
+        sla::Operation::Verify,
+        backup_id,
+        &result,
+        start.elapsed(),
+    );
+    record_verification(server, backup_id, directory, &result);
+    Ok(result)
+}
+
+/// Models a raw verify response; fails when pgmoneta reported a failure.
+pub(crate) fn parse_verify(
+    result: &str,
+    server: &str,
+    backup_id: &str,
+    directory: &str,
+) -> Result<VerifyReport, McpError> {
+    let response = PgmonetaHandler::_parse_and_check_result(result)?;
+    catalog::ensure_success(&response)?;
+    Ok(VerifyReport::from_response(
+        &response, server, backup_id, directory,
+    ))
+}
+
+/// Records the last verification of a backup from a raw verify response.
+///
+/// Verifications of `newest`, `latest` or `oldest` are only recorded when
+/// pgmoneta reports the backup identifier.
+fn record_verification(server: &str, backup_id: &str, directory: &str, result: &str) {
+    let clock = BackupClock::configured();
+    let time = clock.format(clock.now());
+    let (backup, verification) = match parse_verify(result, server, backup_id, directory) {
+        Ok(report) => {
+            let verification = report.to_last_verification(time);
+            (report.backup, verification)
+        }
+        Err(e) => (
+            backup_id.to_string(),
+            LastVerification {
+                time,
+                result: VerifyResult::Error,
+                total_files: None,
+                failed_files: 0,
+                error: Some(e.message.to_string()),
+            },
+        ),
+    };
+    if !is_backup_identifier(&backup) {
+        return;
+    }
+
+    let (generation, snapshot) = {
+        let mut verifications = VERIFICATIONS.lock().unwrap_or_else(PoisonError::into_inner);
+        let backups = verifications.entry(server.to_string()).or_default();
+        backups.insert(backup, verification);
+        while backups.len() > VERIFICATIONS_LIMIT {
+            backups.pop_first();
+        }
+        (VERIFICATION_SNAPSHOTS.next(), verifications.clone())
+    };
+    if let Some(directory) = state_directory() {
+        VERIFICATION_SNAPSHOTS.write(generation, move || {
+            save_verifications(&directory, &snapshot)
+        });
+    }
+}
+
+fn is_backup_identifier(backup: &str) -> bool {
+    backup.len() == 14 && backup.chars().all(|c| c.is_ascii_digit())
+}
+
+/// The last verification of a backup, if any.
+pub(crate) fn last_verification(server: &str, backup: &str) -> Option<LastVerification> {
+    VERIFICATIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(server)
+        .and_then(|backups| backups.get(backup))
+        .cloned()
+}
+
+/// Loads the verification history from `state_directory`; returns the number
+/// of servers with verified backups.
+///
+/// Called once at startup, so no request waits for the file.
+pub fn init() -> usize {
+    let verifications = load_verifications(state_directory().as_deref());
+    let count = verifications.len();
+    *VERIFICATIONS.lock().unwrap_or_else(PoisonError::into_inner) = verifications;
+    count
+}
+
+fn state_directory() -> Option<String> {
+    CONFIG
+        .get()
+        .and_then(|config| config.pgmoneta_mcp.state_directory.clone())
+}
+
+fn load_verifications(
+    directory: Option<&str>,
+) -> BTreeMap<String, BTreeMap<String, LastVerification>> {
+    let Some(directory) = directory else {
+        return BTreeMap::new();
+    };
+    let path = Path::new(directory).join(VERIFICATIONS_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            tracing::warn!(
+                "Ignoring unreadable verification history {}: {}",
+                path.display(),
+                e
+            );
+            BTreeMap::new()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to read verification history {}: {}",
+                path.display(),
+                e
+            );
+            BTreeMap::new()
+        }
+    }
+}
+
+fn save_verifications(
+    directory: &str,
+    verifications: &BTreeMap<String, BTreeMap<String, LastVerification>>,
+) {
+    let result = serde_json::to_string_pretty(verifications)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| {
+            SafeFileWriter::new(directory)
+                .allowed_extensions(vec!["json"])
+                .write(VERIFICATIONS_FILE, &contents)
+        });
+    if let Err(e) = result {
+        tracing::warn!(
+            "Failed to save verification history in {}: {}",
+            directory,
+            e
+        );
+    }
 }
 
 /// Tool for verifying the integrity of a specific backup.
@@ -49,6 +502,9 @@ impl ToolBase for VerifyBackupTool {
             "Verify the integrity of a backup using given backup ID and server name. \
             \"newest\", \"latest\" or \"oldest\" are also accepted as backup identifier. \
             Optionally provide a target directory; /tmp is used by default. \
+            The response counts the verified and failed files, lists the failed files \
+            with their expected and actual checksums, and summarizes the failures by directory. \
+            Set failed_only to only ask pgmoneta for the failed files. \
             The username has to be one of the pgmoneta admins to be able to access pgmoneta"
                 .into(),
         )
@@ -70,19 +526,57 @@ impl AsyncTool<PgmonetaHandler> for VerifyBackupTool {
         _service: &PgmonetaHandler,
         request: VerifyRequest,
     ) -> Result<String, McpError> {
-        let directory = request.directory.as_deref().unwrap_or("/tmp");
-        let result: String = PgmonetaClient::request_verify(
+        validation::validate_backup_id("backup_id", &request.backup_id)?;
+        let directory = request
+            .directory
+            .as_deref()
+            .unwrap_or(DEFAULT_VERIFY_DIRECTORY);
+        validation::validate_path("directory", directory)?;
+        let result: String = run_verify(
             &request.username,
             &request.server,
             &request.backup_id,
             directory,
+            request.failed_only.unwrap_or(false),
         )
         .await
         .map_err(|e| McpError::internal_error(format!("Failed to verify backup: {:?}", e), None))?;
-        PgmonetaHandler::generate_call_tool_result_string(&result)
+        if let Ok(report) = parse_verify(&result, &request.server, &request.backup_id, directory) {
+            events::emit(report.to_event(EventSource::Tool, &request.username));
+        }
+        verify_result_string(&result, &request.server, &request.backup_id, directory)
     }
 }
 
+/// The translated verify response with the `Response` replaced by a [`VerifyReport`].
+///
+/// Unsuccessful responses are only translated.
+pub(crate) fn verify_result_string(
+    result: &str,
+    server: &str,
+    backup_id: &str,
+    directory: &str,
+) -> Result<String, McpError> {
+    let Ok(report) = parse_verify(result, server, backup_id, directory) else {
+        return PgmonetaHandler::generate_call_tool_result_string(result);
+    };
+    let response = PgmonetaHandler::_parse_and_check_result(result)?;
+    let mut translated = PgmonetaHandler::_translate_result(&response).map_err(|e| {
+        McpError::internal_error(
+            format!("Failed to translate some of the result fields: {:?}", e),
+            None,
+        )
+    })?;
+    translated.remove("Failed");
+    translated.remove("All");
+    let report = serde_json::to_value(&report).map_err(|e| {
+        McpError::internal_error(format!("Failed to serialize verify report: {:?}", e), None)
+    })?;
+    translated.insert("Response".to_string(), report);
+    serde_json::to_string(&translated)
+        .map_err(|e| McpError::internal_error(format!("Failed to serialize result: {:?}", e), None))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -160,4 +654,153 @@ mod tests {
         let outcome = parsed["Outcome"].as_object().unwrap();
         assert_eq!(outcome["Error"], "Verify: network error");
     }
+
+    fn verify_response(all: bool) -> String {
+        let mut response = json!({
+            "Header": {"Command": 19},
+            "Outcome": {"Status": true, "Command": 19, "Time": "00:00:03"},
+            "Response": {
+                "Server": "primary",
+                "Backup": 20260706113000_u64,
+                "Directory": "/tmp",
+                "Failed": [
+                    {"FileName": "base/5/1259", "Original": "aa11", "Calculated": "", "HashAlgorithm": "SHA256"},
+                    {"FileName": "base/16384/2610", "Original": "bb22", "Calculated": "cc33", "HashAlgorithm": "SHA256"},
+                    {"FileName": "base/16384/2608", "Original": "dd44", "Calculated": "ee55", "HashAlgorithm": "SHA256"},
+                    {"FileName": "PG_VERSION", "Original": "ff66", "Calculated": "0077", "HashAlgorithm": "SHA256"}
+                ]
+            }
+        });
+        if all {
+            response["Response"]["All"] = Value::Array(
+                (0..400)
+                    .map(|i| json!({"FileName": format!("base/5/{i}"), "Original": "x", "Calculated": "x"}))
+                    .collect(),
+            );
+        }
+        response.to_string()
+    }
+
+    #[test]
+    fn test_verify_report_models_failures() {
+        let report = parse_verify(&verify_response(true), "other", "newest", "/var/tmp").unwrap();
+
+        assert_eq!(report.server, "primary");
+        assert_eq!(report.backup, "20260706113000");
+        assert_eq!(report.directory, "/tmp");
+        assert_eq!(report.result, VerifyResult::Failed);
+        assert_eq!(report.total_files, Some(400));
+        assert_eq!(report.failed_files, 4);
+        let files: Vec<(&str, FailureKind)> = report
+            .failed
+            .iter()
+            .map(|file| (file.file_name.as_str(), file.kind))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("PG_VERSION", FailureKind::Mismatch),
+                ("base/16384/2608", FailureKind::Mismatch),
+                ("base/16384/2610", FailureKind::Mismatch),
+                ("base/5/1259", FailureKind::Missing),
+            ]
+        );
+        assert_eq!(report.failed[0].directory, ".");
+        assert_eq!(report.failed[1].expected.as_deref(), Some("dd44"));
+        assert_eq!(report.failed[1].actual.as_deref(), Some("ee55"));
+        assert_eq!(report.failed[3].actual, None);
+        assert_eq!(report.failed[3].hash_algorithm.as_deref(), Some("SHA256"));
+
+        assert_eq!(report.summary.mismatches, 3);
+        assert_eq!(report.summary.missing, 1);
+        assert_eq!(report.summary.failed_percent, Some(1.0));
+        assert_eq!(
+            report.summary.directories,
+            vec![
+                DirectoryFailures {
+                    directory: "base/16384".to_string(),
+                    failed_files: 2
+                },
+                DirectoryFailures {
+                    directory: ".".to_string(),
+                    failed_files: 1
+                },
+                DirectoryFailures {
+                    directory: "base/5".to_string(),
+                    failed_files: 1
+                },
+            ]
+        );
+        assert_eq!(
+            report.summary.text,
+            "4 of 400 files failed verification (3 checksum mismatches, 1 missing); \
+            most failures are in base/16384 (2)"
+        );
+    }
+
+    #[test]
+    fn test_verify_report_without_all_files() {
+        let report = parse_verify(&verify_response(false), "primary", "newest", "/tmp").unwrap();
+        assert_eq!(report.total_files, None);
+        assert_eq!(report.summary.failed_percent, None);
+        assert!(
+            report
+                .summary
+                .text
+                .starts_with("4 files failed verification")
+        );
+
+        let passed = r#"{"Outcome": {"Status": true}, "Response": {"Failed": [], "All": [{"FileName": "PG_VERSION"}]}}"#;
+        let report = parse_verify(passed, "primary", "20260706113000", "/tmp").unwrap();
+        assert_eq!(report.result, VerifyResult::Passed);
+        assert_eq!(report.server, "primary");
+        assert_eq!(report.backup, "20260706113000");
+        assert_eq!(report.summary.text, "All 1 files passed verification");
+
+        let failed = r#"{"Outcome": {"Status": false, "Command": 19, "Error": 805}}"#;
+        let error = parse_verify(failed, "primary", "newest", "/tmp").unwrap_err();
+        assert!(error.message.contains("Verify: network error"));
+    }
+
+    #[test]
+    fn test_verify_result_string_replaces_response() {
+        let output =
+            verify_result_string(&verify_response(true), "primary", "newest", "/tmp").unwrap();
+        let parsed: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(parsed["Outcome"]["Command"], "verify");
+        assert_eq!(parsed["Response"]["Result"], "failed");
+        assert_eq!(parsed["Response"]["FailedFiles"], 4);
+        assert_eq!(parsed["Response"]["TotalFiles"], 400);
+        assert_eq!(parsed["Response"]["Failed"][0]["FileName"], "PG_VERSION");
+        assert_eq!(parsed["Response"]["Failed"][0]["Kind"], "mismatch");
+        assert!(parsed["Response"].get("All").is_none());
+
+        let failed = r#"{"Outcome": {"Status": false, "Command": 19, "Error": 805}}"#;
+        let output = verify_result_string(failed, "primary", "newest", "/tmp").unwrap();
+        let parsed: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(parsed["Outcome"]["Error"], "Verify: network error");
+    }
+
+    #[test]
+    fn test_record_last_verification() {
+        let server = "verify_test_last_verification";
+        record_verification(server, "newest", "/tmp", &verify_response(true));
+        let last = last_verification(server, "20260706113000").unwrap();
+        assert_eq!(last.result, VerifyResult::Failed);
+        assert_eq!(last.total_files, Some(400));
+        assert_eq!(last.failed_files, 4);
+
+        // A pgmoneta failure is recorded for an explicit backup identifier only
+        let failed = r#"{"Outcome": {"Status": false, "Command": 19, "Error": 805}}"#;
+        record_verification(server, "20260706113000", "/tmp", failed);
+        let last = last_verification(server, "20260706113000").unwrap();
+        assert_eq!(last.result, VerifyResult::Error);
+        assert_eq!(
+            last.error.as_deref(),
+            Some("pgmoneta reported a failure: Verify: network error")
+        );
+        record_verification(server, "oldest", "/tmp", failed);
+        assert!(last_verification(server, "oldest").is_none());
+        assert!(last_verification(server, "20260101000000").is_none());
+    }
 }
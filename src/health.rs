@@ -0,0 +1,455 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::client::PgmonetaClient;
+use crate::configuration::{CONFIG, Configuration};
+use crate::constant::{CLIENT_VERSION, Compression, Encryption};
+use crate::security::SecurityUtil;
+use anyhow::anyhow;
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long pgmoneta may take to answer the `PING` of a readiness check.
+const PING_DEADLINE: Duration = Duration::from_secs(2);
+
+/// How long a readiness result is served before the checks run again.
+const READINESS_TTL: Duration = Duration::from_secs(10);
+
+/// The compressions the responses of pgmoneta are decoded from.
+const COMPRESSIONS: [u8; 5] = [
+    Compression::NONE,
+    Compression::GZIP,
+    Compression::ZSTD,
+    Compression::LZ4,
+    Compression::BZIP2,
+];
+
+/// The encryptions the responses of pgmoneta are decoded from.
+const ENCRYPTIONS: [u8; 4] = [
+    Encryption::NONE,
+    Encryption::AES_256_GCM,
+    Encryption::AES_192_GCM,
+    Encryption::AES_128_GCM,
+];
+
+/// The status of a readiness check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+    /// Not run, because a check it depends on failed.
+    Skipped,
+}
+
+/// A readiness check.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, result: anyhow::Result<()>) -> Self {
+        let (status, error) = match result {
+            Ok(()) => (CheckStatus::Ok, None),
+            Err(e) => (CheckStatus::Failed, Some(format!("{e:#}"))),
+        };
+        Check {
+            name,
+            status,
+            error,
+        }
+    }
+
+    fn skipped(name: &'static str) -> Self {
+        Check {
+            name,
+            status: CheckStatus::Skipped,
+            error: None,
+        }
+    }
+}
+
+/// The result of the readiness checks.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Report {
+    pub ready: bool,
+    pub checks: Vec<Check>,
+}
+
+/// The readiness of the server, checked at most once per [`READINESS_TTL`].
+pub struct Readiness {
+    /// The configuration checked; the global configuration when `None`.
+    config: Option<&'static Configuration>,
+    security_util: SecurityUtil,
+    deadline: Duration,
+    ttl: Duration,
+    last: Mutex<Option<(Instant, Report)>>,
+}
+
+impl Readiness {
+    pub fn new() -> Self {
+        Readiness {
+            config: None,
+            security_util: SecurityUtil::new(),
+            deadline: PING_DEADLINE,
+            ttl: READINESS_TTL,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// The last result if it is recent enough, or the result of new checks.
+    ///
+    /// Concurrent probes wait for the checks in progress instead of pinging
+    /// pgmoneta again.
+    pub async fn report(&self) -> Report {
+        let mut last = self.last.lock().await;
+        if let Some((checked, report)) = last.as_ref()
+            && checked.elapsed() < self.ttl
+        {
+            return report.clone();
+        }
+
+        let report = self.check().await;
+        if !report.ready {
+            tracing::warn!(checks = ?report.checks, "The server is not ready");
+        }
+        *last = Some((Instant::now(), report.clone()));
+        report
+    }
+
+    async fn check(&self) -> Report {
+        let config = self.config.or_else(|| CONFIG.get());
+        let configuration = Check::new(
+            "configuration",
+            config
+                .map(|_| ())
+                .ok_or_else(|| anyhow!("The configuration is not loaded")),
+        );
+        let master_key = Check::new(
+            "master_key",
+            self.security_util.load_master_key().map(|_| ()),
+        );
+        let pgmoneta = match config {
+            Some(config) if master_key.status == CheckStatus::Ok => {
+                Check::new("pgmoneta", self.ping(config).await)
+            }
+            _ => Check::skipped("pgmoneta"),
+        };
+
+        let checks = vec![configuration, master_key, pgmoneta];
+        Report {
+            ready: checks.iter().all(|check| check.status == CheckStatus::Ok),
+            checks,
+        }
+    }
+
+    /// Pings pgmoneta as the first admin.
+    async fn ping(&self, config: &Configuration) -> anyhow::Result<()> {
+        let username = config
+            .admins
+            .keys()
+            .min()
+            .ok_or_else(|| anyhow!("No admin is configured"))?;
+        tokio::time::timeout(
+            self.deadline,
+            PgmonetaClient::ping(config, &self.security_util, username),
+        )
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "pgmoneta did not answer the PING within {}ms",
+                self.deadline.as_millis()
+            )
+        })?
+    }
+}
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The build of the server.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Version {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub git_sha: &'static str,
+    /// The optional features the configuration enables.
+    pub features: Vec<&'static str>,
+    pub protocol: Protocol,
+}
+
+/// The pgmoneta management protocol the server speaks.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Protocol {
+    /// The pgmoneta-cli version the requests are sent as.
+    pub client_version: &'static str,
+    pub compression: Vec<&'static str>,
+    pub encryption: Vec<&'static str>,
+}
+
+impl Version {
+    pub fn new(config: Option<&Configuration>) -> Self {
+        Version {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: env!("PGMONETA_MCP_GIT_SHA"),
+            features: config.map(features).unwrap_or_default(),
+            protocol: Protocol {
+                client_version: CLIENT_VERSION,
+                compression: COMPRESSIONS
+                    .into_iter()
+                    .filter_map(|c| Compression::translate_compression_enum(c).ok())
+                    .collect(),
+                encryption: ENCRYPTIONS
+                    .into_iter()
+                    .filter_map(|e| Encryption::translate_encryption_enum(e).ok())
+                    .collect(),
+            },
+        }
+    }
+}
+
+fn features(config: &Configuration) -> Vec<&'static str> {
+    [
+        ("alerting", config.alerting.is_some()),
+        ("history", config.history.is_some()),
+        ("llm", config.llm.is_some()),
+        ("opentelemetry", config.opentelemetry.is_some()),
+        ("schedules", !config.schedules.is_empty()),
+        ("sla", !config.sla.is_empty()),
+        ("webhooks", config.webhooks.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(feature, enabled)| enabled.then_some(feature))
+    .collect()
+}
+
+/// Answers as long as the process serves requests.
+pub async fn healthz_handler() -> Response {
+    Json(serde_json::json!({ "Status": "ok" })).into_response()
+}
+
+/// Answers 200 when the server can serve tool calls, and 503 otherwise.
+pub async fn readyz_handler(State(readiness): State<Arc<Readiness>>) -> Response {
+    let report = readiness.report().await;
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report)).into_response()
+}
+
+/// Describes the build of the server.
+pub async fn version_handler() -> Response {
+    Json(Version::new(CONFIG.get())).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::load_configuration;
+    use std::io::Write;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// A pgmoneta stand-in accepting every admin without SCRAM and answering
+    /// every request with `response`, or never when `None`.
+    ///
+    /// Returns its port and the count of the requests it received.
+    async fn stand_in(response: Option<&'static str>) -> (u16, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let counter = Arc::clone(&counter);
+                tokio::spawn(async move {
+                    let len = stream.read_u32().await.unwrap() as usize;
+                    let mut startup = vec![0u8; len - 4];
+                    stream.read_exact(&mut startup).await.unwrap();
+                    // AuthenticationOk
+                    stream
+                        .write_all(&[b'R', 0, 0, 0, 8, 0, 0, 0, 0])
+                        .await
+                        .unwrap();
+
+                    let _compression = stream.read_u8().await.unwrap();
+                    let _encryption = stream.read_u8().await.unwrap();
+                    let len = stream.read_u32().await.unwrap() as usize;
+                    let mut payload = vec![0u8; len];
+                    stream.read_exact(&mut payload).await.unwrap();
+                    counter.fetch_add(1, Ordering::SeqCst);
+
+                    match response {
+                        Some(response) => {
+                            stream.write_u8(Compression::NONE).await.unwrap();
+                            stream.write_u8(Encryption::NONE).await.unwrap();
+                            stream.write_u32(response.len() as u32).await.unwrap();
+                            stream.write_all(response.as_bytes()).await.unwrap();
+                        }
+                        None => std::future::pending().await,
+                    }
+                });
+            }
+        });
+        (port, requests)
+    }
+
+    /// The readiness of a server whose pgmoneta listens on `port`.
+    fn readiness_for(port: u16, key_dir: &tempfile::TempDir, deadline: Duration) -> Readiness {
+        let security_util = SecurityUtil::new_with_path(key_dir.path().join("master.key"));
+        security_util
+            .write_master_key("test_master_password", b"test_master_salt")
+            .unwrap();
+        let (master_password, master_salt) = security_util.load_master_key().unwrap();
+        let encrypted = security_util
+            .encrypt_to_base64_string(b"backup_pass", &master_password, &master_salt)
+            .unwrap();
+
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        let mut user_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            config_file,
+            "[pgmoneta_mcp]\nport = 8000\n\n[pgmoneta]\nhost = 127.0.0.1\nport = {port}\n"
+        )
+        .unwrap();
+        writeln!(user_file, "[admins]\nbackup_user = {encrypted}\n").unwrap();
+        let config = load_configuration(
+            config_file.path().to_str().unwrap(),
+            user_file.path().to_str().unwrap(),
+        )
+        .unwrap();
+
+        Readiness {
+            config: Some(Box::leak(Box::new(config))),
+            security_util,
+            deadline,
+            ttl: READINESS_TTL,
+            last: Mutex::new(None),
+        }
+    }
+
+    async fn body(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_healthz() {
+        let response = healthz_handler().await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body(response).await["Status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_version() {
+        let response = version_handler().await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let version = body(response).await;
+        assert_eq!(version["Name"], "pgmoneta-mcp");
+        assert_eq!(version["Version"], env!("CARGO_PKG_VERSION"));
+        assert!(!version["GitSha"].as_str().unwrap().is_empty());
+        assert!(version["Features"].is_array());
+        assert_eq!(version["Protocol"]["ClientVersion"], CLIENT_VERSION);
+        assert_eq!(
+            version["Protocol"]["Encryption"],
+            serde_json::json!(["none", "aes_256_gcm", "aes_192_gcm", "aes_128_gcm"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_readyz() {
+        let key_dir = tempfile::tempdir().unwrap();
+        let (port, requests) = stand_in(Some(r#"{"Outcome":{"Status":true}}"#)).await;
+        let readiness = Arc::new(readiness_for(port, &key_dir, Duration::from_secs(60)));
+
+        let response = readyz_handler(State(Arc::clone(&readiness))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let report = body(response).await;
+        assert_eq!(report["Ready"], true);
+        assert_eq!(
+            report["Checks"],
+            serde_json::json!([
+                {"Name": "configuration", "Status": "ok"},
+                {"Name": "master_key", "Status": "ok"},
+                {"Name": "pgmoneta", "Status": "ok"},
+            ])
+        );
+
+        // The result is cached
+        let response = readyz_handler(State(readiness)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_readyz_not_ready() {
+        let key_dir = tempfile::tempdir().unwrap();
+
+        // pgmoneta answers with an error
+        let (port, _) = stand_in(Some(r#"{"Outcome":{"Status":false,"Error":2}}"#)).await;
+        let readiness = readiness_for(port, &key_dir, Duration::from_secs(60));
+        let response = readyz_handler(State(Arc::new(readiness))).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let report = body(response).await;
+        assert_eq!(report["Ready"], false);
+        assert_eq!(report["Checks"][2]["Status"], "failed");
+        assert!(
+            report["Checks"][2]["Error"]
+                .as_str()
+                .unwrap()
+                .contains("Unknown command")
+        );
+
+        // pgmoneta does not answer in time
+        let (port, _) = stand_in(None).await;
+        let readiness = readiness_for(port, &key_dir, Duration::from_millis(200));
+        let report = readiness.report().await;
+        assert!(!report.ready);
+        assert!(report.checks[2].error.as_ref().unwrap().contains("200ms"));
+
+        // The master key is not readable
+        let (port, requests) = stand_in(Some(r#"{"Outcome":{"Status":true}}"#)).await;
+        let mut readiness = readiness_for(port, &key_dir, Duration::from_secs(60));
+        readiness.security_util = SecurityUtil::new_with_path(key_dir.path().join("missing.key"));
+        let report = readiness.report().await;
+        assert!(!report.ready);
+        assert_eq!(report.checks[1].status, CheckStatus::Failed);
+        assert_eq!(report.checks[2].status, CheckStatus::Skipped);
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The local history of the pgmoneta metrics.
+//!
+//! When the `[history]` section is configured, the pgmoneta metrics endpoint
+//! is scraped every `interval` seconds and every sample is kept in a
+//! [`Store`] in the history directory, so that `query_metrics` can evaluate
+//! range queries such as `rate`, `increase` and `avg_over_time`.
+
+pub mod store;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::client::PgmonetaClient;
+use crate::configuration::{CONFIG, HistoryConfiguration};
+use crate::handler::metrics::exposition::Exposition;
+use chrono::Utc;
+use once_cell::sync::OnceCell;
+use store::{Retention, Store};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// The shortest time an instant selector looks back for the latest sample.
+const MIN_LOOKBACK: i64 = 5 * 60 * 1000;
+
+static STORE: OnceCell<Arc<Store>> = OnceCell::new();
+
+/// The store of the metrics history, once [`start`] opened it.
+pub fn store() -> Option<Arc<Store>> {
+    STORE.get().cloned()
+}
+
+/// How far an instant selector looks back for the latest sample of a series,
+/// in milliseconds: two scrapes or downsampled steps, at least five minutes.
+pub fn lookback() -> i64 {
+    CONFIG
+        .get()
+        .and_then(|config| config.history.as_ref())
+        .map(|history| 2 * i64::from(history.resolution.max(history.interval)) * 1000)
+        .unwrap_or_default()
+        .max(MIN_LOOKBACK)
+}
+
+/// The retention of a history configuration, in milliseconds.
+pub fn retention(history: &HistoryConfiguration) -> Retention {
+    Retention {
+        raw: i64::from(history.raw_retention) * 60 * 60 * 1000,
+        resolution: i64::from(history.resolution) * 1000,
+        total: i64::from(history.retention) * 24 * 60 * 60 * 1000,
+    }
+}
+
+/// Opens the store and starts scraping, unless no history is configured.
+///
+/// The loop ends when `shutdown` is cancelled.
+pub fn start(shutdown: CancellationToken) -> Option<JoinHandle<()>> {
+    let config = CONFIG.get()?;
+    let history = config.history.clone()?;
+    let Some(username) = history
+        .username
+        .clone()
+        .or_else(|| config.admins.keys().min().cloned())
+    else {
+        tracing::error!("Not keeping a metrics history: no admin is configured to scrape as");
+        return None;
+    };
+    let store = match Store::open(Path::new(&history.directory), retention(&history)) {
+        Ok(store) => Arc::new(store),
+        Err(e) => {
+            tracing::error!("Not keeping a metrics history: {:?}", e);
+            return None;
+        }
+    };
+    let _ = STORE.set(store.clone());
+
+    tracing::info!(
+        "Keeping a metrics history in {}, scraped every {} seconds",
+        history.directory,
+        history.interval
+    );
+    Some(tokio::spawn(run_loop(history, username, store, shutdown)))
+}
+
+async fn run_loop(
+    history: HistoryConfiguration,
+    username: String,
+    store: Arc<Store>,
+    shutdown: CancellationToken,
+) {
+    let mut interval =
+        tokio::time::interval(std::time::Duration::from_secs(history.interval.into()));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+        if let Err(e) = scrape(&username, store.clone()).await {
+            tracing::warn!("Failed to record the pgmoneta metrics: {:?}", e);
+        }
+    }
+    tracing::info!("Metrics history stopped");
+}
+
+/// Scrapes the metrics once into the store, then compacts it.
+async fn scrape(username: &str, store: Arc<Store>) -> anyhow::Result<()> {
+    let metrics = PgmonetaClient::request_metrics(username).await?;
+    let timestamp = Utc::now().timestamp_millis();
+    tokio::task::spawn_blocking(move || {
+        let exposition = Exposition::parse(&metrics)?;
+        let count = store.append(
+            timestamp,
+            exposition
+                .families
+                .iter()
+                .flat_map(|family| &family.samples)
+                .map(|sample| (sample.name.as_str(), &sample.labels, sample.value)),
+        )?;
+        tracing::debug!("Recorded {count} metric samples");
+        store.compact(timestamp)
+    })
+    .await?
+}
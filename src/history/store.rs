@@ -0,0 +1,660 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! An embedded, compacting time-series store.
+//!
+//! Samples are appended to `head.bin`. Once the head spans [`BLOCK_SPAN`], it
+//! is sealed into a sorted raw block `raw-<first>-<last>.bin`. Raw blocks
+//! older than the raw retention are downsampled into one block per day,
+//! `down-<day>.bin`, holding one point per series and resolution step, and
+//! blocks older than the retention are removed. The series are numbered in
+//! `series.json`. All timestamps are Unix milliseconds.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::SafeFileWriter;
+
+/// The labels of a series.
+pub type Labels = BTreeMap<String, String>;
+
+/// The span of the head before it is sealed into a block.
+pub const BLOCK_SPAN: i64 = 2 * 60 * 60 * 1000;
+
+const DAY: i64 = 24 * 60 * 60 * 1000;
+
+const SERIES_FILE: &str = "series.json";
+const HEAD_FILE: &str = "head.bin";
+const RAW_PREFIX: &str = "raw-";
+const DOWN_PREFIX: &str = "down-";
+const BLOCK_EXTENSION: &str = ".bin";
+
+/// The size of an encoded [`Record`].
+const RECORD_SIZE: usize = 32;
+
+/// How long samples are kept, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    /// How long samples are kept at full resolution.
+    pub raw: i64,
+    /// The step of the downsampled points.
+    pub resolution: i64,
+    /// How long samples are kept at all.
+    pub total: i64,
+}
+
+/// A point of a series: a sample, or the aggregate of the samples of a
+/// downsampled step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    /// The time of the last sample.
+    pub timestamp: i64,
+    /// The number of samples.
+    pub count: u32,
+    /// The mean of the samples.
+    pub mean: f64,
+    /// The last sample.
+    pub last: f64,
+}
+
+impl Point {
+    pub fn sample(timestamp: i64, value: f64) -> Self {
+        Self {
+            timestamp,
+            count: 1,
+            mean: value,
+            last: value,
+        }
+    }
+
+    /// Combines two points, `self` being the earlier one.
+    fn merge(self, later: Self) -> Self {
+        let count = self.count + later.count;
+        Self {
+            timestamp: later.timestamp,
+            count,
+            mean: (self.mean * f64::from(self.count) + later.mean * f64::from(later.count))
+                / f64::from(count),
+            last: later.last,
+        }
+    }
+}
+
+/// The points of a series.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeriesPoints {
+    pub name: String,
+    pub labels: Labels,
+    /// The points, oldest first.
+    pub points: Vec<Point>,
+}
+
+/// The size of the store.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StoreStats {
+    pub series: usize,
+    pub raw_blocks: usize,
+    pub downsampled_blocks: usize,
+    pub head_samples: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Record {
+    series: u32,
+    point: Point,
+}
+
+impl Record {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&self.series.to_le_bytes());
+        buffer.extend_from_slice(&self.point.count.to_le_bytes());
+        buffer.extend_from_slice(&self.point.timestamp.to_le_bytes());
+        buffer.extend_from_slice(&self.point.mean.to_le_bytes());
+        buffer.extend_from_slice(&self.point.last.to_le_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        Self {
+            series: u32_at(0),
+            point: Point {
+                count: u32_at(4),
+                timestamp: u64_at(8) as i64,
+                mean: f64::from_bits(u64_at(16)),
+                last: f64::from_bits(u64_at(24)),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SeriesEntry {
+    id: u32,
+    name: String,
+    labels: Labels,
+}
+
+/// A block file and the time range it covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Block {
+    path: PathBuf,
+    downsampled: bool,
+    first: i64,
+    last: i64,
+}
+
+impl Block {
+    fn parse(path: PathBuf) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.strip_suffix(BLOCK_EXTENSION)?;
+        if let Some(range) = name.strip_prefix(RAW_PREFIX) {
+            let (first, last) = range.split_once('-')?;
+            Some(Self {
+                downsampled: false,
+                first: first.parse().ok()?,
+                last: last.parse().ok()?,
+                path,
+            })
+        } else {
+            let day = name.strip_prefix(DOWN_PREFIX)?.parse::<i64>().ok()?;
+            Some(Self {
+                downsampled: true,
+                first: day,
+                last: day + DAY - 1,
+                path,
+            })
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Index {
+    entries: Vec<SeriesEntry>,
+    ids: HashMap<(String, Labels), u32>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    index: Index,
+    /// The time of the first sample in the head.
+    head_first: Option<i64>,
+    head_samples: usize,
+}
+
+/// A time-series store in a directory.
+#[derive(Debug)]
+pub struct Store {
+    directory: PathBuf,
+    retention: Retention,
+    inner: Mutex<Inner>,
+}
+
+impl Store {
+    /// Opens the store in `directory`, creating the directory when missing.
+    pub fn open(directory: &Path, retention: Retention) -> Result<Self> {
+        fs::create_dir_all(directory)
+            .with_context(|| format!("Failed to create '{}'", directory.display()))?;
+
+        let mut index = Index::default();
+        let series_path = directory.join(SERIES_FILE);
+        if series_path.exists() {
+            let contents = fs::read_to_string(&series_path)
+                .with_context(|| format!("Failed to read '{}'", series_path.display()))?;
+            index.entries = serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse '{}'", series_path.display()))?;
+            index.ids = index
+                .entries
+                .iter()
+                .map(|entry| ((entry.name.clone(), entry.labels.clone()), entry.id))
+                .collect();
+        }
+
+        let head = read_records(&directory.join(HEAD_FILE))?;
+        let inner = Inner {
+            index,
+            head_first: head.iter().map(|record| record.point.timestamp).min(),
+            head_samples: head.len(),
+        };
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            retention,
+            inner: Mutex::new(inner),
+        })
+    }
+
+    /// Appends samples taken at `timestamp` to the head.
+    pub fn append<'a>(
+        &self,
+        timestamp: i64,
+        samples: impl IntoIterator<Item = (&'a str, &'a Labels, f64)>,
+    ) -> Result<usize> {
+        let mut inner = self.lock();
+        let known = inner.index.entries.len();
+
+        let mut buffer = Vec::new();
+        let mut count = 0;
+        for (name, labels, value) in samples {
+            let key = (name.to_string(), labels.clone());
+            let series = match inner.index.ids.get(&key) {
+                Some(id) => *id,
+                None => {
+                    let id = u32::try_from(inner.index.entries.len())
+                        .map_err(|_| anyhow!("Too many series in the metrics history"))?;
+                    inner.index.entries.push(SeriesEntry {
+                        id,
+                        name: key.0.clone(),
+                        labels: key.1.clone(),
+                    });
+                    inner.index.ids.insert(key, id);
+                    id
+                }
+            };
+            Record {
+                series,
+                point: Point::sample(timestamp, value),
+            }
+            .encode(&mut buffer);
+            count += 1;
+        }
+
+        // The series are saved first, so that every record in the head refers to a known series
+        if inner.index.entries.len() > known {
+            let contents = serde_json::to_string(&inner.index.entries)?;
+            SafeFileWriter::new(&self.directory.to_string_lossy())
+                .allowed_extensions(vec!["json"])
+                .write(SERIES_FILE, &contents)?;
+        }
+        let head = self.directory.join(HEAD_FILE);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&head)
+            .and_then(|mut file| file.write_all(&buffer))
+            .with_context(|| format!("Failed to append to '{}'", head.display()))?;
+
+        inner.head_first.get_or_insert(timestamp);
+        inner.head_samples += count;
+        Ok(count)
+    }
+
+    /// Seals the head into a block once it spans [`BLOCK_SPAN`], downsamples
+    /// the raw blocks older than the raw retention and removes the blocks
+    /// older than the retention.
+    pub fn compact(&self, now: i64) -> Result<()> {
+        let mut inner = self.lock();
+
+        if inner
+            .head_first
+            .is_some_and(|first| first <= now - BLOCK_SPAN)
+        {
+            let head = self.directory.join(HEAD_FILE);
+            let mut records = read_records(&head)?;
+            sort_records(&mut records);
+            if let (Some(first), Some(last)) = (
+                records.iter().map(|record| record.point.timestamp).min(),
+                records.iter().map(|record| record.point.timestamp).max(),
+            ) {
+                self.write_block(&format!("{RAW_PREFIX}{first}-{last}"), &records)?;
+            }
+            fs::remove_file(&head)
+                .with_context(|| format!("Failed to remove '{}'", head.display()))?;
+            inner.head_first = None;
+            inner.head_samples = 0;
+        }
+
+        let cutoff = now - self.retention.total;
+        let raw_cutoff = now - self.retention.raw;
+        let mut downsample: BTreeMap<i64, Vec<Block>> = BTreeMap::new();
+        for block in self.blocks()? {
+            if block.last < cutoff {
+                fs::remove_file(&block.path)
+                    .with_context(|| format!("Failed to remove '{}'", block.path.display()))?;
+            } else if !block.downsampled && block.last < raw_cutoff {
+                downsample
+                    .entry(block.first.div_euclid(DAY) * DAY)
+                    .or_default()
+                    .push(block);
+            }
+        }
+
+        for (day, blocks) in downsample {
+            let name = format!("{DOWN_PREFIX}{day}");
+            let existing = self.directory.join(format!("{name}{BLOCK_EXTENSION}"));
+            let mut records = read_records(&existing)?;
+            for block in &blocks {
+                records.extend(read_records(&block.path)?);
+            }
+            let records = downsample_records(records, self.retention.resolution);
+            self.write_block(&name, &records)?;
+            for block in blocks {
+                fs::remove_file(&block.path)
+                    .with_context(|| format!("Failed to remove '{}'", block.path.display()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The points between `start` and `end`, inclusive, of the series named
+    /// one of `names`, or of all series for `None`.
+    pub fn read(
+        &self,
+        start: i64,
+        end: i64,
+        names: Option<&BTreeSet<String>>,
+    ) -> Result<Vec<SeriesPoints>> {
+        let inner = self.lock();
+        let wanted = |series: u32| {
+            inner
+                .index
+                .entries
+                .get(series as usize)
+                .is_some_and(|entry| names.is_none_or(|names| names.contains(&entry.name)))
+        };
+
+        let mut paths = self
+            .blocks()?
+            .into_iter()
+            .filter(|block| block.last >= start && block.first <= end)
+            .map(|block| block.path)
+            .collect::<Vec<_>>();
+        paths.push(self.directory.join(HEAD_FILE));
+
+        let mut points: BTreeMap<u32, Vec<Point>> = BTreeMap::new();
+        for path in paths {
+            for record in read_records(&path)? {
+                if (start..=end).contains(&record.point.timestamp) && wanted(record.series) {
+                    points.entry(record.series).or_default().push(record.point);
+                }
+            }
+        }
+
+        Ok(points
+            .into_iter()
+            .map(|(series, mut points)| {
+                points.sort_by_key(|point| point.timestamp);
+                // A crash between sealing the head and removing it leaves duplicates
+                points.dedup_by_key(|point| point.timestamp);
+                let entry = &inner.index.entries[series as usize];
+                SeriesPoints {
+                    name: entry.name.clone(),
+                    labels: entry.labels.clone(),
+                    points,
+                }
+            })
+            .collect())
+    }
+
+    /// The number of series, blocks and head samples, and the size on disk.
+    pub fn stats(&self) -> Result<StoreStats> {
+        let inner = self.lock();
+        let blocks = self.blocks()?;
+        let bytes = blocks
+            .iter()
+            .map(|block| block.path.clone())
+            .chain([self.directory.join(HEAD_FILE)])
+            .filter_map(|path| fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        Ok(StoreStats {
+            series: inner.index.entries.len(),
+            raw_blocks: blocks.iter().filter(|block| !block.downsampled).count(),
+            downsampled_blocks: blocks.iter().filter(|block| block.downsampled).count(),
+            head_samples: inner.head_samples,
+            bytes,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn blocks(&self) -> Result<Vec<Block>> {
+        let entries = fs::read_dir(&self.directory)
+            .with_context(|| format!("Failed to read '{}'", self.directory.display()))?;
+        let mut blocks = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| Block::parse(entry.path()))
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|block| (block.first, block.downsampled));
+        Ok(blocks)
+    }
+
+    /// Writes a block through a temporary file, so that it is complete or absent.
+    fn write_block(&self, name: &str, records: &[Record]) -> Result<()> {
+        let mut buffer = Vec::with_capacity(records.len() * RECORD_SIZE);
+        for record in records {
+            record.encode(&mut buffer);
+        }
+        let path = self.directory.join(format!("{name}{BLOCK_EXTENSION}"));
+        let temporary = self.directory.join(format!("{name}.tmp"));
+        File::create(&temporary)
+            .and_then(|mut file| {
+                file.write_all(&buffer)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&temporary, &path))
+            .with_context(|| format!("Failed to write '{}'", path.display()))
+    }
+}
+
+/// Reads the records of a head or block file; a missing file has none.
+///
+/// A partially written record at the end, left by a crash, is ignored.
+fn read_records(path: &Path) -> Result<Vec<Record>> {
+    let mut bytes = Vec::new();
+    match File::open(path) {
+        Ok(mut file) => file
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(anyhow!("Failed to open '{}': {}", path.display(), e)),
+    };
+    Ok(bytes
+        .chunks_exact(RECORD_SIZE)
+        .map(Record::decode)
+        .collect())
+}
+
+fn sort_records(records: &mut [Record]) {
+    records.sort_by_key(|record| (record.series, record.point.timestamp));
+}
+
+/// Merges the records of each series into one per `resolution` step.
+fn downsample_records(mut records: Vec<Record>, resolution: i64) -> Vec<Record> {
+    sort_records(&mut records);
+    let mut downsampled: Vec<Record> = Vec::new();
+    for record in records {
+        match downsampled.last_mut() {
+            Some(previous)
+                if previous.series == record.series
+                    && previous.point.timestamp.div_euclid(resolution)
+                        == record.point.timestamp.div_euclid(resolution) =>
+            {
+                if previous.point.timestamp != record.point.timestamp {
+                    previous.point = previous.point.merge(record.point);
+                }
+            }
+            _ => downsampled.push(record),
+        }
+    }
+    downsampled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 60 * 60 * 1000;
+
+    fn retention() -> Retention {
+        Retention {
+            raw: 2 * DAY,
+            resolution: 5 * 60 * 1000,
+            total: 30 * DAY,
+        }
+    }
+
+    fn labels(name: &str) -> Labels {
+        Labels::from([("name".to_string(), name.to_string())])
+    }
+
+    fn append(store: &Store, timestamp: i64, value: f64) {
+        let primary = labels("primary");
+        let replica = labels("replica");
+        store
+            .append(
+                timestamp,
+                [
+                    ("pgmoneta_backup_count", &primary, value),
+                    ("pgmoneta_backup_count", &replica, value * 2.0),
+                    ("pgmoneta_state", &primary, 1.0),
+                ],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let directory = tempfile::tempdir().unwrap();
+        let store = Store::open(directory.path(), retention()).unwrap();
+        for minute in 0..10 {
+            append(&store, DAY + minute * 60_000, minute as f64);
+        }
+
+        let names = BTreeSet::from(["pgmoneta_backup_count".to_string()]);
+        let series = store.read(DAY, DAY + 4 * 60_000, Some(&names)).unwrap();
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].labels, labels("primary"));
+        assert_eq!(
+            series[0]
+                .points
+                .iter()
+                .map(|point| point.last)
+                .collect::<Vec<_>>(),
+            vec![0.0, 1.0, 2.0, 3.0, 4.0]
+        );
+        assert_eq!(series[1].points[4].last, 8.0);
+        assert!(store.read(0, DAY - 1, None).unwrap().is_empty());
+
+        let stats = store.stats().unwrap();
+        assert_eq!(stats.series, 3);
+        assert_eq!(stats.head_samples, 30);
+        assert_eq!(stats.raw_blocks, 0);
+    }
+
+    #[test]
+    fn test_compact_seals_the_head_and_survives_reopening() {
+        let directory = tempfile::tempdir().unwrap();
+        let store = Store::open(directory.path(), retention()).unwrap();
+        append(&store, DAY, 1.0);
+        append(&store, DAY + HOUR, 2.0);
+        store.compact(DAY + HOUR).unwrap();
+        assert_eq!(store.stats().unwrap().raw_blocks, 0);
+
+        append(&store, DAY + BLOCK_SPAN, 3.0);
+        store.compact(DAY + BLOCK_SPAN).unwrap();
+        let stats = store.stats().unwrap();
+        assert_eq!(stats.raw_blocks, 1);
+        assert_eq!(stats.head_samples, 0);
+        append(&store, DAY + BLOCK_SPAN + HOUR, 4.0);
+        drop(store);
+
+        let store = Store::open(directory.path(), retention()).unwrap();
+        assert_eq!(store.stats().unwrap().head_samples, 3);
+        let series = store.read(0, 2 * DAY, None).unwrap();
+        assert_eq!(series.len(), 3);
+        assert_eq!(
+            series[0]
+                .points
+                .iter()
+                .map(|point| point.last)
+                .collect::<Vec<_>>(),
+            vec![1.0, 2.0, 3.0, 4.0]
+        );
+    }
+
+    #[test]
+    fn test_compact_downsamples_and_expires() {
+        let directory = tempfile::tempdir().unwrap();
+        let store = Store::open(directory.path(), retention()).unwrap();
+        // One sample a minute for 10 minutes: two 5 minute steps
+        for minute in 0..10 {
+            append(&store, DAY + minute * 60_000, minute as f64);
+        }
+        store.compact(DAY + BLOCK_SPAN).unwrap();
+        store.compact(4 * DAY).unwrap();
+        let stats = store.stats().unwrap();
+        assert_eq!(stats.raw_blocks, 0);
+        assert_eq!(stats.downsampled_blocks, 1);
+
+        let names = BTreeSet::from(["pgmoneta_backup_count".to_string()]);
+        let series = store.read(0, 2 * DAY, Some(&names)).unwrap();
+        assert_eq!(
+            series[0].points,
+            vec![
+                Point {
+                    timestamp: DAY + 4 * 60_000,
+                    count: 5,
+                    mean: 2.0,
+                    last: 4.0,
+                },
+                Point {
+                    timestamp: DAY + 9 * 60_000,
+                    count: 5,
+                    mean: 7.0,
+                    last: 9.0,
+                },
+            ]
+        );
+
+        store.compact(32 * DAY).unwrap();
+        assert_eq!(store.stats().unwrap().downsampled_blocks, 0);
+        assert!(store.read(0, 2 * DAY, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_read_records_ignores_a_partial_record() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join(HEAD_FILE);
+        let mut buffer = Vec::new();
+        Record {
+            series: 7,
+            point: Point::sample(DAY, 1.5),
+        }
+        .encode(&mut buffer);
+        buffer.extend_from_slice(&[1, 2, 3]);
+        fs::write(&path, &buffer).unwrap();
+
+        let records = read_records(&path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].series, 7);
+        assert_eq!(records[0].point, Point::sample(DAY, 1.5));
+        assert!(
+            read_records(&directory.path().join("missing.bin"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
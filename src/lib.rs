@@ -23,20 +23,34 @@
 //! * **`constant`**: Defines standard codes, commands, and formatting rules.
 //! * **`client`**: Manages low-level TCP communication with the pgmoneta server.
 //! * **`handler`**: Implements the MCP protocol and routes tool calls.
+//! * **`scheduler`**: Runs the scheduled backups of the configured servers.
+//! * **`history`**: Keeps a local history of the pgmoneta metrics.
+//! * **`alerts`**: Evaluates the alert rules and notifies their sinks.
+//! * **`webhooks`**: Posts the backup events to the configured webhooks.
+//! * **`activity`**: Streams the activity of the server as Server-Sent Events.
+//! * **`otel`**: Exports the traces of the requests with OpenTelemetry.
+//! * **`health`**: Answers the liveness, readiness and version probes.
 //! * **`compression`**: Handles data compression and decompression.
 //! * **`security`**: Handles master key management, AES encryption, and SCRAM authentication.
 //! * **`utils`**: Provides shared helper functions.
 
+pub mod activity;
 pub mod agent;
+pub mod alerts;
 pub mod compression;
 pub mod configuration;
 pub mod constant;
 pub mod handler;
+pub mod health;
+pub mod history;
 pub mod llm;
 
 mod client;
 pub mod logging;
 pub mod mcp_client;
+pub mod otel;
+pub mod scheduler;
 pub mod security;
 pub mod telemetry;
 pub mod utils;
+pub mod webhooks;
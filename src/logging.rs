@@ -15,7 +15,9 @@
 
 use super::constant::{LogLevel, LogType};
 use crate::constant::LogMode;
+use crate::otel;
 use anyhow::Context;
+use opentelemetry_sdk::trace::SdkTracer;
 use std::fs::OpenOptions;
 
 #[cfg(unix)]
@@ -54,6 +56,7 @@ impl Logger {
     /// * `log_path` - The file path (required if `log_type` is "file").
     /// * `log_mode` - "create" (overwrite) or "append".
     /// * `log_rotation_age` - Rotation policy (e.g., "1h", "1d").
+    /// * `tracer` - The tracer exporting the spans, if traces are configured.
     ///
     /// # Returns
     ///
@@ -66,6 +69,7 @@ impl Logger {
         log_path: &str,
         log_mode: &str,
         log_rotation_age: &str,
+        tracer: Option<SdkTracer>,
     ) -> Option<WorkerGuard> {
         let (writer, guard) = Self::make_writer(log_type, log_path, log_mode, log_rotation_age)
             .unwrap_or_else(|e| {
@@ -80,8 +84,15 @@ impl Logger {
             .with_target("pgmoneta_mcp", level)
             .with_target("tokio", LevelFilter::WARN)
             .with_target("rmcp", LevelFilter::WARN)
+            .with_target(otel::SPAN_TARGET, LevelFilter::OFF)
             .with_default(LevelFilter::OFF);
+        let spans = tracer.map(|tracer| {
+            tracing_opentelemetry::layer()
+                .with_tracer(tracer)
+                .with_filter(Targets::new().with_target(otel::SPAN_TARGET, LevelFilter::TRACE))
+        });
         Registry::default()
+            .with(spans)
             .with(
                 tracing_subscriber::fmt::layer()
                     .with_line_number(true)
@@ -0,0 +1,407 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::configuration::{OpenTelemetryConfiguration, TraceExporter};
+use anyhow::{Context as _, anyhow};
+use axum::http::Request;
+use axum::http::request::Parts;
+use axum::middleware::Next;
+use axum::response::Response;
+use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry::trace::{SpanId, TraceContextExt, TraceId, TracerProvider};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_http::HeaderExtractor;
+use opentelemetry_otlp::{WithExportConfig, WithTonicConfig};
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::transform::common::tonic::ResourceAttributesWithSchema;
+use opentelemetry_proto::transform::trace::tonic::group_spans_by_resource_and_scope;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{
+    IdGenerator, RandomIdGenerator, Sampler, SdkTracer, SdkTracerProvider, SpanData, SpanExporter,
+};
+use rmcp::model::Meta;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// The target of the spans exported as traces.
+///
+/// The spans are not logged, and only exported when `[opentelemetry]` is configured.
+pub const SPAN_TARGET: &str = "pgmoneta_mcp::spans";
+
+/// The W3C trace context header and `_meta` key.
+const TRACEPARENT: &str = "traceparent";
+
+/// The instrumentation scope of the spans.
+const SCOPE: &str = "pgmoneta-mcp";
+
+/// Builds the tracer provider exporting the traces as configured.
+///
+/// The provider is also installed as the global provider. It must be shut
+/// down at exit to flush the pending spans.
+pub fn init(config: &OpenTelemetryConfiguration) -> anyhow::Result<SdkTracerProvider> {
+    let timeout = Duration::from_secs(config.timeout.into());
+    let resource = Resource::builder()
+        .with_service_name(config.service_name.clone())
+        .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")))
+        .build();
+    let builder = SdkTracerProvider::builder()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(resource);
+
+    let provider = match config.exporter {
+        TraceExporter::OtlpHttp => {
+            let mut exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .with_timeout(timeout);
+            if let Some(endpoint) = &config.endpoint {
+                exporter = exporter.with_endpoint(http_traces_endpoint(endpoint)?);
+            }
+            builder.with_batch_exporter(exporter.build()?).build()
+        }
+        TraceExporter::OtlpGrpc => {
+            let mut exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_tonic()
+                .with_timeout(timeout);
+            if let Some(endpoint) = &config.endpoint {
+                if endpoint.starts_with("https:") {
+                    exporter = exporter.with_tls_config(
+                        opentelemetry_otlp::tonic_types::transport::ClientTlsConfig::new()
+                            .with_enabled_roots(),
+                    );
+                }
+                exporter = exporter.with_endpoint(endpoint.clone());
+            }
+            builder.with_batch_exporter(exporter.build()?).build()
+        }
+        TraceExporter::File => {
+            let path = config
+                .file
+                .as_deref()
+                .ok_or_else(|| anyhow!("The file exporter needs a file"))?;
+            builder
+                .with_batch_exporter(FileExporter::open(path)?)
+                .build()
+        }
+    };
+
+    opentelemetry::global::set_tracer_provider(provider.clone());
+    Ok(provider)
+}
+
+/// The tracer creating the spans of the `tracing` layer.
+pub fn tracer(provider: &SdkTracerProvider) -> SdkTracer {
+    provider.tracer(SCOPE)
+}
+
+/// The OTLP/HTTP traces URL of a collector URL without a path.
+///
+/// The exporter posts to a configured endpoint as is, unlike to the
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` environment variable.
+fn http_traces_endpoint(endpoint: &str) -> anyhow::Result<String> {
+    let mut url = reqwest::Url::parse(endpoint)
+        .with_context(|| format!("Invalid OpenTelemetry endpoint '{endpoint}'"))?;
+    if url.path() == "/" {
+        url.set_path("/v1/traces");
+    }
+    Ok(url.to_string())
+}
+
+/// Traces a request to `/mcp` as a server span.
+///
+/// The span continues the trace of the `traceparent` header, if any, and its
+/// context is handed to the tool calls of the request through the extensions.
+pub async fn mcp_middleware(mut request: Request<axum::body::Body>, next: Next) -> Response {
+    let method = request.method().to_string();
+    let span = tracing::info_span!(
+        target: SPAN_TARGET,
+        "mcp.request",
+        otel.name = format!("{method} /mcp"),
+        otel.kind = "server",
+        http.request.method = method,
+        http.route = "/mcp",
+        http.response.status_code = tracing::field::Empty,
+    );
+    if let Some(parent) =
+        remote_context(&TraceContextPropagator::new().extract(&HeaderExtractor(request.headers())))
+    {
+        let _ = span.set_parent(parent);
+    }
+    request.extensions_mut().insert(span.context());
+
+    let mut response = next.run(request).instrument(span.clone()).await;
+    span.record("http.response.status_code", response.status().as_u16());
+    // The exemplar of the request latency, see `telemetry::metrics_middleware`
+    response
+        .extensions_mut()
+        .insert(span.context().span().span_context().clone());
+    response
+}
+
+/// The parent of the span of a tool call.
+///
+/// A `traceparent` in the `_meta` of the call wins over the context of the
+/// HTTP request, which is either its `mcp.request` span or its `traceparent`
+/// header.
+pub fn tool_parent(meta: &Meta, parts: Option<&Parts>) -> Option<Context> {
+    let propagator = TraceContextPropagator::new();
+    remote_context(&propagator.extract(&MetaCarrier(meta)))
+        .or_else(|| parts?.extensions.get::<Context>().cloned())
+        .filter(|cx| cx.span().span_context().is_valid())
+        .or_else(|| remote_context(&propagator.extract(&HeaderExtractor(&parts?.headers))))
+}
+
+/// Writes the trace context of the current span into the `_meta` of a request.
+///
+/// When the current span is not exported, the request still carries a
+/// `traceparent` of the given trace so the server groups the calls.
+pub fn inject(meta: &mut Meta, fallback: TraceId) {
+    let cx = tracing::Span::current().context();
+    if cx.span().span_context().is_valid() {
+        TraceContextPropagator::new().inject_context(&cx, &mut MetaCarrier(meta));
+    } else {
+        meta.0.insert(
+            TRACEPARENT.to_string(),
+            traceparent(fallback, RandomIdGenerator::default().new_span_id()).into(),
+        );
+    }
+}
+
+/// A new random trace id.
+pub fn new_trace_id() -> TraceId {
+    RandomIdGenerator::default().new_trace_id()
+}
+
+/// A sampled W3C `traceparent`.
+fn traceparent(trace_id: TraceId, span_id: SpanId) -> String {
+    format!("00-{trace_id}-{span_id}-01")
+}
+
+/// The context if it holds a valid remote span.
+fn remote_context(cx: &Context) -> Option<Context> {
+    cx.span().span_context().is_valid().then(|| cx.clone())
+}
+
+/// The `_meta` of a request as a propagation carrier.
+struct MetaCarrier<M>(M);
+
+impl Extractor for MetaCarrier<&Meta> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.0.get(key)?.as_str()
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.0.keys().map(String::as_str).collect()
+    }
+}
+
+impl Injector for MetaCarrier<&mut Meta> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.0.insert(key.to_string(), value.into());
+    }
+}
+
+/// Appends the batches as OTLP JSON lines to a file.
+#[derive(Debug)]
+struct FileExporter {
+    file: Mutex<File>,
+    resource: ResourceAttributesWithSchema,
+}
+
+impl FileExporter {
+    fn open(path: &str) -> anyhow::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open trace file {path}"))?;
+        Ok(Self {
+            file: Mutex::new(file),
+            resource: ResourceAttributesWithSchema::default(),
+        })
+    }
+
+    fn write(&self, batch: Vec<SpanData>) -> anyhow::Result<()> {
+        let request = ExportTraceServiceRequest {
+            resource_spans: group_spans_by_resource_and_scope(batch, &self.resource),
+        };
+        let mut line = serde_json::to_vec(&request)?;
+        line.push(b'\n');
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| anyhow!("Trace file lock poisoned"))?;
+        file.write_all(&line)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+impl SpanExporter for FileExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        self.write(batch)
+            .map_err(|e| OTelSdkError::InternalFailure(format!("{e:#}")))
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.resource = resource.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::{SpanContext, TraceFlags, TraceState};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn remote(trace_id: u128, span_id: u64) -> Context {
+        Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from(trace_id),
+            SpanId::from(span_id),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        ))
+    }
+
+    fn meta(traceparent: &str) -> Meta {
+        let mut meta = Meta::new();
+        meta.0.insert(TRACEPARENT.to_string(), traceparent.into());
+        meta
+    }
+
+    #[test]
+    fn test_http_traces_endpoint() {
+        for (endpoint, expected) in [
+            ("http://collector:4318", "http://collector:4318/v1/traces"),
+            (
+                "https://collector:4318/",
+                "https://collector:4318/v1/traces",
+            ),
+            (
+                "http://collector/otlp/v1/traces",
+                "http://collector/otlp/v1/traces",
+            ),
+        ] {
+            assert_eq!(http_traces_endpoint(endpoint).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_tool_parent() {
+        let traceparent = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+        let mut parts = Request::builder()
+            .header(
+                TRACEPARENT,
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            )
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0;
+
+        let parent = tool_parent(&meta(traceparent), Some(&parts)).unwrap();
+        assert_eq!(
+            parent.span().span_context().trace_id(),
+            TraceId::from_hex("0af7651916cd43dd8448eb211c80319c").unwrap()
+        );
+
+        let parent = tool_parent(&Meta::new(), Some(&parts)).unwrap();
+        assert_eq!(
+            parent.span().span_context().trace_id(),
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap()
+        );
+
+        parts.extensions.insert(remote(7, 8));
+        let parent = tool_parent(&meta("garbage"), Some(&parts)).unwrap();
+        assert_eq!(parent.span().span_context().trace_id(), TraceId::from(7));
+
+        assert!(tool_parent(&Meta::new(), None).is_none());
+    }
+
+    #[test]
+    fn test_inject() {
+        let trace_id = TraceId::from(0x0af7651916cd43dd8448eb211c80319c);
+        let mut meta = Meta::new();
+        inject(&mut meta, trace_id);
+        let value = meta.0[TRACEPARENT].as_str().unwrap();
+        let fields: Vec<&str> = value.split('-').collect();
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[0], "00");
+        assert_eq!(fields[1], "0af7651916cd43dd8448eb211c80319c");
+        assert_eq!(fields[2].len(), 16);
+        assert_eq!(fields[3], "01");
+
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(tracer(&provider)));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("agent.tool_call");
+            let _ = span.set_parent(remote(7, 8));
+            let _entered = span.enter();
+            let mut meta = Meta::new();
+            inject(&mut meta, trace_id);
+            let parent = TraceContextPropagator::new().extract(&MetaCarrier(&meta));
+            let span_context = parent.span().span_context().clone();
+            assert_eq!(span_context.trace_id(), TraceId::from(7));
+            assert_ne!(span_context.span_id(), SpanId::from(8));
+        });
+    }
+
+    #[tokio::test]
+    async fn test_file_exporter() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let provider = init(&OpenTelemetryConfiguration {
+            exporter: TraceExporter::File,
+            file: Some(file.path().to_str().unwrap().to_string()),
+            service_name: "pgmoneta-mcp-test".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(tracer(&provider)));
+        tracing::subscriber::with_default(subscriber, || {
+            let span =
+                tracing::info_span!(target: SPAN_TARGET, "pgmoneta.request", otel.kind = "client");
+            let _ = span.set_parent(remote(7, 8));
+            let _entered = span.enter();
+        });
+        provider.shutdown().unwrap();
+
+        let content = std::fs::read_to_string(file.path()).unwrap();
+        let line: serde_json::Value =
+            serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        let resource_spans = &line["resourceSpans"][0];
+        assert!(
+            resource_spans["resource"]["attributes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|kv| kv["key"] == "service.name"
+                    && kv["value"]["stringValue"] == "pgmoneta-mcp-test")
+        );
+        let span = &resource_spans["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["name"], "pgmoneta.request");
+        assert_eq!(span["traceId"], "00000000000000000000000000000007");
+        assert_eq!(span["parentSpanId"], "0000000000000008");
+    }
+}
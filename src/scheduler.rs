@@ -0,0 +1,690 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Scheduled backups.
+//!
+//! Every `[schedule:<server>]` section of the configuration describes a cron
+//! schedule. Each run takes a full backup of the server, then optionally
+//! verifies and archives it. Runs start up to `jitter` seconds after their
+//! cron time, at most one run per server is in progress at a time, and a run
+//! due while the previous one is still in progress is recorded as skipped.
+//!
+//! The paused schedules and the history of the runs are kept in
+//! `schedules.json` of the `state_directory`, when one is configured.
+
+pub mod cron;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
+
+use crate::activity::{self, Activity, ActivityKind};
+use crate::client::PgmonetaClient;
+use crate::configuration::{CONFIG, ScheduleConfiguration};
+use crate::handler::PgmonetaHandler;
+use crate::handler::catalog::{self, BackupClock};
+use crate::handler::events::{self, Event, EventKind, EventSource};
+use crate::handler::verify::{self, VerifyResult};
+use crate::utils::{SafeFileWriter, SnapshotWriter};
+use anyhow::bail;
+use chrono::{Duration, NaiveDateTime};
+use cron::CronSchedule;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// The file in `state_directory` holding the paused schedules and run history.
+const STATE_FILE: &str = "schedules.json";
+
+/// The number of runs kept per server.
+const HISTORY_LIMIT: usize = 50;
+
+/// The longest the loop sleeps before looking at the schedules again.
+const MAX_SLEEP: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// The archive position of scheduled archives.
+const ARCHIVE_POSITION: &str = "current";
+
+/// What started a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trigger {
+    Schedule,
+    Manual,
+}
+
+/// The outcome of a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+/// A step of a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Step {
+    Backup,
+    Verify,
+    Archive,
+}
+
+/// The outcome of a step of a run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StepRecord {
+    pub step: Step,
+    pub succeeded: bool,
+    pub seconds: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A finished, failed or skipped run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RunRecord {
+    pub trigger: Trigger,
+    pub status: RunStatus,
+    pub started: String,
+    pub finished: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup: Option<String>,
+    #[serde(default)]
+    pub steps: Vec<StepRecord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl RunRecord {
+    fn skipped(trigger: Trigger, message: &str) -> Self {
+        let clock = BackupClock::configured();
+        let now = clock.format(clock.now());
+        Self {
+            trigger,
+            status: RunStatus::Skipped,
+            started: now.clone(),
+            finished: now,
+            backup: None,
+            steps: Vec::new(),
+            message: Some(message.to_string()),
+        }
+    }
+}
+
+/// The state of the schedule of a server.
+#[derive(Clone, Debug, Default)]
+pub struct ScheduleState {
+    pub paused: bool,
+    pub running: bool,
+    pub next_run: Option<NaiveDateTime>,
+    /// The runs, newest first.
+    pub history: Vec<RunRecord>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PersistedState {
+    #[serde(default)]
+    paused: BTreeSet<String>,
+    #[serde(default)]
+    history: BTreeMap<String, Vec<RunRecord>>,
+}
+
+#[derive(Debug, Default)]
+struct SchedulerState {
+    persisted: PersistedState,
+    running: BTreeSet<String>,
+    next_run: BTreeMap<String, NaiveDateTime>,
+}
+
+static STATE: Mutex<Option<SchedulerState>> = Mutex::new(None);
+
+/// Writes the snapshots of [`STATE`] taken by [`snapshot`].
+static SNAPSHOTS: SnapshotWriter = SnapshotWriter::new();
+
+/// Marks a server as running for as long as it lives.
+struct RunGuard(String);
+
+impl RunGuard {
+    fn acquire(server: &str) -> Option<Self> {
+        with_state(|state| state.running.insert(server.to_string()))
+            .then(|| Self(server.to_string()))
+    }
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        with_state(|state| state.running.remove(&self.0));
+    }
+}
+
+/// The configured schedules.
+pub fn schedules() -> Vec<ScheduleConfiguration> {
+    CONFIG
+        .get()
+        .map(|config| config.schedules.clone())
+        .unwrap_or_default()
+}
+
+/// The state of the schedule of a server.
+pub fn state(server: &str) -> ScheduleState {
+    with_state(|state| ScheduleState {
+        paused: state.persisted.paused.contains(server),
+        running: state.running.contains(server),
+        next_run: state.next_run.get(server).copied(),
+        history: state
+            .persisted
+            .history
+            .get(server)
+            .map(|runs| runs.iter().rev().cloned().collect())
+            .unwrap_or_default(),
+    })
+}
+
+/// Pauses or resumes the schedule of a server, returning whether it changed.
+///
+/// A paused schedule does not run on its own, but can still be run now.
+pub fn set_paused(server: &str, paused: bool) -> bool {
+    let (changed, snapshot) = with_state(|state| {
+        let changed = if paused {
+            state.persisted.paused.insert(server.to_string())
+        } else {
+            state.persisted.paused.remove(server)
+        };
+        (changed, changed.then(|| snapshot(state)))
+    });
+    if let Some((generation, persisted)) = snapshot {
+        persist(generation, persisted);
+    }
+    changed
+}
+
+/// Starts a run of a schedule in the background, as `username`.
+///
+/// Fails when a run of the server is already in progress.
+pub fn run_now(schedule: &ScheduleConfiguration, username: &str) -> anyhow::Result<()> {
+    let guard = RunGuard::acquire(&schedule.server)
+        .ok_or_else(|| anyhow::anyhow!("A run of {} is already in progress", schedule.server))?;
+    tokio::spawn(execute(
+        schedule.clone(),
+        username.to_string(),
+        Trigger::Manual,
+        guard,
+    ));
+    Ok(())
+}
+
+/// Starts the scheduler loop, unless no schedule is configured.
+///
+/// The loop ends when `shutdown` is cancelled; runs in progress are abandoned.
+pub fn start(shutdown: CancellationToken) -> Option<JoinHandle<()>> {
+    let mut schedules = Vec::new();
+    for schedule in self::schedules() {
+        match CronSchedule::parse(&schedule.cron) {
+            Ok(cron) => schedules.push((schedule, cron)),
+            Err(e) => tracing::error!("Ignoring [{}]: {}", schedule.section(), e),
+        }
+    }
+    if schedules.is_empty() {
+        return None;
+    }
+    tracing::info!("Scheduling backups of {} servers", schedules.len());
+    Some(tokio::spawn(run_loop(schedules, shutdown)))
+}
+
+async fn run_loop(
+    schedules: Vec<(ScheduleConfiguration, CronSchedule)>,
+    shutdown: CancellationToken,
+) {
+    let clock = BackupClock::configured();
+    loop {
+        let now = clock.now();
+        for (schedule, cron) in &schedules {
+            let server = schedule.server.as_str();
+            let (due, paused) = with_state(|state| {
+                let due = state.next_run.get(server).is_some_and(|next| *next <= now);
+                if due || !state.next_run.contains_key(server) {
+                    match plan_next(cron, schedule.jitter, now) {
+                        Some(next) => state.next_run.insert(server.to_string(), next),
+                        None => state.next_run.remove(server),
+                    };
+                }
+                (due, state.persisted.paused.contains(server))
+            });
+            if due && paused {
+                tracing::debug!("Schedule of {} is paused", server);
+            } else if due {
+                trigger(schedule);
+            }
+        }
+
+        let earliest = with_state(|state| state.next_run.values().min().copied());
+        let wait = earliest
+            .and_then(|next| (next - clock.now()).to_std().ok())
+            .unwrap_or_default()
+            .clamp(std::time::Duration::from_secs(1), MAX_SLEEP);
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = tokio::time::sleep(wait) => {}
+        }
+    }
+    tracing::info!("Scheduler stopped");
+}
+
+/// The next run after `now`: the next cron time plus a random delay up to `jitter` seconds.
+fn plan_next(cron: &CronSchedule, jitter: u32, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let delay = if jitter > 0 {
+        rand::random_range(0..=jitter)
+    } else {
+        0
+    };
+    cron.next_after(now)
+        .map(|next| next + Duration::seconds(delay.into()))
+}
+
+/// Starts a scheduled run, or records it as skipped.
+fn trigger(schedule: &ScheduleConfiguration) {
+    let Some(username) = schedule.username.clone().or_else(default_admin) else {
+        record(
+            &schedule.server,
+            RunRecord::skipped(
+                Trigger::Schedule,
+                "No admin is configured to run the schedule as",
+            ),
+        );
+        return;
+    };
+    match RunGuard::acquire(&schedule.server) {
+        Some(guard) => {
+            tokio::spawn(execute(
+                schedule.clone(),
+                username,
+                Trigger::Schedule,
+                guard,
+            ));
+        }
+        None => {
+            tracing::warn!(
+                "Skipping the scheduled backup of {}: the previous run is still in progress",
+                schedule.server
+            );
+            record(
+                &schedule.server,
+                RunRecord::skipped(Trigger::Schedule, "The previous run is still in progress"),
+            );
+        }
+    }
+}
+
+fn default_admin() -> Option<String> {
+    CONFIG
+        .get()
+        .and_then(|config| config.admins.keys().min().cloned())
+}
+
+/// Runs the steps of a schedule.
+async fn execute(
+    schedule: ScheduleConfiguration,
+    username: String,
+    trigger: Trigger,
+    _guard: RunGuard,
+) {
+    let server = schedule.server.as_str();
+    let clock = BackupClock::configured();
+    let mut run = RunRecord {
+        trigger,
+        status: RunStatus::Failed,
+        started: clock.format(clock.now()),
+        finished: String::new(),
+        backup: None,
+        steps: Vec::new(),
+        message: None,
+    };
+    let kind = match trigger {
+        Trigger::Schedule => "scheduled",
+        Trigger::Manual => "manual",
+    };
+    tracing::info!("Starting the {} backup run of {}", kind, server);
+    activity::publish(
+        Activity::new(ActivityKind::ScheduleRunStarted)
+            .server(server)
+            .username(&username)
+            .detail("Trigger", kind),
+    );
+
+    let event = |kind: EventKind| {
+        Event::new(kind, EventSource::Scheduler, server)
+            .username(&username)
+            .detail("Incremental", false)
+    };
+    events::emit(event(EventKind::BackupStarted));
+    let backup = run_step(
+        &mut run,
+        Step::Backup,
+        PgmonetaClient::request_full_backup(&username, server),
+    )
+    .await
+    .map(|result| backup_identifier(&result));
+    match &backup {
+        Some(backup) => events::emit(event(EventKind::BackupFinished).backup(backup)),
+        None => {
+            let error = run.steps.last().and_then(|step| step.error.as_deref());
+            events::emit(event(EventKind::BackupFailed).error(error.unwrap_or("unknown error")))
+        }
+    }
+    let mut succeeded = backup.is_some();
+
+    if let Some(backup) = &backup {
+        run.backup = Some(backup.clone());
+        if schedule.verify {
+            let directory = schedule.verify_directory.as_str();
+            let verify = async {
+                let result =
+                    verify::run_verify(&username, server, backup, directory, false).await?;
+                // Files failing verification fail the step, not only a pgmoneta error
+                if let Ok(report) = verify::parse_verify(&result, server, backup, directory) {
+                    events::emit(report.to_event(EventSource::Scheduler, &username));
+                    if report.result == VerifyResult::Failed {
+                        bail!(report.summary.text);
+                    }
+                }
+                Ok(result)
+            };
+            succeeded = run_step(&mut run, Step::Verify, verify).await.is_some();
+        }
+        if let Some(directory) = schedule.archive_directory.as_deref().filter(|_| succeeded) {
+            succeeded = run_step(
+                &mut run,
+                Step::Archive,
+                PgmonetaClient::request_archive(
+                    &username,
+                    server,
+                    backup,
+                    ARCHIVE_POSITION,
+                    directory,
+                ),
+            )
+            .await
+            .is_some();
+        }
+    }
+
+    run.status = if succeeded {
+        RunStatus::Succeeded
+    } else {
+        RunStatus::Failed
+    };
+    run.finished = clock.format(clock.now());
+    match run.status {
+        RunStatus::Succeeded => tracing::info!("The {} backup run of {} succeeded", kind, server),
+        _ => tracing::warn!(
+            "The {} backup run of {} failed: {:?}",
+            kind,
+            server,
+            run.steps
+        ),
+    }
+    record(server, run);
+}
+
+/// Runs a step, returning the raw pgmoneta response if it succeeded.
+async fn run_step(
+    run: &mut RunRecord,
+    step: Step,
+    request: impl Future<Output = anyhow::Result<String>>,
+) -> Option<String> {
+    let start = Instant::now();
+    let outcome = match request.await {
+        Ok(result) => PgmonetaHandler::_parse_and_check_result(&result)
+            .and_then(|response| catalog::ensure_success(&response))
+            .map(|_| result)
+            .map_err(|e| e.message.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    run.steps.push(StepRecord {
+        step,
+        succeeded: outcome.is_ok(),
+        seconds: start.elapsed().as_secs_f64(),
+        error: outcome.as_ref().err().cloned(),
+    });
+    outcome.ok()
+}
+
+/// The identifier of the backup taken, `newest` if the response lacks it.
+fn backup_identifier(result: &str) -> String {
+    serde_json::from_str::<Value>(result)
+        .ok()
+        .and_then(|response| {
+            response
+                .get("Response")
+                .and_then(|value| value.get("Backup"))
+                .or_else(|| response.get("Backup"))
+                .and_then(catalog::value_as_string)
+        })
+        .unwrap_or_else(|| "newest".to_string())
+}
+
+/// Appends a run to the history of a server.
+fn record(server: &str, run: RunRecord) {
+    if let Ok(Value::Object(details)) = serde_json::to_value(&run) {
+        let mut finished = Activity::new(ActivityKind::ScheduleRunFinished).server(server);
+        finished.details = details;
+        activity::publish(finished);
+    }
+    let (generation, persisted) = with_state(|state| {
+        let runs = state
+            .persisted
+            .history
+            .entry(server.to_string())
+            .or_default();
+        runs.push(run);
+        if runs.len() > HISTORY_LIMIT {
+            runs.drain(..runs.len() - HISTORY_LIMIT);
+        }
+        snapshot(state)
+    });
+    persist(generation, persisted);
+}
+
+/// Takes a numbered copy of the persisted state, under the lock of [`STATE`].
+fn snapshot(state: &SchedulerState) -> (u64, PersistedState) {
+    (SNAPSHOTS.next(), state.persisted.clone())
+}
+
+/// Saves a snapshot of the state off the async runtime, if there is a state
+/// directory.
+fn persist(generation: u64, persisted: PersistedState) {
+    if let Some(directory) = state_directory() {
+        SNAPSHOTS.write(generation, move || save_state(&directory, &persisted));
+    }
+}
+
+fn with_state<T>(f: impl FnOnce(&mut SchedulerState) -> T) -> T {
+    let mut state = STATE.lock().unwrap_or_else(PoisonError::into_inner);
+    let state = state.get_or_insert_with(|| SchedulerState {
+        persisted: load_state(state_directory().as_deref()),
+        ..Default::default()
+    });
+    f(state)
+}
+
+fn state_directory() -> Option<String> {
+    CONFIG
+        .get()
+        .and_then(|config| config.pgmoneta_mcp.state_directory.clone())
+}
+
+fn load_state(directory: Option<&str>) -> PersistedState {
+    let Some(directory) = directory else {
+        return PersistedState::default();
+    };
+    let path = Path::new(directory).join(STATE_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            tracing::warn!(
+                "Ignoring unreadable schedule state {}: {}",
+                path.display(),
+                e
+            );
+            PersistedState::default()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => PersistedState::default(),
+        Err(e) => {
+            tracing::warn!("Failed to read schedule state {}: {}", path.display(), e);
+            PersistedState::default()
+        }
+    }
+}
+
+fn save_state(directory: &str, state: &PersistedState) {
+    let result = serde_json::to_string_pretty(state)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| {
+            SafeFileWriter::new(directory)
+                .allowed_extensions(vec!["json"])
+                .write(STATE_FILE, &contents)
+        });
+    if let Err(e) = result {
+        tracing::warn!("Failed to save schedule state in {}: {}", directory, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(server: &str) -> ScheduleConfiguration {
+        ScheduleConfiguration {
+            server: server.to_string(),
+            cron: "0 2 * * *".to_string(),
+            username: Some("admin".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_plan_next_adds_jitter() {
+        let cron = CronSchedule::parse("0 2 * * *").unwrap();
+        let now =
+            NaiveDateTime::parse_from_str("20260713010000", catalog::BACKUP_ID_FORMAT).unwrap();
+        let slot = cron.next_after(now).unwrap();
+
+        assert_eq!(plan_next(&cron, 0, now), Some(slot));
+        for _ in 0..100 {
+            let next = plan_next(&cron, 300, now).unwrap();
+            assert!(
+                next >= slot && next <= slot + Duration::seconds(300),
+                "{next}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_guard_prevents_overlap() {
+        let guard = RunGuard::acquire("guarded").unwrap();
+        assert!(RunGuard::acquire("guarded").is_none());
+        assert!(state("guarded").running);
+
+        // A due run while one is in progress is skipped
+        trigger(&schedule("guarded"));
+        let history = state("guarded").history;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].status, RunStatus::Skipped);
+        assert_eq!(history[0].trigger, Trigger::Schedule);
+
+        assert!(run_now(&schedule("guarded"), "admin").is_err());
+
+        drop(guard);
+        assert!(!state("guarded").running);
+        assert!(RunGuard::acquire("guarded").is_some());
+    }
+
+    #[test]
+    fn test_history_is_capped_newest_first() {
+        for i in 0..HISTORY_LIMIT + 5 {
+            let mut run = RunRecord::skipped(Trigger::Manual, "test");
+            run.backup = Some(i.to_string());
+            record("capped", run);
+        }
+        let history = state("capped").history;
+        assert_eq!(history.len(), HISTORY_LIMIT);
+        assert_eq!(history[0].backup.as_deref(), Some("54"));
+        assert_eq!(history[HISTORY_LIMIT - 1].backup.as_deref(), Some("5"));
+    }
+
+    #[test]
+    fn test_set_paused() {
+        assert!(set_paused("paused", true));
+        assert!(!set_paused("paused", true));
+        assert!(state("paused").paused);
+        assert!(set_paused("paused", false));
+        assert!(!state("paused").paused);
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = directory.path().to_str().unwrap();
+        let mut state = PersistedState::default();
+        state.paused.insert("primary".to_string());
+        state.history.insert(
+            "primary".to_string(),
+            vec![RunRecord {
+                trigger: Trigger::Schedule,
+                status: RunStatus::Failed,
+                started: "2026-07-13 02:00:00".to_string(),
+                finished: "2026-07-13 02:10:00".to_string(),
+                backup: Some("20260713020000".to_string()),
+                steps: vec![StepRecord {
+                    step: Step::Verify,
+                    succeeded: false,
+                    seconds: 12.5,
+                    error: Some("pgmoneta reported a failure".to_string()),
+                }],
+                message: None,
+            }],
+        );
+
+        save_state(directory, &state);
+        let loaded = load_state(Some(directory));
+        assert_eq!(loaded.paused, state.paused);
+        assert_eq!(loaded.history, state.history);
+        assert!(load_state(None).history.is_empty());
+    }
+
+    #[test]
+    fn test_backup_identifier() {
+        assert_eq!(
+            backup_identifier(
+                r#"{"Outcome": {"Status": true}, "Response": {"Backup": "20260713020000"}}"#
+            ),
+            "20260713020000"
+        );
+        assert_eq!(
+            backup_identifier(r#"{"Outcome": {"Status": true}, "Backup": 20260713020000}"#),
+            "20260713020000"
+        );
+        assert_eq!(
+            backup_identifier(r#"{"Outcome": {"Status": true}}"#),
+            "newest"
+        );
+    }
+}
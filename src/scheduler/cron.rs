@@ -0,0 +1,276 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Cron expressions.
+//!
+//! The classic five fields are supported: minute, hour, day of month, month
+//! and day of week, each as `*`, a value, a range `a-b`, a step `*/n` or
+//! `a-b/n`, or a comma separated list of those. Months and days of week also
+//! accept their three letter English names, and `@hourly`, `@daily`,
+//! `@weekly`, `@monthly` and `@yearly` are accepted as shorthands.
+//!
+//! As in cron, a time matches when both the day of month and the day of week
+//! match, or either of them when both are restricted.
+
+use anyhow::{anyhow, bail};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+
+/// How far ahead [`CronSchedule::next_after`] looks for a matching time.
+const SEARCH_DAYS: i64 = 366 * 5;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A parsed cron expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    /// Parses a cron expression such as `30 2 * * *` or `@daily`.
+    pub fn parse(expression: &str) -> anyhow::Result<Self> {
+        let trimmed = expression.trim();
+        let expanded = match trimmed.to_ascii_lowercase().as_str() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            _ => trimmed,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            bail!(
+                "Invalid cron expression '{}': expected 5 fields (minute hour day month weekday)",
+                expression
+            );
+        };
+        let field = |name: &str, text: &str, min: u32, max: u32, names: &[&str]| {
+            parse_field(text, min, max, names)
+                .map_err(|e| anyhow!("Invalid cron expression '{}': {} {}", expression, name, e))
+        };
+
+        let mut weekdays = field("weekday", weekday, 0, 7, &WEEKDAYS)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+        Ok(Self {
+            expression: trimmed.to_string(),
+            minutes: field("minute", minute, 0, 59, &[])?,
+            hours: field("hour", hour, 0, 23, &[])?,
+            days: field("day", day, 1, 31, &[])?,
+            months: field("month", month, 1, 12, &MONTHS)?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    /// The expression as configured.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Whether the minute of `time` matches the expression.
+    pub fn matches(&self, time: NaiveDateTime) -> bool {
+        self.matches_date(time.date())
+            && bit(self.hours, time.hour())
+            && bit(self.minutes, time.minute())
+    }
+
+    /// The first matching minute strictly after `time`, if any within five years.
+    pub fn next_after(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        let limit = time + Duration::days(SEARCH_DAYS);
+        let mut candidate = time.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+
+        while candidate <= limit {
+            let date = candidate.date();
+            if !bit(self.months, date.month()) {
+                let (year, month) = if date.month() == 12 {
+                    (date.year() + 1, 1)
+                } else {
+                    (date.year(), date.month() + 1)
+                };
+                candidate = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.matches_date(date) {
+                candidate = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !bit(self.hours, candidate.hour()) {
+                candidate = date.and_hms_opt(candidate.hour(), 0, 0)? + Duration::hours(1);
+            } else if !bit(self.minutes, candidate.minute()) {
+                candidate += Duration::minutes(1);
+            } else {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !bit(self.months, date.month()) {
+            return false;
+        }
+        let day = bit(self.days, date.day());
+        let weekday = bit(self.weekdays, date.weekday().num_days_from_sunday());
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+}
+
+impl std::fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parses one field into a bit set of the allowed values.
+fn parse_field(text: &str, min: u32, max: u32, names: &[&str]) -> anyhow::Result<u64> {
+    let value = |text: &str| -> anyhow::Result<u32> {
+        let lowered = text.to_ascii_lowercase();
+        let value = match names.iter().position(|name| *name == lowered) {
+            // Month names start at 1, weekday names at 0 (Sunday)
+            Some(index) => index as u32 + min,
+            None => text
+                .parse::<u32>()
+                .map_err(|_| anyhow!("'{}' is not a number", text))?,
+        };
+        if value < min || value > max {
+            bail!("{} is out of range {}-{}", value, min, max);
+        }
+        Ok(value)
+    };
+
+    let mut set = 0u64;
+    for item in text.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| anyhow!("step '{}' is not a positive number", step))?;
+                (range, Some(step))
+            }
+            None => (item, None),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (value(start)?, value(end)?)
+        } else {
+            let start = value(range)?;
+            // `a/n` runs from a to the end of the range
+            (start, if step.is_some() { max } else { start })
+        };
+        if start > end {
+            bail!("range {} is reversed", range);
+        }
+        let mut current = start;
+        while current <= end {
+            set |= 1 << current;
+            current += step.unwrap_or(1);
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn next(expression: &str, after: &str) -> String {
+        CronSchedule::parse(expression)
+            .unwrap()
+            .next_after(time(after))
+            .unwrap()
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_expressions() {
+        for (expression, expected) in [
+            ("* * * *", "expected 5 fields"),
+            ("60 * * * *", "minute 60 is out of range"),
+            ("* 5-2 * * *", "hour range 5-2 is reversed"),
+            ("*/0 * * * *", "step '0'"),
+            ("* * * foo *", "month 'foo' is not a number"),
+        ] {
+            let err = CronSchedule::parse(expression).unwrap_err().to_string();
+            assert!(err.contains(expected), "{expression}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_next_after() {
+        assert_eq!(next("30 2 * * *", "2026-07-13 01:00"), "2026-07-13 02:30");
+        assert_eq!(next("30 2 * * *", "2026-07-13 02:30"), "2026-07-14 02:30");
+        assert_eq!(next("*/15 * * * *", "2026-07-13 10:07"), "2026-07-13 10:15");
+        assert_eq!(next("0 3 * * sun", "2026-07-13 00:00"), "2026-07-19 03:00");
+        assert_eq!(next("0 3 * * 7", "2026-07-13 00:00"), "2026-07-19 03:00");
+        assert_eq!(
+            next("0 0 1 jan,jul *", "2026-07-13 00:00"),
+            "2027-01-01 00:00"
+        );
+        assert_eq!(next("0 1 29 2 *", "2026-03-01 00:00"), "2028-02-29 01:00");
+        assert_eq!(next("@weekly", "2026-07-13 00:00"), "2026-07-19 00:00");
+        assert_eq!(
+            next("0 22 * * mon-fri", "2026-07-17 23:00"),
+            "2026-07-20 22:00"
+        );
+    }
+
+    #[test]
+    fn test_day_of_month_or_weekday() {
+        // Either the 1st or a Monday, as in cron
+        let schedule = CronSchedule::parse("0 0 1 * 1").unwrap();
+        assert!(schedule.matches(time("2026-07-01 00:00")));
+        assert!(schedule.matches(time("2026-07-13 00:00")));
+        assert!(!schedule.matches(time("2026-07-14 00:00")));
+
+        // Only the 1st when the weekday is unrestricted
+        let schedule = CronSchedule::parse("0 0 1 * *").unwrap();
+        assert!(!schedule.matches(time("2026-07-13 00:00")));
+    }
+
+    #[test]
+    fn test_next_after_impossible_date() {
+        let schedule = CronSchedule::parse("0 0 31 2 *").unwrap();
+        assert_eq!(schedule.next_after(time("2026-07-13 00:00")), None);
+        assert_eq!(schedule.to_string(), "0 0 31 2 *");
+    }
+}
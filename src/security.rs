@@ -14,6 +14,8 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::constant::{Encryption, MASTER_KEY_PATH};
+use crate::otel;
+use crate::telemetry::{self, ConnectionStage};
 use aes_gcm::aead::consts::U12;
 use aes_gcm::aead::{Aead, KeyInit};
 use aes_gcm::{Aes128Gcm, Aes256Gcm, AesGcm, Nonce};
@@ -31,10 +33,11 @@ use scram::ScramClient;
 use sha2::Sha256;
 use std::fs;
 use std::sync::Mutex;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio::time::timeout;
+use tracing::Instrument;
 use zeroize::{Zeroize, Zeroizing};
 
 struct CachedMasterKey {
@@ -247,6 +250,27 @@ impl SecurityUtil {
         )
     }
 
+    /// Checks `password` against a password encrypted by [`Self::encrypt_to_base64_string`],
+    /// comparing them in constant time.
+    pub fn verify_password(
+        &self,
+        password_encrypted: &str,
+        password: &[u8],
+        master_password: &[u8],
+        master_salt: &[u8],
+    ) -> anyhow::Result<bool> {
+        let stored = Zeroizing::new(self.decrypt_from_base64_string(
+            password_encrypted,
+            master_password,
+            master_salt,
+        )?);
+        let difference = stored
+            .iter()
+            .zip(password)
+            .fold(0u8, |difference, (a, b)| difference | (a ^ b));
+        Ok(stored.len() == password.len() && difference == 0)
+    }
+
     /// Generate a random password of the specified length.
     /// Uses alphanumeric characters and common special characters.
     pub fn generate_password(&self, length: usize) -> anyhow::Result<String> {
@@ -550,16 +574,62 @@ impl SecurityUtil {
         username: &str,
         password: &str,
     ) -> anyhow::Result<TcpStream> {
-        let scram = ScramClient::new(username, password, None);
+        let metrics = telemetry::metrics();
         let address = format!("{}:{}", host, port);
         tracing::debug!(host = host, port = port, "Beginning SASL handshake");
-        let mut stream = match timeout(Duration::from_secs(5), TcpStream::connect(address)).await {
+        let span = tracing::info_span!(
+            target: otel::SPAN_TARGET,
+            "pgmoneta.connect",
+            server.address = host,
+            server.port = port,
+            otel.status_code = tracing::field::Empty,
+        );
+        let start = Instant::now();
+        let stream = match timeout(Duration::from_secs(5), TcpStream::connect(address))
+            .instrument(span.clone())
+            .await
+        {
             Ok(Ok(s)) => s,
-            Ok(Err(e)) => return Err(anyhow!("Failed to connect to {host}:{port}: {e}")),
-            Err(_) => return Err(anyhow!("Connection to {host}:{port} timed out after 5s")),
+            Ok(Err(e)) => {
+                metrics.record_pgmoneta_connection_failure(ConnectionStage::Connect);
+                span.record("otel.status_code", "error");
+                return Err(anyhow!("Failed to connect to {host}:{port}: {e}"));
+            }
+            Err(_) => {
+                metrics.record_pgmoneta_connection_failure(ConnectionStage::Connect);
+                span.record("otel.status_code", "error");
+                return Err(anyhow!("Connection to {host}:{port} timed out after 5s"));
+            }
         };
+        metrics.record_pgmoneta_connection(ConnectionStage::Connect, start.elapsed());
         tracing::debug!(host = host, port = port, "Connected to server");
 
+        let span = tracing::info_span!(
+            target: otel::SPAN_TARGET,
+            "pgmoneta.handshake",
+            pgmoneta.username = username,
+            otel.status_code = tracing::field::Empty,
+        );
+        let start = Instant::now();
+        let stream = Self::authenticate(stream, username, password)
+            .instrument(span.clone())
+            .await
+            .inspect_err(|_| {
+                metrics.record_pgmoneta_connection_failure(ConnectionStage::Handshake);
+                span.record("otel.status_code", "error");
+            })?;
+        metrics.record_pgmoneta_connection(ConnectionStage::Handshake, start.elapsed());
+        Ok(stream)
+    }
+
+    /// Runs the SCRAM-SHA-256 handshake on a connected stream, see
+    /// [`Self::connect_to_server`].
+    async fn authenticate(
+        mut stream: TcpStream,
+        username: &str,
+        password: &str,
+    ) -> anyhow::Result<TcpStream> {
+        let scram = ScramClient::new(username, password, None);
         let startup_msg = Self::create_startup_message(username).await?;
         match timeout(
             Duration::from_secs(2),
@@ -718,12 +788,7 @@ impl SecurityUtil {
                 auth_type
             ));
         }
-        tracing::debug!(
-            host = host,
-            port = port,
-            username = username,
-            "Authenticated with server"
-        );
+        tracing::debug!(username = username, "Authenticated with server");
         Ok(stream)
     }
 
@@ -932,6 +997,26 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_verify_password() {
+        let sutil = SecurityUtil::new();
+        let master_password = "test_master_password".as_bytes();
+        let master_salt = "test_master_salt".as_bytes();
+        let encrypted = sutil
+            .encrypt_to_base64_string(b"secret", master_password, master_salt)
+            .expect("Encryption should succeed");
+
+        let verify = |password: &[u8]| {
+            sutil
+                .verify_password(&encrypted, password, master_password, master_salt)
+                .expect("Verification should succeed")
+        };
+        assert!(verify(b"secret"));
+        assert!(!verify(b"secreT"));
+        assert!(!verify(b"secret2"));
+        assert!(!verify(b""));
+    }
+
     #[test]
     fn test_encrypt_decrypt_aes_gcm_bundle() {
         // Use a temporary path for the master key to ensure hermeticity
@@ -13,18 +13,27 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::activity;
+use crate::configuration::{CONFIG, MetricsAuth, MetricsConfiguration};
+use crate::constant::ManagementError;
 use anyhow::anyhow;
-use axum::extract::State;
-use axum::http::{Request, StatusCode, header};
+use axum::extract::{MatchedPath, RawQuery, State};
+use axum::http::{HeaderMap, HeaderValue, Request, StatusCode, header};
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
 use once_cell::sync::Lazy;
+use opentelemetry::trace::SpanContext;
 use prometheus_client::encoding::text::encode;
 use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::exemplar::HistogramWithExemplars;
 use prometheus_client::metrics::family::Family;
 use prometheus_client::metrics::gauge::Gauge;
-use prometheus_client::metrics::histogram::Histogram;
+use prometheus_client::metrics::histogram::{Histogram, exponential_buckets};
 use prometheus_client::registry::Registry;
+use sha2::{Digest, Sha256};
+use std::cell::Cell;
+use std::future::Future;
+use std::sync::atomic::AtomicU64;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -32,16 +41,86 @@ const HTTP_DURATION_BUCKETS: [f64; 11] = [
     0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
 ];
 
+/// Tool calls such as backups and restores run for minutes.
+const TOOL_DURATION_BUCKETS: [f64; 13] = [
+    0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0,
+];
+
+/// The `path` label of requests matching no route.
+const UNMATCHED_PATH: &str = "unmatched";
+
+/// The `tool` label of calls of tools that do not exist.
+pub const UNKNOWN_TOOL: &str = "unknown";
+
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+const BASIC_REALM: &str = "Basic realm=\"pgmoneta-mcp\"";
+const BEARER_REALM: &str = "Bearer realm=\"pgmoneta-mcp\"";
+
+tokio::task_local! {
+    /// The first pgmoneta error code of the tool call in progress.
+    static PGMONETA_ERROR: Cell<Option<u32>>;
+}
+
+/// A stage of a connection to pgmoneta.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionStage {
+    /// The TCP connection.
+    Connect,
+    /// The SCRAM-SHA-256 handshake.
+    Handshake,
+}
+
+impl ConnectionStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionStage::Connect => "connect",
+            ConnectionStage::Handshake => "handshake",
+        }
+    }
+}
+
+/// The direction of a management message exchanged with pgmoneta.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageDirection {
+    Request,
+    Response,
+}
+
+impl MessageDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageDirection::Request => "request",
+            MessageDirection::Response => "response",
+        }
+    }
+}
+
 type Labels = Vec<(String, String)>;
 type HistogramFamily = Family<Labels, Histogram, fn() -> Histogram>;
+/// Histograms whose buckets link to the trace of their last observation.
+type ExemplarHistogramFamily =
+    Family<Labels, HistogramWithExemplars<Labels>, fn() -> HistogramWithExemplars<Labels>>;
+type FloatGauge = Gauge<f64, AtomicU64>;
 
 pub struct Metrics {
     registry: Mutex<Registry>,
     http_requests_total: Family<Labels, Counter>,
-    http_request_duration_seconds: HistogramFamily,
+    http_request_duration_seconds: ExemplarHistogramFamily,
     http_requests_in_flight: Gauge,
     pgmoneta_metrics_scrapes_total: Family<Labels, Counter>,
     pgmoneta_metrics_scrape_duration_seconds: HistogramFamily,
+    sla_status: Family<Labels, Gauge>,
+    sla_backup_age_seconds: Family<Labels, FloatGauge>,
+    sla_restore_estimate_seconds: Family<Labels, FloatGauge>,
+    sla_last_check_timestamp_seconds: FloatGauge,
+    tool_calls_total: Family<Labels, Counter>,
+    tool_call_duration_seconds: ExemplarHistogramFamily,
+    sessions: Gauge,
+    pgmoneta_connection_duration_seconds: HistogramFamily,
+    pgmoneta_connection_failures_total: Family<Labels, Counter>,
+    pgmoneta_message_size_bytes: HistogramFamily,
 }
 
 static METRICS: Lazy<Arc<Metrics>> = Lazy::new(|| Arc::new(Metrics::new()));
@@ -53,16 +132,33 @@ pub fn metrics() -> Arc<Metrics> {
 impl Metrics {
     pub fn new() -> Self {
         let http_requests_total = Family::<Labels, Counter>::default();
-        let http_request_duration_seconds =
-            Family::<Labels, Histogram, fn() -> Histogram>::new_with_constructor(
-                http_duration_histogram,
-            );
+        let http_request_duration_seconds = ExemplarHistogramFamily::new_with_constructor(|| {
+            HistogramWithExemplars::new(HTTP_DURATION_BUCKETS.into_iter())
+        });
         let http_requests_in_flight = Gauge::default();
         let pgmoneta_metrics_scrapes_total = Family::<Labels, Counter>::default();
         let pgmoneta_metrics_scrape_duration_seconds =
             Family::<Labels, Histogram, fn() -> Histogram>::new_with_constructor(
                 http_duration_histogram,
             );
+        let sla_status = Family::<Labels, Gauge>::default();
+        let sla_backup_age_seconds = Family::<Labels, FloatGauge>::default();
+        let sla_restore_estimate_seconds = Family::<Labels, FloatGauge>::default();
+        let sla_last_check_timestamp_seconds = FloatGauge::default();
+        let tool_calls_total = Family::<Labels, Counter>::default();
+        let tool_call_duration_seconds = ExemplarHistogramFamily::new_with_constructor(|| {
+            HistogramWithExemplars::new(TOOL_DURATION_BUCKETS.into_iter())
+        });
+        let sessions = Gauge::default();
+        let pgmoneta_connection_duration_seconds =
+            Family::<Labels, Histogram, fn() -> Histogram>::new_with_constructor(
+                http_duration_histogram,
+            );
+        let pgmoneta_connection_failures_total = Family::<Labels, Counter>::default();
+        let pgmoneta_message_size_bytes =
+            Family::<Labels, Histogram, fn() -> Histogram>::new_with_constructor(
+                message_size_histogram,
+            );
 
         let mut registry = Registry::default();
         registry.register(
@@ -90,6 +186,56 @@ impl Metrics {
             "Latency of scrapes against the configured pgmoneta metrics endpoint.",
             pgmoneta_metrics_scrape_duration_seconds.clone(),
         );
+        registry.register(
+            "pgmoneta_mcp_sla_status",
+            "SLA status of a server from the last check (0 = pass, 1 = warn, 2 = fail).",
+            sla_status.clone(),
+        );
+        registry.register(
+            "pgmoneta_mcp_sla_backup_age_seconds",
+            "Age of the newest valid backup of a server at the last SLA check.",
+            sla_backup_age_seconds.clone(),
+        );
+        registry.register(
+            "pgmoneta_mcp_sla_restore_estimate_seconds",
+            "Estimated restore duration of a server at the last SLA check.",
+            sla_restore_estimate_seconds.clone(),
+        );
+        registry.register(
+            "pgmoneta_mcp_sla_last_check_timestamp_seconds",
+            "Unix time of the last SLA check.",
+            sla_last_check_timestamp_seconds.clone(),
+        );
+        registry.register(
+            "pgmoneta_mcp_tool_calls",
+            "Number of MCP tool calls by tool, outcome and pgmoneta error code.",
+            tool_calls_total.clone(),
+        );
+        registry.register(
+            "pgmoneta_mcp_tool_call_duration_seconds",
+            "MCP tool call latency by tool and outcome.",
+            tool_call_duration_seconds.clone(),
+        );
+        registry.register(
+            "pgmoneta_mcp_sessions",
+            "Number of active MCP sessions.",
+            sessions.clone(),
+        );
+        registry.register(
+            "pgmoneta_mcp_pgmoneta_connection_duration_seconds",
+            "Latency of the TCP connection and the SCRAM handshake with pgmoneta.",
+            pgmoneta_connection_duration_seconds.clone(),
+        );
+        registry.register(
+            "pgmoneta_mcp_pgmoneta_connection_failures",
+            "Number of failed TCP connections and SCRAM handshakes with pgmoneta.",
+            pgmoneta_connection_failures_total.clone(),
+        );
+        registry.register(
+            "pgmoneta_mcp_pgmoneta_message_size_bytes",
+            "Size of the management requests and responses exchanged with pgmoneta, before and after compression.",
+            pgmoneta_message_size_bytes.clone(),
+        );
 
         Self {
             registry: Mutex::new(registry),
@@ -98,15 +244,28 @@ impl Metrics {
             http_requests_in_flight,
             pgmoneta_metrics_scrapes_total,
             pgmoneta_metrics_scrape_duration_seconds,
+            sla_status,
+            sla_backup_age_seconds,
+            sla_restore_estimate_seconds,
+            sla_last_check_timestamp_seconds,
+            tool_calls_total,
+            tool_call_duration_seconds,
+            sessions,
+            pgmoneta_connection_duration_seconds,
+            pgmoneta_connection_failures_total,
+            pgmoneta_message_size_bytes,
         }
     }
 
+    /// Records an HTTP request; `trace` is the span of the request, whose
+    /// identifiers become the exemplar of the latency if it is exported.
     pub fn record_http_request(
         &self,
         method: &str,
         path: &str,
         status: StatusCode,
         duration: Duration,
+        trace: Option<&SpanContext>,
     ) {
         let labels = vec![
             ("method".to_string(), method.to_string()),
@@ -117,7 +276,7 @@ impl Metrics {
         self.http_requests_total.get_or_create(&labels).inc();
         self.http_request_duration_seconds
             .get_or_create(&labels)
-            .observe(duration.as_secs_f64());
+            .observe(duration.as_secs_f64(), exemplar(trace), None);
     }
 
     pub fn increment_http_requests_in_flight(&self) {
@@ -139,6 +298,115 @@ impl Metrics {
             .observe(duration.as_secs_f64());
     }
 
+    /// Records the SLA check result of a server.
+    ///
+    /// A `None` backup age or restore estimate removes the previous value.
+    pub fn record_sla_check(
+        &self,
+        server: &str,
+        status: i64,
+        backup_age_seconds: Option<f64>,
+        restore_estimate_seconds: Option<f64>,
+    ) {
+        let labels = vec![("server".to_string(), server.to_string())];
+
+        self.sla_status.get_or_create(&labels).set(status);
+        for (family, value) in [
+            (&self.sla_backup_age_seconds, backup_age_seconds),
+            (&self.sla_restore_estimate_seconds, restore_estimate_seconds),
+        ] {
+            match value {
+                Some(value) => {
+                    family.get_or_create(&labels).set(value);
+                }
+                None => {
+                    family.remove(&labels);
+                }
+            }
+        }
+        self.sla_last_check_timestamp_seconds.set(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0.0, |now| now.as_secs_f64()),
+        );
+    }
+
+    /// Forgets the SLA results of all servers, before a check of every server.
+    pub fn clear_sla_checks(&self) {
+        self.sla_status.clear();
+        self.sla_backup_age_seconds.clear();
+        self.sla_restore_estimate_seconds.clear();
+    }
+
+    /// Records a tool call.
+    ///
+    /// `tool` is [`UNKNOWN_TOOL`] for tools that do not exist, and error codes
+    /// pgmoneta does not define are recorded as `other`. The identifiers of the
+    /// `trace` of the call become the exemplar of the latency.
+    pub fn record_tool_call(
+        &self,
+        tool: &str,
+        outcome: &str,
+        error_code: Option<u32>,
+        duration: Duration,
+        trace: Option<&SpanContext>,
+    ) {
+        let labels = vec![
+            ("tool".to_string(), tool.to_string()),
+            ("outcome".to_string(), outcome.to_string()),
+        ];
+        let mut counter_labels = labels.clone();
+        counter_labels.push(("error_code".to_string(), error_code_label(error_code)));
+
+        self.tool_calls_total.get_or_create(&counter_labels).inc();
+        self.tool_call_duration_seconds
+            .get_or_create(&labels)
+            .observe(duration.as_secs_f64(), exemplar(trace), None);
+    }
+
+    pub fn increment_sessions(&self) {
+        self.sessions.inc();
+    }
+
+    pub fn decrement_sessions(&self) {
+        self.sessions.dec();
+    }
+
+    pub fn record_pgmoneta_connection(&self, stage: ConnectionStage, duration: Duration) {
+        let labels = vec![("stage".to_string(), stage.as_str().to_string())];
+
+        self.pgmoneta_connection_duration_seconds
+            .get_or_create(&labels)
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn record_pgmoneta_connection_failure(&self, stage: ConnectionStage) {
+        let labels = vec![("stage".to_string(), stage.as_str().to_string())];
+
+        self.pgmoneta_connection_failures_total
+            .get_or_create(&labels)
+            .inc();
+    }
+
+    /// Records the size of a management message before and after compression.
+    pub fn record_pgmoneta_message(
+        &self,
+        direction: MessageDirection,
+        uncompressed: usize,
+        compressed: usize,
+    ) {
+        for (stage, size) in [("uncompressed", uncompressed), ("compressed", compressed)] {
+            let labels = vec![
+                ("direction".to_string(), direction.as_str().to_string()),
+                ("stage".to_string(), stage.to_string()),
+            ];
+            self.pgmoneta_message_size_bytes
+                .get_or_create(&labels)
+                .observe(size as f64);
+        }
+    }
+
+    /// Encodes all metrics in the OpenMetrics format, with exemplars.
     pub fn encode(&self) -> anyhow::Result<String> {
         let registry = self
             .registry
@@ -149,6 +417,12 @@ impl Metrics {
             .map_err(|error| anyhow!("Failed to encode metrics registry: {error}"))?;
         Ok(buffer)
     }
+
+    /// Encodes the metric families matching `names`, or all of them when
+    /// `names` is empty, in the given format.
+    pub fn expose(&self, format: ExpositionFormat, names: &[String]) -> anyhow::Result<String> {
+        Ok(expose(&self.encode()?, format, names))
+    }
 }
 
 impl Default for Metrics {
@@ -157,24 +431,264 @@ impl Default for Metrics {
     }
 }
 
+/// The exposition formats of `/metrics`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpositionFormat {
+    /// The Prometheus text format 0.0.4, without exemplars.
+    Prometheus,
+    /// OpenMetrics 1.0.0, with exemplars.
+    OpenMetrics,
+}
+
+impl ExpositionFormat {
+    /// OpenMetrics if the `Accept` header prefers it over the Prometheus text
+    /// format, the Prometheus text format otherwise.
+    pub fn negotiate(headers: &HeaderMap) -> Self {
+        let mut open_metrics = 0.0;
+        let mut text = 0.0;
+        for range in headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+        {
+            let mut parameters = range.split(';').map(str::trim);
+            let media_type = parameters.next().unwrap_or_default().to_ascii_lowercase();
+            let quality = parameters
+                .filter_map(|parameter| parameter.split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+                .map_or(Some(1.0), |(_, quality)| quality.trim().parse::<f64>().ok())
+                .unwrap_or(0.0);
+            let best = match media_type.as_str() {
+                "application/openmetrics-text" => &mut open_metrics,
+                "text/plain" => &mut text,
+                _ => continue,
+            };
+            *best = f64::max(*best, quality);
+        }
+
+        if open_metrics > 0.0 && open_metrics >= text {
+            ExpositionFormat::OpenMetrics
+        } else {
+            ExpositionFormat::Prometheus
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExpositionFormat::Prometheus => PROMETHEUS_CONTENT_TYPE,
+            ExpositionFormat::OpenMetrics => OPENMETRICS_CONTENT_TYPE,
+        }
+    }
+}
+
+/// Serves the metrics in the format negotiated with the `Accept` header,
+/// limited to the families of the `name[]` query parameters.
+///
+/// The scrape authenticates as configured in the `[metrics]` section.
 pub async fn metrics_handler(
     State(metrics): State<Arc<Metrics>>,
-) -> Result<impl IntoResponse, StatusCode> {
-    metrics
-        .encode()
-        .map(|body| {
-            (
-                [(
-                    header::CONTENT_TYPE,
-                    "application/openmetrics-text; version=1.0.0; charset=utf-8",
-                )],
-                body,
-            )
-        })
-        .map_err(|error| {
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(access) = CONFIG.get().and_then(|config| config.metrics.as_ref())
+        && let Err(challenge) = authorize(access, &headers).await
+    {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static(challenge),
+            )],
+            "Authentication required\n",
+        )
+            .into_response();
+    }
+
+    let format = ExpositionFormat::negotiate(&headers);
+    let names = requested_names(query.as_deref());
+    match metrics.expose(format, &names) {
+        Ok(body) => ([(header::CONTENT_TYPE, format.content_type())], body).into_response(),
+        Err(error) => {
             tracing::error!(error = %error, "Failed to encode Prometheus metrics");
-            StatusCode::INTERNAL_SERVER_ERROR
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Checks the credentials of a scrape, returning the challenge to answer
+/// when they are missing or wrong.
+async fn authorize(access: &MetricsConfiguration, headers: &HeaderMap) -> Result<(), &'static str> {
+    match access.auth {
+        MetricsAuth::None => Ok(()),
+        MetricsAuth::Basic => activity::authenticate(headers)
+            .await
+            .map(|_| ())
+            .ok_or(BASIC_REALM),
+        MetricsAuth::Bearer => {
+            let expected = access.token.as_deref().unwrap_or_default();
+            match bearer_token(headers) {
+                Some(token) if same_token(token, expected) => Ok(()),
+                Some(_) => {
+                    tracing::warn!("Rejected the bearer token of a metrics scrape");
+                    Err(BEARER_REALM)
+                }
+                None => Err(BEARER_REALM),
+            }
+        }
+    }
+}
+
+/// The token of a Bearer `Authorization` header.
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.trim().split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then_some(token.trim())
+}
+
+/// Compares the digests of two tokens in constant time.
+fn same_token(token: &str, expected: &str) -> bool {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .zip(Sha256::digest(expected.as_bytes()).iter())
+        .fold(0u8, |difference, (a, b)| difference | (a ^ b))
+        == 0
+}
+
+/// The metric names of the `name[]` (or `name`) query parameters.
+fn requested_names(query: Option<&str>) -> Vec<String> {
+    form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .filter(|(key, _)| key == "name[]" || key == "name")
+        .map(|(_, name)| name.into_owned())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// A metric family of an OpenMetrics exposition: its metadata and samples.
+struct FamilyBlock<'a> {
+    name: &'a str,
+    kind: &'a str,
+    lines: Vec<&'a str>,
+}
+
+impl FamilyBlock<'_> {
+    /// Whether the family or one of its samples is named after one of `names`.
+    fn matches(&self, names: &[String]) -> bool {
+        names.iter().any(|name| {
+            name == self.name
+                || self
+                    .lines
+                    .iter()
+                    .filter(|line| !line.starts_with('#'))
+                    .any(|line| sample_name(line) == name)
         })
+    }
+}
+
+/// Re-encodes the OpenMetrics exposition of the registry in `format`,
+/// keeping the families matching `names` when there are any.
+fn expose(open_metrics: &str, format: ExpositionFormat, names: &[String]) -> String {
+    let mut families: Vec<FamilyBlock> = Vec::new();
+    for line in open_metrics.lines() {
+        if line == "# EOF" {
+            break;
+        }
+        if let Some(metadata) = line.strip_prefix("# HELP ") {
+            families.push(FamilyBlock {
+                name: metadata.split(' ').next().unwrap_or_default(),
+                kind: "unknown",
+                lines: Vec::new(),
+            });
+        }
+        let Some(family) = families.last_mut() else {
+            continue;
+        };
+        if let Some(metadata) = line.strip_prefix("# TYPE ") {
+            family.kind = metadata.split(' ').nth(1).unwrap_or("unknown");
+        }
+        family.lines.push(line);
+    }
+
+    let mut body = String::new();
+    for family in families
+        .iter()
+        .filter(|family| names.is_empty() || family.matches(names))
+    {
+        for line in &family.lines {
+            match format {
+                ExpositionFormat::OpenMetrics => body.push_str(line),
+                ExpositionFormat::Prometheus => {
+                    let Some(line) = prometheus_line(family, line) else {
+                        continue;
+                    };
+                    body.push_str(&line);
+                }
+            }
+            body.push('\n');
+        }
+    }
+    if format == ExpositionFormat::OpenMetrics {
+        body.push_str("# EOF\n");
+    }
+    body
+}
+
+/// A line of an OpenMetrics family in the Prometheus text format 0.0.4,
+/// which has no units, exemplars or `unknown` type, and names counters after
+/// their `_total` sample.
+fn prometheus_line(family: &FamilyBlock, line: &str) -> Option<String> {
+    let counter = family.kind == "counter";
+    if let Some(metadata) = line.strip_prefix("# ") {
+        let (keyword, rest) = metadata.split_once(' ')?;
+        let rest = rest.strip_prefix(family.name)?;
+        let name = if counter {
+            format!("{}_total", family.name)
+        } else {
+            family.name.to_string()
+        };
+        return match keyword {
+            "HELP" => Some(format!("# HELP {name}{rest}")),
+            "TYPE" if family.kind == "unknown" => Some(format!("# TYPE {name} untyped")),
+            "TYPE" => Some(format!("# TYPE {name}{rest}")),
+            _ => None,
+        };
+    }
+    if counter && sample_name(line).ends_with("_created") {
+        return None;
+    }
+    Some(without_exemplar(line).to_string())
+}
+
+/// The name of a sample line.
+fn sample_name(line: &str) -> &str {
+    line.split(['{', ' ']).next().unwrap_or_default()
+}
+
+/// A sample line without its ` # {labels} value` exemplar.
+fn without_exemplar(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return line[..index].trim_end(),
+            _ => {}
+        }
+    }
+    line
+}
+
+/// The exemplar of an observation made in an exported trace.
+fn exemplar(trace: Option<&SpanContext>) -> Option<Labels> {
+    let trace = trace.filter(|trace| trace.is_valid() && trace.is_sampled())?;
+    Some(vec![
+        ("trace_id".to_string(), trace.trace_id().to_string()),
+        ("span_id".to_string(), trace.span_id().to_string()),
+    ])
 }
 
 pub async fn metrics_middleware(
@@ -183,13 +697,24 @@ pub async fn metrics_middleware(
     next: Next,
 ) -> Response {
     let method = request.method().to_string();
-    let path = request.uri().path().to_string();
+    // The route, not the requested path, to keep the label bounded
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_PATH, MatchedPath::as_str)
+        .to_string();
     let start = Instant::now();
     metrics.increment_http_requests_in_flight();
     let _guard = InFlightGuard::new(Arc::clone(&metrics));
 
     let response = next.run(request).await;
-    metrics.record_http_request(&method, &path, response.status(), start.elapsed());
+    metrics.record_http_request(
+        &method,
+        &path,
+        response.status(),
+        start.elapsed(),
+        response.extensions().get::<SpanContext>(),
+    );
 
     response
 }
@@ -198,6 +723,42 @@ fn http_duration_histogram() -> Histogram {
     Histogram::new(HTTP_DURATION_BUCKETS)
 }
 
+/// From 64 bytes to 16 MiB.
+fn message_size_histogram() -> Histogram {
+    Histogram::new(exponential_buckets(64.0, 4.0, 10))
+}
+
+fn error_code_label(error_code: Option<u32>) -> String {
+    match error_code {
+        None => "none".to_string(),
+        Some(code) if ManagementError::translate_error_enum(code) != "Unknown error" => {
+            code.to_string()
+        }
+        Some(_) => "other".to_string(),
+    }
+}
+
+/// Runs a tool call, returning its output and the first pgmoneta error code
+/// it got, see [`record_pgmoneta_error`].
+pub async fn track_pgmoneta_error<F: Future>(call: F) -> (F::Output, Option<u32>) {
+    PGMONETA_ERROR
+        .scope(Cell::new(None), async move {
+            let output = call.await;
+            (output, PGMONETA_ERROR.with(Cell::get))
+        })
+        .await
+}
+
+/// Records the error code of a pgmoneta response for the tool call in
+/// progress; requests outside of a tool call, or spawned by it, are ignored.
+pub fn record_pgmoneta_error(code: u32) {
+    let _ = PGMONETA_ERROR.try_with(|error| {
+        if error.get().is_none() {
+            error.set(Some(code));
+        }
+    });
+}
+
 struct InFlightGuard {
     metrics: Arc<Metrics>,
 }
@@ -214,6 +775,24 @@ impl Drop for InFlightGuard {
     }
 }
 
+/// Counts an MCP session as active until dropped.
+pub struct SessionGuard {
+    metrics: Arc<Metrics>,
+}
+
+impl SessionGuard {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        metrics.increment_sessions();
+        Self { metrics }
+    }
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.metrics.decrement_sessions();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -221,7 +800,13 @@ mod tests {
     #[test]
     fn test_metrics_encode_records_http_and_scrape_metrics() {
         let metrics = Metrics::new();
-        metrics.record_http_request("GET", "/mcp", StatusCode::OK, Duration::from_millis(20));
+        metrics.record_http_request(
+            "GET",
+            "/mcp",
+            StatusCode::OK,
+            Duration::from_millis(20),
+            None,
+        );
         metrics.record_pgmoneta_metrics_scrape("200", Duration::from_millis(15));
 
         let encoded = metrics.encode().unwrap();
@@ -231,4 +816,325 @@ mod tests {
         assert!(encoded.contains("pgmoneta_mcp_pgmoneta_metrics_scrapes_total"));
         assert!(encoded.contains("outcome=\"200\""));
     }
+
+    #[test]
+    fn test_metrics_encode_records_sla_checks() {
+        let metrics = Metrics::new();
+        metrics.record_sla_check("primary", 1, Some(7200.0), None);
+        metrics.record_sla_check("replica", 2, None, Some(90.5));
+
+        let encoded = metrics.encode().unwrap();
+
+        assert!(encoded.contains("pgmoneta_mcp_sla_status{server=\"primary\"} 1"));
+        assert!(encoded.contains("pgmoneta_mcp_sla_backup_age_seconds{server=\"primary\"} 7200.0"));
+        assert!(
+            encoded.contains("pgmoneta_mcp_sla_restore_estimate_seconds{server=\"replica\"} 90.5")
+        );
+        assert!(!encoded.contains("pgmoneta_mcp_sla_backup_age_seconds{server=\"replica\"}"));
+
+        metrics.record_sla_check("primary", 0, None, None);
+        metrics.clear_sla_checks();
+        let encoded = metrics.encode().unwrap();
+        assert!(!encoded.contains("server=\"primary\""));
+        assert!(encoded.contains("pgmoneta_mcp_sla_last_check_timestamp_seconds"));
+    }
+
+    #[test]
+    fn test_metrics_encode_records_tool_calls() {
+        let metrics = Metrics::new();
+        metrics.record_tool_call("backup", "success", None, Duration::from_secs(90), None);
+        metrics.record_tool_call("backup", "error", Some(111), Duration::from_secs(1), None);
+        metrics.record_tool_call(UNKNOWN_TOOL, "error", Some(424242), Duration::ZERO, None);
+
+        let encoded = metrics.encode().unwrap();
+
+        assert!(encoded.contains(
+            "pgmoneta_mcp_tool_calls_total{tool=\"backup\",outcome=\"success\",error_code=\"none\"} 1"
+        ));
+        assert!(encoded.contains(
+            "pgmoneta_mcp_tool_calls_total{tool=\"backup\",outcome=\"error\",error_code=\"111\"} 1"
+        ));
+        assert!(encoded.contains(
+            "pgmoneta_mcp_tool_calls_total{tool=\"unknown\",outcome=\"error\",error_code=\"other\"} 1"
+        ));
+        assert!(encoded.contains(
+            "pgmoneta_mcp_tool_call_duration_seconds_count{tool=\"backup\",outcome=\"success\"} 1"
+        ));
+    }
+
+    #[test]
+    fn test_metrics_encode_records_sessions_and_connections() {
+        let metrics = Arc::new(Metrics::new());
+        let first = SessionGuard::new(Arc::clone(&metrics));
+        let second = SessionGuard::new(Arc::clone(&metrics));
+        drop(first);
+        metrics.record_pgmoneta_connection(ConnectionStage::Connect, Duration::from_millis(2));
+        metrics.record_pgmoneta_connection_failure(ConnectionStage::Handshake);
+        metrics.record_pgmoneta_message(MessageDirection::Response, 4096, 512);
+
+        let encoded = metrics.encode().unwrap();
+
+        assert!(encoded.contains("pgmoneta_mcp_sessions 1"));
+        assert!(encoded.contains(
+            "pgmoneta_mcp_pgmoneta_connection_duration_seconds_count{stage=\"connect\"} 1"
+        ));
+        assert!(
+            encoded
+                .contains("pgmoneta_mcp_pgmoneta_connection_failures_total{stage=\"handshake\"} 1")
+        );
+        assert!(encoded.contains(
+            "pgmoneta_mcp_pgmoneta_message_size_bytes_sum{direction=\"response\",stage=\"uncompressed\"} 4096.0"
+        ));
+        assert!(encoded.contains(
+            "pgmoneta_mcp_pgmoneta_message_size_bytes_sum{direction=\"response\",stage=\"compressed\"} 512.0"
+        ));
+
+        drop(second);
+        assert!(
+            metrics
+                .encode()
+                .unwrap()
+                .contains("pgmoneta_mcp_sessions 0")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_track_pgmoneta_error() {
+        // Outside of a tool call the error is ignored
+        record_pgmoneta_error(111);
+
+        let (output, error_code) = track_pgmoneta_error(async {
+            record_pgmoneta_error(304);
+            record_pgmoneta_error(111);
+            "done"
+        })
+        .await;
+        assert_eq!(output, "done");
+        assert_eq!(error_code, Some(304));
+
+        let (_, error_code) = track_pgmoneta_error(async {}).await;
+        assert_eq!(error_code, None);
+    }
+
+    fn sampled_span() -> SpanContext {
+        use opentelemetry::trace::{SpanId, TraceFlags, TraceId, TraceState};
+
+        SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        )
+    }
+
+    #[test]
+    fn test_metrics_encode_records_exemplars() {
+        let metrics = Metrics::new();
+        let trace = sampled_span();
+        metrics.record_tool_call(
+            "backup",
+            "success",
+            None,
+            Duration::from_secs(2),
+            Some(&trace),
+        );
+        metrics.record_http_request(
+            "POST",
+            "/mcp",
+            StatusCode::OK,
+            Duration::from_millis(30),
+            Some(&SpanContext::empty_context()),
+        );
+
+        let encoded = metrics.encode().unwrap();
+
+        assert!(encoded.contains(
+            "pgmoneta_mcp_tool_call_duration_seconds_bucket{le=\"2.5\",tool=\"backup\",outcome=\"success\"} 1 # {trace_id=\"4bf92f3577b34da6a3ce929d0e0e4736\",span_id=\"00f067aa0ba902b7\"} 2.0"
+        ));
+        assert!(!encoded.contains("path=\"/mcp\",status=\"200\"} 1 #"));
+    }
+
+    #[test]
+    fn test_exposition_format_negotiate() {
+        for (accept, expected) in [
+            (None, ExpositionFormat::Prometheus),
+            (Some("*/*"), ExpositionFormat::Prometheus),
+            (Some("text/plain"), ExpositionFormat::Prometheus),
+            (
+                Some("application/openmetrics-text; version=1.0.0"),
+                ExpositionFormat::OpenMetrics,
+            ),
+            (
+                Some(
+                    "application/openmetrics-text;version=1.0.0;q=0.5,\
+                     text/plain;version=0.0.4;q=0.3,*/*;q=0.2",
+                ),
+                ExpositionFormat::OpenMetrics,
+            ),
+            (
+                Some("application/openmetrics-text;q=0.2, text/plain;q=0.9"),
+                ExpositionFormat::Prometheus,
+            ),
+            (
+                Some("application/openmetrics-text;q=0"),
+                ExpositionFormat::Prometheus,
+            ),
+        ] {
+            let mut headers = HeaderMap::new();
+            if let Some(accept) = accept {
+                headers.insert(header::ACCEPT, HeaderValue::from_str(accept).unwrap());
+            }
+            assert_eq!(
+                ExpositionFormat::negotiate(&headers),
+                expected,
+                "{accept:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_metrics_expose_prometheus_text() {
+        let metrics = Metrics::new();
+        let trace = sampled_span();
+        metrics.record_tool_call(
+            "backup",
+            "success",
+            None,
+            Duration::from_secs(2),
+            Some(&trace),
+        );
+        metrics.record_pgmoneta_metrics_scrape("200", Duration::from_millis(15));
+
+        let text = metrics.expose(ExpositionFormat::Prometheus, &[]).unwrap();
+
+        assert!(text.contains("# TYPE pgmoneta_mcp_tool_calls_total counter\n"));
+        assert!(text.contains("# HELP pgmoneta_mcp_tool_calls_total Number of MCP tool calls"));
+        assert!(text.contains("# TYPE pgmoneta_mcp_tool_call_duration_seconds histogram\n"));
+        assert!(text.contains(
+            "pgmoneta_mcp_tool_call_duration_seconds_bucket{le=\"2.5\",tool=\"backup\",outcome=\"success\"} 1\n"
+        ));
+        assert!(!text.contains("trace_id"));
+        assert!(!text.contains("# EOF"));
+
+        let open_metrics = metrics.expose(ExpositionFormat::OpenMetrics, &[]).unwrap();
+        assert_eq!(open_metrics, metrics.encode().unwrap());
+    }
+
+    #[test]
+    fn test_metrics_expose_filters_by_name() {
+        let metrics = Metrics::new();
+        metrics.record_tool_call("backup", "success", None, Duration::from_secs(2), None);
+        metrics.record_sla_check("primary", 1, Some(7200.0), None);
+
+        let names = requested_names(Some(
+            "name[]=pgmoneta_mcp_tool_calls_total&name%5B%5D=pgmoneta_mcp_sla_status&other=1",
+        ));
+        assert_eq!(
+            names,
+            ["pgmoneta_mcp_tool_calls_total", "pgmoneta_mcp_sla_status"]
+        );
+
+        let text = metrics
+            .expose(ExpositionFormat::Prometheus, &names)
+            .unwrap();
+        assert!(text.contains("pgmoneta_mcp_tool_calls_total{tool=\"backup\""));
+        assert!(text.contains("pgmoneta_mcp_sla_status{server=\"primary\"} 1"));
+        assert!(!text.contains("pgmoneta_mcp_sla_backup_age_seconds"));
+        assert!(!text.contains("pgmoneta_mcp_tool_call_duration_seconds"));
+
+        let open_metrics = metrics
+            .expose(
+                ExpositionFormat::OpenMetrics,
+                &["pgmoneta_mcp_tool_call_duration_seconds_count".to_string()],
+            )
+            .unwrap();
+        assert!(open_metrics.starts_with("# HELP pgmoneta_mcp_tool_call_duration_seconds "));
+        assert!(open_metrics.ends_with("# EOF\n"));
+        assert!(!open_metrics.contains("pgmoneta_mcp_tool_calls_total"));
+
+        let none = metrics
+            .expose(ExpositionFormat::OpenMetrics, &["missing".to_string()])
+            .unwrap();
+        assert_eq!(none, "# EOF\n");
+    }
+
+    #[test]
+    fn test_without_exemplar() {
+        assert_eq!(without_exemplar("up 1"), "up 1");
+        assert_eq!(
+            without_exemplar("a_bucket{le=\"1\"} 3 # {trace_id=\"abc\"} 0.5"),
+            "a_bucket{le=\"1\"} 3"
+        );
+        assert_eq!(
+            without_exemplar("a_total{path=\"/#\\\"#\"} 3 # {trace_id=\"abc\"} 1"),
+            "a_total{path=\"/#\\\"#\"} 3"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_authorize_bearer_token() {
+        let access = MetricsConfiguration {
+            auth: MetricsAuth::Bearer,
+            token: Some("s3cr3t".to_string()),
+        };
+
+        for (authorization, expected) in [
+            (None, Err(BEARER_REALM)),
+            (Some("Bearer s3cr3t"), Ok(())),
+            (Some("bearer  s3cr3t "), Ok(())),
+            (Some("Bearer s3cr3"), Err(BEARER_REALM)),
+            (Some("Basic czNjcjN0"), Err(BEARER_REALM)),
+        ] {
+            let mut headers = HeaderMap::new();
+            if let Some(authorization) = authorization {
+                headers.insert(
+                    header::AUTHORIZATION,
+                    HeaderValue::from_str(authorization).unwrap(),
+                );
+            }
+            assert_eq!(
+                authorize(&access, &headers).await,
+                expected,
+                "{authorization:?}"
+            );
+        }
+        assert_eq!(
+            authorize(&MetricsConfiguration::default(), &HeaderMap::new()).await,
+            Ok(())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_metrics_middleware_labels_routes() {
+        let metrics = Arc::new(Metrics::new());
+        let app = axum::Router::new()
+            .route("/metrics", axum::routing::get(metrics_handler))
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::clone(&metrics),
+                metrics_middleware,
+            ))
+            .with_state(Arc::clone(&metrics));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("The listener should bind");
+        let address = listener.local_addr().expect("The listener has an address");
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = reqwest::Client::new();
+        for path in ["/metrics", "/random/1", "/random/2"] {
+            client
+                .get(format!("http://{address}{path}"))
+                .send()
+                .await
+                .expect("The request should succeed");
+        }
+
+        let encoded = metrics.encode().unwrap();
+        assert!(encoded.contains("path=\"/metrics\",status=\"200\""));
+        assert!(encoded.contains(
+            "pgmoneta_mcp_http_requests_total{method=\"GET\",path=\"unmatched\",status=\"404\"} 2"
+        ));
+        assert!(!encoded.contains("/random"));
+    }
 }
@@ -16,6 +16,8 @@
 use anyhow::{Result, bail};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
 
 pub const CONSOLE_TITLE_ICON: &str = "🟠";
 const TERMINAL_TITLE_SEQUENCE_PREFIX: &str = "\x1b]0;";
@@ -31,6 +33,21 @@ pub struct SafeFileReader {
     allowed_base_dir: Option<PathBuf>,
 }
 
+pub struct SafeFileWriter {
+    allowed_extensions: Option<Vec<String>>,
+    base_dir: PathBuf,
+}
+
+/// Writes snapshots of an in-memory state to disk on a blocking thread, so
+/// async code holding the state never waits for the disk.
+///
+/// Snapshots are numbered when taken, and an older snapshot never overwrites
+/// a newer one.
+pub struct SnapshotWriter {
+    taken: AtomicU64,
+    written: Mutex<u64>,
+}
+
 impl Utility {
     /// Formats a raw byte count into a human-readable file size string.
     ///
@@ -192,6 +209,129 @@ impl Default for SafeFileReader {
     }
 }
 
+impl SafeFileWriter {
+    /// Creates a writer that only writes files below `base_dir`.
+    pub fn new(base_dir: &str) -> Self {
+        Self {
+            allowed_extensions: None,
+            base_dir: PathBuf::from(base_dir),
+        }
+    }
+
+    pub fn allowed_extensions(mut self, extensions: Vec<&str>) -> Self {
+        self.allowed_extensions = Some(extensions.iter().map(|e| e.to_lowercase()).collect());
+        self
+    }
+
+    /// Writes `contents` to `file_path`, relative to the base directory, and
+    /// returns the path written.
+    ///
+    /// Absolute paths are accepted when they are inside the base directory.
+    /// The parent directory has to exist already.
+    pub fn write(&self, file_path: &str, contents: &str) -> Result<PathBuf> {
+        let canonical_base = self.base_dir.canonicalize().map_err(|e| {
+            anyhow::anyhow!(
+                "Invalid base directory '{}': {}",
+                self.base_dir.display(),
+                e
+            )
+        })?;
+
+        let requested = Path::new(file_path);
+        if file_path.trim().is_empty() || file_path.chars().any(char::is_control) {
+            bail!("Invalid file path '{}'", file_path);
+        }
+        if requested
+            .components()
+            .any(|component| component == std::path::Component::ParentDir)
+        {
+            bail!("Invalid file path '{}': '..' is not allowed", file_path);
+        }
+        let Some(file_name) = requested.file_name() else {
+            bail!("Invalid file path '{}': missing file name", file_path);
+        };
+
+        if let Some(ref allowed) = self.allowed_extensions {
+            let ext = requested
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase())
+                .unwrap_or_default();
+
+            if !allowed.contains(&ext) {
+                bail!(
+                    "File '{}' has extension '.{}', but only [{}] are allowed",
+                    file_path,
+                    ext,
+                    allowed.join(", ")
+                );
+            }
+        }
+
+        let joined = if requested.is_absolute() {
+            requested.to_path_buf()
+        } else {
+            self.base_dir.join(requested)
+        };
+        let parent = joined
+            .parent()
+            .unwrap_or(&self.base_dir)
+            .canonicalize()
+            .map_err(|e| anyhow::anyhow!("Invalid file path '{}': {}", file_path, e))?;
+        if !parent.starts_with(&canonical_base) {
+            bail!(
+                "Access denied: '{}' is outside the allowed directory '{}'",
+                file_path,
+                canonical_base.display()
+            );
+        }
+
+        let path = parent.join(file_name);
+        if path.is_symlink() || path.is_dir() {
+            bail!("'{}' is not a regular file", path.display());
+        }
+        std::fs::write(&path, contents)
+            .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+impl SnapshotWriter {
+    pub const fn new() -> Self {
+        Self {
+            taken: AtomicU64::new(0),
+            written: Mutex::new(0),
+        }
+    }
+
+    /// Numbers a snapshot; call it under the lock the snapshot is taken with.
+    pub fn next(&self) -> u64 {
+        self.taken.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Runs `write` for the snapshot `generation` on a blocking thread, or
+    /// inline outside of a runtime, unless a newer snapshot was written.
+    pub fn write(&'static self, generation: u64, write: impl FnOnce() + Send + 'static) {
+        let persist = move || {
+            let mut written = self.written.lock().unwrap_or_else(PoisonError::into_inner);
+            if *written < generation {
+                write();
+                *written = generation;
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => drop(runtime.spawn_blocking(persist)),
+            Err(_) => persist(),
+        }
+    }
+}
+
+impl Default for SnapshotWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -282,6 +422,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_snapshot_writer_skips_older_snapshots() {
+        static WRITER: SnapshotWriter = SnapshotWriter::new();
+        let written = std::sync::Arc::new(Mutex::new(Vec::new()));
+
+        let first = WRITER.next();
+        let second = WRITER.next();
+        for generation in [second, first] {
+            let written = std::sync::Arc::clone(&written);
+            WRITER.write(generation, move || written.lock().unwrap().push(generation));
+        }
+        assert_eq!(*written.lock().unwrap(), vec![second]);
+    }
+
     #[test]
     fn test_console_title_uses_icon_and_optional_detail() {
         assert_eq!(
@@ -319,4 +473,29 @@ mod tests {
 
         assert!(output.is_empty());
     }
+
+    #[test]
+    fn test_safe_file_writer_stays_in_base_dir() {
+        let base = tempfile::tempdir().unwrap();
+        std::fs::create_dir(base.path().join("daily")).unwrap();
+        let base_dir = base.path().to_str().unwrap();
+        let writer = SafeFileWriter::new(base_dir).allowed_extensions(vec!["md"]);
+
+        let path = writer.write("report.md", "# Report").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Report");
+        assert!(writer.write("daily/report.md", "# Daily").is_ok());
+
+        let absolute = base.path().join("absolute.md");
+        assert!(
+            writer
+                .write(absolute.to_str().unwrap(), "# Absolute")
+                .is_ok()
+        );
+
+        assert!(writer.write("../escape.md", "x").is_err());
+        assert!(writer.write("/tmp/outside.md", "x").is_err());
+        assert!(writer.write("missing/report.md", "x").is_err());
+        assert!(writer.write("report.txt", "x").is_err());
+        assert!(writer.write("", "x").is_err());
+    }
 }
@@ -0,0 +1,502 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Webhooks of the backup events.
+//!
+//! Every `[webhook:<name>]` section of the configuration is an endpoint the
+//! events of the event bus are posted to, as far as its `events` and `servers`
+//! filters accept them. Every endpoint has its own queue, so that a slow
+//! endpoint does not hold up the others. An event is posted as JSON, signed
+//! with HMAC-SHA256 over the timestamp and the body. A failed delivery is
+//! retried `retries` times, with a delay doubling from `retry_interval`; an
+//! event that cannot be delivered, or finds the queue of its endpoint full
+//! with `queue_size` events, is appended to the dead-letter file.
+//!
+//! The catalog poller of the event bus runs every `catalog_interval` seconds
+//! while webhooks are configured.
+
+use std::time::Duration;
+
+use crate::configuration::{CONFIG, WebhookEndpoint, WebhooksConfiguration};
+use crate::handler::catalog::BackupClock;
+use crate::handler::events::{self, Event};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_util::sync::CancellationToken;
+
+/// The header with the type of the event.
+pub const EVENT_HEADER: &str = "X-Pgmoneta-Event";
+
+/// The header with the identifier of the event, the same for every retry.
+pub const DELIVERY_HEADER: &str = "X-Pgmoneta-Delivery";
+
+/// The header with the Unix time the request was signed at.
+pub const TIMESTAMP_HEADER: &str = "X-Pgmoneta-Timestamp";
+
+/// The header with the signature, `sha256=` and the hex encoded HMAC-SHA256
+/// of `<timestamp>.<body>`.
+pub const SIGNATURE_HEADER: &str = "X-Pgmoneta-Signature";
+
+/// The longest an endpoint may take to answer.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The longest delay between two retries.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+/// An event that could not be delivered, as a line of the dead-letter file.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DeadLetter<'a> {
+    pub endpoint: &'a str,
+    pub url: &'a str,
+    pub attempts: u32,
+    pub error: String,
+    pub failed_at: String,
+    pub event: &'a Event,
+}
+
+/// A failed post.
+#[derive(Debug)]
+struct PostError {
+    message: String,
+    /// Whether a retry may succeed; not for client errors other than 408 and 429.
+    retryable: bool,
+}
+
+/// The delivery of the events to one endpoint.
+#[derive(Clone)]
+struct Delivery {
+    client: reqwest::Client,
+    endpoint: WebhookEndpoint,
+    retries: u32,
+    retry_interval: Duration,
+    dead_letter: Option<String>,
+}
+
+/// Starts posting the events to the webhook endpoints and polling the backup
+/// catalog, if any endpoint is configured.
+pub fn start(shutdown: CancellationToken) -> Option<JoinHandle<()>> {
+    let config = CONFIG.get()?;
+    let webhooks = config.webhooks.clone()?;
+    if webhooks.endpoints.is_empty() {
+        return None;
+    }
+    let username = webhooks
+        .username
+        .clone()
+        .or_else(|| config.admins.keys().min().cloned());
+    if username.is_none() {
+        tracing::error!("Not polling the backup catalog: no admin is configured to poll as");
+    }
+
+    // Subscribe before returning, so that no event emitted from now on is missed
+    let receiver = events::subscribe();
+    tracing::info!(
+        "Posting the backup events to {} webhooks",
+        webhooks.endpoints.len()
+    );
+    Some(tokio::spawn(run(webhooks, username, receiver, shutdown)))
+}
+
+async fn run(
+    webhooks: WebhooksConfiguration,
+    username: Option<String>,
+    mut receiver: tokio::sync::broadcast::Receiver<Event>,
+    shutdown: CancellationToken,
+) {
+    let client = match reqwest::Client::builder().timeout(TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Failed to create the webhook client: {}", e);
+            return;
+        }
+    };
+
+    let mut tasks = JoinSet::new();
+    let mut queues = Vec::new();
+    for endpoint in &webhooks.endpoints {
+        let (sender, queue) = mpsc::channel(webhooks.queue_size);
+        let delivery = Delivery {
+            client: client.clone(),
+            endpoint: endpoint.clone(),
+            retries: webhooks.retries,
+            retry_interval: Duration::from_secs(webhooks.retry_interval.into()),
+            dead_letter: webhooks.dead_letter.clone(),
+        };
+        queues.push((delivery.clone(), sender));
+        tasks.spawn(delivery.run(queue, shutdown.clone()));
+    }
+    if let Some(username) = username {
+        tasks.spawn(poll_loop(
+            username,
+            webhooks.catalog_interval,
+            shutdown.clone(),
+        ));
+    }
+
+    loop {
+        let event = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            received = receiver.recv() => match received {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("The webhooks missed {} events", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+        for (delivery, sender) in &queues {
+            if delivery.endpoint.accepts(event.kind, &event.server) {
+                delivery.enqueue(sender, &event).await;
+            }
+        }
+    }
+    drop(queues);
+    while tasks.join_next().await.is_some() {}
+    tracing::info!("Webhook delivery stopped");
+}
+
+async fn poll_loop(username: String, interval: u32, shutdown: CancellationToken) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval.into()));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+        if let Err(e) = events::poll_catalog(&username).await {
+            tracing::warn!("Failed to poll the backup catalog: {}", e.message);
+        }
+    }
+}
+
+impl Delivery {
+    /// Queues an event for the endpoint, or gives it up when the queue is full
+    /// because the endpoint has been failing for a while.
+    async fn enqueue(&self, queue: &mpsc::Sender<Event>, event: &Event) {
+        if let Err(TrySendError::Full(event)) = queue.try_send(event.clone()) {
+            self.give_up(&event, 0, "the queue of the endpoint is full".to_string())
+                .await;
+        }
+    }
+
+    /// Delivers the events of the queue one after the other; on shutdown the
+    /// events not delivered yet go to the dead-letter file.
+    async fn run(self, mut queue: mpsc::Receiver<Event>, shutdown: CancellationToken) {
+        loop {
+            let event = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                event = queue.recv() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+            };
+            self.deliver(&event, &shutdown).await;
+        }
+        while let Ok(event) = queue.try_recv() {
+            self.give_up(
+                &event,
+                0,
+                "the server shut down before the delivery".to_string(),
+            )
+            .await;
+        }
+    }
+
+    /// Posts an event, retrying until it is delivered or the retries are used up.
+    async fn deliver(&self, event: &Event, shutdown: &CancellationToken) {
+        let body = match serde_json::to_vec(event) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::error!("Failed to serialize the event {}: {}", event.id, e);
+                return;
+            }
+        };
+        let mut delay = self.retry_interval;
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let error = match post(&self.client, &self.endpoint, event, body.clone()).await {
+                Ok(()) => return,
+                Err(error) => error,
+            };
+            if !error.retryable || attempts > self.retries {
+                self.give_up(event, attempts, error.message).await;
+                return;
+            }
+            tracing::debug!(
+                "Retrying the event {} for [{}] in {:?}: {}",
+                event.id,
+                self.endpoint.section(),
+                delay,
+                error.message
+            );
+            tokio::select! {
+                _ = shutdown.cancelled() => {
+                    self.give_up(event, attempts, error.message).await;
+                    return;
+                }
+                _ = tokio::time::sleep(delay) => {}
+            }
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        }
+    }
+
+    /// Appends an event that could not be delivered to the dead-letter file.
+    async fn give_up(&self, event: &Event, attempts: u32, error: String) {
+        tracing::warn!(
+            "Failed to deliver the event {} to [{}] after {} attempts: {}",
+            event.id,
+            self.endpoint.section(),
+            attempts,
+            error
+        );
+        let clock = BackupClock::configured();
+        let letter = DeadLetter {
+            endpoint: &self.endpoint.name,
+            url: &self.endpoint.url,
+            attempts,
+            error,
+            failed_at: clock.format(clock.now()),
+            event,
+        };
+        let Some(path) = &self.dead_letter else {
+            tracing::error!(
+                "Dropping the undeliverable event, as no dead-letter file is configured: {}",
+                serde_json::to_string(&letter).unwrap_or_default()
+            );
+            return;
+        };
+        if let Err(e) = append_dead_letter(path, &letter).await {
+            tracing::error!("Failed to append the event to {}: {:#}", path, e);
+        }
+    }
+}
+
+/// Posts an event with its signature headers.
+async fn post(
+    client: &reqwest::Client,
+    endpoint: &WebhookEndpoint,
+    event: &Event,
+    body: Vec<u8>,
+) -> Result<(), PostError> {
+    let timestamp = Utc::now().timestamp();
+    let signature = sign(&endpoint.secret, timestamp, &body);
+    let response = client
+        .post(&endpoint.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, event.kind.as_str())
+        .header(DELIVERY_HEADER, &event.id)
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(SIGNATURE_HEADER, signature)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| PostError {
+            message: e.to_string(),
+            retryable: true,
+        })?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    Err(PostError {
+        message: format!("the endpoint answered {status}"),
+        retryable: !status.is_client_error()
+            || status == reqwest::StatusCode::REQUEST_TIMEOUT
+            || status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+    })
+}
+
+/// The signature of a body: `sha256=` and the hex encoded HMAC-SHA256 of
+/// `<timestamp>.<body>` keyed with the secret of the endpoint.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("sha256={hex}")
+}
+
+async fn append_dead_letter(path: &str, letter: &DeadLetter<'_>) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(letter)?;
+    line.push('\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(line.as_bytes()).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::events::{EventKind, EventSource};
+    use axum::body::Bytes;
+    use axum::{Router, extract::State, http::HeaderMap, http::StatusCode, routing::post};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type Received = mpsc::UnboundedSender<(HeaderMap, Vec<u8>)>;
+
+    /// A webhook stand-in answering 503 to the first `failures` posts of `/flaky`.
+    async fn serve(failures: usize) -> (String, mpsc::UnboundedReceiver<(HeaderMap, Vec<u8>)>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route(
+                "/flaky",
+                post(
+                    move |State(sender): State<Received>, headers: HeaderMap, body: Bytes| {
+                        let attempts = attempts.clone();
+                        async move {
+                            if attempts.fetch_add(1, Ordering::SeqCst) < failures {
+                                return StatusCode::SERVICE_UNAVAILABLE;
+                            }
+                            sender.send((headers, body.to_vec())).unwrap();
+                            StatusCode::NO_CONTENT
+                        }
+                    },
+                ),
+            )
+            .route("/rejecting", post(|| async { StatusCode::BAD_REQUEST }))
+            .with_state(sender);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (format!("http://{address}"), receiver)
+    }
+
+    fn delivery(url: String, retries: u32, dead_letter: Option<String>) -> Delivery {
+        Delivery {
+            client: reqwest::Client::new(),
+            endpoint: WebhookEndpoint {
+                name: "chatops".to_string(),
+                url,
+                secret: "s3cr3t".to_string(),
+                events: Vec::new(),
+                servers: Vec::new(),
+            },
+            retries,
+            retry_interval: Duration::from_millis(10),
+            dead_letter,
+        }
+    }
+
+    fn event() -> Event {
+        Event::new(EventKind::BackupFailed, EventSource::Scheduler, "primary")
+            .username("admin")
+            .error("pgmoneta reported a failure: Backup: server not found")
+    }
+
+    #[test]
+    fn test_sign() {
+        // echo -n '1767225600.{"Type":"backup_finished"}' | openssl dgst -sha256 -hmac s3cr3t
+        assert_eq!(
+            sign("s3cr3t", 1767225600, br#"{"Type":"backup_finished"}"#),
+            "sha256=ad79f71577db15aff5f4688a006994f9638f27d773b43bc3e692ada4d20300e8"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deliver_retries_and_signs() {
+        let (base, mut received) = serve(2).await;
+        let delivery = delivery(format!("{base}/flaky"), 2, None);
+        let event = event();
+        delivery.deliver(&event, &CancellationToken::new()).await;
+
+        let (headers, body) = received.try_recv().unwrap();
+        assert_eq!(headers[EVENT_HEADER], "backup_failed");
+        assert_eq!(headers[DELIVERY_HEADER], event.id.as_str());
+        let timestamp: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        assert_eq!(
+            headers[SIGNATURE_HEADER].to_str().unwrap(),
+            sign("s3cr3t", timestamp, &body)
+        );
+        assert_eq!(serde_json::from_slice::<Event>(&body).unwrap(), event);
+    }
+
+    #[tokio::test]
+    async fn test_undeliverable_events_go_to_the_dead_letter_file() {
+        let (base, mut received) = serve(10).await;
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("dead_letter.jsonl");
+        let path = path.to_str().unwrap().to_string();
+
+        let event = event();
+        delivery(format!("{base}/flaky"), 2, Some(path.clone()))
+            .deliver(&event, &CancellationToken::new())
+            .await;
+        delivery(format!("{base}/rejecting"), 2, Some(path.clone()))
+            .deliver(&event, &CancellationToken::new())
+            .await;
+        assert!(received.try_recv().is_err());
+
+        let letters = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(letters.len(), 2);
+        assert_eq!(letters[0]["Endpoint"], "chatops");
+        assert_eq!(letters[0]["Attempts"], 3);
+        assert_eq!(
+            letters[0]["Error"],
+            "the endpoint answered 503 Service Unavailable"
+        );
+        assert_eq!(letters[0]["Event"]["Id"], event.id.as_str());
+        // Client errors are not retried
+        assert_eq!(letters[1]["Attempts"], 1);
+        assert_eq!(letters[1]["Error"], "the endpoint answered 400 Bad Request");
+    }
+
+    #[tokio::test]
+    async fn test_full_queues_go_to_the_dead_letter_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("dead_letter.jsonl");
+        let path = path.to_str().unwrap().to_string();
+        let delivery = delivery("http://127.0.0.1:9/down".to_string(), 2, Some(path.clone()));
+        let (sender, mut queue) = mpsc::channel(1);
+
+        let (first, second) = (event(), event());
+        delivery.enqueue(&sender, &first).await;
+        delivery.enqueue(&sender, &second).await;
+
+        assert_eq!(queue.try_recv().unwrap().id, first.id);
+        let letters = std::fs::read_to_string(&path).unwrap();
+        let letters = letters
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0]["Attempts"], 0);
+        assert_eq!(letters[0]["Error"], "the queue of the endpoint is full");
+        assert_eq!(letters[0]["Event"]["Id"], second.id.as_str());
+    }
+}
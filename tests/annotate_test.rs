@@ -14,7 +14,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use pgmoneta_mcp::handler::PgmonetaHandler;
-use pgmoneta_mcp::handler::annotate::{AnnotateBackupTool, AnnotateRequest};
+use pgmoneta_mcp::handler::annotate::{AnnotateAction, AnnotateBackupTool, AnnotateRequest};
 use pgmoneta_mcp::handler::info::{GetBackupInfoTool, InfoRequest as GetBackupInfoRequest};
 use rmcp::handler::server::router::tool::AsyncTool;
 use serde_json::Value;
@@ -28,7 +28,7 @@ async fn clean_annotations(key: &str) {
         username: "backup_user".to_string(),
         server: "primary".to_string(),
         backup_id: "newest".to_string(),
-        action: "remove".to_string(),
+        action: Some(AnnotateAction::Remove),
         key: key.to_string(),
         comment: None,
     };
@@ -76,7 +76,7 @@ async fn annotate_add_comment_test() {
         username: "backup_user".to_string(),
         server: "primary".to_string(),
         backup_id: "newest".to_string(),
-        action: "add".to_string(),
+        action: Some(AnnotateAction::Add),
         key: "mcp-test-key-add".to_string(),
         comment: Some("initial comment".to_string()),
     };
@@ -121,7 +121,7 @@ async fn annotate_update_comment_test() {
         username: "backup_user".to_string(),
         server: "primary".to_string(),
         backup_id: "newest".to_string(),
-        action: "add".to_string(),
+        action: Some(AnnotateAction::Add),
         key: "mcp-test-key-add".to_string(),
         comment: Some("old comment".to_string()),
     };
@@ -134,7 +134,7 @@ async fn annotate_update_comment_test() {
         username: "backup_user".to_string(),
         server: "primary".to_string(),
         backup_id: "newest".to_string(),
-        action: "update".to_string(),
+        action: Some(AnnotateAction::Update),
         key: "mcp-test-key-add".to_string(),
         comment: Some("new comment".to_string()),
     };
@@ -179,7 +179,7 @@ async fn annotate_remove_comment_test() {
         username: "backup_user".to_string(),
         server: "primary".to_string(),
         backup_id: "newest".to_string(),
-        action: "add".to_string(),
+        action: Some(AnnotateAction::Add),
         key: "mcp".to_string(),
         comment: Some("first_comment".to_string()),
     };
@@ -192,7 +192,7 @@ async fn annotate_remove_comment_test() {
         username: "backup_user".to_string(),
         server: "primary".to_string(),
         backup_id: "newest".to_string(),
-        action: "remove".to_string(),
+        action: Some(AnnotateAction::Remove),
         key: "mcp".to_string(),
         comment: None,
     };
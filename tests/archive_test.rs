@@ -15,6 +15,7 @@
 
 use pgmoneta_mcp::handler::PgmonetaHandler;
 use pgmoneta_mcp::handler::archive::{ArchiveRequest, ArchiveTool};
+use pgmoneta_mcp::handler::restore::RecoveryAction;
 use rmcp::handler::server::router::tool::AsyncTool;
 use serde_json::Value;
 use serial_test::serial;
@@ -119,7 +120,7 @@ async fn archive_with_primary_action_timeline_test() {
         lsn: None,
         inclusive: None,
         timeline: Some("1".to_string()),
-        action: Some("pause".to_string()),
+        action: Some(RecoveryAction::Pause),
     };
 
     let response = ArchiveTool::invoke(&handler, request)
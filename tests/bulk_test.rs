@@ -0,0 +1,78 @@
+// Copyright (C) 2026 The pgmoneta community
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use pgmoneta_mcp::handler::PgmonetaHandler;
+use pgmoneta_mcp::handler::annotate::AnnotateAction;
+use pgmoneta_mcp::handler::backup::{BackupRequest, BackupServerTool};
+use pgmoneta_mcp::handler::bulk::{BackupSelector, BulkAnnotateRequest, BulkAnnotateTool};
+use rmcp::handler::server::router::tool::AsyncTool;
+use serde_json::Value;
+
+mod common;
+
+fn annotate_request(dry_run: bool) -> BulkAnnotateRequest {
+    BulkAnnotateRequest {
+        username: "backup_user".to_string(),
+        server: "primary".to_string(),
+        selector: BackupSelector {
+            largest: Some(1),
+            ..Default::default()
+        },
+        action: Some(AnnotateAction::Add),
+        key: "bulk".to_string(),
+        comment: Some("integration test".to_string()),
+        dry_run: Some(dry_run),
+        concurrency: None,
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires pgmoneta stack (see test/check.sh and full-test CI job)"]
+async fn bulk_annotate_dry_run_then_execute_test() {
+    common::init_config();
+    let handler = PgmonetaHandler::new();
+
+    let request = BackupRequest {
+        username: "backup_user".to_string(),
+        server: "primary".to_string(),
+        backup_id: None,
+    };
+    BackupServerTool::invoke(&handler, request)
+        .await
+        .expect("backup should succeed");
+
+    let response = BulkAnnotateTool::invoke(&handler, annotate_request(true))
+        .await
+        .expect("bulk_annotate dry run should succeed");
+    let json: Value = serde_json::from_str(&response).expect("response should be valid json");
+    assert_eq!(json["DryRun"], true, "unexpected response: {response}");
+    assert_eq!(
+        json["Selected"].as_array().map(Vec::len),
+        Some(1),
+        "unexpected response: {response}"
+    );
+    assert_eq!(
+        json["Results"].as_array().map(Vec::len),
+        Some(0),
+        "unexpected response: {response}"
+    );
+
+    let response = BulkAnnotateTool::invoke(&handler, annotate_request(false))
+        .await
+        .expect("bulk_annotate should succeed");
+    let json: Value = serde_json::from_str(&response).expect("response should be valid json");
+    assert_eq!(json["Succeeded"], 1, "unexpected response: {response}");
+    assert_eq!(json["Failed"], 0, "unexpected response: {response}");
+}
@@ -64,6 +64,11 @@ pub fn init_config() {
                 log_line_prefix: "%Y-%m-%d %H:%M:%S".to_string(),
                 log_mode: "append".to_string(),
                 log_rotation_age: "0".to_string(),
+                timezone: "local".to_string(),
+                prompts_directory: None,
+                runbooks_directory: None,
+                report_directory: None,
+                state_directory: None,
             },
             pgmoneta: PgmonetaConfiguration {
                 host: "127.0.0.1".to_string(),
@@ -74,6 +79,13 @@ pub fn init_config() {
             },
             admins,
             llm: None,
+            sla: Vec::new(),
+            schedules: Vec::new(),
+            history: None,
+            alerting: None,
+            webhooks: None,
+            opentelemetry: None,
+            metrics: None,
         };
 
         CONFIG
@@ -1,5 +1,5 @@
 use pgmoneta_mcp::handler::PgmonetaHandler;
-use pgmoneta_mcp::handler::info::{ListBackupsRequest, ListBackupsTool};
+use pgmoneta_mcp::handler::info::{ListBackupsRequest, ListBackupsTool, SortOrder};
 use rmcp::handler::server::router::tool::AsyncTool;
 use serde_json::Value;
 mod common;
@@ -16,7 +16,7 @@ async fn list_backup_test() {
     let info_request = ListBackupsRequest {
         username: "backup_user".to_string(),
         server: "primary".to_string(),
-        sort: Some("asc".to_string()),
+        sort: Some(SortOrder::Asc),
     };
 
     let response = ListBackupsTool::invoke(&handler, info_request)
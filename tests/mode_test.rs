@@ -14,7 +14,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use pgmoneta_mcp::handler::PgmonetaHandler;
-use pgmoneta_mcp::handler::mode::{ModeRequest, SetModeTool};
+use pgmoneta_mcp::handler::mode::{ModeAction, ModeRequest, SetModeTool};
 use rmcp::handler::server::router::tool::AsyncTool;
 use serde_json::Value;
 
@@ -50,7 +50,7 @@ async fn set_mode_online_test() {
     let request = ModeRequest {
         username: "backup_user".to_string(),
         server: "primary".to_string(),
-        action: "online".to_string(),
+        action: Some(ModeAction::Online),
     };
 
     let response = SetModeTool::invoke(&handler, request)
@@ -70,7 +70,7 @@ async fn set_mode_offline_test() {
     let request = ModeRequest {
         username: "backup_user".to_string(),
         server: "primary".to_string(),
-        action: "offline".to_string(),
+        action: Some(ModeAction::Offline),
     };
 
     let response = SetModeTool::invoke(&handler, request)
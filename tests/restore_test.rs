@@ -14,7 +14,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use pgmoneta_mcp::handler::PgmonetaHandler;
-use pgmoneta_mcp::handler::restore::{RestoreRequest, RestoreTool};
+use pgmoneta_mcp::handler::restore::{RecoveryAction, RestoreRequest, RestoreTool};
 use rmcp::handler::server::router::tool::AsyncTool;
 use serde_json::Value;
 use serial_test::serial;
@@ -119,7 +119,7 @@ async fn restore_with_primary_action_timeline_test() {
         lsn: None,
         inclusive: None,
         timeline: Some("1".to_string()),
-        action: Some("pause".to_string()),
+        action: Some(RecoveryAction::Pause),
     };
 
     let response = RestoreTool::invoke(&handler, request)
@@ -33,6 +33,7 @@ async fn verify_test() {
         server: "primary".to_string(),
         backup_id,
         directory: Some("/tmp".to_string()),
+        failed_only: None,
     };
 
     let response = VerifyBackupTool::invoke(&handler, verify_request)
@@ -61,4 +62,20 @@ async fn verify_test() {
     } else {
         panic!("Outcome field missing: {response}");
     };
+
+    let report = json
+        .get("Response")
+        .unwrap_or_else(|| panic!("Response field missing: {response}"));
+    assert_eq!(
+        report["Result"], "passed",
+        "unexpected result in response: {response}"
+    );
+    assert_eq!(
+        report["FailedFiles"], 0,
+        "unexpected failed files in response: {response}"
+    );
+    assert!(
+        report["TotalFiles"].as_u64().is_some_and(|total| total > 0),
+        "TotalFiles missing in response: {response}"
+    );
 }